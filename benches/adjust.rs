@@ -0,0 +1,43 @@
+//! Adjustment of many annotations across many hunks, on synthetic diffs.
+//!
+//! `cargo bench --features bench --bench adjust` compares runs; for a
+//! profiler, run one case for a fixed time without analysis, e.g.
+//! `cargo bench --features bench --bench adjust -- --profile-time 10 adjust/5000x500`.
+
+use annotator::git::adjust::{AdjustOptions, adjust_annotations, apply_adjustments};
+use annotator::git::synthetic::Scenario;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+/// Files, hunks per file and annotations per file.
+const SIZES: [(usize, usize, usize); 3] = [(1, 100, 1_000), (5, 100, 1_000), (10, 500, 500)];
+
+fn bench_adjust(c: &mut Criterion) {
+    let options = AdjustOptions::default();
+    let mut group = c.benchmark_group("adjust");
+    for (files, hunks, annotations) in SIZES {
+        let scenario = Scenario::generate(files, hunks, annotations, 42);
+        let id = format!("{}x{}", files * annotations, files * hunks);
+        group.bench_with_input(BenchmarkId::from_parameter(&id), &scenario, |b, s| {
+            b.iter(|| adjust_annotations(&s.annotations, &s.diffs, &options))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("adjust_and_apply");
+    for (files, hunks, annotations) in SIZES {
+        let scenario = Scenario::generate(files, hunks, annotations, 42);
+        let id = format!("{}x{}", files * annotations, files * hunks);
+        group.bench_with_input(BenchmarkId::from_parameter(&id), &scenario, |b, s| {
+            b.iter(|| {
+                let mut annotations = s.annotations.clone();
+                let results = adjust_annotations(&annotations, &s.diffs, &options);
+                apply_adjustments(&mut annotations, &results);
+                annotations
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_adjust);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -6,44 +6,448 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+    /// When to use colors in output and the TUI
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorChoice,
 }
 
 #[derive(Subcommand)]
 pub enum Command {
     /// TUI review mode
     Review {
+        /// Repository, or subtrees of it to restrict the review to (defaults to current directory)
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Only review files matching this glob, e.g. 'crates/foo/**' (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+        /// Only review the paths REVIEWERS.toml assigns to you
+        #[arg(long)]
+        mine: bool,
+        /// Run commands from a script file ("-" for stdin) instead of opening the TUI
+        #[arg(long, value_name = "FILE")]
+        script: Option<PathBuf>,
+        /// Review the files of a commit, read from git objects instead of the working tree
+        #[arg(long, value_name = "COMMIT")]
+        rev: Option<String>,
+    },
+    /// Adjust annotation positions after code changes
+    Adjust(AdjustArgs),
+    /// Export annotations
+    Export {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Export format (defaults to `export.format` in the config, else markdown)
+        #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(crate::export::names()))]
+        format: Option<String>,
+        /// Replace @mentions and email addresses with stable pseudonyms
+        #[arg(long)]
+        redact_authors: bool,
+        /// Rewrite a path prefix, as PREFIX or PREFIX=REPLACEMENT (repeatable)
+        #[arg(long = "redact-paths", value_name = "PREFIX[=REPLACEMENT]")]
+        redact_paths: Vec<String>,
+        /// Only export annotations with this #tag (repeatable; any of them matches)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Show the output through $PAGER (default `less`) when writing to a terminal
+        #[arg(long)]
+        pager: bool,
+    },
+    /// Import annotations from a JSON export, updating the ones already here
+    Import {
+        /// JSON written by `export --format json` ("-" for stdin)
+        file: PathBuf,
         /// Path to repository (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
     },
-    /// Adjust annotation positions after code changes
-    Adjust {
+    /// Remove review data for files that are no longer tracked
+    Gc(GcArgs),
+    /// Rewrite the store files sorted and without leftovers from interrupted writes
+    Compact {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Merge annotations with identical file, range and text
+    Dedupe {
         /// Path to repository (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
-        /// Automatically resolve non-conflicting adjustments
+        /// Report duplicates without writing anything
         #[arg(long)]
-        auto_resolve: bool,
+        dry_run: bool,
     },
-    /// Export annotations
-    Export {
+    /// Fix annotations whose line range runs past the end of their file
+    Repair {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Move annotations to where their original code now appears, when it is unique
+        #[arg(long)]
+        reanchor: bool,
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check the annotation store for corrupt or dangling records
+    Verify {
         /// Path to repository (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
-        /// Export format
-        #[arg(long, default_value = "markdown")]
-        format: ExportFormat,
     },
+    /// Exchange review data with other reviewers through a git ref
+    #[command(subcommand)]
+    Sync(SyncCommand),
+    /// Post annotations as review comments on a code host
+    #[command(subcommand)]
+    Publish(PublishCommand),
+    /// Copy annotations from another named review into this one
+    Copy {
+        /// Review to copy from; its data lives on refs/annotator/reviews/<NAME>
+        #[arg(long, value_name = "NAME")]
+        from_review: String,
+        /// Commit the review's line numbers refer to, if it doesn't record one
+        #[arg(long, value_name = "REV")]
+        base: Option<String>,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Show annotations added, removed or changed since an earlier version
+    Diff {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Compare against the review recorded on refs/annotator/reviews/<NAME> [default: default]
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["rev", "store"])]
+        review: Option<String>,
+        /// Compare against review data recorded by a commit, e.g. refs/annotator/reviews/default~1
+        #[arg(long, value_name = "REV", conflicts_with = "store")]
+        rev: Option<String>,
+        /// Compare against a copy of the store directory
+        #[arg(long, value_name = "DIR")]
+        store: Option<PathBuf>,
+    },
+    /// Replace the review data with a recorded version, e.g. refs/annotator/history~1
+    Restore {
+        /// Commit holding review data, on the history ref or a review ref
+        rev: String,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// List annotations, or with --deleted those archived when their file was removed
+    List {
+        /// List annotations archived because adjust found their file deleted
+        #[arg(long)]
+        deleted: bool,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Find annotations whose text or file path contains every word of a query
+    Search {
+        /// Words to look for, ignoring case
+        query: String,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Add or remove labels on a file, such as needs-second-opinion, or list them
+    Label {
+        /// File to label, relative to the repository root
+        file: String,
+        /// Label to add (repeatable)
+        #[arg(long, value_name = "LABEL")]
+        add: Vec<String>,
+        /// Label to remove (repeatable)
+        #[arg(long, value_name = "LABEL")]
+        remove: Vec<String>,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Bring back an annotation archived when its file was deleted
+    Undelete {
+        /// Id of the archived annotation, or a unique prefix as shown by `list --deleted`
+        id: String,
+        /// File to place it on instead of its original path
+        #[arg(long, value_name = "FILE")]
+        to: Option<String>,
+        /// Line range to place it on instead of its original one, e.g. 12-15
+        #[arg(long, value_name = "RANGE")]
+        lines: Option<String>,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// List annotations that may need re-validation
+    Stale {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Annotations not edited for at least this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<i64>,
+        /// Annotations whose file changed in at least this many commits since
+        #[arg(long, value_name = "N")]
+        changes: Option<usize>,
+    },
+    /// Show time spent reviewing, per file and in total
+    Stats {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Print a file with the annotations covering each line
+    Blame {
+        /// File to show
+        file: PathBuf,
+    },
+    /// Read or change settings
+    #[command(subcommand)]
+    Config(ConfigCommand),
     /// Show review progress
     Status {
+        /// Exit with an error when any `--fail-on` condition holds
+        #[arg(long)]
+        check: bool,
+        /// Condition that fails the check (repeatable; defaults to unreviewed)
+        #[arg(long, value_enum, value_name = "CONDITION", requires = "check")]
+        fail_on: Vec<CheckCondition>,
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+/// What `status --check` treats as an incomplete review.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum CheckCondition {
+    /// Files not yet marked annotated or clean
+    Unreviewed,
+    /// Any annotation left in the store
+    OpenAnnotations,
+    /// Annotations whose lines were partly deleted since the last adjust
+    Conflicts,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to emit colors on a stream. In auto mode colors need a terminal
+    /// and are turned off by `NO_COLOR` or `TERM=dumb`.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+                is_terminal && !no_color && !dumb
+            }
+        }
+    }
+}
+
+/// Wraps `text` in an ANSI SGR sequence such as `"31"` (red) when `enabled`.
+pub fn paint(enabled: bool, sgr: &str, text: &str) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", sgr, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// SGR color of a severity label: blockers bold red down to dim info.
+pub fn severity_sgr(severity: crate::core::annotation::Severity) -> &'static str {
+    use crate::core::annotation::Severity;
+    match severity {
+        Severity::Blocker => "1;31",
+        Severity::Major => "31",
+        Severity::Minor => "33",
+        Severity::Nit => "36",
+        Severity::Info => "2",
+    }
+}
+
+/// Shows `text` through `$PAGER`, or `less` when it is unset, falling back to
+/// printing it when the pager can't be started.
+pub fn page(text: &str) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let pager = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()).unwrap_or_else(|| "less".into());
+    let mut words = pager.split_whitespace();
+    let mut command = std::process::Command::new(words.next().unwrap_or("less"));
+    command.args(words).stdin(std::process::Stdio::piped());
+    // Like git: exit at once when it fits on the screen, and keep the colors
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Could not start pager `{}`: {}", pager, e);
+            println!("{}", text);
+            return Ok(());
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe; that is not an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[derive(Args)]
+pub struct GcArgs {
+    /// Path to repository (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    /// Also delete annotations on untracked files (asks for confirmation)
+    #[arg(long)]
+    pub annotations: bool,
+    /// Delete annotations without asking
+    #[arg(long, short)]
+    pub yes: bool,
+    /// Report what would be removed without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// Record the local review data on the review ref and push it
+    Push(SyncArgs),
+    /// Fetch the review ref and merge it into the local review data
+    Pull(SyncArgs),
+}
+
+#[derive(Args)]
+pub struct SyncArgs {
+    /// Path to repository (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    /// Review name; the data lives on refs/annotator/reviews/<NAME>
+    #[arg(long, default_value = "default")]
+    pub name: String,
+    /// Remote to exchange the review ref with
+    #[arg(long, default_value = "origin")]
+    pub remote: String,
+}
+
+#[derive(Subcommand)]
+pub enum PublishCommand {
+    /// Comment on a GitHub pull request; the token is read from GITHUB_TOKEN or GH_TOKEN
+    Github(GithubArgs),
+}
+
+#[derive(Args)]
+pub struct GithubArgs {
+    /// Pull request number
+    #[arg(long)]
+    pub pr: u64,
+    /// Repository as OWNER/NAME (defaults to the one the remote's URL names)
+    #[arg(long, value_name = "OWNER/NAME")]
+    pub repo: Option<String>,
+    /// Remote naming the repository, and to fetch missing commits from
+    #[arg(long, default_value = "origin")]
+    pub remote: String,
+    /// Show what would be posted without posting anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Path to repository (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective value of a setting such as `ui.theme`
+    Get {
+        key: String,
         /// Path to repository (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
     },
+    /// Set a setting in the repository's config, or the user config with --global
+    Set {
+        key: String,
+        /// TOML value; anything that doesn't parse is taken as a string
+        value: String,
+        /// Write ~/.config/annotator/config.toml instead
+        #[arg(long)]
+        global: bool,
+        /// Path to repository (defaults to current directory)
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+    },
+    /// Check the settings, reporting key bindings that are invalid or clash with built-in ones
+    Check {
+        /// Path to repository (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Args)]
+pub struct AdjustArgs {
+    /// Path to repository (defaults to current directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+    /// Automatically resolve non-conflicting adjustments
+    #[arg(long)]
+    pub auto_resolve: bool,
+    /// Revision to adjust from (defaults to the last recorded adjust commit)
+    #[arg(long)]
+    pub from: Option<String>,
+    /// Revision to adjust to (defaults to HEAD)
+    #[arg(long)]
+    pub to: Option<String>,
+    /// Do not follow annotated blocks that were moved elsewhere in the file
+    #[arg(long)]
+    pub no_follow_moves: bool,
+    /// Ignore whitespace-only changes (re-indentation, trailing spaces)
+    #[arg(long)]
+    pub ignore_whitespace: bool,
+    /// Similarity percentage (0-100) required to detect a rename or copy [default: 50]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(0..=100))]
+    pub rename_threshold: Option<u16>,
+    /// Detect copied files (slower on large diffs)
+    #[arg(long)]
+    pub detect_copies: bool,
+    /// Duplicate annotations into detected copies (implies --detect-copies)
+    #[arg(long)]
+    pub copy_annotations: bool,
+    /// Review each proposed change and accept, skip, or edit it before writing
+    #[arg(long, short)]
+    pub interactive: bool,
+    /// Print a JSON report of renames, copies and per-annotation results instead of text
+    #[arg(long, conflicts_with = "interactive")]
+    pub json: bool,
+    /// Check that adjusted annotations still cover the lines they were written on, writing nothing
+    #[arg(long, conflicts_with_all = ["interactive", "json"])]
+    pub self_check: bool,
 }
 
-#[derive(Clone, clap::ValueEnum)]
-pub enum ExportFormat {
-    Markdown,
-    Json,
+impl AdjustArgs {
+    /// The configured `defaults` with the flags applied on top.
+    pub fn adjust_options(&self, defaults: &crate::git::adjust::AdjustOptions) -> crate::git::adjust::AdjustOptions {
+        let copy_annotations = defaults.copy_annotations || self.copy_annotations;
+        crate::git::adjust::AdjustOptions {
+            follow_moves: defaults.follow_moves && !self.no_follow_moves,
+            detect_copies: defaults.detect_copies || self.detect_copies || copy_annotations,
+            ignore_whitespace: defaults.ignore_whitespace || self.ignore_whitespace,
+            rename_threshold: self.rename_threshold.unwrap_or(defaults.rename_threshold),
+            copy_annotations,
+        }
+    }
 }
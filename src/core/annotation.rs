@@ -1,29 +1,129 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+/// Longest snippet kept per annotation; longer ranges store only their first lines.
+pub const MAX_SNIPPET_LINES: usize = 20;
+
+/// Version of the stored record format written by this build. Records from
+/// newer builds still load; their unknown fields are kept in `extra` and
+/// written back unchanged.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Format of records written before they carried a version.
+pub(crate) fn unversioned_format() -> u32 {
+    1
+}
+
+pub(crate) fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// Takes `key` out of `record` when its value doesn't parse as `T`, such as
+/// an enum variant added by a newer build, so the record still loads and
+/// the value can be kept in `extra` as written.
+fn set_aside_unknown<T: serde::de::DeserializeOwned>(
+    record: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Option<(String, serde_json::Value)> {
+    T::deserialize(record.get(key)?).is_err().then(|| record.remove_entry(key)).flatten()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(remote = "Self")]
 pub struct Annotation {
+    #[serde(default = "unversioned_format")]
+    pub format: u32,
     pub id: Uuid,
     pub file_path: String,
     pub start_line: u32,
     pub end_line: u32,
+    #[serde(default)]
     pub text: String,
+    /// A severity this build doesn't know is `None` here and kept in `extra`
+    /// until the text gets a label of its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// Leading lines of the annotated code when it was created, used to re-anchor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Reviewer who wrote the annotation, from the `[author]` setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Commit the file was at when the annotation was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Blob of the file version the line numbers refer to, so an adjustment
+    /// can diff from it when that version isn't the one at its start commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+    /// Related annotations, such as the same issue in other files. Links are
+    /// kept on both sides.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Uuid>,
+    /// `#tags` in the text, without the `#`, kept in sync by `set_text`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Raised to put the annotation ahead of others in exports; 0 is none.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: u32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// When the text was last written, which staleness counts from; moving,
+    /// adjusting or relinking the annotation leaves it alone. Records from
+    /// before it was kept use `updated_at`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_edited_at: Option<DateTime<Utc>>,
+    /// Fields written by a newer build, kept so rewrites don't drop them.
+    #[serde(flatten, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl Serialize for Annotation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.severity.is_some() && self.extra.contains_key("severity") {
+            let mut known = self.clone();
+            known.extra.remove("severity");
+            return Annotation::serialize(&known, serializer);
+        }
+        Annotation::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Annotation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut record = serde_json::Map::deserialize(deserializer)?;
+        let unknown = set_aside_unknown::<Severity>(&mut record, "severity");
+        let mut annotation =
+            Annotation::deserialize(serde_json::Value::Object(record)).map_err(serde::de::Error::custom)?;
+        annotation.extra.extend(unknown);
+        Ok(annotation)
+    }
 }
 
 impl Annotation {
     pub fn new(file_path: String, start_line: u32, end_line: u32, text: String) -> Self {
         let now = Utc::now();
         Self {
+            format: FORMAT_VERSION,
             id: Uuid::new_v4(),
             file_path,
             start_line,
             end_line,
+            severity: Severity::from_text(&text),
+            snippet: None,
+            author: None,
+            commit: None,
+            blob: None,
+            links: Vec::new(),
+            priority: 0,
+            tags: parse_tags(&text),
             text,
             created_at: now,
             updated_at: now,
+            text_edited_at: Some(now),
+            extra: BTreeMap::new(),
         }
     }
 
@@ -38,26 +138,279 @@ impl Annotation {
     pub fn overlaps(&self, start: u32, end: u32) -> bool {
         self.start_line <= end && start <= self.end_line
     }
+
+    /// Records the annotated lines from `content` (the whole file) as the snippet.
+    pub fn capture_snippet(&mut self, content: &[String]) {
+        let start = self.start_line.saturating_sub(1) as usize;
+        let end = (self.end_line as usize).min(start + MAX_SNIPPET_LINES).min(content.len());
+        self.snippet = (start < end).then(|| content[start..end].join("\n"));
+    }
+
+    /// Ends this annotation above `line` and returns the rest of its range,
+    /// from `line` on, as a new annotation with the same text, author,
+    /// commit and blob. `content` is the whole file, for the snippets;
+    /// `line` must be below the first line and within the range.
+    pub fn split_at(&mut self, line: u32, content: &[String]) -> Annotation {
+        let mut rest = Annotation::new(self.file_path.clone(), line, self.end_line, self.text.clone());
+        rest.author = self.author.clone();
+        rest.commit = self.commit.clone();
+        rest.blob = self.blob.clone();
+        rest.created_at = self.created_at;
+        rest.capture_snippet(content);
+        self.end_line = line - 1;
+        self.updated_at = Utc::now();
+        self.capture_snippet(content);
+        rest
+    }
+
+    /// Whether `next` can be joined onto this annotation: it starts within
+    /// this one's range or right below it.
+    pub fn joins(&self, next: &Annotation) -> bool {
+        next.id != self.id && next.start_line >= self.start_line && next.start_line <= self.end_line + 1
+    }
+
+    /// Extends this annotation over `next`'s lines and appends its text below
+    /// this one's. `content` is the whole file, for the snippet.
+    pub fn join(&mut self, next: &Annotation, content: &[String]) {
+        self.end_line = self.end_line.max(next.end_line);
+        self.set_text(format!("{}\n{}", self.text, next.text));
+        self.capture_snippet(content);
+    }
+
+    /// The range with its start and end moved by the given deltas, or `None`
+    /// when that leaves a file of `total_lines` lines or the range would
+    /// end above its start.
+    pub fn moved_range(&self, start_delta: i32, end_delta: i32, total_lines: u32) -> Option<(u32, u32)> {
+        let start = self.start_line as i64 + start_delta as i64;
+        let end = self.end_line as i64 + end_delta as i64;
+        (start >= 1 && end <= total_lines as i64 && start <= end).then_some((start as u32, end as u32))
+    }
+
+    /// A new annotation on lines `start`-`end` of `file` with this one's text
+    /// and severity.
+    pub fn copy_to(&self, file: &str, start: u32, end: u32) -> Annotation {
+        let mut copy = Annotation::new(file.to_string(), start, end, self.text.clone());
+        copy.severity = self.severity;
+        copy
+    }
+
+    /// Moves the annotation to lines `start`-`end` of `file`, whose whole
+    /// content is `content`, keeping its text.
+    pub fn anchor_to(&mut self, file: &str, start: u32, end: u32, content: &[String]) {
+        self.file_path = file.to_string();
+        self.start_line = start;
+        self.end_line = end;
+        self.updated_at = Utc::now();
+        self.capture_snippet(content);
+    }
+
+    /// Replaces the text, re-deriving the severity from its label.
+    pub fn set_text(&mut self, text: String) {
+        self.severity = Severity::from_text(&text);
+        self.extra.remove("severity");
+        self.tags = parse_tags(&text);
+        self.text = text;
+        self.updated_at = Utc::now();
+        self.text_edited_at = Some(self.updated_at);
+    }
+
+    /// When the text was last written; see `text_edited_at`.
+    pub fn last_edited(&self) -> DateTime<Utc> {
+        self.text_edited_at.unwrap_or(self.updated_at)
+    }
+
+    /// First `#tag` mentioned in the text, without the `#`.
+    pub fn first_tag(&self) -> Option<&str> {
+        tags_in(&self.text).next()
+    }
+
+    /// Whether the annotation is tagged `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim_start_matches('#')))
+    }
+}
+
+/// Every distinct `#tag` in `text`, without the `#`, in order of appearance.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in tags_in(text) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Indices of the annotations linked to `start`, directly or through others,
+/// including `start` itself. Links to deleted annotations are skipped.
+pub fn linked_group(annotations: &[Annotation], start: usize) -> Vec<usize> {
+    let mut group = vec![start];
+    let mut next = 0;
+    while let Some(&i) = group.get(next) {
+        for id in &annotations[i].links {
+            if let Some(j) = annotations.iter().position(|a| a.id == *id)
+                && !group.contains(&j)
+            {
+                group.push(j);
+            }
+        }
+        next += 1;
+    }
+    group
+}
+
+/// Tag to filter by after `current`: the tags used in `annotations`,
+/// lowercased and in order, then `None` for no filter.
+pub fn next_tag(annotations: &[Annotation], current: Option<&str>) -> Option<String> {
+    let mut tags: Vec<String> = annotations.iter().flat_map(|a| &a.tags).map(|t| t.to_lowercase()).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    match current {
+        None => tags.into_iter().next(),
+        Some(current) => tags.into_iter().skip_while(|t| t != current).nth(1),
+    }
+}
+
+fn tags_in(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace().filter_map(|word| {
+        let tag = word.strip_prefix('#')?;
+        let tag = tag.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_');
+        (!tag.is_empty()).then_some(tag)
+    })
+}
+
+/// How serious a finding is, from most to least severe.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Blocker,
+    Major,
+    Minor,
+    Nit,
+    Info,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+impl Severity {
+    pub const ALL: [Severity; 5] = [
+        Severity::Blocker,
+        Severity::Major,
+        Severity::Minor,
+        Severity::Nit,
+        Severity::Info,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Blocker => "blocker",
+            Severity::Major => "major",
+            Severity::Minor => "minor",
+            Severity::Nit => "nit",
+            Severity::Info => "info",
+        }
+    }
+
+    /// Reads a leading label such as `blocker:` or `Nit:` from annotation text.
+    pub fn from_text(text: &str) -> Option<Self> {
+        let (label, _) = text.trim_start().split_once(':')?;
+        let label = label.trim().to_ascii_lowercase();
+        Self::ALL.into_iter().find(|s| s.as_str() == label)
+    }
+}
+
+/// Parses a 1-based line range written as `12-15` or `12`.
+pub fn parse_line_range(input: &str) -> Option<(u32, u32)> {
+    let input = input.trim();
+    let (start, end) = match input.split_once('-') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let line = input.parse().ok()?;
+            (line, line)
+        }
+    };
+    (start >= 1 && start <= end).then_some((start, end))
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum FileStatus {
+    #[default]
     Unreviewed,
     Annotated,
     Clean,
 }
 
-impl Default for FileStatus {
-    fn default() -> Self {
-        Self::Unreviewed
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(remote = "Self")]
 pub struct FileReviewState {
+    #[serde(default = "unversioned_format")]
+    pub format: u32,
     pub file_path: String,
+    /// A status this build doesn't know reads as unreviewed and is kept in
+    /// `extra` until `set_status` replaces it.
+    #[serde(default)]
     pub status: FileStatus,
+    /// Checklist items ticked off for this file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checked: Vec<String>,
+    /// Free-form labels such as `security-sensitive`, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(flatten, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl FileReviewState {
+    pub fn new(file_path: String, status: FileStatus) -> Self {
+        Self {
+            format: FORMAT_VERSION,
+            file_path,
+            status,
+            checked: Vec::new(),
+            labels: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the status, dropping one kept from a newer build.
+    pub fn set_status(&mut self, status: FileStatus) {
+        self.status = status;
+        self.extra.remove("status");
+    }
+}
+
+// Written by hand so a kept unknown status goes out in place of `status`
+impl Serialize for FileReviewState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("format", &self.format)?;
+        map.serialize_entry("file_path", &self.file_path)?;
+        match self.extra.get("status") {
+            Some(kept) => map.serialize_entry("status", kept)?,
+            None => map.serialize_entry("status", &self.status)?,
+        }
+        if !self.checked.is_empty() {
+            map.serialize_entry("checked", &self.checked)?;
+        }
+        if !self.labels.is_empty() {
+            map.serialize_entry("labels", &self.labels)?;
+        }
+        for (key, value) in self.extra.iter().filter(|(key, _)| *key != "status") {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for FileReviewState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut record = serde_json::Map::deserialize(deserializer)?;
+        let unknown = set_aside_unknown::<FileStatus>(&mut record, "status");
+        let mut state =
+            FileReviewState::deserialize(serde_json::Value::Object(record)).map_err(serde::de::Error::custom)?;
+        state.extra.extend(unknown);
+        Ok(state)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -71,6 +424,12 @@ pub enum AdjustResult {
     Conflict {
         deleted_lines: Vec<u32>,
     },
+    /// The annotated block was deleted here but re-added verbatim elsewhere.
+    Moved {
+        new_path: String,
+        new_start: u32,
+        new_end: u32,
+    },
     Deleted,
     Unchanged,
 }
@@ -111,6 +470,160 @@ mod tests {
         assert!(a.overlaps(3, 20));
     }
 
+    #[test]
+    fn test_severity_from_text() {
+        assert_eq!(Severity::from_text("blocker: leaks fd"), Some(Severity::Blocker));
+        assert_eq!(Severity::from_text("  Nit: naming"), Some(Severity::Nit));
+        assert_eq!(Severity::from_text("note: see above"), None);
+        assert_eq!(Severity::from_text("no label here"), None);
+
+        let mut a = Annotation::new("f.rs".into(), 1, 1, "major: wrong bound".into());
+        assert_eq!(a.severity, Some(Severity::Major));
+        a.set_text("fine after all".into());
+        assert_eq!(a.severity, None);
+    }
+
+    #[test]
+    fn test_capture_snippet() {
+        let content: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+        let mut a = Annotation::new("f.rs".into(), 2, 3, "t".into());
+        a.capture_snippet(&content);
+        assert_eq!(a.snippet.as_deref(), Some("line 2\nline 3"));
+
+        let mut long = Annotation::new("f.rs".into(), 1, 30, "t".into());
+        long.capture_snippet(&content);
+        assert_eq!(long.snippet.unwrap().lines().count(), MAX_SNIPPET_LINES);
+
+        let mut past_end = Annotation::new("f.rs".into(), 40, 41, "t".into());
+        past_end.capture_snippet(&content);
+        assert_eq!(past_end.snippet, None);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let content: Vec<String> = ["one", "two", "three", "four"].map(String::from).to_vec();
+        let mut first = Annotation::new("f.rs".into(), 1, 4, "nit: two issues".into());
+        first.author = Some("Ann".into());
+        let rest = first.split_at(3, &content);
+        assert_eq!((first.start_line, first.end_line, first.snippet.as_deref()), (1, 2, Some("one\ntwo")));
+        assert_eq!((rest.start_line, rest.end_line, rest.snippet.as_deref()), (3, 4, Some("three\nfour")));
+        assert_ne!(rest.id, first.id);
+        assert_eq!((rest.text.as_str(), rest.severity), ("nit: two issues", Some(Severity::Nit)));
+        assert_eq!((rest.author.as_deref(), rest.created_at), (Some("Ann"), first.created_at));
+    }
+
+    #[test]
+    fn test_join() {
+        let content: Vec<String> = ["one", "two", "three", "four", "five"].map(String::from).to_vec();
+        let mut first = Annotation::new("f.rs".into(), 1, 2, "second".into());
+        let adjacent = Annotation::new("f.rs".into(), 3, 4, "nit: first".into());
+        let overlapping = Annotation::new("f.rs".into(), 2, 2, "x".into());
+        let gap = Annotation::new("f.rs".into(), 4, 5, "y".into());
+        assert!(first.joins(&adjacent) && first.joins(&overlapping));
+        assert!(!first.joins(&gap) && !first.joins(&first.clone()) && !adjacent.joins(&first));
+
+        first.join(&adjacent, &content);
+        assert_eq!((first.start_line, first.end_line), (1, 4));
+        assert_eq!((first.text.as_str(), first.severity), ("second\nnit: first", None));
+        assert_eq!(first.snippet.as_deref(), Some("one\ntwo\nthree\nfour"));
+    }
+
+    #[test]
+    fn test_moved_range() {
+        let a = Annotation::new("f.rs".into(), 2, 3, "t".into());
+        assert_eq!(a.moved_range(1, 1, 4), Some((3, 4)));
+        assert_eq!(a.moved_range(-1, 0, 4), Some((1, 3)));
+        assert_eq!(a.moved_range(1, 0, 4), Some((3, 3)));
+        // Past either end of the file, or collapsed
+        assert_eq!(a.moved_range(2, 2, 4), None);
+        assert_eq!(a.moved_range(-2, 0, 4), None);
+        assert_eq!(a.moved_range(0, -2, 4), None);
+    }
+
+    #[test]
+    fn test_copy_to() {
+        let mut a = Annotation::new("a.rs".into(), 2, 2, "major: unchecked #errors".into());
+        a.priority = 2;
+        let copy = a.copy_to("b.rs", 1, 2);
+        assert_ne!(copy.id, a.id);
+        assert_eq!((copy.file_path.as_str(), copy.start_line, copy.end_line), ("b.rs", 1, 2));
+        assert_eq!((copy.text.as_str(), copy.severity), ("major: unchecked #errors", Some(Severity::Major)));
+        assert_eq!((copy.tags.clone(), copy.priority), (vec!["errors".to_string()], 0));
+    }
+
+    #[test]
+    fn test_anchor_to() {
+        let mut a = Annotation::new("a.rs".into(), 1, 3, "wrong lines".into());
+        a.snippet = Some("old".into());
+        let edited = a.last_edited();
+        a.anchor_to("b.rs", 2, 3, &["alpha".into(), "beta".into(), "gamma".into()]);
+        assert_eq!((a.file_path.as_str(), a.start_line, a.end_line), ("b.rs", 2, 3));
+        assert_eq!(a.snippet.as_deref(), Some("beta\ngamma"));
+        assert_eq!((a.text.as_str(), a.last_edited()), ("wrong lines", edited));
+    }
+
+    #[test]
+    fn test_first_tag() {
+        let a = Annotation::new("f.rs".into(), 1, 1, "unchecked input #security, #perf".into());
+        assert_eq!(a.first_tag(), Some("security"));
+        let b = Annotation::new("f.rs".into(), 1, 1, "issue # 12".into());
+        assert_eq!(b.first_tag(), None);
+    }
+
+    #[test]
+    fn test_tags_follow_text() {
+        let mut a = Annotation::new("f.rs".into(), 1, 1, "#todo check #Security, then #todo".into());
+        assert_eq!(a.tags, vec!["todo", "Security"]);
+        assert!(a.has_tag("#security"));
+        a.set_text("fixed".into());
+        assert!(a.tags.is_empty());
+        let json = serde_json::to_string(&a).unwrap();
+        assert!(!json.contains("tags"));
+    }
+
+    #[test]
+    fn test_linked_group() {
+        let mut annotations: Vec<Annotation> =
+            (1..=4).map(|n| Annotation::new("f.rs".into(), n, n, format!("note {}", n))).collect();
+        annotations[0].links = vec![annotations[1].id];
+        annotations[1].links = vec![annotations[0].id, annotations[2].id, Uuid::new_v4()];
+        annotations[2].links = vec![annotations[1].id];
+        // Reached through the second annotation, and each only once
+        assert_eq!(linked_group(&annotations, 0), vec![0, 1, 2]);
+        assert_eq!(linked_group(&annotations, 2), vec![2, 1, 0]);
+        assert_eq!(linked_group(&annotations, 3), vec![3]);
+    }
+
+    #[test]
+    fn test_next_tag() {
+        let annotations = vec![
+            Annotation::new("f.rs".into(), 1, 1, "#todo rename".into()),
+            Annotation::new("f.rs".into(), 2, 2, "check #Security #todo".into()),
+            Annotation::new("f.rs".into(), 3, 3, "untagged".into()),
+        ];
+        assert_eq!(next_tag(&annotations, None).as_deref(), Some("security"));
+        assert_eq!(next_tag(&annotations, Some("security")).as_deref(), Some("todo"));
+        assert_eq!(next_tag(&annotations, Some("todo")), None);
+        assert_eq!(next_tag(&annotations[2..], None), None);
+    }
+
+    #[test]
+    fn test_annotation_without_severity_deserializes() {
+        let json = r#"{"id":"00000000-0000-0000-0000-000000000000","file_path":"f.rs","start_line":1,"end_line":1,"text":"t","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}"#;
+        let a: Annotation = serde_json::from_str(json).unwrap();
+        assert_eq!(a.severity, None);
+    }
+
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(parse_line_range("12-15"), Some((12, 15)));
+        assert_eq!(parse_line_range(" 7 "), Some((7, 7)));
+        assert_eq!(parse_line_range("3 - 4"), Some((3, 4)));
+        assert_eq!(parse_line_range("15-12"), None);
+        assert_eq!(parse_line_range("0"), None);
+        assert_eq!(parse_line_range("abc"), None);
+    }
+
     #[test]
     fn test_file_status_default() {
         assert_eq!(FileStatus::default(), FileStatus::Unreviewed);
@@ -126,12 +639,34 @@ mod tests {
 
     #[test]
     fn test_file_review_state_serialization() {
-        let s = FileReviewState {
-            file_path: "src/lib.rs".into(),
-            status: FileStatus::Clean,
-        };
+        let s = FileReviewState::new("src/lib.rs".into(), FileStatus::Clean);
         let json = serde_json::to_string(&s).unwrap();
         let s2: FileReviewState = serde_json::from_str(&json).unwrap();
         assert_eq!(s, s2);
     }
+
+    #[test]
+    fn test_records_from_newer_format_keep_unknown_fields() {
+        let json = r#"{"format":2,"id":"00000000-0000-0000-0000-000000000000","file_path":"f.rs","start_line":1,"end_line":1,"text":"t","severity":"critical","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","labels":["x"]}"#;
+        let mut a: Annotation = serde_json::from_str(json).unwrap();
+        assert_eq!(a.format, 2);
+        assert_eq!(a.severity, None);
+        let out: serde_json::Value = serde_json::to_value(&a).unwrap();
+        assert_eq!(out["labels"], serde_json::json!(["x"]));
+        assert_eq!(out["format"], 2);
+        assert_eq!(out["severity"], "critical");
+        a.set_text("nit: relabeled".into());
+        assert_eq!(serde_json::to_value(&a).unwrap()["severity"], "nit");
+
+        let mut s: FileReviewState =
+            serde_json::from_str(r#"{"file_path":"f.rs","status":"skipped","reviewer":"ana"}"#).unwrap();
+        assert_eq!(s.format, unversioned_format());
+        assert_eq!(s.status, FileStatus::Unreviewed);
+        let json = serde_json::to_string(&s).unwrap();
+        assert!(json.contains(r#""reviewer":"ana""#));
+        assert_eq!(json.matches(r#""status":"skipped""#).count(), 1);
+        assert_eq!(serde_json::from_str::<FileReviewState>(&json).unwrap(), s);
+        s.set_status(FileStatus::Clean);
+        assert!(serde_json::to_string(&s).unwrap().contains(r#""status":"clean""#));
+    }
 }
@@ -0,0 +1,93 @@
+//! Differences between two versions of the annotation store, for `annotator diff`.
+
+use crate::core::annotation::Annotation;
+use std::collections::HashMap;
+
+/// Annotations matched by id between an old and a new version.
+#[derive(Debug, Default)]
+pub struct AnnotationChanges<'a> {
+    pub added: Vec<&'a Annotation>,
+    pub removed: Vec<&'a Annotation>,
+    /// Old and new version of annotations whose location or text changed.
+    pub modified: Vec<(&'a Annotation, &'a Annotation)>,
+}
+
+impl AnnotationChanges<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Compares by id. Timestamps and snippets are ignored, so an adjustment that
+/// leaves an annotation in place doesn't count as a change. Each list is
+/// sorted by location.
+pub fn compare<'a>(old: &'a [Annotation], new: &'a [Annotation]) -> AnnotationChanges<'a> {
+    let old_by_id: HashMap<_, _> = old.iter().map(|a| (a.id, a)).collect();
+    let new_by_id: HashMap<_, _> = new.iter().map(|a| (a.id, a)).collect();
+
+    let mut changes = AnnotationChanges::default();
+    for a in new {
+        match old_by_id.get(&a.id) {
+            None => changes.added.push(a),
+            Some(before) if !changed_fields(before, a).is_empty() => changes.modified.push((before, a)),
+            Some(_) => {}
+        }
+    }
+    changes.removed = old.iter().filter(|a| !new_by_id.contains_key(&a.id)).collect();
+
+    let location = |a: &Annotation| (a.file_path.clone(), a.start_line, a.end_line);
+    changes.added.sort_by_key(|a| location(a));
+    changes.removed.sort_by_key(|a| location(a));
+    changes.modified.sort_by_key(|(_, a)| location(a));
+    changes
+}
+
+/// Names of what differs between two versions of an annotation.
+pub fn changed_fields(old: &Annotation, new: &Annotation) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if old.file_path != new.file_path {
+        fields.push("file");
+    }
+    if (old.start_line, old.end_line) != (new.start_line, new.end_line) {
+        fields.push("lines");
+    }
+    if old.text != new.text {
+        fields.push("text");
+    }
+    if old.links != new.links {
+        fields.push("links");
+    }
+    if old.priority != new.priority {
+        fields.push("priority");
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let kept = Annotation::new("a.rs".into(), 1, 1, "same".into());
+        let removed = Annotation::new("a.rs".into(), 2, 2, "gone".into());
+        let edited = Annotation::new("b.rs".into(), 3, 3, "before".into());
+        let old = vec![kept.clone(), removed.clone(), edited.clone()];
+
+        let mut touched = kept.clone();
+        touched.updated_at += chrono::Duration::seconds(5);
+        let mut after = edited.clone();
+        after.start_line = 4;
+        after.end_line = 4;
+        after.set_text("after".into());
+        let added = Annotation::new("c.rs".into(), 1, 1, "new".into());
+        let new = vec![added.clone(), after.clone(), touched];
+
+        let changes = compare(&old, &new);
+        assert_eq!(changes.added, vec![&added]);
+        assert_eq!(changes.removed, vec![&removed]);
+        assert_eq!(changes.modified, vec![(&edited, &after)]);
+        assert_eq!(changed_fields(&edited, &after), vec!["lines", "text"]);
+        assert!(compare(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::core::annotation::FileReviewState;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Committed at the repository root, listing what to check in every file:
+///
+/// ```toml
+/// items = ["Error handling", "Input validation"]
+/// ```
+pub const FILE_NAME: &str = "CHECKLIST.toml";
+
+/// The team's review checklist.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Checklist {
+    pub items: Vec<String>,
+}
+
+impl Checklist {
+    /// Reads `CHECKLIST.toml` from `repo_root`; `None` when there is none.
+    pub fn load(repo_root: &Path) -> Result<Option<Self>> {
+        let path = repo_root.join(FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+            .map(Some)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let checklist: Self = toml::from_str(content)?;
+        let mut seen = std::collections::HashSet::new();
+        if let Some(item) = checklist.items.iter().find(|item| !seen.insert(item.as_str())) {
+            bail!("'{}' is listed twice", item);
+        }
+        Ok(checklist)
+    }
+
+    /// `checked` with `item` ticked or, if it was, unticked, in checklist
+    /// order after any items no longer on the list.
+    pub fn toggle(&self, checked: &[String], item: &str) -> Vec<String> {
+        let mut checked = checked.to_vec();
+        match checked.iter().position(|c| c == item) {
+            Some(pos) => {
+                checked.remove(pos);
+            }
+            None => checked.push(item.to_string()),
+        }
+        checked.sort_by_key(|c| self.items.iter().position(|i| i == c));
+        checked
+    }
+
+    /// Items ticked off per file, leaving out items no longer on the list
+    /// and files with none ticked.
+    pub fn completion(&self, statuses: &[FileReviewState]) -> Completion {
+        let files = statuses
+            .iter()
+            .map(|s| {
+                let checked: Vec<String> = self.items.iter().filter(|item| s.checked.contains(item)).cloned().collect();
+                (s.file_path.clone(), checked)
+            })
+            .filter(|(_, checked)| !checked.is_empty())
+            .collect();
+        Completion { items: self.items.clone(), files }
+    }
+}
+
+/// Checklist progress as exported next to the annotations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Completion {
+    pub items: Vec<String>,
+    /// Ticked items by file, in checklist order.
+    pub files: BTreeMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::annotation::FileStatus;
+
+    #[test]
+    fn test_completion_keeps_listed_items() {
+        let checklist = Checklist::parse(r#"items = ["Errors", "Input"]"#).unwrap();
+        let mut a = FileReviewState::new("a.rs".into(), FileStatus::Clean);
+        a.checked = vec!["Input".into(), "Removed".into(), "Errors".into()];
+        let mut b = FileReviewState::new("b.rs".into(), FileStatus::Clean);
+        b.checked = vec!["Removed".into()];
+        let completion = checklist.completion(&[a, b]);
+        assert_eq!(completion.files.len(), 1);
+        assert_eq!(completion.files["a.rs"], vec!["Errors", "Input"]);
+        assert!(Checklist::parse(r#"items = ["x", "x"]"#).is_err());
+        assert!(Checklist::parse(r#"item = ["x"]"#).is_err());
+    }
+
+    #[test]
+    fn test_toggle_keeps_checklist_order() {
+        let checklist = Checklist::parse(r#"items = ["Errors", "Input", "Tests"]"#).unwrap();
+        let checked = checklist.toggle(&[], "Tests");
+        let checked = checklist.toggle(&checked, "Errors");
+        assert_eq!(checked, vec!["Errors", "Tests"]);
+        assert_eq!(checklist.toggle(&checked, "Errors"), vec!["Tests"]);
+        assert_eq!(checklist.toggle(&["Tests".into(), "Removed".into()], "Input"), vec!["Removed", "Input", "Tests"]);
+    }
+}
@@ -0,0 +1,648 @@
+//! Settings from `~/.config/annotator/config.toml`, overridden key by key by
+//! the store's `config.toml`.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    pub ui: UiConfig,
+    pub store: StoreConfig,
+    pub review: ReviewConfig,
+    pub export: ExportConfig,
+    pub author: AuthorConfig,
+    pub stale: StaleConfig,
+    /// Defaults for `adjust` and for adjusting from the TUI; flags still win.
+    pub adjust: AdjustConfig,
+    /// Read-only annotation sources shown alongside the local ones.
+    #[serde(rename = "overlay", skip_serializing_if = "Vec::is_empty")]
+    pub overlays: Vec<OverlayConfig>,
+}
+
+/// Another review's annotations to overlay in the viewer, from either an
+/// exported JSON file or a synced review ref.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct OverlayConfig {
+    /// Shown in front of each of its notes.
+    pub name: String,
+    /// An `annotator export --format json` file, relative to the repository.
+    pub file: Option<PathBuf>,
+    /// A review ref such as `refs/annotator/remotes/origin/alice`.
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    /// Accent as `#rrggbb`.
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ReviewConfig {
+    /// Include files of initialized submodules, adjusted against their own history.
+    pub submodules: bool,
+    /// Globs the review is restricted to when `review` is given no paths or
+    /// `--include`, such as `src/**`.
+    pub include: Vec<String>,
+    /// Globs of files left out of the review, such as `*.lock` or `vendor/**`.
+    pub ignore: Vec<String>,
+    /// Record time spent per file in the TUI.
+    pub track_time: bool,
+    /// Seconds without input after which time stops counting.
+    pub idle_timeout: u64,
+    /// Record the review data on `refs/annotator/history` after every session
+    /// and every command that changes it.
+    pub history: bool,
+    /// Revision changed hunks are found against. Defaults to HEAD, or to the
+    /// parent of the reviewed commit with `--rev`.
+    pub base: Option<String>,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        Self {
+            submodules: false,
+            include: Vec::new(),
+            ignore: Vec::new(),
+            track_time: true,
+            idle_timeout: 120,
+            history: false,
+            base: None,
+        }
+    }
+}
+
+impl ReviewConfig {
+    /// Drops files matching an `ignore` glob.
+    pub fn filter_ignored(&self, files: Vec<String>) -> Result<Vec<String>> {
+        let patterns = self
+            .ignore
+            .iter()
+            .map(|p| glob::Pattern::new(p).with_context(|| format!("invalid review.ignore pattern '{}'", p)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(files
+            .into_iter()
+            .filter(|f| !patterns.iter().any(|p| p.matches(f)))
+            .collect())
+    }
+}
+
+/// `[adjust]`: how annotations follow changes, the defaults of the
+/// `adjust` flags.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AdjustConfig {
+    /// Follow annotated blocks that were deleted and re-added verbatim.
+    pub follow_moves: bool,
+    /// Detect copies, and look for moved blocks in other files as well.
+    pub detect_copies: bool,
+    /// Treat lines differing only in whitespace as unchanged.
+    pub ignore_whitespace: bool,
+    /// Similarity percentage (0-100) for a delete/add pair to count as a rename or copy.
+    pub rename_threshold: u16,
+    /// Duplicate annotations from a copy's source into the copied file.
+    pub copy_annotations: bool,
+}
+
+impl Default for AdjustConfig {
+    fn default() -> Self {
+        Self {
+            follow_moves: true,
+            detect_copies: false,
+            ignore_whitespace: false,
+            rename_threshold: 50,
+            copy_annotations: false,
+        }
+    }
+}
+
+/// Default limits for `annotator stale`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StaleConfig {
+    /// Days since an annotation was last edited.
+    pub max_age_days: i64,
+    /// Commits that changed the annotated file since the last edit.
+    pub max_changes: usize,
+}
+
+impl Default for StaleConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_changes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Format used when `export` is run without `--format`, one of
+    /// `crate::export::EXPORTERS`.
+    #[serde(deserialize_with = "export_format")]
+    pub format: String,
+}
+
+fn export_format<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<String, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    crate::export::find(&name).map_err(serde::de::Error::custom)?;
+    Ok(name)
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            format: crate::export::DEFAULT_FORMAT.to_string(),
+        }
+    }
+}
+
+/// Who new annotations are attributed to.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct AuthorConfig {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl AuthorConfig {
+    /// `Name <email>`, or whichever part is set.
+    pub fn identity(&self) -> Option<String> {
+        match (&self.name, &self.email) {
+            (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+            (Some(name), None) => Some(name.clone()),
+            (None, Some(email)) => Some(email.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct UiConfig {
+    pub annotation_layout: AnnotationLayout,
+    /// Initial height of the bottom annotation panel, including its border.
+    pub panel_height: u16,
+    /// ASCII-only borders and icons with high-contrast hint text.
+    pub accessible: bool,
+    /// Syntax highlighting theme, one of syntect's bundled themes.
+    pub theme: String,
+    pub keymap: KeymapProfile,
+    pub gutter: GutterConfig,
+    pub inline: InlineConfig,
+    /// Syntax per file path, overriding detection by extension, e.g.
+    /// `"src/io.h" = "C++"`.
+    pub syntax: BTreeMap<String, String>,
+    pub progress: ProgressWeight,
+    /// Where the cursor goes after a new annotation is confirmed.
+    pub after_annotate: AfterAnnotate,
+    /// Let the cursor move past the end of lines instead of stopping after
+    /// their last character.
+    pub virtual_edit: bool,
+    /// Annotations made with a single function key on the cursor line or
+    /// selection, such as `F1 = "nit: naming"`. Keys are F1 to F12.
+    #[serde(deserialize_with = "verdict_keys")]
+    pub verdicts: BTreeMap<String, String>,
+    /// Viewer key chords bound to actions, over the built-in bindings, such
+    /// as `"ctrl+k" = "delete_annotation"`; `"none"` unbinds a chord. The
+    /// annotation input, popups and lists keep their built-in keys.
+    /// `annotator config check` validates them.
+    pub keys: BTreeMap<String, String>,
+    /// `keys` of each config file read, the user's first, so a repository
+    /// binding replaces a user one for the same key written differently.
+    #[serde(skip)]
+    pub key_layers: Vec<BTreeMap<String, String>>,
+}
+
+impl UiConfig {
+    /// Key bindings in the order they apply, later ones replacing earlier
+    /// ones: those of each config file, or `keys` when not read from files.
+    pub fn key_layers(&self) -> Vec<&BTreeMap<String, String>> {
+        if self.key_layers.is_empty() {
+            vec![&self.keys]
+        } else {
+            self.key_layers.iter().collect()
+        }
+    }
+
+    /// Text of the annotation function key `n` makes, if any.
+    pub fn verdict(&self, n: u8) -> Option<&str> {
+        self.verdicts.get(&format!("F{}", n)).map(|s| s.as_str())
+    }
+}
+
+/// Number of a function key named like `F5`, in any case.
+pub fn function_key(name: &str) -> Option<u8> {
+    let n: u8 = name.strip_prefix(['F', 'f'])?.parse().ok()?;
+    (1..=12).contains(&n).then_some(n)
+}
+
+fn verdict_keys<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<BTreeMap<String, String>, D::Error> {
+    BTreeMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, text)| match function_key(&key) {
+            Some(n) if !text.trim().is_empty() => Ok((format!("F{}", n), text)),
+            Some(_) => Err(serde::de::Error::custom(format!("verdict {} has no text", key))),
+            None => Err(serde::de::Error::custom(format!("'{}' is not a function key F1 to F12", key))),
+        })
+        .collect()
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            annotation_layout: AnnotationLayout::default(),
+            panel_height: 6,
+            accessible: false,
+            theme: "base16-ocean.dark".into(),
+            keymap: KeymapProfile::default(),
+            gutter: GutterConfig::default(),
+            inline: InlineConfig::default(),
+            syntax: BTreeMap::new(),
+            progress: ProgressWeight::default(),
+            after_annotate: AfterAnnotate::default(),
+            virtual_edit: false,
+            verdicts: BTreeMap::new(),
+            keys: BTreeMap::new(),
+            key_layers: Vec::new(),
+        }
+    }
+}
+
+/// Cursor movement after annotating, for quick passes over many findings.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AfterAnnotate {
+    /// Keep the cursor where it is.
+    #[default]
+    Stay,
+    /// The line after the annotated range.
+    NextLine,
+    /// The start of the next changed hunk, compared to `review.base`.
+    NextHunk,
+}
+
+/// What the status bar progress bar counts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressWeight {
+    /// Every file counts the same.
+    #[default]
+    Files,
+    /// Files count by their number of lines.
+    Lines,
+}
+
+/// Line numbers and annotation markers left of the code.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GutterConfig {
+    pub line_numbers: bool,
+    /// Minimum digits reserved for line numbers; longer files widen the gutter.
+    pub width: u16,
+    /// Shown next to annotated lines; empty for none.
+    pub marker: String,
+    /// Marker per tag, used instead of `marker` when a line's annotation has
+    /// that tag first, e.g. `security = "S"`.
+    pub tags: BTreeMap<String, String>,
+    /// No gutter at all, leaving the whole width to the code.
+    pub hidden: bool,
+    /// Hide the gutter while the terminal is narrower than this many columns.
+    pub hide_below: u16,
+}
+
+impl Default for GutterConfig {
+    fn default() -> Self {
+        Self {
+            line_numbers: true,
+            width: 4,
+            marker: ">".into(),
+            tags: BTreeMap::new(),
+            hidden: false,
+            hide_below: 0,
+        }
+    }
+}
+
+/// Annotation text shown after the last line of its range.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct InlineConfig {
+    /// Written before the note, e.g. `" # "` for shell-like languages.
+    pub prefix: String,
+    /// Blank columns between the end of the code and the prefix.
+    pub gap: u16,
+    /// Longest note shown inline, in characters; 0 for as much as fits.
+    pub max_length: usize,
+}
+
+impl Default for InlineConfig {
+    fn default() -> Self {
+        Self {
+            prefix: " // ".into(),
+            gap: 2,
+            max_length: 0,
+        }
+    }
+}
+
+/// Key bindings of the file viewer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeymapProfile {
+    /// Arrow keys and Ctrl shortcuts only.
+    #[default]
+    Default,
+    /// Also `h`/`j`/`k`/`l` to move, `H`/`J`/`K`/`L` to select, `^`/`$` for line start/end.
+    Vim,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct StoreConfig {
+    pub location: StoreLocation,
+    /// Base directory for `data_dir` stores, instead of `~/.local/share/annotator`.
+    pub data_dir: Option<PathBuf>,
+}
+
+/// Where review data lives when the repository has no `.annotator/` yet.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StoreLocation {
+    /// `.annotator/` inside the worktree.
+    #[default]
+    Repo,
+    /// A per-repository directory under the user's data directory.
+    DataDir,
+}
+
+/// Where the text of the annotation under the cursor is shown.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationLayout {
+    /// One-line preview in the status bar.
+    #[default]
+    StatusBar,
+    /// Multi-line panel between the viewer and the status bar.
+    BottomPanel,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::load_layered(None, path)
+    }
+
+    /// The effective settings for a store: the user config overlaid with the
+    /// store's own `config.toml`.
+    pub fn load_for(annotator_dir: &Path) -> Result<Self> {
+        let user = crate::core::location::user_config_path();
+        Self::load_layered(user.as_deref(), &annotator_dir.join("config.toml"))
+    }
+
+    /// Merges `user` and `repo` table by table, the repository's keys winning.
+    pub fn load_layered(user: Option<&Path>, repo: &Path) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        let mut key_layers = Vec::new();
+        for path in user.into_iter().chain([repo]) {
+            let table = read_table(path)?;
+            if let Some(keys) = lookup_table(&table, "ui.keys") {
+                let keys: BTreeMap<String, String> =
+                    keys.clone().try_into().with_context(|| format!("invalid ui.keys in {}", path.display()))?;
+                key_layers.push(keys);
+            }
+            merge(&mut merged, table);
+        }
+        let description = match user {
+            Some(user) => format!("{} and {}", user.display(), repo.display()),
+            None => repo.display().to_string(),
+        };
+        let mut config: Self = merged.try_into().with_context(|| format!("invalid settings in {}", description))?;
+        config.ui.key_layers = key_layers;
+        Ok(config)
+    }
+
+    /// The value of a dotted key such as `ui.theme`, if it is set.
+    pub fn get(&self, key: &str) -> Result<Option<toml::Value>> {
+        let value = toml::Value::try_from(self).context("serializing settings")?;
+        Ok(lookup(&value, key).cloned())
+    }
+
+    /// Sets a dotted key in the config file at `path`. `value` is parsed as a
+    /// TOML value, falling back to a plain string. The rest of the file,
+    /// comments and layout included, is left as it was.
+    pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+        let value = format!("v = {}", value)
+            .parse::<toml_edit::DocumentMut>()
+            .ok()
+            .and_then(|mut doc| doc.remove("v"))
+            .and_then(|item| item.into_value().ok())
+            .unwrap_or_else(|| toml_edit::Value::from(value));
+
+        let mut doc = read_document(path)?;
+        let (parents, name) = match key.rsplit_once('.') {
+            Some((parents, name)) => (parents.split('.').collect(), name),
+            None => (Vec::new(), key),
+        };
+        let section = section(&mut doc, &parents)?;
+        match section.get_mut(name).and_then(|item| item.as_value_mut()) {
+            // Keeps a comment after the old value
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = value;
+                *old.decor_mut() = decor;
+            }
+            None => {
+                section.insert(name, toml_edit::Item::Value(value));
+            }
+        }
+
+        let config: Config = toml::from_str(&doc.to_string()).with_context(|| format!("invalid value for {}", key))?;
+        if config.get(key)?.is_none() {
+            bail!("unknown setting '{}'", key);
+        }
+
+        write_document(path, &doc)
+    }
+
+    /// Sets or, with `None`, removes the `ui.syntax` entry of `file` in the
+    /// config file at `path`. File paths contain dots, so `set` can't.
+    pub fn set_syntax(path: &Path, file: &str, syntax: Option<&str>) -> Result<()> {
+        let mut doc = read_document(path)?;
+        let section = section(&mut doc, &["ui", "syntax"])?;
+        match syntax {
+            Some(syntax) => section.insert(file, toml_edit::value(syntax)),
+            None => section.remove(file),
+        };
+        write_document(path, &doc)
+    }
+}
+
+/// The table at `parents` in `doc`, created when missing.
+fn section<'a>(doc: &'a mut toml_edit::DocumentMut, parents: &[&str]) -> Result<&'a mut dyn toml_edit::TableLike> {
+    let mut current: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for part in parents {
+        let entry = current.entry(part).or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            // Written only as `[parent.child]` headers unless given keys of its own
+            table.set_implicit(true);
+            toml_edit::Item::Table(table)
+        });
+        let Some(next) = entry.as_table_like_mut() else {
+            bail!("'{}' is not a section", part);
+        };
+        current = next;
+    }
+    Ok(current)
+}
+
+fn write_document(path: &Path, doc: &toml_edit::DocumentMut) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    std::fs::write(path, doc.to_string()).with_context(|| format!("writing {}", path.display()))
+}
+
+fn read_document(path: &Path) -> Result<toml_edit::DocumentMut> {
+    if !path.exists() {
+        return Ok(toml_edit::DocumentMut::new());
+    }
+    let data = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    data.parse().with_context(|| format!("parsing {}", path.display()))
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let data = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    toml::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn lookup_table<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Table> {
+    key.split('.').try_fold(table, |table, part| table.get(part)?.as_table())
+}
+
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(value, |value, part| value.get(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_config_is_default() {
+        let dir = TempDir::new().unwrap();
+        let config = Config::load(&dir.path().join("config.toml")).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[ui]\nannotation_layout = \"bottom_panel\"\n").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.ui.annotation_layout, AnnotationLayout::BottomPanel);
+        assert_eq!(config.ui.panel_height, 6);
+    }
+
+    #[test]
+    fn test_repo_config_overrides_user_config() {
+        let dir = TempDir::new().unwrap();
+        let user = dir.path().join("user.toml");
+        let repo = dir.path().join("repo.toml");
+        std::fs::write(&user, "[ui]\ntheme = \"InspiredGitHub\"\npanel_height = 10\n[author]\nname = \"Ann\"\n").unwrap();
+        std::fs::write(&repo, "[ui]\npanel_height = 8\n[adjust]\nrename_threshold = 70\n").unwrap();
+
+        let config = Config::load_layered(Some(&user), &repo).unwrap();
+        assert_eq!(config.ui.theme, "InspiredGitHub");
+        assert_eq!(config.ui.panel_height, 8);
+        assert_eq!(config.author.identity().as_deref(), Some("Ann"));
+        assert_eq!(config.adjust.rename_threshold, 70);
+        assert!(config.adjust.follow_moves);
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sub").join("config.toml");
+        Config::set(&path, "ui.keymap", "vim").unwrap();
+        Config::set(&path, "review.ignore", "[\"*.lock\"]").unwrap();
+        Config::set(&path, "author.email", "ann@example.com").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.ui.keymap, KeymapProfile::Vim);
+        assert_eq!(config.get("author.email").unwrap(), Some(toml::Value::String("ann@example.com".into())));
+        assert_eq!(config.get("author.name").unwrap(), None);
+        assert_eq!(
+            config.review.filter_ignored(vec!["Cargo.lock".into(), "src/a.rs".into()]).unwrap(),
+            vec!["src/a.rs"]
+        );
+
+        assert!(Config::set(&path, "ui.panel_height", "tall").is_err());
+        assert!(Config::set(&path, "ui.colour", "red").is_err());
+        assert!(Config::set(&path, "ui.keymap.x", "1").is_err());
+        assert_eq!(Config::load(&path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_set_keeps_comments() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "# Team settings\n[ui]\ntheme = \"InspiredGitHub\"  # light terminals\n").unwrap();
+        Config::set(&path, "ui.theme", "Solarized (dark)").unwrap();
+        Config::set(&path, "ui.syntax.x", "1").unwrap_err();
+        Config::set_syntax(&path, "src/io.h", Some("C++")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# Team settings\n[ui]\ntheme = \"Solarized (dark)\"  # light terminals\n\n[ui.syntax]\n\"src/io.h\" = \"C++\"\n"
+        );
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[ui.verdicts]\nF1 = \"nit: naming\"\nf12 = \"missing error handling\"\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.ui.verdict(1), Some("nit: naming"));
+        assert_eq!(config.ui.verdict(12), Some("missing error handling"));
+        assert_eq!(config.ui.verdict(2), None);
+
+        assert!(Config::set(&path, "ui.verdicts.F13", "x").is_err());
+        assert!(Config::set(&path, "ui.verdicts.F2", "\"\"").is_err());
+        Config::set(&path, "ui.verdicts.F2", "needs a test").unwrap();
+        assert_eq!(Config::load(&path).unwrap().ui.verdict(2), Some("needs a test"));
+    }
+
+    #[test]
+    fn test_set_syntax() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        Config::set(&path, "ui.keymap", "vim").unwrap();
+        Config::set_syntax(&path, "src/io.h", Some("C++")).unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.ui.syntax["src/io.h"], "C++");
+        assert_eq!(config.ui.keymap, KeymapProfile::Vim);
+
+        Config::set_syntax(&path, "src/io.h", None).unwrap();
+        assert!(Config::load(&path).unwrap().ui.syntax.is_empty());
+    }
+}
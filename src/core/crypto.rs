@@ -0,0 +1,243 @@
+//! Optional age encryption of store files at rest.
+//!
+//! A key comes from `ANNOTATOR_IDENTITY_FILE` (an age identity, as written by
+//! `age-keygen`) or `ANNOTATOR_PASSPHRASE`. Encrypted files are recognized by
+//! their age header, so plaintext and encrypted stores load the same way.
+//!
+//! Data is written as ASCII-armored age blocks. A file may hold several of
+//! them, one per append, and reads as their plaintexts joined, so appending
+//! a record never rewrites what is already there.
+
+use age::secrecy::{ExposeSecret, SecretString};
+use anyhow::{Context, Result, anyhow, bail};
+use bech32::ToBase32;
+use std::path::Path;
+
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1\n";
+const ARMOR_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+const ARMOR_END: &str = "-----END AGE ENCRYPTED FILE-----";
+
+/// age's default scrypt work factor, used to derive the passphrase identity.
+const PASSPHRASE_WORK_FACTOR: u8 = 18;
+/// Fixed, so everyone sharing a passphrase derives the same identity and can
+/// read each other's synced data.
+const PASSPHRASE_SALT: &[u8] = b"annotator store identity v1";
+
+#[derive(Clone)]
+pub enum StoreKey {
+    Identity(age::x25519::Identity),
+    /// Data is encrypted to an identity derived from the passphrase once per
+    /// process, so only opening the store pays for scrypt. The passphrase
+    /// itself still decrypts files written with it directly.
+    Passphrase {
+        passphrase: SecretString,
+        identity: age::x25519::Identity,
+    },
+}
+
+impl StoreKey {
+    /// The key provided through the environment, if any.
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Some(path) = std::env::var_os("ANNOTATOR_IDENTITY_FILE").filter(|v| !v.is_empty()) {
+            return Self::from_identity_file(Path::new(&path)).map(Some);
+        }
+        std::env::var("ANNOTATOR_PASSPHRASE")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(|p| Self::from_passphrase(SecretString::from(p)))
+            .transpose()
+    }
+
+    /// Derives the identity data is encrypted to from `passphrase`.
+    pub fn from_passphrase(passphrase: SecretString) -> Result<Self> {
+        let params = scrypt::Params::new(PASSPHRASE_WORK_FACTOR, 8, 1, 32).map_err(|e| anyhow!("scrypt: {}", e))?;
+        let mut secret = [0u8; 32];
+        scrypt::scrypt(passphrase.expose_secret().as_bytes(), PASSPHRASE_SALT, &params, &mut secret)
+            .map_err(|e| anyhow!("scrypt: {}", e))?;
+        let encoded = bech32::encode("age-secret-key-", secret.to_base32(), bech32::Variant::Bech32)?;
+        let identity = encoded
+            .to_uppercase()
+            .parse()
+            .map_err(|e| anyhow!("deriving the key from the passphrase: {}", e))?;
+        Ok(StoreKey::Passphrase { passphrase, identity })
+    }
+
+    /// Reads the first `AGE-SECRET-KEY-` line of an identity file.
+    pub fn from_identity_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("reading identity file {}", path.display()))?;
+        let line = content
+            .lines()
+            .map(str::trim)
+            .find(|l| l.starts_with("AGE-SECRET-KEY-"))
+            .with_context(|| format!("{} contains no age secret key", path.display()))?;
+        let identity = line
+            .parse()
+            .map_err(|e| anyhow!("invalid age secret key in {}: {}", path.display(), e))?;
+        Ok(StoreKey::Identity(identity))
+    }
+
+    fn identity(&self) -> &age::x25519::Identity {
+        match self {
+            StoreKey::Identity(identity) | StoreKey::Passphrase { identity, .. } => identity,
+        }
+    }
+
+    /// One armored age block holding `plaintext`.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let armored = age::encrypt_and_armor(&self.identity().to_public(), plaintext).context("encrypting store data")?;
+        Ok(format!("{}\n", armored).into_bytes())
+    }
+
+    /// Decrypts one age file, binary or armored.
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let decrypted = match self {
+            StoreKey::Identity(identity) => age::decrypt(identity, ciphertext),
+            StoreKey::Passphrase { passphrase, identity } => {
+                let legacy = age::scrypt::Identity::new(passphrase.clone());
+                age::Decryptor::new_buffered(age::armor::ArmoredReader::new(ciphertext)).and_then(|decryptor| {
+                    let identities: [&dyn age::Identity; 2] = [identity, &legacy];
+                    let mut plaintext = Vec::new();
+                    std::io::Read::read_to_end(&mut decryptor.decrypt(identities.into_iter())?, &mut plaintext)?;
+                    Ok(plaintext)
+                })
+            }
+        };
+        decrypted.map_err(|e| anyhow!("decrypting store data failed ({}); is it the right key?", e))
+    }
+
+    /// Decrypts a binary age file, or each armored block of `data` in turn.
+    fn decrypt_blocks(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if data.starts_with(AGE_HEADER) {
+            return self.decrypt(data);
+        }
+        let text = std::str::from_utf8(data).context("encrypted data is not valid armor")?;
+        let mut plaintext = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(ARMOR_BEGIN) {
+            rest = &rest[start..];
+            let next = rest[ARMOR_BEGIN.len()..].find(ARMOR_BEGIN).map(|i| i + ARMOR_BEGIN.len());
+            match rest.find(ARMOR_END).map(|i| i + ARMOR_END.len()) {
+                Some(end) if next.is_none_or(|next| end <= next) => {
+                    plaintext.extend(self.decrypt(&rest.as_bytes()[..end])?);
+                    rest = &rest[end..];
+                }
+                // A block without its end marker is an append that never
+                // finished; the record it was writing was never stored
+                _ => rest = &rest[next.unwrap_or(rest.len())..],
+            }
+        }
+        Ok(plaintext)
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(AGE_HEADER) || is_armored(data)
+}
+
+/// Bytes at the start of a file that `is_encrypted` and `is_armored` look at.
+pub const HEADER_LEN: usize = if AGE_HEADER.len() > ARMOR_BEGIN.len() { AGE_HEADER.len() } else { ARMOR_BEGIN.len() };
+
+/// Whether `data` is made of armored blocks, which can be appended to.
+pub fn is_armored(data: &[u8]) -> bool {
+    data.starts_with(ARMOR_BEGIN.as_bytes())
+}
+
+/// Reads a store file, decrypting it if it is encrypted.
+pub fn read_text(path: &Path, key: Option<&StoreKey>) -> Result<String> {
+    let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if !is_encrypted(&data) {
+        return String::from_utf8(data).with_context(|| format!("{} is not valid UTF-8", path.display()));
+    }
+    let Some(key) = key else {
+        bail!(
+            "{} is encrypted; set ANNOTATOR_IDENTITY_FILE or ANNOTATOR_PASSPHRASE",
+            path.display()
+        );
+    };
+    decrypt_text(&data, key).with_context(|| format!("reading {}", path.display()))
+}
+
+/// Decrypts age-encrypted UTF-8 text.
+pub fn decrypt_text(data: &[u8], key: &StoreKey) -> Result<String> {
+    String::from_utf8(key.decrypt_blocks(data)?).context("decrypted data is not valid UTF-8")
+}
+
+/// Bytes to write for `text`: encrypted when a key is given.
+pub fn encode(text: &str, key: Option<&StoreKey>) -> Result<Vec<u8>> {
+    match key {
+        Some(key) => key.encrypt(text.as_bytes()),
+        None => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_roundtrip_with_identity_and_passphrase() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data");
+        let keys = [
+            StoreKey::Identity(age::x25519::Identity::generate()),
+            StoreKey::from_passphrase(SecretString::from("correct horse".to_string())).unwrap(),
+        ];
+        for key in &keys {
+            let bytes = encode("secret finding\n", Some(key)).unwrap();
+            assert!(is_encrypted(&bytes));
+            std::fs::write(&path, bytes).unwrap();
+            assert_eq!(read_text(&path, Some(key)).unwrap(), "secret finding\n");
+            assert!(read_text(&path, None).unwrap_err().to_string().contains("encrypted"));
+        }
+        let wrong = StoreKey::from_passphrase(SecretString::from("wrong".to_string())).unwrap();
+        assert!(read_text(&path, Some(&wrong)).is_err());
+
+        // Written by scrypt directly, as before the identity was derived
+        let mut recipient = age::scrypt::Recipient::new(SecretString::from("correct horse".to_string()));
+        recipient.set_work_factor(10);
+        std::fs::write(&path, age::encrypt(&recipient, b"old finding\n").unwrap()).unwrap();
+        assert_eq!(read_text(&path, Some(&keys[1])).unwrap(), "old finding\n");
+    }
+
+    #[test]
+    fn test_appended_blocks_read_as_one_text() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data");
+        let key = StoreKey::Identity(age::x25519::Identity::generate());
+        let mut data = encode("first\n", Some(&key)).unwrap();
+        data.extend(encode("second\n", Some(&key)).unwrap());
+        // An append cut short by a crash, then later ones
+        data.extend(&encode("lost\n", Some(&key)).unwrap()[..40]);
+        data.extend(encode("third\n", Some(&key)).unwrap());
+        data.extend(&encode("lost\n", Some(&key)).unwrap()[..40]);
+        std::fs::write(&path, data).unwrap();
+        assert_eq!(read_text(&path, Some(&key)).unwrap(), "first\nsecond\nthird\n");
+    }
+
+    #[test]
+    fn test_plaintext_reads_with_or_without_key() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data");
+        std::fs::write(&path, "plain\n").unwrap();
+        let key = StoreKey::Identity(age::x25519::Identity::generate());
+        assert_eq!(read_text(&path, Some(&key)).unwrap(), "plain\n");
+        assert_eq!(read_text(&path, None).unwrap(), "plain\n");
+    }
+
+    #[test]
+    fn test_identity_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("key.txt");
+        let identity = age::x25519::Identity::generate();
+        let secret = identity.to_string();
+        std::fs::write(&path, format!("# created: now\n{}\n", secret.expose_secret())).unwrap();
+        let key = StoreKey::from_identity_file(&path).unwrap();
+        let bytes = encode("x", Some(&key)).unwrap();
+        assert_eq!(key.decrypt(&bytes).unwrap(), b"x");
+
+        std::fs::write(&path, "nothing here\n").unwrap();
+        assert!(StoreKey::from_identity_file(&path).is_err());
+    }
+}
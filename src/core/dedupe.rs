@@ -0,0 +1,62 @@
+use crate::core::annotation::Annotation;
+use std::collections::HashMap;
+
+/// Splits annotations into those to keep and exact duplicates (same file,
+/// range and text) to drop. The earliest-created copy of each group is kept,
+/// and the original order of kept annotations is preserved.
+pub fn dedupe(annotations: Vec<Annotation>) -> (Vec<Annotation>, Vec<Annotation>) {
+    let mut earliest: HashMap<(&str, u32, u32, &str), usize> = HashMap::new();
+    for (i, a) in annotations.iter().enumerate() {
+        let key = (a.file_path.as_str(), a.start_line, a.end_line, a.text.as_str());
+        earliest
+            .entry(key)
+            .and_modify(|best| {
+                if a.created_at < annotations[*best].created_at {
+                    *best = i;
+                }
+            })
+            .or_insert(i);
+    }
+    let mut keep = vec![false; annotations.len()];
+    for i in earliest.into_values() {
+        keep[i] = true;
+    }
+
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for (a, keep) in annotations.into_iter().zip(keep) {
+        if keep {
+            kept.push(a);
+        } else {
+            removed.push(a);
+        }
+    }
+    (kept, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_earliest_copy() {
+        let mut late = Annotation::new("f.rs".into(), 1, 2, "same".into());
+        let early = Annotation::new("f.rs".into(), 1, 2, "same".into());
+        late.created_at = early.created_at + chrono::Duration::seconds(5);
+        let other = Annotation::new("f.rs".into(), 1, 2, "different".into());
+
+        let (kept, removed) = dedupe(vec![late.clone(), other.clone(), early.clone()]);
+        assert_eq!(kept, vec![other, early]);
+        assert_eq!(removed, vec![late]);
+    }
+
+    #[test]
+    fn test_range_must_match() {
+        let a = Annotation::new("f.rs".into(), 1, 2, "same".into());
+        let b = Annotation::new("f.rs".into(), 1, 3, "same".into());
+        let c = Annotation::new("g.rs".into(), 1, 2, "same".into());
+        let (kept, removed) = dedupe(vec![a, b, c]);
+        assert_eq!(kept.len(), 3);
+        assert!(removed.is_empty());
+    }
+}
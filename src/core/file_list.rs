@@ -1,8 +1,29 @@
+use crate::core::progress::Progress;
 use anyhow::{Context, Result};
 use std::path::Path;
 
-/// Lists all git-tracked files in the repository, skipping binary files.
+/// Index mode of a gitlink (submodule) entry.
+const GITLINK_MODE: u32 = 0o160000;
+/// Index mode of a symbolic link; its blob holds the target path, not code.
+const SYMLINK_MODE: u32 = 0o120000;
+
+/// Lists all git-tracked files in the repository, skipping binary files and
+/// symlinks (a link's target is reviewed under its own path when tracked).
 pub fn list_tracked_files(repo_path: &Path) -> Result<Vec<String>> {
+    list_files(repo_path, false)
+}
+
+/// Like `list_tracked_files`, optionally descending into initialized
+/// submodules, whose files are listed under the submodule's path.
+pub fn list_files(repo_path: &Path, submodules: bool) -> Result<Vec<String>> {
+    let files = list_candidate_files(repo_path, submodules)?;
+    let binary = binary_files(repo_path, &files, &Progress::new());
+    Ok(files.into_iter().filter(|f| !binary.contains(f)).collect())
+}
+
+/// Like `list_files`, but only reads the index: files are dropped by
+/// extension, and binary content is left for `binary_files` to find.
+pub fn list_candidate_files(repo_path: &Path, submodules: bool) -> Result<Vec<String>> {
     let repo = git2::Repository::open(repo_path)
         .with_context(|| format!("opening git repo at {}", repo_path.display()))?;
     let index = repo.index()?;
@@ -12,7 +33,16 @@ pub fn list_tracked_files(repo_path: &Path) -> Result<Vec<String>> {
         let path = String::from_utf8_lossy(&entry.path).to_string();
         let full_path = repo_path.join(&path);
 
-        if is_binary_path(&full_path) {
+        if entry.mode == GITLINK_MODE {
+            if submodules && git2::Repository::open(&full_path).is_ok() {
+                for file in list_candidate_files(&full_path, true)? {
+                    files.push(format!("{}/{}", path, file));
+                }
+            }
+            continue;
+        }
+
+        if entry.mode == SYMLINK_MODE || has_binary_extension(&full_path) {
             continue;
         }
 
@@ -23,8 +53,118 @@ pub fn list_tracked_files(repo_path: &Path) -> Result<Vec<String>> {
     Ok(files)
 }
 
-fn is_binary_path(path: &Path) -> bool {
-    // Check by extension first
+/// Paths of initialized submodules, nested ones included, deepest first.
+pub fn submodule_paths(repo_path: &Path) -> Result<Vec<String>> {
+    let repo = git2::Repository::open(repo_path)
+        .with_context(|| format!("opening git repo at {}", repo_path.display()))?;
+    let mut paths = Vec::new();
+    for entry in repo.index()?.iter() {
+        if entry.mode != GITLINK_MODE {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path).to_string();
+        let full_path = repo_path.join(&path);
+        if git2::Repository::open(&full_path).is_err() {
+            continue;
+        }
+        for nested in submodule_paths(&full_path)? {
+            paths.push(format!("{}/{}", path, nested));
+        }
+        paths.push(path);
+    }
+    paths.sort_by_key(|p| std::cmp::Reverse(p.matches('/').count()));
+    Ok(paths)
+}
+
+/// Lists the files in the tree of commit `rev`, skipping binary files,
+/// symlinks and submodules. Works without a working tree, e.g. in bare repositories.
+pub fn list_files_at(repo_path: &Path, rev: &str) -> Result<Vec<String>> {
+    let repo = git2::Repository::open(repo_path)
+        .with_context(|| format!("opening git repo at {}", repo_path.display()))?;
+    let tree = repo
+        .revparse_single(rev)
+        .and_then(|o| o.peel_to_tree())
+        .with_context(|| format!("reading tree of '{}'", rev))?;
+    let mut files = Vec::new();
+    let mut walk_error = None;
+
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(git2::ObjectType::Blob) || entry.filemode() == SYMLINK_MODE as i32 {
+            return git2::TreeWalkResult::Ok;
+        }
+        let path = format!("{}{}", dir, String::from_utf8_lossy(entry.name_bytes()));
+        if has_binary_extension(Path::new(&path)) {
+            return git2::TreeWalkResult::Ok;
+        }
+        match repo.find_blob(entry.id()) {
+            Ok(blob) if !is_binary_data(blob.content()) => files.push(path),
+            Ok(_) => {}
+            Err(e) => {
+                walk_error = Some(e);
+                return git2::TreeWalkResult::Abort;
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+    if let Some(e) = walk_error {
+        return Err(e.into());
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Those of `files` (relative to `repo_path`) whose content is binary,
+/// checked on several threads since it reads the start of every file. Once
+/// `progress` is cancelled, only the binary files found so far are returned.
+pub fn binary_files(repo_path: &Path, files: &[String], progress: &Progress) -> std::collections::HashSet<String> {
+    progress.set_total(files.len());
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk = files.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = files
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .take_while(|_| !progress.is_cancelled())
+                        .filter(|f| {
+                            progress.advance();
+                            is_binary_path(&repo_path.join(f))
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers.into_iter().flat_map(|w| w.join().unwrap_or_default()).collect()
+    })
+}
+
+pub fn is_binary_path(path: &Path) -> bool {
+    use std::io::Read;
+    if has_binary_extension(path) {
+        return true;
+    }
+
+    // Null bytes near the start mark binary content, as git does
+    let mut head = Vec::with_capacity(BINARY_CHECK_LEN);
+    match std::fs::File::open(path) {
+        Ok(file) => file.take(BINARY_CHECK_LEN as u64).read_to_end(&mut head).is_ok() && is_binary_data(&head),
+        Err(_) => false,
+    }
+}
+
+/// Bytes at the start of a file searched for a null byte.
+const BINARY_CHECK_LEN: usize = 8192;
+
+fn is_binary_data(data: &[u8]) -> bool {
+    let check_len = data.len().min(BINARY_CHECK_LEN);
+    data[..check_len].contains(&0)
+}
+
+fn has_binary_extension(path: &Path) -> bool {
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
         let binary_exts = [
             "png", "jpg", "jpeg", "gif", "bmp", "ico", "svg",
@@ -35,17 +175,8 @@ fn is_binary_path(path: &Path) -> bool {
             "mp3", "mp4", "wav", "avi", "mkv", "mov",
             "db", "sqlite", "sqlite3",
         ];
-        if binary_exts.contains(&ext.to_lowercase().as_str()) {
-            return true;
-        }
-    }
-
-    // Check file content for null bytes (binary indicator)
-    if let Ok(data) = std::fs::read(path) {
-        let check_len = data.len().min(8192);
-        return data[..check_len].contains(&0);
+        return binary_exts.contains(&ext.to_lowercase().as_str());
     }
-
     false
 }
 
@@ -104,13 +235,73 @@ mod tests {
         assert_eq!(files, vec!["lib.rs", "main.rs", "src/util.rs"]);
     }
 
+    #[test]
+    fn test_submodules() {
+        let dir = TempDir::new().unwrap();
+        let lib = dir.path().join("lib");
+        let app = dir.path().join("app");
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        init_git_repo(&lib);
+        std::fs::write(lib.join("lib.rs"), "pub fn foo() {}").unwrap();
+        add_and_commit(&lib, &["lib.rs"]);
+        init_git_repo(&app);
+        std::fs::write(app.join("main.rs"), "fn main() {}").unwrap();
+        let status = Command::new("git")
+            .args(["-c", "protocol.file.allow=always", "submodule", "add", "-q"])
+            .arg(&lib)
+            .arg("vendor/lib")
+            .current_dir(&app)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        add_and_commit(&app, &["main.rs"]);
+
+        assert_eq!(list_tracked_files(&app).unwrap(), vec![".gitmodules", "main.rs"]);
+        assert_eq!(
+            list_files(&app, true).unwrap(),
+            vec![".gitmodules", "main.rs", "vendor/lib/lib.rs"]
+        );
+        assert_eq!(submodule_paths(&app).unwrap(), vec!["vendor/lib"]);
+    }
+
+    #[test]
+    fn test_list_files_at_revision() {
+        let dir = TempDir::new().unwrap();
+        init_git_repo(dir.path());
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/old.rs"), "// old").unwrap();
+        std::fs::write(dir.path().join("blob.dat"), [1u8, 0, 2]).unwrap();
+        add_and_commit(dir.path(), &["src/old.rs", "blob.dat"]);
+        std::fs::write(dir.path().join("new.rs"), "// new").unwrap();
+        add_and_commit(dir.path(), &["new.rs"]);
+
+        assert_eq!(list_files_at(dir.path(), "HEAD~1").unwrap(), vec!["src/old.rs"]);
+        assert_eq!(list_files_at(dir.path(), "HEAD").unwrap(), vec!["new.rs", "src/old.rs"]);
+        assert!(list_files_at(dir.path(), "nope").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_skipped() {
+        let dir = TempDir::new().unwrap();
+        init_git_repo(dir.path());
+        std::fs::write(dir.path().join("real.rs"), "fn main() {}").unwrap();
+        std::os::unix::fs::symlink("real.rs", dir.path().join("link.rs")).unwrap();
+        std::os::unix::fs::symlink("missing.rs", dir.path().join("dangling.rs")).unwrap();
+        add_and_commit(dir.path(), &["real.rs", "link.rs", "dangling.rs"]);
+
+        assert_eq!(list_tracked_files(dir.path()).unwrap(), vec!["real.rs"]);
+        assert_eq!(list_files_at(dir.path(), "HEAD").unwrap(), vec!["real.rs"]);
+    }
+
     #[test]
     fn test_binary_skipped() {
         let dir = TempDir::new().unwrap();
         init_git_repo(dir.path());
 
         std::fs::write(dir.path().join("code.rs"), "fn main() {}").unwrap();
-        std::fs::write(dir.path().join("image.png"), &[0u8; 100]).unwrap();
+        std::fs::write(dir.path().join("image.png"), [0u8; 100]).unwrap();
 
         add_and_commit(dir.path(), &["code.rs", "image.png"]);
 
@@ -133,4 +324,22 @@ mod tests {
         let files = list_tracked_files(dir.path()).unwrap();
         assert_eq!(files, vec!["text.dat"]);
     }
+
+    #[test]
+    fn test_binary_files_reports_progress_and_stops_when_cancelled() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("text.dat"), "hello world").unwrap();
+        std::fs::write(dir.path().join("blob.dat"), [0x47, 0x00, 0x01]).unwrap();
+        let files = vec!["text.dat".to_string(), "blob.dat".to_string(), "missing.dat".to_string()];
+
+        let progress = Progress::new();
+        let binary = binary_files(dir.path(), &files, &progress);
+        assert_eq!(binary, std::collections::HashSet::from(["blob.dat".to_string()]));
+        assert_eq!(progress.percent(), Some(100));
+
+        let progress = Progress::new();
+        progress.cancel();
+        assert!(binary_files(dir.path(), &files, &progress).is_empty());
+        assert_eq!(progress.percent(), Some(0));
+    }
 }
@@ -0,0 +1,113 @@
+//! Where the annotations of a review concentrate, file by file.
+
+use crate::core::annotation::{Annotation, Severity};
+use std::collections::{BTreeMap, HashMap};
+
+/// Annotations of one file, weighed by severity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHeat<'a> {
+    pub path: &'a str,
+    pub annotations: usize,
+    /// Sum of the annotations' weights.
+    pub heat: u32,
+    /// Lines in the file, when it could be read.
+    pub lines: Option<usize>,
+    /// Most severe label among the annotations, if any has one.
+    pub worst: Option<Severity>,
+}
+
+/// How much an annotation adds to its file's heat: a blocker counts as
+/// eight unlabeled annotations.
+pub fn weight(severity: Option<Severity>) -> u32 {
+    match severity {
+        Some(Severity::Blocker) => 8,
+        Some(Severity::Major) => 4,
+        Some(Severity::Minor) => 2,
+        Some(Severity::Nit) | Some(Severity::Info) | None => 1,
+    }
+}
+
+impl FileHeat<'_> {
+    /// Heat per hundred lines, so a long file with a few annotations isn't
+    /// ranked above a short one riddled with them.
+    pub fn density(&self) -> Option<f64> {
+        self.lines.map(|lines| f64::from(self.heat) * 100.0 / lines.max(1) as f64)
+    }
+}
+
+/// Every annotated file, densest first, then those of unknown length by
+/// heat, then by path. `line_counts` holds the length of each file that
+/// could be read.
+pub fn heatmap<'a>(annotations: &'a [Annotation], line_counts: &HashMap<String, usize>) -> Vec<FileHeat<'a>> {
+    let mut files: BTreeMap<&str, FileHeat> = BTreeMap::new();
+    for a in annotations {
+        let entry = files.entry(&a.file_path).or_insert(FileHeat {
+            path: &a.file_path,
+            annotations: 0,
+            heat: 0,
+            lines: line_counts.get(&a.file_path).copied(),
+            worst: None,
+        });
+        entry.annotations += 1;
+        entry.heat += weight(a.severity);
+        entry.worst = match (entry.worst, a.severity) {
+            (Some(w), Some(s)) => Some(w.min(s)),
+            (w, s) => w.or(s),
+        };
+    }
+    let mut files: Vec<FileHeat> = files.into_values().collect();
+    // Stable, so equally hot files stay in path order
+    files.sort_by(|a, b| match (a.density(), b.density()) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.heat.cmp(&a.heat),
+    });
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_weighs_by_severity() {
+        let annotations = vec![
+            Annotation::new("b.rs".into(), 1, 1, "nit: naming".into()),
+            Annotation::new("b.rs".into(), 2, 2, "unlabeled".into()),
+            Annotation::new("a.rs".into(), 5, 5, "major: wrong lock".into()),
+            Annotation::new("c.rs".into(), 1, 1, "blocker: data loss".into()),
+            Annotation::new("c.rs".into(), 9, 9, "minor: typo".into()),
+            Annotation::new("d.rs".into(), 3, 3, "plain".into()),
+            Annotation::new("d.rs".into(), 4, 4, "plain".into()),
+        ];
+        let heat: Vec<(&str, usize, u32, Option<Severity>)> =
+            heatmap(&annotations, &HashMap::new()).iter().map(|f| (f.path, f.annotations, f.heat, f.worst)).collect();
+        assert_eq!(
+            heat,
+            vec![
+                ("c.rs", 2, 10, Some(Severity::Blocker)),
+                ("a.rs", 1, 4, Some(Severity::Major)),
+                ("b.rs", 2, 2, Some(Severity::Nit)),
+                ("d.rs", 2, 2, None),
+            ]
+        );
+        assert!(heatmap(&[], &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_heatmap_ranks_by_density() {
+        let annotations = vec![
+            Annotation::new("long.rs".into(), 1, 1, "blocker: one".into()),
+            Annotation::new("short.rs".into(), 1, 1, "major: two".into()),
+            Annotation::new("gone.rs".into(), 1, 1, "blocker: three".into()),
+        ];
+        let line_counts = HashMap::from([("long.rs".to_string(), 800), ("short.rs".to_string(), 20)]);
+        let heat: Vec<(&str, u32, Option<f64>)> =
+            heatmap(&annotations, &line_counts).iter().map(|f| (f.path, f.heat, f.density())).collect();
+        assert_eq!(
+            heat,
+            vec![("short.rs", 4, Some(20.0)), ("long.rs", 8, Some(1.0)), ("gone.rs", 8, None)]
+        );
+    }
+}
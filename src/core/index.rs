@@ -0,0 +1,56 @@
+//! Annotations grouped by file, so per-file lookups don't scan the whole store.
+
+use crate::core::annotation::Annotation;
+use std::collections::HashMap;
+
+/// Positions of each file's annotations in the slice it was built from. It
+/// must be rebuilt whenever annotations are added, removed or change file.
+#[derive(Debug, Default)]
+pub struct AnnotationIndex {
+    by_file: HashMap<String, Vec<usize>>,
+}
+
+impl AnnotationIndex {
+    pub fn build(annotations: &[Annotation]) -> Self {
+        let mut by_file: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, a) in annotations.iter().enumerate() {
+            by_file.entry(a.file_path.clone()).or_default().push(idx);
+        }
+        Self { by_file }
+    }
+
+    /// Positions of the annotations on `file`, in slice order.
+    pub fn positions(&self, file: &str) -> &[usize] {
+        self.by_file.get(file).map_or(&[], |p| p.as_slice())
+    }
+
+    pub fn for_file<'a>(&self, annotations: &'a [Annotation], file: &str) -> Vec<&'a Annotation> {
+        self.positions(file).iter().map(|&i| &annotations[i]).collect()
+    }
+
+    /// Annotated files, sorted by path.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = self.by_file.keys().map(|f| f.as_str()).collect();
+        files.sort_unstable();
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_by_file() {
+        let annotations = vec![
+            Annotation::new("b.rs".into(), 1, 1, "x".into()),
+            Annotation::new("a.rs".into(), 2, 2, "y".into()),
+            Annotation::new("b.rs".into(), 3, 3, "z".into()),
+        ];
+        let index = AnnotationIndex::build(&annotations);
+        assert_eq!(index.positions("b.rs"), &[0, 2]);
+        assert!(index.positions("c.rs").is_empty());
+        assert_eq!(index.for_file(&annotations, "a.rs"), vec![&annotations[1]]);
+        assert_eq!(index.files(), vec!["a.rs", "b.rs"]);
+    }
+}
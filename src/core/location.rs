@@ -0,0 +1,178 @@
+use crate::core::config::{Config, StoreConfig, StoreLocation};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Store directory name inside the repository.
+pub const REPO_DIR: &str = ".annotator";
+
+/// Directory holding the review data for `repo_root`.
+///
+/// Linked `git worktree` checkouts share the store of their main worktree. An
+/// existing `.annotator/` in the repository always wins, so reviews started in
+/// the worktree keep working. Otherwise the user config decides between the
+/// worktree and `~/.local/share/annotator/<repo-id>/`.
+pub fn annotator_dir(repo_root: &Path) -> Result<PathBuf> {
+    let repo_root = match linked_worktree(repo_root) {
+        Some((main_root, _)) => main_root,
+        None => repo_root.to_path_buf(),
+    };
+    let repo_root = repo_root.as_path();
+    let in_repo = repo_root.join(REPO_DIR);
+    if in_repo.is_dir() {
+        return Ok(in_repo);
+    }
+    let config = match user_config_path() {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+    Ok(resolve_dir(repo_root, &config.store, data_home()))
+}
+
+/// Directory for per-checkout state (session and undo history) inside the
+/// store at `annotator_dir`; linked worktrees get `worktrees/<name>/`.
+pub fn session_dir(repo_root: &Path, annotator_dir: &Path) -> PathBuf {
+    match linked_worktree(repo_root) {
+        Some((_, name)) => annotator_dir.join("worktrees").join(name),
+        None => annotator_dir.to_path_buf(),
+    }
+}
+
+/// Main worktree root and worktree name, if `repo_root` is a linked worktree
+/// of a non-bare repository.
+fn linked_worktree(repo_root: &Path) -> Option<(PathBuf, String)> {
+    let repo = git2::Repository::open(repo_root).ok()?;
+    if !repo.is_worktree() {
+        return None;
+    }
+    let name = repo.path().file_name()?.to_string_lossy().to_string();
+    let common = git2::Repository::open(repo.commondir()).ok()?;
+    let main_root = common.workdir()?.to_path_buf();
+    Some((main_root, name))
+}
+
+fn resolve_dir(repo_root: &Path, store: &StoreConfig, data_home: Option<PathBuf>) -> PathBuf {
+    match store.location {
+        StoreLocation::Repo => repo_root.join(REPO_DIR),
+        StoreLocation::DataDir => {
+            match store.data_dir.clone().or_else(|| data_home.map(|d| d.join("annotator"))) {
+                Some(base) => base.join(repo_id(repo_root)),
+                None => repo_root.join(REPO_DIR),
+            }
+        }
+    }
+}
+
+/// `~/.config/annotator/config.toml`, honoring `XDG_CONFIG_HOME`.
+pub fn user_config_path() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|d| d.join("annotator").join("config.toml"))
+}
+
+fn data_home() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(var: &str, home_relative: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(home_relative)))
+}
+
+/// Stable directory name for a repository: its folder name plus a hash of the
+/// canonical path, so two checkouts named `app` don't share a review.
+pub fn repo_id(repo_root: &Path) -> String {
+    let canonical = repo_root.canonicalize().unwrap_or_else(|_| repo_root.to_path_buf());
+    let name: String = canonical
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}-{:012x}", name, fnv1a(canonical.to_string_lossy().as_bytes()) & 0xffff_ffff_ffff)
+}
+
+// FNV-1a rather than std's hasher, whose output may change between releases
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_repo_id_is_stable_and_distinct() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        assert_eq!(repo_id(a.path()), repo_id(a.path()));
+        assert_ne!(repo_id(a.path()), repo_id(b.path()));
+        assert!(repo_id(a.path()).chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn test_resolve_dir() {
+        let repo = TempDir::new().unwrap();
+        let repo_store = StoreConfig::default();
+        assert_eq!(resolve_dir(repo.path(), &repo_store, None), repo.path().join(REPO_DIR));
+
+        let external = StoreConfig {
+            location: StoreLocation::DataDir,
+            data_dir: None,
+        };
+        let dir = resolve_dir(repo.path(), &external, Some(PathBuf::from("/data")));
+        assert_eq!(dir, PathBuf::from("/data/annotator").join(repo_id(repo.path())));
+
+        let custom = StoreConfig {
+            location: StoreLocation::DataDir,
+            data_dir: Some(PathBuf::from("/reviews")),
+        };
+        let dir = resolve_dir(repo.path(), &custom, None);
+        assert_eq!(dir, PathBuf::from("/reviews").join(repo_id(repo.path())));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_worktrees_share_store() {
+        let tmp = TempDir::new().unwrap();
+        let main = tmp.path().join("main");
+        std::fs::create_dir(&main).unwrap();
+        git(&main, &["init", "-q"]);
+        git(&main, &["config", "user.email", "t@t"]);
+        git(&main, &["config", "user.name", "t"]);
+        std::fs::write(main.join("a.txt"), "a\n").unwrap();
+        git(&main, &["add", "."]);
+        git(&main, &["commit", "-q", "-m", "init"]);
+        git(&main, &["worktree", "add", "-q", "../feature"]);
+        std::fs::create_dir(main.join(REPO_DIR)).unwrap();
+
+        let feature = tmp.path().join("feature");
+        let main_dir = annotator_dir(&main).unwrap();
+        let feature_dir = annotator_dir(&feature).unwrap();
+        assert_eq!(feature_dir.canonicalize().unwrap(), main_dir.canonicalize().unwrap());
+        assert_eq!(session_dir(&main, &main_dir), main_dir);
+        assert_eq!(session_dir(&feature, &feature_dir), feature_dir.join("worktrees/feature"));
+    }
+
+    #[test]
+    fn test_existing_repo_dir_wins() {
+        let repo = TempDir::new().unwrap();
+        std::fs::create_dir(repo.path().join(REPO_DIR)).unwrap();
+        assert_eq!(annotator_dir(repo.path()).unwrap(), repo.path().join(REPO_DIR));
+    }
+}
@@ -1,5 +1,22 @@
 pub mod annotation;
+pub mod changes;
+pub mod checklist;
+pub mod config;
+pub mod crypto;
+pub mod dedupe;
 pub mod file_list;
+pub mod heatmap;
+pub mod index;
+pub mod location;
+pub mod overlay;
+pub mod progress;
+pub mod repair;
+pub mod reviewers;
+pub mod scope;
+pub mod search;
 pub mod session;
 pub mod store;
+pub mod timing;
+pub mod tombstone;
 pub mod undo;
+pub mod verify;
@@ -0,0 +1,117 @@
+//! Annotations from other reviews, shown next to the local ones but never
+//! written to the store.
+
+use crate::core::annotation::Annotation;
+use crate::core::config::OverlayConfig;
+use crate::core::crypto::StoreKey;
+use crate::git::adjust::AdjustOptions;
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub name: String,
+    pub color: Option<String>,
+    pub annotations: Vec<Annotation>,
+    /// Commit the line numbers refer to, when the source recorded one.
+    pub base: Option<String>,
+}
+
+impl Overlay {
+    /// Brings the line numbers from the commit the overlay was written at to
+    /// `target`. Overlays without a recorded commit are left as they are.
+    pub fn adjust_to(&mut self, repo: &git2::Repository, target: &str, options: &AdjustOptions) -> Result<()> {
+        let Some(base) = &self.base else {
+            return Ok(());
+        };
+        let from = crate::git::repo::resolve_commit(repo, base)
+            .with_context(|| format!("it was written at {}, which is not in this repository", base))?;
+        if from != target {
+            crate::git::history::adjust_across(repo, &from, target, &mut self.annotations, options)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the annotations of `source`. Refs on an encrypted store need `key`.
+pub fn load(repo_root: &Path, source: &OverlayConfig, key: Option<&StoreKey>) -> Result<Overlay> {
+    let (annotations, base) = match (&source.file, &source.git_ref) {
+        (Some(file), None) => {
+            let path = repo_root.join(file);
+            let data = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let imported = crate::export::json::import_json(&data)?;
+            (imported.annotations, imported.commit)
+        }
+        (None, Some(refname)) => {
+            let repo = crate::git::repo::open_repo(repo_root)?;
+            let snapshot = crate::git::sync::read_revision(&repo, refname, key)?;
+            (snapshot.annotations, snapshot.base)
+        }
+        _ => bail!("overlay '{}' needs either `file` or `ref`", source.name),
+    };
+    Ok(Overlay {
+        name: source.name.clone(),
+        color: source.color.clone(),
+        annotations,
+        base,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_exported_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let exported = Annotation::new("a.rs".into(), 2, 3, "major: racy".into());
+        let json = crate::export::json::export_json(std::slice::from_ref(&exported), Some("abc")).unwrap();
+        std::fs::write(dir.path().join("alice.json"), json).unwrap();
+
+        let source = OverlayConfig {
+            name: "alice".into(),
+            file: Some("alice.json".into()),
+            ..Default::default()
+        };
+        let overlay = load(dir.path(), &source, None).unwrap();
+        assert_eq!(overlay.base.as_deref(), Some("abc"));
+        assert_eq!(overlay.annotations.len(), 1);
+        assert_eq!(overlay.annotations[0].id, exported.id);
+
+        let neither = OverlayConfig { name: "x".into(), ..Default::default() };
+        assert!(load(dir.path(), &neither, None).is_err());
+    }
+
+    #[test]
+    fn test_adjust_to_target_commit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git").args(args).current_dir(dir.path()).output().unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "t@t"]);
+        git(&["config", "user.name", "t"]);
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "init"]);
+        let base = git(&["rev-parse", "HEAD"]);
+        std::fs::write(dir.path().join("a.txt"), "zero\none\ntwo\nthree\n").unwrap();
+        git(&["commit", "-q", "-am", "prepend"]);
+        let head = git(&["rev-parse", "HEAD"]);
+
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let mut overlay = Overlay {
+            name: "alice".into(),
+            color: None,
+            annotations: vec![Annotation::new("a.txt".into(), 3, 3, "off by one".into())],
+            base: Some(base),
+        };
+        overlay.adjust_to(&repo, &head, &AdjustOptions::default()).unwrap();
+        assert_eq!(overlay.annotations[0].start_line, 4);
+
+        overlay.base = Some("0123456789abcdef0123456789abcdef01234567".into());
+        let err = overlay.adjust_to(&repo, &head, &AdjustOptions::default()).unwrap_err();
+        assert!(format!("{:#}", err).contains("which is not in this repository"), "{:#}", err);
+    }
+}
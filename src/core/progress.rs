@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Progress of a long operation, shared between the thread doing the work
+/// and the interface showing it. Clones share state, so either side can
+/// cancel; the work stops before its next unit.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Units of work the operation will go through.
+    pub fn set_total(&self, total: usize) {
+        self.inner.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Records one more unit of work done.
+    pub fn advance(&self) {
+        self.inner.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Share of the work done so far, `None` until the total is known.
+    pub fn percent(&self) -> Option<usize> {
+        let total = self.inner.total.load(Ordering::Relaxed);
+        let done = self.inner.done.load(Ordering::Relaxed).min(total);
+        (total > 0).then(|| done * 100 / total)
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_shared_between_clones() {
+        let progress = Progress::new();
+        assert_eq!(progress.percent(), None);
+
+        let worker = progress.clone();
+        worker.set_total(4);
+        worker.advance();
+        assert_eq!(progress.percent(), Some(25));
+        assert!(!worker.is_cancelled());
+
+        progress.cancel();
+        assert!(worker.is_cancelled());
+    }
+}
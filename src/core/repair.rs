@@ -0,0 +1,118 @@
+use crate::core::annotation::Annotation;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    /// End line pulled back to the last line of the file.
+    Clamped { new_end: u32 },
+    /// Moved to where the stored snippet now appears.
+    Reanchored { new_start: u32, new_end: u32 },
+    /// Nothing of the range is left; move the annotation out of the store.
+    Quarantined,
+}
+
+/// Decides how to fix an annotation whose range doesn't fit the file's `lines`.
+/// Returns None when the range is valid.
+pub fn plan_repair(annotation: &Annotation, lines: &[String], reanchor: bool) -> Option<Repair> {
+    let line_count = lines.len() as u32;
+    let (start, end) = (annotation.start_line, annotation.end_line);
+    if start >= 1 && start <= end && end <= line_count {
+        return None;
+    }
+
+    if reanchor
+        && let Some(snippet) = &annotation.snippet
+        && let Some(new_start) = find_snippet(lines, snippet)
+    {
+        let len = end.saturating_sub(start);
+        return Some(Repair::Reanchored {
+            new_start,
+            new_end: (new_start + len).min(line_count),
+        });
+    }
+
+    if start >= 1 && start <= end && start <= line_count {
+        Some(Repair::Clamped { new_end: line_count })
+    } else {
+        Some(Repair::Quarantined)
+    }
+}
+
+pub fn apply_repair(annotation: &mut Annotation, repair: &Repair) {
+    match repair {
+        Repair::Clamped { new_end } => annotation.end_line = *new_end,
+        Repair::Reanchored { new_start, new_end } => {
+            annotation.start_line = *new_start;
+            annotation.end_line = *new_end;
+        }
+        Repair::Quarantined => return,
+    }
+    annotation.updated_at = chrono::Utc::now();
+}
+
+/// 1-based line where `snippet` starts in `lines`, if it occurs exactly once.
+pub(crate) fn find_snippet(lines: &[String], snippet: &str) -> Option<u32> {
+    let needle: Vec<&str> = snippet.lines().collect();
+    if needle.is_empty() || needle.len() > lines.len() {
+        return None;
+    }
+    let mut matches = (0..=lines.len() - needle.len()).filter(|&i| {
+        lines[i..i + needle.len()]
+            .iter()
+            .zip(&needle)
+            .all(|(line, want)| line == want)
+    });
+    let first = matches.next()?;
+    if matches.next().is_some() {
+        return None;
+    }
+    Some(first as u32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("line {}", i)).collect()
+    }
+
+    #[test]
+    fn test_valid_range_untouched() {
+        let a = Annotation::new("f.rs".into(), 2, 5, "t".into());
+        assert_eq!(plan_repair(&a, &lines(5), true), None);
+    }
+
+    #[test]
+    fn test_clamp_past_eof() {
+        let a = Annotation::new("f.rs".into(), 3, 9, "t".into());
+        assert_eq!(plan_repair(&a, &lines(5), false), Some(Repair::Clamped { new_end: 5 }));
+    }
+
+    #[test]
+    fn test_quarantine_when_start_past_eof() {
+        let a = Annotation::new("f.rs".into(), 8, 9, "t".into());
+        assert_eq!(plan_repair(&a, &lines(5), false), Some(Repair::Quarantined));
+        let reversed = Annotation::new("f.rs".into(), 4, 2, "t".into());
+        assert_eq!(plan_repair(&reversed, &lines(5), false), Some(Repair::Quarantined));
+    }
+
+    #[test]
+    fn test_reanchor_by_snippet() {
+        let mut a = Annotation::new("f.rs".into(), 8, 9, "t".into());
+        a.snippet = Some("line 2\nline 3".into());
+        assert_eq!(
+            plan_repair(&a, &lines(5), true),
+            Some(Repair::Reanchored { new_start: 2, new_end: 3 })
+        );
+        // Without --reanchor the snippet is ignored
+        assert_eq!(plan_repair(&a, &lines(5), false), Some(Repair::Quarantined));
+    }
+
+    #[test]
+    fn test_ambiguous_snippet_not_used() {
+        let mut a = Annotation::new("f.rs".into(), 3, 9, "t".into());
+        a.snippet = Some("dup".into());
+        let content: Vec<String> = ["dup", "x", "y", "dup"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(plan_repair(&a, &content, true), Some(Repair::Clamped { new_end: 4 }));
+    }
+}
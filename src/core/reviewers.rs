@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Committed at the repository root, mapping path globs to reviewers:
+///
+/// ```toml
+/// "src/core/**" = ["alice@example.com"]
+/// "docs/**" = ["Bob", "alice@example.com"]
+/// ```
+pub const FILE_NAME: &str = "REVIEWERS.toml";
+
+/// Who is assigned to review which paths.
+#[derive(Debug, Clone, Default)]
+pub struct Reviewers {
+    rules: Vec<(glob::Pattern, Vec<String>)>,
+}
+
+impl Reviewers {
+    /// Reads `REVIEWERS.toml` from `repo_root`; `None` when there is none.
+    pub fn load(repo_root: &Path) -> Result<Option<Self>> {
+        let path = repo_root.join(FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+            .map(Some)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let table: BTreeMap<String, Vec<String>> = toml::from_str(content)?;
+        let rules = table
+            .into_iter()
+            .map(|(glob, reviewers)| {
+                let pattern = glob::Pattern::new(&glob).with_context(|| format!("invalid pattern '{}'", glob))?;
+                Ok((pattern, reviewers))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Every reviewer named in the file, sorted and deduplicated.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.rules.iter().flat_map(|(_, r)| r.iter().map(|s| s.as_str())).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Globs assigned to a reviewer matching any of `identities`.
+    pub fn patterns_for(&self, identities: &[String]) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|(_, reviewers)| reviewers.iter().any(|r| is_identity(r, identities)))
+            .map(|(pattern, _)| pattern.as_str().to_string())
+            .collect()
+    }
+
+    /// Whether `reviewer` is assigned to `path`.
+    pub fn is_assigned(&self, reviewer: &str, path: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|(pattern, reviewers)| pattern.matches(path) && reviewers.iter().any(|r| r == reviewer))
+    }
+}
+
+/// The ways the current user may be named in `REVIEWERS.toml`: the
+/// configured author, else git's `user.name` and `user.email`, each alone
+/// and as `Name <email>`.
+pub fn current_identities(repo_root: &Path, author: &crate::core::config::AuthorConfig) -> Vec<String> {
+    let git_config = git2::Repository::open(repo_root).and_then(|r| r.config()).ok();
+    let from_git = |key: &str| git_config.as_ref().and_then(|c| c.get_string(key).ok());
+    let name = author.name.clone().or_else(|| from_git("user.name"));
+    let email = author.email.clone().or_else(|| from_git("user.email"));
+    let mut identities: Vec<String> = name.iter().chain(email.iter()).cloned().collect();
+    if let (Some(name), Some(email)) = (&name, &email) {
+        identities.push(format!("{} <{}>", name, email));
+    }
+    identities
+}
+
+fn is_identity(reviewer: &str, identities: &[String]) -> bool {
+    identities.iter().any(|id| id.eq_ignore_ascii_case(reviewer.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+"src/core/**" = ["alice@example.com"]
+"docs/**" = ["Bob", "alice@example.com"]
+"#;
+
+    #[test]
+    fn test_patterns_for_matches_any_identity() {
+        let reviewers = Reviewers::parse(FILE).unwrap();
+        let alice = vec!["Alice".to_string(), "ALICE@example.com".to_string()];
+        assert_eq!(reviewers.patterns_for(&alice), vec!["docs/**", "src/core/**"]);
+        assert_eq!(reviewers.patterns_for(&["bob".to_string()]), vec!["docs/**"]);
+        assert!(reviewers.patterns_for(&["carol".to_string()]).is_empty());
+        assert_eq!(reviewers.names(), vec!["Bob", "alice@example.com"]);
+        assert!(reviewers.is_assigned("Bob", "docs/a.md"));
+        assert!(!reviewers.is_assigned("Bob", "src/core/a.rs"));
+        assert!(Reviewers::parse(r#""[" = ["x"]"#).is_err());
+    }
+}
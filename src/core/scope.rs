@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+
+/// Restricts a review to some subtrees and glob patterns of the repository.
+/// An empty scope includes every file.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    prefixes: Vec<String>,
+    patterns: Vec<glob::Pattern>,
+    /// When non-empty, files must also match one of these, e.g. the paths
+    /// assigned to the reviewer.
+    required: Vec<glob::Pattern>,
+}
+
+impl Scope {
+    /// `prefixes` are repo-relative directories or files ("" is the whole
+    /// repository); `patterns` are globs such as `crates/foo/**`.
+    pub fn new(prefixes: Vec<String>, patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| glob::Pattern::new(p).with_context(|| format!("invalid --include pattern '{}'", p)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            prefixes,
+            patterns,
+            required: Vec::new(),
+        })
+    }
+
+    /// Narrows the scope to files that also match one of `patterns`.
+    pub fn restrict(mut self, patterns: &[String]) -> Result<Self> {
+        for p in patterns {
+            self.required
+                .push(glob::Pattern::new(p).with_context(|| format!("invalid assignment pattern '{}'", p))?);
+        }
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty() && self.patterns.is_empty() && self.required.is_empty()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        (self.required.is_empty() || self.required.iter().any(|p| p.matches(path))) && self.contains_unrestricted(path)
+    }
+
+    fn contains_unrestricted(&self, path: &str) -> bool {
+        (self.prefixes.is_empty() && self.patterns.is_empty())
+            || self.prefixes.iter().any(|prefix| {
+                prefix.is_empty()
+                    || path
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            || self.patterns.iter().any(|p| p.matches(path))
+    }
+
+    pub fn filter(&self, files: Vec<String>) -> Vec<String> {
+        files.into_iter().filter(|f| self.contains(f)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<String> {
+        ["README.md", "crates/foo/lib.rs", "crates/foobar/lib.rs", "crates/bar/lib.rs", "docs/a.md"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_scope_includes_everything() {
+        let scope = Scope::default();
+        assert_eq!(scope.filter(files()), files());
+        let root = Scope::new(vec![String::new()], &[]).unwrap();
+        assert_eq!(root.filter(files()), files());
+    }
+
+    #[test]
+    fn test_prefixes_match_whole_components() {
+        let scope = Scope::new(vec!["crates/foo".into(), "README.md".into()], &[]).unwrap();
+        assert_eq!(scope.filter(files()), vec!["README.md", "crates/foo/lib.rs"]);
+    }
+
+    #[test]
+    fn test_patterns_and_prefixes_combine() {
+        let scope = Scope::new(vec!["docs".into()], &["crates/b*/**".into()]).unwrap();
+        assert_eq!(scope.filter(files()), vec!["crates/bar/lib.rs", "docs/a.md"]);
+        assert!(Scope::new(Vec::new(), &["[".into()]).is_err());
+    }
+
+    #[test]
+    fn test_restrict_intersects() {
+        let scope = Scope::new(vec!["crates".into()], &[]).unwrap();
+        let mine = scope.restrict(&["crates/foo*/**".into(), "docs/**".into()]).unwrap();
+        assert_eq!(mine.filter(files()), vec!["crates/foo/lib.rs", "crates/foobar/lib.rs"]);
+        let nothing = Scope::default().restrict(&[]).unwrap();
+        assert_eq!(nothing.filter(files()), files());
+    }
+}
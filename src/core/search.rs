@@ -0,0 +1,73 @@
+//! Case-insensitive search over annotation text and file paths.
+
+use crate::core::annotation::Annotation;
+
+/// Lowercased text and path of each annotation in the slice it was built
+/// from, so a search doesn't lowercase the whole store on every keystroke.
+/// Like `AnnotationIndex`, it must be rebuilt when the annotations change.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    text: String,
+    path: String,
+    /// Where results are listed: by path, then line.
+    order: (String, u32),
+}
+
+impl SearchIndex {
+    pub fn build(annotations: &[Annotation]) -> Self {
+        let entries = annotations
+            .iter()
+            .map(|a| Entry {
+                text: a.text.to_lowercase(),
+                path: a.file_path.to_lowercase(),
+                order: (a.file_path.clone(), a.start_line),
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Positions of the annotations containing every whitespace-separated
+    /// term of `query` in their text or path, sorted by path and line. An
+    /// empty query matches nothing.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| {
+                let e = &self.entries[i];
+                terms.iter().all(|t| e.text.contains(t) || e.path.contains(t))
+            })
+            .collect();
+        found.sort_by(|&a, &b| self.entries[a].order.cmp(&self.entries[b].order));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_matches_every_term_in_text_or_path() {
+        let annotations = vec![
+            Annotation::new("src/store.rs".into(), 40, 41, "Leaks the Lock on error".into()),
+            Annotation::new("src/main.rs".into(), 3, 3, "lock is held too long".into()),
+            Annotation::new("src/main.rs".into(), 1, 1, "unrelated".into()),
+            Annotation::new("src/lock.rs".into(), 9, 9, "typo".into()),
+        ];
+        let index = SearchIndex::build(&annotations);
+        assert_eq!(index.search("lock"), vec![3, 1, 0]);
+        assert_eq!(index.search("LOCK store"), vec![0]);
+        assert_eq!(index.search("main"), vec![2, 1]);
+        assert!(index.search("  ").is_empty());
+        assert!(index.search("missing").is_empty());
+    }
+}
@@ -1,13 +1,45 @@
+use crate::core::annotation::{unversioned_format, FORMAT_VERSION};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Missing fields fall back to their defaults, so sessions from older and
+/// newer builds both load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Session {
+    #[serde(default = "unversioned_format")]
+    pub format: u32,
     pub current_file: Option<String>,
     pub current_line: u32,
     pub current_col: u32,
     pub scroll_offset: u32,
-    pub last_adjust_commit: Option<String>,
+    /// Annotations an adjustment left in conflict that haven't been visited yet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<Uuid>,
+    /// Unreviewed files set aside, e.g. while waiting on the author; moving to
+    /// the next unreviewed file passes over them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    /// Fields written by a newer build, kept so saving doesn't drop them.
+    #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            format: FORMAT_VERSION,
+            current_file: None,
+            current_line: 0,
+            current_col: 0,
+            scroll_offset: 0,
+            conflicts: Vec::new(),
+            skipped: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
 }
 
 impl Session {
@@ -20,12 +52,64 @@ impl Session {
         }
     }
 
+    /// Queues the annotations an adjustment left in conflict.
+    pub fn add_conflicts(&mut self, results: &[(crate::core::annotation::Annotation, crate::core::annotation::AdjustResult)]) {
+        for (a, result) in results {
+            if matches!(result, crate::core::annotation::AdjustResult::Conflict { .. }) && !self.conflicts.contains(&a.id) {
+                self.conflicts.push(a.id);
+            }
+        }
+    }
+
     pub fn save(&self, path: &Path) -> anyhow::Result<()> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let data = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, data)?;
+        // Saved while the review runs, so a kill mid-write must not truncate it
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// File in the store holding its `AdjustBase`.
+const ADJUST_BASE_FILE: &str = "adjust_base.json";
+
+/// Commits the annotations' lines refer to. Kept in the store rather than
+/// the session, since linked worktrees share the store but not sessions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdjustBase {
+    /// Commit the annotations were last adjusted to.
+    pub commit: Option<String>,
+    /// Last adjusted commit of each reviewed submodule, keyed by its path.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub submodules: BTreeMap<String, String>,
+}
+
+impl AdjustBase {
+    /// The base recorded in the store at `annotator_dir`. A store without
+    /// one takes it from `session`, where older builds kept it.
+    pub fn load(annotator_dir: &Path, session: &Session) -> anyhow::Result<Self> {
+        let path = annotator_dir.join(ADJUST_BASE_FILE);
+        if path.exists() {
+            let data = std::fs::read_to_string(&path)?;
+            return Ok(serde_json::from_str(&data)?);
+        }
+        let legacy = |key: &str| session.extra.get(key).cloned().unwrap_or_default();
+        Ok(Self {
+            commit: serde_json::from_value(legacy("last_adjust_commit")).unwrap_or_default(),
+            submodules: serde_json::from_value(legacy("submodule_commits")).unwrap_or_default(),
+        })
+    }
+
+    pub fn save(&self, annotator_dir: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(annotator_dir)?;
+        let path = annotator_dir.join(ADJUST_BASE_FILE);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
         Ok(())
     }
 }
@@ -42,7 +126,7 @@ mod tests {
         assert_eq!(s.current_line, 0);
         assert_eq!(s.current_col, 0);
         assert_eq!(s.scroll_offset, 0);
-        assert!(s.last_adjust_commit.is_none());
+        assert!(s.conflicts.is_empty());
     }
 
     #[test]
@@ -55,7 +139,7 @@ mod tests {
             current_line: 42,
             current_col: 8,
             scroll_offset: 30,
-            last_adjust_commit: Some("abc123".into()),
+            ..Session::default()
         };
         s.save(&path).unwrap();
 
@@ -64,7 +148,23 @@ mod tests {
         assert_eq!(loaded.current_line, 42);
         assert_eq!(loaded.current_col, 8);
         assert_eq!(loaded.scroll_offset, 30);
-        assert_eq!(loaded.last_adjust_commit.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn test_adjust_base_kept_in_store() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+        // Written by a build that kept the base in the session
+        std::fs::write(&path, r#"{"last_adjust_commit":"abc123","submodule_commits":{"vendor/lib":"def456"}}"#).unwrap();
+        let session = Session::load(&path).unwrap();
+        let base = AdjustBase::load(dir.path(), &session).unwrap();
+        assert_eq!(base.commit.as_deref(), Some("abc123"));
+        assert_eq!(base.submodules["vendor/lib"], "def456");
+
+        let moved = AdjustBase { commit: Some("fed321".into()), ..base };
+        moved.save(dir.path()).unwrap();
+        assert_eq!(AdjustBase::load(dir.path(), &session).unwrap(), moved);
+        assert_eq!(AdjustBase::load(dir.path(), &Session::default()).unwrap(), moved);
     }
 
     #[test]
@@ -74,4 +174,17 @@ mod tests {
         let s = Session::load(&path).unwrap();
         assert!(s.current_file.is_none());
     }
+
+    #[test]
+    fn test_session_from_other_versions_loads() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+        std::fs::write(&path, r#"{"current_line":7,"layout":"split"}"#).unwrap();
+
+        let s = Session::load(&path).unwrap();
+        assert_eq!(s.current_line, 7);
+        assert_eq!(s.format, unversioned_format());
+        s.save(&path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains(r#""layout": "split""#));
+    }
 }
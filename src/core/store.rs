@@ -1,21 +1,59 @@
 use crate::core::annotation::{Annotation, FileReviewState};
-use anyhow::{Context, Result};
+use crate::core::crypto::{self, StoreKey};
+use crate::core::search::SearchIndex;
+use crate::core::timing::TimeEntry;
+use crate::core::tombstone::Tombstone;
+use anyhow::{Context, Result, bail};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 pub struct Store {
     annotations_path: PathBuf,
     file_status_path: PathBuf,
+    quarantine_path: PathBuf,
+    tombstones_path: PathBuf,
+    time_path: PathBuf,
+    /// Encrypts everything written; encrypted files can't be read without it.
+    key: Option<StoreKey>,
+    /// `file_status.jsonl` as last read, with the modification time and size
+    /// it had then, so status lookups made every frame don't decrypt and
+    /// parse it again.
+    statuses: Mutex<Option<(FileStamp, Vec<FileReviewState>)>>,
 }
 
+/// Modification time and size of a file.
+type FileStamp = (SystemTime, u64);
+
 impl Store {
     pub fn new(annotator_dir: &Path) -> Self {
+        Self::with_key(annotator_dir, None)
+    }
+
+    /// Opens the store with the encryption key from the environment, if any,
+    /// finishing or discarding a rewrite that was interrupted by a crash.
+    pub fn open(annotator_dir: &Path) -> Result<Self> {
+        recover_journal(annotator_dir).with_context(|| format!("recovering the journal in {}", annotator_dir.display()))?;
+        Ok(Self::with_key(annotator_dir, StoreKey::from_env()?))
+    }
+
+    pub fn with_key(annotator_dir: &Path, key: Option<StoreKey>) -> Self {
         Self {
             annotations_path: annotator_dir.join("annotations.jsonl"),
             file_status_path: annotator_dir.join("file_status.jsonl"),
+            quarantine_path: annotator_dir.join("quarantine.jsonl"),
+            tombstones_path: annotator_dir.join("tombstones.jsonl"),
+            time_path: annotator_dir.join("time.jsonl"),
+            key,
+            statuses: Mutex::new(None),
         }
     }
 
+    pub fn key(&self) -> Option<&StoreKey> {
+        self.key.as_ref()
+    }
+
     pub fn ensure_dir(&self) -> Result<()> {
         if let Some(parent) = self.annotations_path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -26,17 +64,37 @@ impl Store {
     // --- Annotations ---
 
     pub fn load_annotations(&self) -> Result<Vec<Annotation>> {
-        load_jsonl(&self.annotations_path)
+        let mut annotations = load_jsonl(&self.annotations_path, self.key())?;
+        fill_tags(&mut annotations);
+        Ok(annotations)
+    }
+
+    /// Like `load_annotations`, but collects unparseable lines instead of failing.
+    pub fn scan_annotations(&self) -> Result<Scan<Annotation>> {
+        let mut scan = scan_jsonl(&self.annotations_path, self.key())?;
+        fill_tags(&mut scan.items);
+        Ok(scan)
     }
 
     pub fn append_annotation(&self, annotation: &Annotation) -> Result<()> {
         self.ensure_dir()?;
-        append_jsonl(&self.annotations_path, annotation)
+        append_jsonl(&self.annotations_path, annotation, self.key())
     }
 
     pub fn save_annotations(&self, annotations: &[Annotation]) -> Result<()> {
         self.ensure_dir()?;
-        atomic_write_jsonl(&self.annotations_path, annotations)
+        atomic_write_jsonl(&self.annotations_path, annotations, self.key())
+    }
+
+    /// Replaces annotations and file statuses together: after a crash either
+    /// both files are the old ones or both are the new ones.
+    pub fn save_all(&self, annotations: &[Annotation], statuses: &[FileReviewState]) -> Result<()> {
+        self.ensure_dir()?;
+        self.forget_statuses();
+        write_journaled(&[
+            (&self.annotations_path, crypto::encode(&to_jsonl(annotations)?, self.key())?),
+            (&self.file_status_path, crypto::encode(&to_jsonl(statuses)?, self.key())?),
+        ])
     }
 
     pub fn update_annotation(&self, updated: &Annotation) -> Result<()> {
@@ -53,6 +111,72 @@ impl Store {
         self.save_annotations(&filtered)
     }
 
+    /// Keeps only annotations whose file satisfies `keep`, returning the removed ones.
+    pub fn retain_annotations(&self, keep: impl Fn(&str) -> bool) -> Result<Vec<Annotation>> {
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .load_annotations()?
+            .into_iter()
+            .partition(|a| keep(&a.file_path));
+        if !removed.is_empty() {
+            self.save_annotations(&kept)?;
+        }
+        Ok(removed)
+    }
+
+    /// Appends annotations that could not be repaired to `quarantine.jsonl`.
+    pub fn quarantine_annotations(&self, annotations: &[Annotation]) -> Result<()> {
+        self.ensure_dir()?;
+        for a in annotations {
+            append_jsonl(&self.quarantine_path, a, self.key())?;
+        }
+        Ok(())
+    }
+
+    /// Moves lines of `annotations.jsonl` and `file_status.jsonl` that don't
+    /// parse to a `<file>.corrupt` sidecar, so the rest of the store loads.
+    /// Returns each file that had any, with how many lines were moved.
+    pub fn set_aside_malformed(&self) -> Result<Vec<(PathBuf, usize)>> {
+        let mut moved = Vec::new();
+        let annotations = set_aside_malformed::<Annotation>(&self.annotations_path, self.key())?;
+        let statuses = set_aside_malformed::<FileReviewState>(&self.file_status_path, self.key())?;
+        self.forget_statuses();
+        for (path, count) in [(&self.annotations_path, annotations), (&self.file_status_path, statuses)] {
+            if count > 0 {
+                moved.push((path.clone(), count));
+            }
+        }
+        Ok(moved)
+    }
+
+    // --- Deleted files ---
+
+    pub fn load_tombstones(&self) -> Result<Vec<Tombstone>> {
+        load_jsonl(&self.tombstones_path, self.key())
+    }
+
+    /// Appends annotations whose file was deleted to `tombstones.jsonl`.
+    pub fn archive_tombstones(&self, tombstones: &[Tombstone]) -> Result<()> {
+        self.ensure_dir()?;
+        for t in tombstones {
+            append_jsonl(&self.tombstones_path, t, self.key())?;
+        }
+        Ok(())
+    }
+
+    /// Brings an archived annotation back: adds `restored` and drops its
+    /// tombstone in one journaled write.
+    pub fn restore_tombstone(&self, restored: &Annotation) -> Result<()> {
+        let mut annotations = self.load_annotations()?;
+        annotations.push(restored.clone());
+        let mut tombstones = self.load_tombstones()?;
+        tombstones.retain(|t| t.annotation.id != restored.id);
+        self.ensure_dir()?;
+        write_journaled(&[
+            (&self.annotations_path, crypto::encode(&to_jsonl(&annotations)?, self.key())?),
+            (&self.tombstones_path, crypto::encode(&to_jsonl(&tombstones)?, self.key())?),
+        ])
+    }
+
     pub fn annotations_for_file(&self, file_path: &str) -> Result<Vec<Annotation>> {
         Ok(self
             .load_annotations()?
@@ -61,30 +185,189 @@ impl Store {
             .collect())
     }
 
+    /// Annotations whose text or path contains every term of `query`, ignoring
+    /// case, sorted by path and line.
+    pub fn search(&self, query: &str) -> Result<Vec<Annotation>> {
+        let annotations = self.load_annotations()?;
+        let found = SearchIndex::build(&annotations).search(query);
+        Ok(found.into_iter().map(|i| annotations[i].clone()).collect())
+    }
+
     // --- File status ---
 
     pub fn load_file_statuses(&self) -> Result<Vec<FileReviewState>> {
-        load_jsonl(&self.file_status_path)
+        let Ok(metadata) = std::fs::metadata(&self.file_status_path) else {
+            return load_jsonl(&self.file_status_path, self.key());
+        };
+        let stamp = (metadata.modified()?, metadata.len());
+        let mut cached = self.statuses.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((at, statuses)) = cached.as_ref()
+            && *at == stamp
+        {
+            return Ok(statuses.clone());
+        }
+        let statuses: Vec<FileReviewState> = load_jsonl(&self.file_status_path, self.key())?;
+        *cached = Some((stamp, statuses.clone()));
+        Ok(statuses)
+    }
+
+    /// Drops the cached statuses; a rewrite within the file system's
+    /// timestamp granularity could keep both modification time and size.
+    fn forget_statuses(&self) {
+        *self.statuses.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub fn scan_file_statuses(&self) -> Result<Scan<FileReviewState>> {
+        scan_jsonl(&self.file_status_path, self.key())
     }
 
     pub fn save_file_statuses(&self, statuses: &[FileReviewState]) -> Result<()> {
         self.ensure_dir()?;
-        atomic_write_jsonl(&self.file_status_path, statuses)
+        self.forget_statuses();
+        atomic_write_jsonl(&self.file_status_path, statuses, self.key())
+    }
+
+    /// Keeps only statuses whose file satisfies `keep`, returning the removed ones.
+    pub fn retain_file_statuses(&self, keep: impl Fn(&str) -> bool) -> Result<Vec<FileReviewState>> {
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .load_file_statuses()?
+            .into_iter()
+            .partition(|s| keep(&s.file_path));
+        if !removed.is_empty() {
+            self.save_file_statuses(&kept)?;
+        }
+        Ok(removed)
     }
 
     pub fn set_file_status(&self, file_path: &str, status: crate::core::annotation::FileStatus) -> Result<()> {
         let mut all = self.load_file_statuses()?;
         if let Some(existing) = all.iter_mut().find(|s| s.file_path == file_path) {
-            existing.status = status;
+            existing.set_status(status);
         } else {
-            all.push(FileReviewState {
-                file_path: file_path.to_string(),
-                status,
-            });
+            all.push(FileReviewState::new(file_path.to_string(), status));
         }
         self.save_file_statuses(&all)
     }
 
+    /// Checklist items ticked off for `file_path`.
+    pub fn checked_items(&self, file_path: &str) -> Result<Vec<String>> {
+        Ok(self
+            .load_file_statuses()?
+            .into_iter()
+            .find(|s| s.file_path == file_path)
+            .map(|s| s.checked)
+            .unwrap_or_default())
+    }
+
+    pub fn set_checked_items(&self, file_path: &str, checked: &[String]) -> Result<()> {
+        let mut all = self.load_file_statuses()?;
+        if let Some(existing) = all.iter_mut().find(|s| s.file_path == file_path) {
+            existing.checked = checked.to_vec();
+        } else {
+            let mut state = FileReviewState::new(file_path.to_string(), Default::default());
+            state.checked = checked.to_vec();
+            all.push(state);
+        }
+        self.save_file_statuses(&all)
+    }
+
+    /// Labels of `file_path`, sorted.
+    pub fn labels(&self, file_path: &str) -> Result<Vec<String>> {
+        Ok(self
+            .load_file_statuses()?
+            .into_iter()
+            .find(|s| s.file_path == file_path)
+            .map(|s| s.labels)
+            .unwrap_or_default())
+    }
+
+    pub fn set_labels(&self, file_path: &str, labels: &[String]) -> Result<()> {
+        let mut labels = labels.to_vec();
+        labels.sort();
+        labels.dedup();
+        let mut all = self.load_file_statuses()?;
+        if let Some(existing) = all.iter_mut().find(|s| s.file_path == file_path) {
+            existing.labels = labels;
+        } else {
+            let mut state = FileReviewState::new(file_path.to_string(), Default::default());
+            state.labels = labels;
+            all.push(state);
+        }
+        self.save_file_statuses(&all)
+    }
+
+    // --- Time tracking ---
+
+    pub fn load_time_entries(&self) -> Result<Vec<TimeEntry>> {
+        load_jsonl(&self.time_path, self.key())
+    }
+
+    pub fn append_time_entries(&self, entries: &[TimeEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.ensure_dir()?;
+        for entry in entries {
+            append_jsonl(&self.time_path, entry, self.key())?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the JSONL files sorted and without stale temp files, duplicate
+    /// status records, or a partial last line left by an interrupted append.
+    /// Refuses to run if any other line is malformed, since that needs `verify`.
+    pub fn compact(&self) -> Result<CompactReport> {
+        let mut report = CompactReport::default();
+        let dir = self.annotations_path.parent().unwrap_or(Path::new("."));
+        let paths = [&self.annotations_path, &self.file_status_path, &self.quarantine_path, &self.tombstones_path];
+        report.bytes_before = paths.iter().map(|p| file_size(p)).sum();
+
+        let mut annotations: Vec<Annotation> = load_compactable(&self.annotations_path, self.key(), &mut report)?;
+        let mut statuses: Vec<FileReviewState> = load_compactable(&self.file_status_path, self.key(), &mut report)?;
+        let mut quarantined: Vec<Annotation> = load_compactable(&self.quarantine_path, self.key(), &mut report)?;
+        let tombstones: Vec<Tombstone> = load_compactable(&self.tombstones_path, self.key(), &mut report)?;
+
+        if dir.exists() {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == "tmp") {
+                    report.bytes_before += file_size(&path);
+                    std::fs::remove_file(&path)?;
+                    report.removed_tmp.push(path);
+                }
+            }
+        }
+
+        let by_location = |a: &Annotation, b: &Annotation| {
+            (&a.file_path, a.start_line, a.end_line, a.created_at)
+                .cmp(&(&b.file_path, b.start_line, b.end_line, b.created_at))
+        };
+        annotations.sort_by(by_location);
+        quarantined.sort_by(by_location);
+        // The last record for a path wins, matching how set_file_status updates
+        statuses.reverse();
+        let mut seen = std::collections::HashSet::new();
+        statuses.retain(|s| seen.insert(s.file_path.clone()));
+        statuses.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let mut writes = Vec::new();
+        for (path, content) in [
+            (&self.annotations_path, to_jsonl(&annotations)?),
+            (&self.file_status_path, to_jsonl(&statuses)?),
+            (&self.quarantine_path, to_jsonl(&quarantined)?),
+            (&self.tombstones_path, to_jsonl(&tombstones)?),
+        ] {
+            if path.exists() {
+                writes.push((path.as_path(), crypto::encode(&content, self.key())?));
+            }
+        }
+        self.forget_statuses();
+        write_journaled(&writes).context("rewriting the store")?;
+
+        report.bytes_after = paths.iter().map(|p| file_size(p)).sum();
+        Ok(report)
+    }
+
     pub fn get_file_status(&self, file_path: &str) -> Result<crate::core::annotation::FileStatus> {
         let all = self.load_file_statuses()?;
         Ok(all
@@ -95,12 +378,18 @@ impl Store {
     }
 }
 
-fn load_jsonl<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+/// Records written before tags were stored get them from their text.
+fn fill_tags(annotations: &mut [Annotation]) {
+    for a in annotations.iter_mut().filter(|a| a.tags.is_empty()) {
+        a.tags = crate::core::annotation::parse_tags(&a.text);
+    }
+}
+
+fn load_jsonl<T: serde::de::DeserializeOwned>(path: &Path, key: Option<&StoreKey>) -> Result<Vec<T>> {
     if !path.exists() {
         return Ok(Vec::new());
     }
-    let content = std::fs::read_to_string(path)
-        .with_context(|| format!("reading {}", path.display()))?;
+    let content = crypto::read_text(path, key)?;
     let mut items = Vec::new();
     for (i, line) in content.lines().enumerate() {
         let line = line.trim();
@@ -114,29 +403,273 @@ fn load_jsonl<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
     Ok(items)
 }
 
-fn append_jsonl<T: serde::Serialize>(path: &Path, item: &T) -> Result<()> {
+#[derive(Debug, Default)]
+pub struct CompactReport {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub removed_tmp: Vec<PathBuf>,
+    /// Files whose unterminated, unparseable last line was dropped.
+    pub dropped_partial: Vec<PathBuf>,
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn load_compactable<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    key: Option<&StoreKey>,
+    report: &mut CompactReport,
+) -> Result<Vec<T>> {
+    let scan = scan_jsonl(path, key)?;
+    let Some(&(line, ref error)) = scan.malformed.first() else {
+        return Ok(scan.items);
+    };
+    let content = crypto::read_text(path, key)?;
+    let is_partial_tail = scan.malformed.len() == 1
+        && !content.ends_with('\n')
+        && line == content.lines().count();
+    if !is_partial_tail {
+        bail!(
+            "{}:{}: malformed record ({}); run 'annotator verify' and fix it before compacting",
+            path.display(),
+            line,
+            error
+        );
+    }
+    report.dropped_partial.push(path.to_path_buf());
+    Ok(scan.items)
+}
+
+/// Records read from a JSONL file, plus the lines that failed to parse.
+pub struct Scan<T> {
+    pub path: PathBuf,
+    pub items: Vec<T>,
+    /// (1-based line number, parse error)
+    pub malformed: Vec<(usize, String)>,
+}
+
+fn scan_jsonl<T: serde::de::DeserializeOwned>(path: &Path, key: Option<&StoreKey>) -> Result<Scan<T>> {
+    let mut scan = Scan {
+        path: path.to_path_buf(),
+        items: Vec::new(),
+        malformed: Vec::new(),
+    };
+    if !path.exists() {
+        return Ok(scan);
+    }
+    let content = crypto::read_text(path, key)?;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(item) => scan.items.push(item),
+            Err(e) => scan.malformed.push((i + 1, e.to_string())),
+        }
+    }
+    Ok(scan)
+}
+
+/// Where `set_aside_malformed` keeps the unparseable lines of `path`.
+pub fn corrupt_sidecar(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+fn set_aside_malformed<T: serde::de::DeserializeOwned + serde::Serialize>(
+    path: &Path,
+    key: Option<&StoreKey>,
+) -> Result<usize> {
+    let scan: Scan<T> = scan_jsonl(path, key)?;
+    if scan.malformed.is_empty() {
+        return Ok(0);
+    }
+    let content = crypto::read_text(path, key)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let sidecar = corrupt_sidecar(path);
+    let mut corrupt = if sidecar.exists() { crypto::read_text(&sidecar, key)? } else { String::new() };
+    for (line, _) in &scan.malformed {
+        corrupt.push_str(lines[line - 1]);
+        corrupt.push('\n');
+    }
+    // Both land or neither, so no line is lost or kept twice
+    write_journaled(&[
+        (sidecar.as_path(), crypto::encode(&corrupt, key)?),
+        (path, crypto::encode(&to_jsonl(&scan.items)?, key)?),
+    ])
+    .with_context(|| format!("setting aside malformed lines of {}", path.display()))?;
+    Ok(scan.malformed.len())
+}
+
+fn append_jsonl<T: serde::Serialize>(path: &Path, item: &T, key: Option<&StoreKey>) -> Result<()> {
     use std::io::Write;
+    let json = format!("{}\n", serde_json::to_string(item)?);
+    let header = read_header(path)?;
+    // Plaintext and encrypted records can't share a file, and an old binary
+    // age file can't be appended to, so those are rewritten as a whole once
+    let appendable = header.is_empty()
+        || match key {
+            Some(_) => crypto::is_armored(&header),
+            None => !crypto::is_encrypted(&header),
+        };
+    if !appendable {
+        let mut content = crypto::read_text(path, key)?;
+        content.push_str(&json);
+        return write_atomically(path, &crypto::encode(&content, key)?);
+    }
     let mut file = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
         .open(path)?;
-    let json = serde_json::to_string(item)?;
-    writeln!(file, "{json}")?;
+    file.write_all(&crypto::encode(&json, key)?)?;
     Ok(())
 }
 
-fn atomic_write_jsonl<T: serde::Serialize>(path: &Path, items: &[T]) -> Result<()> {
-    let tmp = path.with_extension("jsonl.tmp");
-    {
-        use std::io::Write;
-        let mut file = std::fs::File::create(&tmp)?;
-        for item in items {
-            let json = serde_json::to_string(item)?;
-            writeln!(file, "{json}")?;
+/// The first bytes of `path`, enough to tell how it is encoded, so appending
+/// doesn't read the whole file. Empty when the file doesn't exist.
+fn read_header(path: &Path) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let mut header = Vec::with_capacity(crypto::HEADER_LEN);
+    file.take(crypto::HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .with_context(|| format!("reading {}", path.display()))?;
+    Ok(header)
+}
+
+fn atomic_write_jsonl<T: serde::Serialize>(path: &Path, items: &[T], key: Option<&StoreKey>) -> Result<()> {
+    write_atomically(path, &crypto::encode(&to_jsonl(items)?, key)?)
+}
+
+fn to_jsonl<T: serde::Serialize>(items: &[T]) -> Result<String> {
+    let mut content = String::new();
+    for item in items {
+        content.push_str(&serde_json::to_string(item)?);
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    write_journaled(&[(path, data.to_vec())])
+}
+
+// --- Journal ---
+//
+// A rewrite first stages every new file as `<name>.new` and syncs it, then
+// creates `journal` listing the staged names. Creating the journal is the
+// commit point: from then on the rewrite is finished even after a crash, by
+// `recover_journal` renaming the staged files into place. Staged files
+// without a journal belong to a rewrite that never committed and are dropped.
+//
+// Every rewrite and recovery holds an exclusive lock on `lock`, so processes
+// sharing the store never stage over each other's files, and recovery never
+// mistakes a rewrite still in progress for one that crashed.
+
+const JOURNAL_FILE: &str = "journal";
+const LOCK_FILE: &str = "lock";
+const STAGED_EXTENSION: &str = "new";
+
+/// Replaces each file with its data, all or none of them surviving a crash.
+/// The files must share a directory.
+fn write_journaled(writes: &[(&Path, Vec<u8>)]) -> Result<()> {
+    let Some(dir) = writes.first().and_then(|(path, _)| path.parent()) else {
+        return Ok(());
+    };
+    let _lock = lock_dir(dir)?;
+    stage_writes(dir, writes)?;
+    apply_journal(dir)
+}
+
+/// Waits for and takes the journal lock of `dir`, held until dropped.
+fn lock_dir(dir: &Path) -> Result<std::fs::File> {
+    let path = dir.join(LOCK_FILE);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    file.lock().with_context(|| format!("locking {}", path.display()))?;
+    Ok(file)
+}
+
+fn stage_writes(dir: &Path, writes: &[(&Path, Vec<u8>)]) -> Result<()> {
+    let mut names = String::new();
+    for (path, data) in writes {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .with_context(|| format!("{} has no file name", path.display()))?;
+        write_synced(&staged_path(dir, name), data)?;
+        names.push_str(name);
+        names.push('\n');
+    }
+    let tmp = dir.join(format!("{}.tmp", JOURNAL_FILE));
+    write_synced(&tmp, names.as_bytes())?;
+    std::fs::rename(&tmp, dir.join(JOURNAL_FILE))?;
+    sync_dir(dir)
+}
+
+/// Moves the files listed in the journal into place and removes it.
+fn apply_journal(dir: &Path) -> Result<()> {
+    let journal = dir.join(JOURNAL_FILE);
+    for name in std::fs::read_to_string(&journal)?.lines().filter(|l| !l.is_empty()) {
+        let staged = staged_path(dir, name);
+        // Already moved if a previous recovery was interrupted
+        if staged.exists() {
+            std::fs::rename(&staged, dir.join(name))?;
+        }
+    }
+    sync_dir(dir)?;
+    std::fs::remove_file(&journal)?;
+    sync_dir(dir)
+}
+
+/// Finishes a committed rewrite or drops an uncommitted one.
+fn recover_journal(dir: &Path) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let _lock = lock_dir(dir)?;
+    if dir.join(JOURNAL_FILE).exists() {
+        return apply_journal(dir);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == STAGED_EXTENSION) {
+            std::fs::remove_file(&path)?;
         }
-        file.flush()?;
     }
-    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn staged_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.{}", name, STAGED_EXTENSION))
+}
+
+fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Makes renames in `dir` durable. Directories can't be synced on Windows,
+/// where renames are journaled by the filesystem instead.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
     Ok(())
 }
 
@@ -176,6 +709,14 @@ mod tests {
         assert_eq!(loaded[1], a2);
     }
 
+    #[test]
+    fn test_records_without_tags_get_them_from_text() {
+        let (dir, store) = make_store();
+        let json = r##"{"id":"00000000-0000-0000-0000-000000000000","file_path":"f.rs","start_line":1,"end_line":1,"text":"#perf slow","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}"##;
+        std::fs::write(dir.path().join(".annotator/annotations.jsonl"), format!("{}\n", json)).unwrap();
+        assert_eq!(store.load_annotations().unwrap()[0].tags, vec!["perf"]);
+    }
+
     #[test]
     fn test_atomic_rewrite() {
         let (_dir, store) = make_store();
@@ -186,7 +727,7 @@ mod tests {
         store.append_annotation(&a2).unwrap();
 
         // Rewrite with just a1
-        store.save_annotations(&[a1.clone()]).unwrap();
+        store.save_annotations(std::slice::from_ref(&a1)).unwrap();
         let loaded = store.load_annotations().unwrap();
         assert_eq!(loaded.len(), 1);
         assert_eq!(loaded[0], a1);
@@ -238,7 +779,7 @@ mod tests {
 
     #[test]
     fn test_file_status() {
-        let (_dir, store) = make_store();
+        let (dir, store) = make_store();
         assert_eq!(store.get_file_status("f.rs").unwrap(), FileStatus::Unreviewed);
 
         store.set_file_status("f.rs", FileStatus::Clean).unwrap();
@@ -246,5 +787,171 @@ mod tests {
 
         store.set_file_status("f.rs", FileStatus::Annotated).unwrap();
         assert_eq!(store.get_file_status("f.rs").unwrap(), FileStatus::Annotated);
+
+        // A write by another process shows through the cached statuses
+        Store::new(&dir.path().join(".annotator")).set_file_status("f.rs", FileStatus::Clean).unwrap();
+        assert_eq!(store.get_file_status("f.rs").unwrap(), FileStatus::Clean);
+    }
+
+    #[test]
+    fn test_retain_file_statuses() {
+        let (_dir, store) = make_store();
+        store.set_file_status("kept.rs", FileStatus::Clean).unwrap();
+        store.set_file_status("gone.rs", FileStatus::Annotated).unwrap();
+
+        let removed = store.retain_file_statuses(|f| f == "kept.rs").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].file_path, "gone.rs");
+        assert_eq!(store.load_file_statuses().unwrap().len(), 1);
+        assert_eq!(store.get_file_status("kept.rs").unwrap(), FileStatus::Clean);
+    }
+
+    #[test]
+    fn test_retain_annotations() {
+        let (_dir, store) = make_store();
+        let a1 = Annotation::new("kept.rs".into(), 1, 1, "a".into());
+        let a2 = Annotation::new("gone.rs".into(), 2, 2, "b".into());
+        store.append_annotation(&a1).unwrap();
+        store.append_annotation(&a2).unwrap();
+
+        let removed = store.retain_annotations(|f| f == "kept.rs").unwrap();
+        assert_eq!(removed, vec![a2]);
+        assert_eq!(store.load_annotations().unwrap(), vec![a1]);
+    }
+
+    #[test]
+    fn test_compact() {
+        let (dir, store) = make_store();
+        let annotator_dir = dir.path().join(".annotator");
+        let late = Annotation::new("b.rs".into(), 1, 1, "b".into());
+        let early = Annotation::new("a.rs".into(), 5, 5, "a".into());
+        store.append_annotation(&late).unwrap();
+        store.append_annotation(&early).unwrap();
+        store.set_file_status("b.rs", FileStatus::Clean).unwrap();
+        store.set_file_status("a.rs", FileStatus::Annotated).unwrap();
+
+        // Interrupted append and a stale temp file from an interrupted rewrite
+        let path = annotator_dir.join("annotations.jsonl");
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("{\"id\":\"trunc");
+        std::fs::write(&path, content).unwrap();
+        std::fs::write(annotator_dir.join("annotations.jsonl.tmp"), "junk").unwrap();
+
+        let report = store.compact().unwrap();
+        assert_eq!(report.dropped_partial, vec![path]);
+        assert_eq!(report.removed_tmp.len(), 1);
+        assert!(report.bytes_after < report.bytes_before);
+        assert_eq!(store.load_annotations().unwrap(), vec![early, late]);
+        let statuses = store.load_file_statuses().unwrap();
+        assert_eq!(statuses[0].file_path, "a.rs");
+        assert_eq!(statuses[1].file_path, "b.rs");
+    }
+
+    #[test]
+    fn test_compact_refuses_corrupt_middle_line() {
+        let (dir, store) = make_store();
+        let path = dir.path().join(".annotator/annotations.jsonl");
+        let a = Annotation::new("a.rs".into(), 1, 1, "a".into());
+        let line = serde_json::to_string(&a).unwrap();
+        std::fs::write(&path, format!("garbage\n{}\n", line)).unwrap();
+        assert!(store.compact().is_err());
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with("garbage"));
+    }
+
+    #[test]
+    fn test_set_aside_malformed() {
+        let (dir, store) = make_store();
+        let path = dir.path().join(".annotator/annotations.jsonl");
+        let a = Annotation::new("a.rs".into(), 1, 1, "a".into());
+        let line = serde_json::to_string(&a).unwrap();
+        std::fs::write(&path, format!("garbage\n{}\n{{\"id\":", line)).unwrap();
+        assert!(store.load_annotations().is_err());
+
+        assert_eq!(store.set_aside_malformed().unwrap(), vec![(path.clone(), 2)]);
+        assert_eq!(store.load_annotations().unwrap(), vec![a]);
+        let sidecar = dir.path().join(".annotator/annotations.jsonl.corrupt");
+        assert_eq!(std::fs::read_to_string(&sidecar).unwrap(), "garbage\n{\"id\":\n");
+        assert!(store.set_aside_malformed().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_store() {
+        let (dir, plain) = make_store();
+        let a = Annotation::new("a.rs".into(), 1, 1, "secret finding".into());
+        plain.append_annotation(&a).unwrap();
+
+        let annotator_dir = dir.path().join(".annotator");
+        let key = StoreKey::Identity(age::x25519::Identity::generate());
+        let store = Store::with_key(&annotator_dir, Some(key));
+        // Plaintext data is readable and encrypted on the next write
+        assert_eq!(store.load_annotations().unwrap(), vec![a.clone()]);
+        let b = Annotation::new("b.rs".into(), 2, 2, "another".into());
+        store.append_annotation(&b).unwrap();
+        store.set_file_status("a.rs", FileStatus::Annotated).unwrap();
+
+        for file in ["annotations.jsonl", "file_status.jsonl"] {
+            let raw = std::fs::read(annotator_dir.join(file)).unwrap();
+            assert!(crypto::is_encrypted(&raw));
+        }
+        assert_eq!(store.load_annotations().unwrap(), vec![a.clone(), b.clone()]);
+        assert_eq!(store.get_file_status("a.rs").unwrap(), FileStatus::Annotated);
+
+        // Once encrypted, appends add a block instead of rewriting the file
+        let before = std::fs::read(annotator_dir.join("annotations.jsonl")).unwrap();
+        let c = Annotation::new("c.rs".into(), 3, 3, "third".into());
+        store.append_annotation(&c).unwrap();
+        assert!(std::fs::read(annotator_dir.join("annotations.jsonl")).unwrap().starts_with(&before));
+        assert_eq!(store.load_annotations().unwrap(), vec![a, b, c]);
+        assert!(plain.load_annotations().is_err());
+        assert!(plain.append_annotation(&Annotation::new("c.rs".into(), 1, 1, "c".into())).is_err());
+    }
+
+    #[test]
+    fn test_journal_recovery() {
+        let (dir, store) = make_store();
+        let annotator_dir = dir.path().join(".annotator");
+        let a = Annotation::new("a.rs".into(), 1, 1, "a".into());
+        let b = Annotation::new("b.rs".into(), 2, 2, "b".into());
+        store.save_annotations(std::slice::from_ref(&a)).unwrap();
+        let staged = |ann: &Annotation| {
+            let status = FileReviewState::new(ann.file_path.clone(), FileStatus::Annotated);
+            vec![
+                (store.annotations_path.as_path(), to_jsonl(std::slice::from_ref(ann)).unwrap().into_bytes()),
+                (store.file_status_path.as_path(), to_jsonl(&[status]).unwrap().into_bytes()),
+            ]
+        };
+
+        // Crash before the journal was written: the old data stays
+        stage_writes(&annotator_dir, &staged(&b)).unwrap();
+        std::fs::remove_file(annotator_dir.join(JOURNAL_FILE)).unwrap();
+        let store = Store::open(&annotator_dir).unwrap();
+        assert_eq!(store.load_annotations().unwrap(), vec![a.clone()]);
+        assert!(store.load_file_statuses().unwrap().is_empty());
+        assert!(!staged_path(&annotator_dir, "annotations.jsonl").exists());
+
+        // Crash after it: both files are replaced on the next open
+        stage_writes(&annotator_dir, &staged(&b)).unwrap();
+        let store = Store::open(&annotator_dir).unwrap();
+        assert_eq!(store.load_annotations().unwrap(), vec![b]);
+        assert_eq!(store.get_file_status("b.rs").unwrap(), FileStatus::Annotated);
+        assert!(!annotator_dir.join(JOURNAL_FILE).exists());
+
+        store.save_all(std::slice::from_ref(&a), &[]).unwrap();
+        assert_eq!(store.load_annotations().unwrap(), vec![a.clone()]);
+        assert!(store.load_file_statuses().unwrap().is_empty());
+
+        // Files another process is still staging are left alone
+        let lock = lock_dir(&annotator_dir).unwrap();
+        write_synced(&staged_path(&annotator_dir, "annotations.jsonl"), b"").unwrap();
+        let recovery = {
+            let annotator_dir = annotator_dir.clone();
+            std::thread::spawn(move || Store::open(&annotator_dir).map(|_| ()))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(staged_path(&annotator_dir, "annotations.jsonl").exists());
+        drop(lock);
+        recovery.join().unwrap().unwrap();
+        assert!(!staged_path(&annotator_dir, "annotations.jsonl").exists());
+        assert_eq!(store.load_annotations().unwrap(), vec![a]);
     }
 }
@@ -0,0 +1,132 @@
+//! Time spent with each file focused in the TUI, for effort accounting.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Active time on one file during one stretch of a session.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeEntry {
+    pub file_path: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+/// Counts the time between input events towards the focused file. A gap
+/// longer than `idle_timeout` counts as idle and is dropped entirely.
+#[derive(Debug)]
+pub struct TimeTracker {
+    idle_timeout: Duration,
+    last_input: Option<Instant>,
+    pending: BTreeMap<String, (DateTime<Utc>, Duration)>,
+}
+
+impl TimeTracker {
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            last_input: None,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Records input at `now`; `file` is the file that was focused before it.
+    pub fn input(&mut self, now: Instant, file: Option<&str>) {
+        if let (Some(last), Some(file)) = (self.last_input, file) {
+            let gap = now.saturating_duration_since(last);
+            if gap <= self.idle_timeout {
+                let started = Utc::now() - chrono::Duration::from_std(gap).unwrap_or_default();
+                self.pending.entry(file.to_string()).or_insert((started, Duration::ZERO)).1 += gap;
+            }
+        }
+        self.last_input = Some(now);
+    }
+
+    /// Entries accumulated since the last call.
+    pub fn take_entries(&mut self) -> Vec<TimeEntry> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .filter(|(_, (_, duration))| !duration.is_zero())
+            .map(|(file_path, (started_at, duration))| TimeEntry {
+                file_path,
+                started_at,
+                duration_ms: duration.as_millis() as u64,
+            })
+            .collect()
+    }
+}
+
+/// Totals over all entries.
+#[derive(Debug, Default, PartialEq)]
+pub struct TimeSummary {
+    pub total: Duration,
+    /// Per file, most time first.
+    pub files: Vec<(String, Duration)>,
+    /// Distinct days with recorded time.
+    pub days: usize,
+}
+
+pub fn summarize(entries: &[TimeEntry]) -> TimeSummary {
+    let mut by_file: BTreeMap<&str, Duration> = BTreeMap::new();
+    let mut days = std::collections::BTreeSet::new();
+    for e in entries {
+        *by_file.entry(&e.file_path).or_default() += Duration::from_millis(e.duration_ms);
+        days.insert(e.started_at.date_naive());
+    }
+    let mut files: Vec<(String, Duration)> = by_file.into_iter().map(|(f, d)| (f.to_string(), d)).collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    TimeSummary {
+        total: files.iter().map(|(_, d)| *d).sum(),
+        files,
+        days: days.len(),
+    }
+}
+
+/// `1h 05m`, `4m 10s` or `12s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_gaps_are_dropped() {
+        let mut tracker = TimeTracker::new(Duration::from_secs(60));
+        let t0 = Instant::now();
+        tracker.input(t0, Some("a.rs"));
+        tracker.input(t0 + Duration::from_secs(30), Some("a.rs"));
+        // Away for ten minutes, then back on a.rs and over to b.rs
+        tracker.input(t0 + Duration::from_secs(630), Some("a.rs"));
+        tracker.input(t0 + Duration::from_secs(640), Some("a.rs"));
+        tracker.input(t0 + Duration::from_secs(660), Some("b.rs"));
+
+        let entries = tracker.take_entries();
+        let durations: Vec<(&str, u64)> = entries.iter().map(|e| (e.file_path.as_str(), e.duration_ms)).collect();
+        assert_eq!(durations, vec![("a.rs", 40_000), ("b.rs", 20_000)]);
+        assert!(tracker.take_entries().is_empty());
+    }
+
+    #[test]
+    fn test_summary() {
+        let day = |d: u32| format!("2026-03-{:02}T10:00:00Z", d).parse().unwrap();
+        let entry = |f: &str, d: u32, secs: u64| TimeEntry {
+            file_path: f.into(),
+            started_at: day(d),
+            duration_ms: secs * 1000,
+        };
+        let summary = summarize(&[entry("a.rs", 1, 60), entry("b.rs", 1, 300), entry("a.rs", 2, 30)]);
+        assert_eq!(summary.total, Duration::from_secs(390));
+        assert_eq!(summary.files[0], ("b.rs".to_string(), Duration::from_secs(300)));
+        assert_eq!(summary.days, 2);
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h 05m");
+        assert_eq!(format_duration(Duration::from_secs(250)), "4m 10s");
+    }
+}
@@ -0,0 +1,68 @@
+//! Annotations whose file was deleted, archived instead of dropped so they can
+//! be looked up or brought back.
+
+use crate::core::annotation::Annotation;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tombstone {
+    pub annotation: Annotation,
+    /// Commit that deleted the file.
+    pub deleted_in: String,
+    /// The annotated lines as they were just before the deletion.
+    #[serde(default)]
+    pub content: Vec<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+impl Tombstone {
+    /// Archives `annotation`, keeping its lines from `old_content`, the file
+    /// as it was before `deleted_in`.
+    pub fn new(annotation: Annotation, deleted_in: &str, old_content: &str) -> Self {
+        let content = old_content
+            .lines()
+            .skip(annotation.start_line.saturating_sub(1) as usize)
+            .take((annotation.end_line + 1).saturating_sub(annotation.start_line) as usize)
+            .map(String::from)
+            .collect();
+        Self {
+            annotation,
+            deleted_in: deleted_in.to_string(),
+            content,
+            archived_at: Utc::now(),
+        }
+    }
+}
+
+/// Finds the tombstone whose annotation id starts with `prefix`. Fails when
+/// none or several match.
+pub fn find_by_id<'a>(tombstones: &'a [Tombstone], prefix: &str) -> anyhow::Result<&'a Tombstone> {
+    let prefix = prefix.to_ascii_lowercase().replace('-', "");
+    let matches: Vec<&Tombstone> = tombstones
+        .iter()
+        .filter(|t| t.annotation.id.simple().to_string().starts_with(&prefix))
+        .collect();
+    match matches.as_slice() {
+        [t] => Ok(t),
+        [] => anyhow::bail!("No deleted annotation with id {}", prefix),
+        _ => anyhow::bail!("Id {} matches {} deleted annotations; give more digits", prefix, matches.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tombstone_keeps_annotated_lines() {
+        let a = Annotation::new("f.rs".into(), 2, 3, "note".into());
+        let t = Tombstone::new(a.clone(), "abc", "one\ntwo\nthree\nfour\n");
+        assert_eq!(t.content, vec!["two", "three"]);
+
+        let tombstones = vec![t];
+        let short = &a.id.simple().to_string()[..8];
+        assert_eq!(find_by_id(&tombstones, short).unwrap().annotation.id, a.id);
+        assert!(find_by_id(&tombstones, "zz").is_err());
+    }
+}
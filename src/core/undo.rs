@@ -1,12 +1,28 @@
-use crate::core::annotation::Annotation;
+use crate::core::annotation::{Annotation, FileStatus};
+use crate::core::crypto::StoreKey;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-#[derive(Debug, Clone)]
+/// Number of entries per stack kept when persisting, oldest dropped first.
+const MAX_PERSISTED: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum UndoAction {
     Create(Annotation),
     Delete(Annotation),
     Update {
-        old: Annotation,
-        new: Annotation,
+        old: Box<Annotation>,
+        new: Box<Annotation>,
+    },
+    FileStatus {
+        file_path: String,
+        old: FileStatus,
+        new: FileStatus,
+    },
+    /// Several mutations undone and redone as a single step.
+    Group {
+        actions: Vec<UndoAction>,
     },
 }
 
@@ -19,22 +35,59 @@ impl UndoAction {
                 old: new.clone(),
                 new: old.clone(),
             },
+            UndoAction::FileStatus { file_path, old, new } => UndoAction::FileStatus {
+                file_path: file_path.clone(),
+                old: *new,
+                new: *old,
+            },
+            UndoAction::Group { actions } => UndoAction::Group {
+                actions: actions.iter().rev().map(|a| a.invert()).collect(),
+            },
         }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct UndoStack {
     undo: Vec<UndoAction>,
     redo: Vec<UndoAction>,
+    /// Commit the recorded annotations' lines refer to, when saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    #[serde(skip)]
+    open_group: Option<Vec<UndoAction>>,
 }
 
 impl UndoStack {
     pub fn push(&mut self, action: UndoAction) {
+        if let Some(group) = self.open_group.as_mut() {
+            group.push(action);
+            return;
+        }
         self.undo.push(action);
         self.redo.clear();
     }
 
+    /// Starts collecting pushed actions into one undo step until `end_group`.
+    pub fn begin_group(&mut self) {
+        if self.open_group.is_none() {
+            self.open_group = Some(Vec::new());
+        }
+    }
+
+    /// Closes the group started by `begin_group`. Empty groups are dropped and
+    /// single-action groups are pushed as a plain action.
+    pub fn end_group(&mut self) {
+        let Some(mut actions) = self.open_group.take() else {
+            return;
+        };
+        match actions.len() {
+            0 => {}
+            1 => self.push(actions.remove(0)),
+            _ => self.push(UndoAction::Group { actions }),
+        }
+    }
+
     pub fn undo(&mut self) -> Option<UndoAction> {
         let action = self.undo.pop()?;
         let inverted = action.invert();
@@ -56,6 +109,42 @@ impl UndoStack {
     pub fn can_redo(&self) -> bool {
         !self.redo.is_empty()
     }
+
+    /// Forgets every step, for when the annotations were adjusted to other
+    /// lines and undoing would bring back the old ones.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The history saved for annotations on the lines of commit `base`. One
+    /// saved at another commit is dropped, as the annotations have been
+    /// adjusted since.
+    pub fn load(path: &Path, base: Option<&str>, key: Option<&StoreKey>) -> anyhow::Result<Self> {
+        if path.exists() {
+            let data = crate::core::crypto::read_text(path, key)?;
+            let stack: Self = serde_json::from_str(&data)?;
+            Ok(if stack.base.as_deref() == base { stack } else { Self::default() })
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Saves the most recent history for annotations on the lines of commit
+    /// `base`, encrypted when `key` is given since it holds annotation text.
+    pub fn save(&self, path: &Path, base: Option<&str>, key: Option<&StoreKey>) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let trimmed = UndoStack {
+            undo: self.undo[self.undo.len().saturating_sub(MAX_PERSISTED)..].to_vec(),
+            redo: self.redo[self.redo.len().saturating_sub(MAX_PERSISTED)..].to_vec(),
+            base: base.map(str::to_string),
+            open_group: None,
+        };
+        let data = serde_json::to_string(&trimmed)?;
+        std::fs::write(path, crate::core::crypto::encode(&data, key)?)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -105,13 +194,103 @@ mod tests {
         assert!(stack.redo().is_none());
     }
 
+    #[test]
+    fn test_group_is_single_step() {
+        let mut stack = UndoStack::default();
+        stack.begin_group();
+        stack.push(UndoAction::Create(make_annotation("a")));
+        stack.push(UndoAction::FileStatus {
+            file_path: "f.rs".into(),
+            old: FileStatus::Unreviewed,
+            new: FileStatus::Annotated,
+        });
+        assert!(!stack.can_undo());
+        stack.end_group();
+        assert!(stack.can_undo());
+
+        match stack.undo().unwrap() {
+            UndoAction::Group { actions } => {
+                assert_eq!(actions.len(), 2);
+                // Inverted in reverse order: status first, then the annotation
+                assert!(matches!(
+                    actions[0],
+                    UndoAction::FileStatus {
+                        new: FileStatus::Unreviewed,
+                        ..
+                    }
+                ));
+                assert!(matches!(actions[1], UndoAction::Delete(_)));
+            }
+            other => panic!("expected Group, got {:?}", other),
+        }
+        assert!(!stack.can_undo());
+    }
+
+    #[test]
+    fn test_group_collapsing() {
+        let mut stack = UndoStack::default();
+        stack.begin_group();
+        stack.end_group();
+        assert!(!stack.can_undo());
+
+        stack.begin_group();
+        stack.push(UndoAction::Create(make_annotation("a")));
+        stack.end_group();
+        assert!(matches!(stack.undo(), Some(UndoAction::Delete(_))));
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("undo.json");
+
+        let mut stack = UndoStack::default();
+        stack.push(UndoAction::Create(make_annotation("a")));
+        stack.push(UndoAction::Update {
+            old: Box::new(make_annotation("b")),
+            new: Box::new(make_annotation("c")),
+        });
+        stack.undo();
+        stack.save(&path, Some("c1"), None).unwrap();
+
+        let mut loaded = UndoStack::load(&path, Some("c1"), None).unwrap();
+        assert!(loaded.can_undo());
+        assert!(loaded.can_redo());
+        assert!(matches!(loaded.redo(), Some(UndoAction::Update { .. })));
+        assert!(matches!(loaded.undo(), Some(UndoAction::Update { .. })));
+        assert!(matches!(loaded.undo(), Some(UndoAction::Delete(_))));
+
+        // Adjusted to another commit since
+        let loaded = UndoStack::load(&path, Some("c2"), None).unwrap();
+        assert!(!loaded.can_undo() && !loaded.can_redo());
+    }
+
+    #[test]
+    fn test_save_trims_history() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("undo.json");
+
+        let mut stack = UndoStack::default();
+        for i in 0..MAX_PERSISTED + 10 {
+            stack.push(UndoAction::Create(make_annotation(&i.to_string())));
+        }
+        stack.save(&path, Some("c1"), None).unwrap();
+
+        let loaded = UndoStack::load(&path, Some("c1"), None).unwrap();
+        assert_eq!(loaded.undo.len(), MAX_PERSISTED);
+        match loaded.undo.last().unwrap() {
+            UndoAction::Create(a) => assert_eq!(a.text, (MAX_PERSISTED + 9).to_string()),
+            _ => panic!("expected Create"),
+        }
+    }
+
     #[test]
     fn test_update_invert() {
         let old = make_annotation("old");
         let new = make_annotation("new");
         let action = UndoAction::Update {
-            old: old.clone(),
-            new: new.clone(),
+            old: Box::new(old.clone()),
+            new: Box::new(new.clone()),
         };
         let inverted = action.invert();
         match inverted {
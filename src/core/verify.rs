@@ -0,0 +1,157 @@
+use crate::core::store::Store;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    MalformedLine {
+        store_file: PathBuf,
+        line: usize,
+        error: String,
+    },
+    DuplicateId {
+        id: Uuid,
+        count: usize,
+    },
+    MissingFile {
+        id: Uuid,
+        file_path: String,
+    },
+    InvalidRange {
+        id: Uuid,
+        file_path: String,
+        start: u32,
+        end: u32,
+        line_count: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MalformedLine { store_file, line, error } => {
+                write!(f, "{}:{}: malformed record: {}", store_file.display(), line, error)
+            }
+            Issue::DuplicateId { id, count } => {
+                write!(f, "annotation id {} appears {} times", id, count)
+            }
+            Issue::MissingFile { id, file_path } => {
+                write!(f, "annotation {} points at untracked file {}", id, file_path)
+            }
+            Issue::InvalidRange { id, file_path, start, end, line_count } => write!(
+                f,
+                "annotation {} has invalid range {}:{}-{} (file has {} lines)",
+                id, file_path, start, end, line_count
+            ),
+        }
+    }
+}
+
+/// Checks the store for malformed records, duplicate ids, and annotations
+/// whose file or line range no longer exists in the working tree.
+pub fn verify_store(store: &Store, repo_root: &Path, tracked: &HashSet<String>) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+
+    let annotations = store.scan_annotations()?;
+    let statuses = store.scan_file_statuses()?;
+    for (store_file, malformed) in [
+        (&annotations.path, &annotations.malformed),
+        (&statuses.path, &statuses.malformed),
+    ] {
+        for (line, error) in malformed {
+            issues.push(Issue::MalformedLine {
+                store_file: store_file.clone(),
+                line: *line,
+                error: error.clone(),
+            });
+        }
+    }
+
+    let mut id_counts: HashMap<Uuid, usize> = HashMap::new();
+    for a in &annotations.items {
+        *id_counts.entry(a.id).or_default() += 1;
+    }
+    let mut duplicates: Vec<_> = id_counts.into_iter().filter(|(_, n)| *n > 1).collect();
+    duplicates.sort();
+    for (id, count) in duplicates {
+        issues.push(Issue::DuplicateId { id, count });
+    }
+
+    let mut line_counts: HashMap<&str, usize> = HashMap::new();
+    for a in &annotations.items {
+        if !tracked.contains(&a.file_path) {
+            issues.push(Issue::MissingFile {
+                id: a.id,
+                file_path: a.file_path.clone(),
+            });
+            continue;
+        }
+        let line_count = *line_counts.entry(&a.file_path).or_insert_with(|| {
+            std::fs::read_to_string(repo_root.join(&a.file_path))
+                .map(|c| c.lines().count())
+                .unwrap_or(0)
+        });
+        if a.start_line == 0 || a.start_line > a.end_line || a.end_line as usize > line_count {
+            issues.push(Issue::InvalidRange {
+                id: a.id,
+                file_path: a.file_path.clone(),
+                start: a.start_line,
+                end: a.end_line,
+                line_count,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::annotation::Annotation;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, Store, HashSet<String>) {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.rs"), "1\n2\n3\n").unwrap();
+        let store = Store::new(&dir.path().join(".annotator"));
+        store.ensure_dir().unwrap();
+        let tracked = HashSet::from(["a.rs".to_string()]);
+        (dir, store, tracked)
+    }
+
+    #[test]
+    fn test_clean_store() {
+        let (dir, store, tracked) = setup();
+        store
+            .append_annotation(&Annotation::new("a.rs".into(), 1, 3, "ok".into()))
+            .unwrap();
+        assert!(verify_store(&store, dir.path(), &tracked).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_detects_problems() {
+        let (dir, store, tracked) = setup();
+        let dup = Annotation::new("a.rs".into(), 1, 1, "dup".into());
+        store.append_annotation(&dup).unwrap();
+        store.append_annotation(&dup).unwrap();
+        let past_end = Annotation::new("a.rs".into(), 2, 9, "past end".into());
+        store.append_annotation(&past_end).unwrap();
+        let missing = Annotation::new("gone.rs".into(), 1, 1, "gone".into());
+        store.append_annotation(&missing).unwrap();
+        let path = dir.path().join(".annotator/annotations.jsonl");
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("{not json\n");
+        std::fs::write(&path, content).unwrap();
+
+        let issues = verify_store(&store, dir.path(), &tracked).unwrap();
+        assert_eq!(issues.len(), 4);
+        assert!(matches!(issues[0], Issue::MalformedLine { line: 5, .. }));
+        assert_eq!(issues[1], Issue::DuplicateId { id: dup.id, count: 2 });
+        assert!(matches!(&issues[2], Issue::InvalidRange { id, line_count: 3, .. } if *id == past_end.id));
+        assert!(matches!(&issues[3], Issue::MissingFile { id, .. } if *id == missing.id));
+    }
+}
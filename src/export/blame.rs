@@ -0,0 +1,94 @@
+use crate::core::annotation::Annotation;
+
+const SUMMARY_WIDTH: usize = 32;
+
+/// One file line with the annotations covering it.
+#[derive(Debug, PartialEq)]
+pub struct BlameLine<'a> {
+    pub line: u32,
+    pub annotations: Vec<&'a Annotation>,
+}
+
+/// Pairs each line of `content` with the annotations covering it, earliest
+/// starting annotation first.
+pub fn blame<'a>(content: &[String], annotations: &[&'a Annotation]) -> Vec<BlameLine<'a>> {
+    let mut sorted = annotations.to_vec();
+    sorted.sort_by_key(|a| (a.start_line, a.end_line));
+    (1..=content.len() as u32)
+        .map(|line| BlameLine {
+            line,
+            annotations: sorted.iter().copied().filter(|a| a.contains_line(line)).collect(),
+        })
+        .collect()
+}
+
+/// Short id used in blame output, the first 8 hex digits.
+pub fn short_id(annotation: &Annotation) -> String {
+    annotation.id.simple().to_string()[..8].to_string()
+}
+
+/// First line of the text, cut to `SUMMARY_WIDTH` characters.
+pub fn summary(annotation: &Annotation) -> String {
+    let first = annotation.text.lines().next().unwrap_or("");
+    if first.chars().count() > SUMMARY_WIDTH {
+        let cut: String = first.chars().take(SUMMARY_WIDTH - 1).collect();
+        format!("{}…", cut)
+    } else {
+        first.to_string()
+    }
+}
+
+/// `git blame`-style text: annotation ids and the summary of the first one,
+/// the line number, then the line. `paint` styles the id column.
+pub fn export_blame(content: &[String], annotations: &[&Annotation], paint: impl Fn(&str) -> String) -> String {
+    let lines = blame(content, annotations);
+    let ids: Vec<String> = lines
+        .iter()
+        .map(|l| l.annotations.iter().map(|a| short_id(a)).collect::<Vec<_>>().join(","))
+        .collect();
+    let id_width = ids.iter().map(|s| s.len()).max().unwrap_or(0);
+    let number_width = content.len().max(1).to_string().len();
+
+    let mut out = String::new();
+    for ((line, ids), text) in lines.iter().zip(&ids).zip(content) {
+        let summary = line.annotations.first().map(|a| summary(a)).unwrap_or_default();
+        if id_width > 0 {
+            let padded = format!("{:<width$}", ids, width = id_width);
+            out.push_str(&paint(&padded));
+            out.push_str(&format!(" {:<width$} ", summary, width = SUMMARY_WIDTH));
+        }
+        out.push_str(&format!("{:>width$} | {}\n", line.line, text, width = number_width));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("line {}", i)).collect()
+    }
+
+    #[test]
+    fn test_blame_lines() {
+        let a = Annotation::new("f.rs".into(), 2, 3, "nit: first\nmore".into());
+        let b = Annotation::new("f.rs".into(), 3, 3, "second".into());
+        let out = export_blame(&content(4), &[&b, &a], |s| s.to_string());
+        let lines: Vec<&str> = out.lines().collect();
+
+        let (ida, idb) = (short_id(&a), short_id(&b));
+        assert!(lines[0].starts_with(&" ".repeat(17)), "{:?}", lines[0]);
+        assert!(lines[0].ends_with("1 | line 1"));
+        assert!(lines[1].starts_with(&format!("{}          nit: first ", ida)), "{:?}", lines[1]);
+        assert!(lines[2].starts_with(&format!("{},{} nit: first", ida, idb)));
+        assert!(lines[3].ends_with("4 | line 4"));
+    }
+
+    #[test]
+    fn test_no_annotations_is_plain_listing() {
+        assert_eq!(export_blame(&content(2), &[], |s| s.to_string()), "1 | line 1\n2 | line 2\n");
+        let long = Annotation::new("f.rs".into(), 1, 1, "x".repeat(50));
+        assert_eq!(summary(&long).chars().count(), SUMMARY_WIDTH);
+    }
+}
@@ -0,0 +1,77 @@
+//! Optional CSV export, built with `--features csv`: one row per annotation,
+//! for spreadsheets and issue-tracker imports.
+
+use crate::core::annotation::Annotation;
+
+const HEADER: &str = "file,start_line,end_line,severity,author,priority,text";
+
+pub struct Csv;
+
+impl super::Exporter for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn render(&self, context: &super::ExportContext) -> anyhow::Result<String> {
+        let mut out = format!("{HEADER}\n");
+        for (_, anns) in super::report_order(context.annotations) {
+            for a in anns {
+                out.push_str(&row(a));
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn row(a: &Annotation) -> String {
+    [
+        field(&a.file_path),
+        a.start_line.to_string(),
+        a.end_line.to_string(),
+        a.severity.map_or(String::new(), |s| s.as_str().to_string()),
+        field(a.author.as_deref().unwrap_or("")),
+        a.priority.to_string(),
+        field(&a.text),
+    ]
+    .join(",")
+}
+
+/// `value` quoted when it holds a comma, quote or line break, with quotes
+/// doubled (RFC 4180).
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportContext, Exporter};
+
+    #[test]
+    fn test_rows_quote_fields() {
+        let mut a = Annotation::new("a.rs".into(), 3, 4, "major: \"unwrap\", twice\nhere".into());
+        a.author = Some("Ann".into());
+        let csv = Csv
+            .render(&ExportContext {
+                annotations: &[a],
+                summary: None,
+                commit: None,
+                checklist: None,
+                repo_root: None,
+            })
+            .unwrap();
+        assert_eq!(
+            csv,
+            format!("{HEADER}\na.rs,3,4,major,Ann,0,\"major: \"\"unwrap\"\", twice\nhere\"\n")
+        );
+    }
+}
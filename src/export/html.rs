@@ -0,0 +1,396 @@
+use crate::core::annotation::{Annotation, Severity};
+use crate::core::heatmap::heatmap;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{IncludeBackground, styled_line_to_highlighted_html};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Lines of code shown above and below each annotated range.
+const CONTEXT_LINES: u32 = 3;
+
+/// Files highlighted in parallel before their sections are written out.
+const BATCH_FILES: usize = 64;
+
+/// Light bundled theme, readable on the white report background.
+const THEME: &str = "InspiredGitHub";
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; color: #222; }
+h2 code { font-size: 1rem; }
+.toolbar button { margin-right: .5rem; }
+details { border: 1px solid #ddd; border-left: 4px solid #999; border-radius: 4px; margin: .5rem 0; padding: .25rem .75rem; }
+details.blocker { border-left-color: #d33; }
+details.major { border-left-color: #e80; }
+details.minor { border-left-color: #db0; }
+details.nit { border-left-color: #6a6; }
+details.question { border-left-color: #58c; }
+summary { cursor: pointer; }
+.lines { font-weight: bold; }
+.severity { font-size: .8rem; text-transform: uppercase; margin-left: .5rem; color: #666; }
+.text { white-space: pre-wrap; }
+.meta { color: #777; font-size: .8rem; }
+pre { background: #fafafa; padding: .5rem 0; overflow-x: auto; }
+pre .line { display: block; padding: 0 .75rem; }
+pre .line.annotated { background: #fff4c4; }
+.heatmap td { padding: .1rem .5rem .1rem 0; }
+.heatmap .bar { width: 12rem; }
+.heatmap .bar div { height: .8rem; border-radius: 2px; }
+pre .number { display: inline-block; width: 4ch; margin-right: 1ch; text-align: right; color: #999; user-select: none; }
+";
+
+const SCRIPT: &str = "
+function setAll(open) { document.querySelectorAll('details').forEach(d => d.open = open); }
+";
+
+/// Single-file report with highlighted code around every annotation, each
+/// annotation collapsible.
+pub struct Html;
+
+impl super::Exporter for Html {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn render(&self, context: &super::ExportContext) -> anyhow::Result<String> {
+        let mut out = Vec::new();
+        self.write(context, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn write(&self, context: &super::ExportContext, out: &mut dyn Write) -> anyhow::Result<()> {
+        let syntaxes = SyntaxSet::load_defaults_newlines();
+        let themes = ThemeSet::load_defaults();
+        let code = CodeRenderer { syntaxes: &syntaxes, theme: &themes.themes[THEME] };
+
+        out.write_all(b"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n")?;
+        write!(out, "<title>Annotations</title>\n<style>{STYLE}</style>\n<script>{SCRIPT}</script>\n")?;
+        out.write_all(b"</head>\n<body>\n<h1>Annotations</h1>\n")?;
+        if let Some(summary) = context.summary {
+            out.write_all(summary_list(context.annotations, summary).as_bytes())?;
+        }
+        if context.annotations.is_empty() {
+            out.write_all(b"<p>No annotations found.</p>\n</body>\n</html>\n")?;
+            return Ok(());
+        }
+        let sources = Sources::open(context.repo_root, context.commit);
+        out.write_all(heatmap_table(context.annotations, &sources).as_bytes())?;
+        out.write_all(b"<p class=\"toolbar\"><button onclick=\"setAll(true)\">Expand all</button>")?;
+        out.write_all(b"<button onclick=\"setAll(false)\">Collapse all</button></p>\n")?;
+
+        // Files are read one after the other through a single repository
+        // handle, which git2 can't share between threads, then highlighted
+        // in parallel; each batch is written out before the next is read
+        for batch in super::report_order(context.annotations).chunks(BATCH_FILES) {
+            let contents: Vec<Option<String>> = batch.iter().map(|(file, _)| sources.read(file)).collect();
+            let sections: Vec<String> = batch
+                .par_iter()
+                .zip(contents.par_iter())
+                .map(|((file, anns), source)| file_section(file, anns, source.as_deref(), &code))
+                .collect();
+            for section in sections {
+                out.write_all(section.as_bytes())?;
+            }
+        }
+        out.write_all(b"</body>\n</html>\n")?;
+        Ok(())
+    }
+}
+
+/// Where the annotated files are read from: the repository at a commit, the
+/// working tree, or nowhere when the report must not quote code.
+enum Sources<'a> {
+    None,
+    WorkTree(&'a Path),
+    Commit(git2::Repository, &'a str),
+}
+
+impl<'a> Sources<'a> {
+    fn open(repo_root: Option<&'a Path>, commit: Option<&'a str>) -> Self {
+        match (repo_root, commit) {
+            (None, _) => Sources::None,
+            (Some(root), None) => Sources::WorkTree(root),
+            (Some(root), Some(commit)) => match crate::git::repo::open_repo(root) {
+                Ok(repo) => Sources::Commit(repo, commit),
+                Err(_) => Sources::None,
+            },
+        }
+    }
+
+    /// The file's content, if it can be read.
+    fn read(&self, file: &str) -> Option<String> {
+        match self {
+            Sources::None => None,
+            Sources::WorkTree(root) => std::fs::read_to_string(root.join(file)).ok(),
+            Sources::Commit(repo, commit) => crate::git::repo::read_file_at(repo, commit, file).ok().flatten(),
+        }
+    }
+}
+
+/// A file's section: its annotations, each with the highlighted code around it.
+fn file_section(file: &str, anns: &[&Annotation], source: Option<&str>, code: &CodeRenderer) -> String {
+    let highlighted = source.map(|s| code.highlight(s, file)).unwrap_or_default();
+    let mut out = format!("<section>\n<h2><code>{}</code></h2>\n", escape(file));
+    for a in anns {
+        out.push_str(&annotation_details(a, &highlighted, code));
+    }
+    out.push_str("</section>\n");
+    out
+}
+
+fn annotation_details(a: &Annotation, highlighted: &[String], code: &CodeRenderer) -> String {
+    let class = a.severity.map_or("", |s| s.as_str());
+    let lines = if a.start_line == a.end_line {
+        format!("Line {}", a.start_line)
+    } else {
+        format!("Lines {}-{}", a.start_line, a.end_line)
+    };
+    let first_line = a.text.lines().next().unwrap_or_default();
+    let severity = a
+        .severity
+        .map(|s| format!("<span class=\"severity\">{}</span>", s.as_str()))
+        .unwrap_or_default();
+
+    let mut out = format!("<details open class=\"{class}\" id=\"{}\">\n", a.id);
+    out.push_str(&format!(
+        "<summary><span class=\"lines\">{}</span>{} {}</summary>\n",
+        lines,
+        severity,
+        escape(first_line)
+    ));
+    out.push_str(&format!("<div class=\"text\">{}</div>\n", escape(&a.text)));
+    out.push_str(&format!("<div class=\"meta\">{}</div>\n", escape(&meta(a))));
+    if !highlighted.is_empty() {
+        out.push_str(&code_block(a, highlighted, 1));
+    } else if let Some(snippet) = &a.snippet {
+        // The file is gone or unreadable; quote what was annotated
+        out.push_str(&code_block(a, &code.highlight(snippet, &a.file_path), a.start_line));
+    }
+    out.push_str("</details>\n");
+    out
+}
+
+/// Author, creation date, commit and priority of an annotation.
+fn meta(a: &Annotation) -> String {
+    let mut parts = Vec::new();
+    if let Some(author) = &a.author {
+        parts.push(author.clone());
+    }
+    parts.push(format!("created {}", a.created_at.format("%Y-%m-%d %H:%M UTC")));
+    if let Some(commit) = &a.commit {
+        parts.push(format!("at {}", crate::git::repo::short_id(commit)));
+    }
+    if a.priority > 0 {
+        parts.push(format!("priority {}", a.priority));
+    }
+    parts.join(" · ")
+}
+
+/// The annotated range with `CONTEXT_LINES` around it, from `highlighted`
+/// lines numbered from `first_number`.
+fn code_block(a: &Annotation, highlighted: &[String], first_number: u32) -> String {
+    let first = a.start_line.saturating_sub(CONTEXT_LINES).max(first_number);
+    let mut out = String::from("<pre>");
+    for number in first..=a.end_line + CONTEXT_LINES {
+        let Some(line) = highlighted.get((number - first_number) as usize) else {
+            break;
+        };
+        let class = if a.contains_line(number) { "line annotated" } else { "line" };
+        out.push_str(&format!("<span class=\"{class}\"><span class=\"number\">{number}</span>{line}</span>"));
+    }
+    out.push_str("</pre>\n");
+    out
+}
+
+struct CodeRenderer<'a> {
+    syntaxes: &'a SyntaxSet,
+    theme: &'a syntect::highlighting::Theme,
+}
+
+impl CodeRenderer<'_> {
+    /// Each line of `content` as highlighted HTML, without its line break.
+    fn highlight(&self, content: &str, file_path: &str) -> Vec<String> {
+        let syntax = self
+            .syntaxes
+            .find_syntax_for_file(file_path)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, self.theme);
+        LinesWithEndings::from(content)
+            .map(|line| {
+                // The newline goes through the highlighter, which expects
+                // it, but not into the output
+                highlighter
+                    .highlight_line(line, self.syntaxes)
+                    .and_then(|regions| {
+                        let regions: Vec<_> = regions.into_iter().map(|(style, text)| (style, trim_newline(text))).collect();
+                        styled_line_to_highlighted_html(&regions, IncludeBackground::No)
+                    })
+                    .unwrap_or_else(|_| escape(trim_newline(line)))
+            })
+            .collect()
+    }
+}
+
+fn trim_newline(text: &str) -> &str {
+    text.trim_end_matches(['\n', '\r'])
+}
+
+fn summary_list(annotations: &[Annotation], summary: &super::markdown::ReviewSummary) -> String {
+    let mut out = String::from("<ul>\n");
+    if !summary.reviewers.is_empty() {
+        out.push_str(&format!("<li><b>Reviewers</b>: {}</li>\n", escape(&summary.reviewers.join(", "))));
+    }
+    if let Some(commit) = &summary.commit {
+        out.push_str(&format!("<li><b>Commit</b>: <code>{}</code></li>\n", crate::git::repo::short_id(commit)));
+    }
+    let counts: Vec<String> = Severity::ALL
+        .iter()
+        .map(|s| (s, annotations.iter().filter(|a| a.severity == Some(*s)).count()))
+        .filter(|(_, n)| *n > 0)
+        .map(|(s, n)| format!("{} {}", n, s.as_str()))
+        .collect();
+    out.push_str(&format!("<li><b>Annotations</b>: {}", annotations.len()));
+    if !counts.is_empty() {
+        out.push_str(&format!(" ({})", counts.join(", ")));
+    }
+    out.push_str("</li>\n");
+    if summary.files > 0 {
+        out.push_str(&format!(
+            "<li><b>Files</b>: {} of {} reviewed</li>\n",
+            summary.annotated + summary.clean,
+            summary.files
+        ));
+    }
+    out.push_str("</ul>\n");
+    out
+}
+
+/// Annotated files, densest first, each with a bar as long as its share of
+/// the densest file's heat per hundred lines and shaded from yellow to red.
+/// Files that can't be read are ranked and measured by heat alone.
+fn heatmap_table(annotations: &[Annotation], sources: &Sources) -> String {
+    let mut files: Vec<&str> = annotations.iter().map(|a| a.file_path.as_str()).collect();
+    files.sort();
+    files.dedup();
+    let line_counts: HashMap<String, usize> = files
+        .into_iter()
+        .filter_map(|f| Some((f.to_string(), sources.read(f)?.lines().count())))
+        .filter(|(_, lines)| *lines > 0)
+        .collect();
+    let files = heatmap(annotations, &line_counts);
+    let densest = files.first().and_then(|f| f.density()).filter(|d| *d > 0.0);
+    let hottest = files.iter().map(|f| f.heat).max().unwrap_or(1).max(1);
+    let mut out = String::from("<h2>Heatmap</h2>\n<table class=\"heatmap\">\n");
+    out.push_str("<tr><th></th><th>Annotations</th><th>Heat</th><th>Per 100 lines</th><th></th><th>File</th></tr>\n");
+    for file in &files {
+        let fraction = match (file.density(), densest) {
+            (Some(density), Some(densest)) => density / densest,
+            _ => f64::from(file.heat) / f64::from(hottest),
+        };
+        let percent = (fraction.clamp(0.0, 1.0) * 100.0).round() as u32;
+        let hue = 50 - percent / 2;
+        out.push_str(&format!(
+            "<tr><td class=\"bar\"><div style=\"width: {percent}%; background: hsl({hue}, 85%, 50%)\"></div></td>\
+             <td>{}</td><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td></tr>\n",
+            file.annotations,
+            file.heat,
+            file.density().map_or("-".to_string(), |d| format!("{:.1}", d)),
+            file.worst.map_or("", |s| s.as_str()),
+            escape(file.path)
+        ));
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportContext, Exporter};
+
+    #[test]
+    fn test_code_around_annotations() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let content: String = (1..=10).map(|n| format!("let x{n} = {n};\n")).collect();
+        std::fs::write(dir.path().join("a.rs"), content).unwrap();
+        let mut a = Annotation::new("a.rs".into(), 5, 6, "major: <b>unsafe</b> & wrong".into());
+        let mut gone = Annotation::new("gone.rs".into(), 2, 2, "stale".into());
+        gone.snippet = Some("fn gone() {}".into());
+        a.author = Some("Ann".into());
+
+        let html = Html
+            .render(&ExportContext {
+                annotations: &[a, gone],
+                summary: None,
+                commit: None,
+                checklist: None,
+                repo_root: Some(dir.path()),
+            })
+            .unwrap();
+        assert!(html.contains("<details open class=\"major\""));
+        assert!(html.contains("major: &lt;b&gt;unsafe&lt;/b&gt; &amp; wrong"));
+        assert!(!html.contains("<b>unsafe"));
+        // Three lines of context on each side of 5-6
+        assert!(html.contains("<span class=\"number\">2</span>"));
+        assert!(html.contains("<span class=\"number\">9</span>"));
+        assert!(!html.contains("<span class=\"number\">10</span>"));
+        assert_eq!(html.matches("line annotated").count(), 3);
+        assert!(html.contains("gone"));
+        // The major finding makes a.rs the densest file, at 4 per 10 lines;
+        // gone.rs can't be read and is measured by heat alone
+        assert!(html.contains("<div style=\"width: 100%; background: hsl(0, 85%, 50%)\"></div></td><td>1</td><td>4</td><td>40.0</td><td>major</td><td><code>a.rs</code>"));
+        assert!(html.contains("width: 25%"));
+        assert!(html.contains("<td>1</td><td>1</td><td>-</td><td></td><td><code>gone.rs</code>"));
+    }
+
+    #[test]
+    fn test_sections_keep_report_order_across_batches() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let annotations: Vec<Annotation> = (0..BATCH_FILES * 2 + 5)
+            .map(|n| {
+                let file = format!("f{:03}.rs", n);
+                std::fs::write(dir.path().join(&file), "fn f() {}\n").unwrap();
+                Annotation::new(file, 1, 1, "x".into())
+            })
+            .collect();
+        let context = ExportContext {
+            annotations: &annotations,
+            summary: None,
+            commit: None,
+            checklist: None,
+            repo_root: Some(dir.path()),
+        };
+        let html = Html.render(&context).unwrap();
+        let positions: Vec<usize> = annotations
+            .iter()
+            .map(|a| html.find(&format!("<h2><code>{}</code></h2>", a.file_path)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(html.matches("<span class=\"number\">1</span>").count(), annotations.len());
+    }
+}
@@ -1,13 +1,34 @@
-use crate::core::annotation::Annotation;
-use serde::Serialize;
+use crate::core::annotation::{Annotation, Severity, is_zero};
+use crate::core::checklist::Completion;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use uuid::Uuid;
 
 #[derive(Serialize)]
 struct ExportAnnotation<'a> {
+    id: Uuid,
     file_path: &'a str,
     start_line: u32,
     end_line: u32,
     text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a str>,
+    /// Commit the annotation was written against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<&'a str>,
+    /// Ids of related annotations.
+    #[serde(skip_serializing_if = "<[Uuid]>::is_empty")]
+    links: &'a [Uuid],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "is_zero")]
+    priority: u32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
 }
 
 #[derive(Serialize)]
@@ -18,50 +39,204 @@ struct ExportFile<'a> {
 
 #[derive(Serialize)]
 struct ExportRoot<'a> {
+    /// Commit the line numbers refer to, so an import can adjust them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<&'a str>,
     files: Vec<ExportFile<'a>>,
     total_annotations: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checklist: Option<ExportChecklist<'a>>,
 }
 
-pub fn export_json(annotations: &[Annotation]) -> anyhow::Result<String> {
-    let mut by_file: BTreeMap<&str, Vec<&Annotation>> = BTreeMap::new();
-    for a in annotations {
-        by_file.entry(&a.file_path).or_default().push(a);
+#[derive(Serialize)]
+struct ExportChecklist<'a> {
+    items: &'a [String],
+    /// Ticked items by file.
+    files: &'a BTreeMap<String, Vec<String>>,
+}
+
+/// Structured export that `import` and overlays read back.
+pub struct Json;
+
+impl super::Exporter for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
     }
 
-    let files: Vec<ExportFile> = by_file
+    fn render(&self, context: &super::ExportContext) -> anyhow::Result<String> {
+        render_json(context.annotations, context.commit, context.checklist)
+    }
+}
+
+pub fn export_json(annotations: &[Annotation], commit: Option<&str>) -> anyhow::Result<String> {
+    render_json(annotations, commit, None)
+}
+
+fn render_json(annotations: &[Annotation], commit: Option<&str>, checklist: Option<&Completion>) -> anyhow::Result<String> {
+    let files: Vec<ExportFile> = super::report_order(annotations)
         .into_iter()
-        .map(|(file, mut anns)| {
-            anns.sort_by(|a, b| b.start_line.cmp(&a.start_line));
-            ExportFile {
-                file,
-                annotations: anns
-                    .iter()
-                    .map(|a| ExportAnnotation {
-                        file_path: &a.file_path,
-                        start_line: a.start_line,
-                        end_line: a.end_line,
-                        text: &a.text,
-                    })
-                    .collect(),
-            }
+        .map(|(file, anns)| ExportFile {
+            file,
+            annotations: anns
+                .iter()
+                .map(|a| ExportAnnotation {
+                    id: a.id,
+                    file_path: &a.file_path,
+                    start_line: a.start_line,
+                    end_line: a.end_line,
+                    text: &a.text,
+                    severity: a.severity,
+                    author: a.author.as_deref(),
+                    commit: a.commit.as_deref(),
+                    links: &a.links,
+                    tags: &a.tags,
+                    priority: a.priority,
+                    created_at: a.created_at,
+                    updated_at: a.updated_at,
+                })
+                .collect(),
         })
         .collect();
 
     let root = ExportRoot {
+        commit,
         total_annotations: annotations.len(),
         files,
+        checklist: checklist.map(|c| ExportChecklist { items: &c.items, files: &c.files }),
     };
 
     Ok(serde_json::to_string_pretty(&root)?)
 }
 
+#[derive(Deserialize)]
+struct ImportAnnotation {
+    /// Absent for annotations created outside annotator.
+    #[serde(default)]
+    id: Option<Uuid>,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+    text: String,
+    #[serde(default)]
+    severity: Option<Severity>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    commit: Option<String>,
+    #[serde(default)]
+    links: Vec<Uuid>,
+    #[serde(default)]
+    priority: u32,
+    #[serde(default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct ImportFile {
+    annotations: Vec<ImportAnnotation>,
+}
+
+#[derive(Deserialize)]
+struct ImportRoot {
+    #[serde(default)]
+    commit: Option<String>,
+    files: Vec<ImportFile>,
+}
+
+/// Annotations read back from a JSON export.
+#[derive(Debug)]
+pub struct Imported {
+    /// Commit their line numbers refer to, if the export recorded one.
+    pub commit: Option<String>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Parses the format written by `export_json`. Annotations without an id get
+/// a new one.
+pub fn import_json(data: &str) -> anyhow::Result<Imported> {
+    let root: ImportRoot = serde_json::from_str(data).context("parsing exported annotations")?;
+    let annotations = root
+        .files
+        .into_iter()
+        .flat_map(|f| f.annotations)
+        .map(|i| {
+            let mut a = Annotation::new(i.file_path, i.start_line, i.end_line, i.text);
+            if let Some(id) = i.id {
+                a.id = id;
+            }
+            a.severity = i.severity.or(a.severity);
+            a.author = i.author;
+            a.commit = i.commit;
+            a.links = i.links;
+            a.priority = i.priority;
+            a.created_at = i.created_at.unwrap_or(a.created_at);
+            a.updated_at = i.updated_at.unwrap_or(a.updated_at);
+            a.text_edited_at = Some(a.updated_at);
+            a
+        })
+        .collect();
+    Ok(Imported { commit: root.commit, annotations })
+}
+
+/// What importing did to the store.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportCounts {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// Merges imported annotations into `existing`: ones with a known id replace
+/// its location, text, severity and author, others are added.
+pub fn apply_import(existing: &mut Vec<Annotation>, imported: Vec<Annotation>) -> ImportCounts {
+    let mut counts = ImportCounts::default();
+    for new in imported {
+        match existing.iter_mut().find(|a| a.id == new.id) {
+            Some(current) => {
+                let same = crate::core::changes::changed_fields(current, &new).is_empty()
+                    && current.severity == new.severity
+                    && current.author == new.author;
+                if same {
+                    counts.unchanged += 1;
+                    continue;
+                }
+                if current.text != new.text {
+                    current.text_edited_at = Some(chrono::Utc::now());
+                }
+                current.file_path = new.file_path;
+                current.start_line = new.start_line;
+                current.end_line = new.end_line;
+                current.text = new.text;
+                current.tags = new.tags;
+                current.severity = new.severity;
+                current.author = new.author;
+                current.links = new.links;
+                current.priority = new.priority;
+                current.updated_at = chrono::Utc::now();
+                counts.updated += 1;
+            }
+            None => {
+                existing.push(new);
+                counts.added += 1;
+            }
+        }
+    }
+    counts
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_empty() {
-        let json = export_json(&[]).unwrap();
+        let json = export_json(&[], None).unwrap();
         assert!(json.contains("\"total_annotations\": 0"));
     }
 
@@ -72,13 +247,58 @@ mod tests {
             Annotation::new("src/a.rs".into(), 20, 25, "second".into()),
             Annotation::new("src/b.rs".into(), 1, 1, "note".into()),
         ];
-        let json = export_json(&anns).unwrap();
+        let json = export_json(&anns, Some("abc")).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed["total_annotations"], 3);
+        assert_eq!(parsed["commit"], "abc");
         assert_eq!(parsed["files"].as_array().unwrap().len(), 2);
         // Within a.rs, annotations should be in reverse line order
         let a_file = &parsed["files"][0];
         assert_eq!(a_file["annotations"][0]["start_line"], 20);
         assert_eq!(a_file["annotations"][1]["start_line"], 5);
+        assert!(a_file["annotations"][0]["created_at"].is_string());
+        assert!(a_file["annotations"][0].get("commit").is_none());
+        assert!(parsed.get("checklist").is_none());
+
+        let checklist = Completion {
+            items: vec!["Errors".into()],
+            files: [("src/a.rs".to_string(), vec!["Errors".to_string()])].into(),
+        };
+        let json = render_json(&anns, None, Some(&checklist)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["checklist"]["files"]["src/a.rs"], serde_json::json!(["Errors"]));
+        assert!(import_json(&json).is_ok());
+    }
+
+    #[test]
+    fn test_import_roundtrip_updates_by_id() {
+        let mut store = vec![
+            Annotation::new("a.rs".into(), 1, 2, "keep".into()),
+            Annotation::new("a.rs".into(), 5, 5, "old text".into()),
+        ];
+        let mut edited = store.clone();
+        edited[1].text = "major: new text".into();
+        edited[1].links = vec![edited[0].id];
+        edited[1].commit = Some("c0".into());
+        let json = export_json(&edited, Some("c1")).unwrap();
+        let external = json.replacen(
+            "\"files\": [",
+            "\"files\": [{\"file\": \"b.rs\", \"annotations\": [{\"file_path\": \"b.rs\", \"start_line\": 3, \"end_line\": 3, \"text\": \"from tracker\"}]},",
+            1,
+        );
+
+        let imported = import_json(&external).unwrap();
+        assert_eq!(imported.commit.as_deref(), Some("c1"));
+        let counts = apply_import(&mut store, imported.annotations);
+        assert_eq!(counts, ImportCounts { added: 1, updated: 1, unchanged: 1 });
+        assert_eq!(store.len(), 3);
+        assert_eq!(store[1].text, "major: new text");
+        assert_eq!(store[1].links, vec![store[0].id]);
+        assert_eq!(store[2].file_path, "b.rs");
+
+        let roundtrip = import_json(&export_json(&edited, None).unwrap()).unwrap().annotations;
+        let back = roundtrip.iter().find(|a| a.id == edited[1].id).unwrap();
+        assert_eq!(back.commit.as_deref(), Some("c0"));
+        assert_eq!(back.created_at, edited[1].created_at);
     }
 }
@@ -1,30 +1,89 @@
-use crate::core::annotation::Annotation;
-use std::collections::BTreeMap;
+use crate::core::annotation::{Annotation, FileReviewState, FileStatus, Severity};
+use crate::core::checklist::Completion;
 
-pub fn export_markdown(annotations: &[Annotation]) -> String {
-    if annotations.is_empty() {
-        return "# Annotations\n\nNo annotations found.\n".to_string();
+/// Review context for the summary section at the top of the report.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewSummary {
+    pub reviewers: Vec<String>,
+    /// Commit the annotations were last adjusted to.
+    pub commit: Option<String>,
+    pub files: usize,
+    pub annotated: usize,
+    pub clean: usize,
+}
+
+impl ReviewSummary {
+    /// Summary of `annotations` over a review of `files` files; the
+    /// reviewers are the annotations' authors, else `fallback_reviewer`.
+    pub fn new(
+        annotations: &[Annotation],
+        fallback_reviewer: Option<String>,
+        commit: Option<String>,
+        files: usize,
+        statuses: &[FileReviewState],
+    ) -> Self {
+        let mut reviewers: Vec<String> = annotations.iter().filter_map(|a| a.author.clone()).collect();
+        reviewers.sort();
+        reviewers.dedup();
+        if reviewers.is_empty() {
+            reviewers.extend(fallback_reviewer);
+        }
+        let count = |status| statuses.iter().filter(|s| s.status == status).count();
+        ReviewSummary {
+            reviewers,
+            commit,
+            files,
+            annotated: count(FileStatus::Annotated),
+            clean: count(FileStatus::Clean),
+        }
+    }
+}
+
+/// Report grouped by file, opening with the review summary.
+pub struct Markdown;
+
+impl super::Exporter for Markdown {
+    fn name(&self) -> &'static str {
+        "markdown"
     }
 
-    let mut by_file: BTreeMap<&str, Vec<&Annotation>> = BTreeMap::new();
-    for a in annotations {
-        by_file.entry(&a.file_path).or_default().push(a);
+    fn extension(&self) -> &'static str {
+        "md"
     }
 
+    fn render(&self, context: &super::ExportContext) -> anyhow::Result<String> {
+        let mut out = export_markdown(context.annotations, context.summary);
+        if let Some(checklist) = context.checklist {
+            out.push_str(&checklist_section(checklist));
+        }
+        Ok(out)
+    }
+}
+
+pub fn export_markdown(annotations: &[Annotation], summary: Option<&ReviewSummary>) -> String {
     let mut out = String::from("# Annotations\n\n");
+    if let Some(summary) = summary {
+        out.push_str(&summary_section(annotations, summary));
+    }
+    if annotations.is_empty() {
+        out.push_str("No annotations found.\n");
+        return out;
+    }
 
-    for (file, mut anns) in by_file {
-        anns.sort_by(|a, b| b.start_line.cmp(&a.start_line));
+    for (file, anns) in super::report_order(annotations) {
         out.push_str(&format!("## `{file}`\n\n"));
         for a in anns {
+            let author = a.author.as_deref().map(|name| format!(" — *{}*", name)).unwrap_or_default();
+            let links = see_also(a, annotations);
             if a.start_line == a.end_line {
-                out.push_str(&format!("- **Line {}**: {}\n", a.start_line, a.text));
+                out.push_str(&format!("- **Line {}**: {}{}{}\n", a.start_line, a.text, author, links));
             } else {
                 out.push_str(&format!(
-                    "- **Lines {}-{}**: {}\n",
-                    a.start_line, a.end_line, a.text
+                    "- **Lines {}-{}**: {}{}{}\n",
+                    a.start_line, a.end_line, a.text, author, links
                 ));
             }
+            out.push_str(&metadata_line(a));
         }
         out.push('\n');
     }
@@ -32,13 +91,147 @@ pub fn export_markdown(annotations: &[Annotation]) -> String {
     out
 }
 
+/// The report with the severity label leading each annotation styled by
+/// `paint`, e.g. with terminal colors.
+pub fn paint_severities(markdown: &str, paint: impl Fn(Severity, &str) -> String) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    for line in markdown.split_inclusive('\n') {
+        let text = line.strip_prefix("- **Line").and_then(|rest| rest.split_once("**: ")).map(|(_, text)| text);
+        match text.and_then(|text| Some((text, Severity::from_text(text)?))) {
+            Some((text, severity)) => {
+                let (label, rest) = text.split_at(text.find(':').unwrap_or_default() + 1);
+                out.push_str(&line[..line.len() - text.len()]);
+                out.push_str(&paint(severity, label));
+                out.push_str(rest);
+            }
+            None => out.push_str(line),
+        }
+    }
+    out
+}
+
+/// Ticked-off checklist items per file, e.g.
+/// `- `src/a.rs` (1/2): [x] Error handling, [ ] Input validation`.
+fn checklist_section(checklist: &Completion) -> String {
+    let mut out = String::from("## Checklist\n\n");
+    if checklist.files.is_empty() {
+        out.push_str("No checklist items ticked off.\n");
+        return out;
+    }
+    for (file, checked) in &checklist.files {
+        let items: Vec<String> = checklist
+            .items
+            .iter()
+            .map(|item| format!("[{}] {}", if checked.contains(item) { "x" } else { " " }, item))
+            .collect();
+        out.push_str(&format!(
+            "- `{}` ({}/{}): {}\n",
+            file,
+            checked.len(),
+            checklist.items.len(),
+            items.join(", ")
+        ));
+    }
+    out
+}
+
+/// Cross-references to the exported annotations `a` is linked to, e.g.
+/// ` (see also `src/b.rs:3`, `src/c.rs:4-6`)`.
+fn see_also(a: &Annotation, annotations: &[Annotation]) -> String {
+    let targets: Vec<String> = a
+        .links
+        .iter()
+        .filter_map(|id| annotations.iter().find(|other| other.id == *id))
+        .map(|other| {
+            if other.start_line == other.end_line {
+                format!("`{}:{}`", other.file_path, other.start_line)
+            } else {
+                format!("`{}:{}-{}`", other.file_path, other.start_line, other.end_line)
+            }
+        })
+        .collect();
+    if targets.is_empty() {
+        return String::new();
+    }
+    format!(" (see also {})", targets.join(", "))
+}
+
+/// When and against which commit an annotation was written, as a small
+/// line under its bullet.
+fn metadata_line(a: &Annotation) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+    let mut line = format!("  <sub>created {}", a.created_at.format(FORMAT));
+    if let Some(commit) = &a.commit {
+        line.push_str(&format!(" at `{}`", crate::git::repo::short_id(commit)));
+    }
+    let updated = a.updated_at.format(FORMAT).to_string();
+    if updated != a.created_at.format(FORMAT).to_string() {
+        line.push_str(&format!(", updated {}", updated));
+    }
+    if a.priority > 0 {
+        line.push_str(&format!(", priority {}", a.priority));
+    }
+    line.push_str("</sub>\n");
+    line
+}
+
+fn summary_section(annotations: &[Annotation], summary: &ReviewSummary) -> String {
+    let mut out = String::from("## Summary\n\n");
+    if !summary.reviewers.is_empty() {
+        out.push_str(&format!("- **Reviewers**: {}\n", summary.reviewers.join(", ")));
+    }
+    if let Some(commit) = &summary.commit {
+        out.push_str(&format!("- **Commit**: `{}`\n", crate::git::repo::short_id(commit)));
+    }
+    let first = annotations.iter().map(|a| a.created_at).min();
+    let last = annotations.iter().map(|a| a.updated_at).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        let (first, last) = (first.format("%Y-%m-%d"), last.format("%Y-%m-%d"));
+        if first.to_string() == last.to_string() {
+            out.push_str(&format!("- **Date**: {}\n", first));
+        } else {
+            out.push_str(&format!("- **Dates**: {} to {}\n", first, last));
+        }
+    }
+
+    let mut counts: Vec<String> = Severity::ALL
+        .iter()
+        .map(|s| (s, annotations.iter().filter(|a| a.severity == Some(*s)).count()))
+        .filter(|(_, n)| *n > 0)
+        .map(|(s, n)| format!("{} {}", n, s.as_str()))
+        .collect();
+    let unlabeled = annotations.iter().filter(|a| a.severity.is_none()).count();
+    if unlabeled > 0 && unlabeled < annotations.len() {
+        counts.push(format!("{} without severity", unlabeled));
+    }
+    out.push_str(&format!("- **Annotations**: {}", annotations.len()));
+    if !counts.is_empty() {
+        out.push_str(&format!(" ({})", counts.join(", ")));
+    }
+    out.push('\n');
+
+    if summary.files > 0 {
+        let reviewed = summary.annotated + summary.clean;
+        out.push_str(&format!(
+            "- **Files**: {} of {} reviewed ({} annotated, {} clean), {}%\n",
+            reviewed,
+            summary.files,
+            summary.annotated,
+            summary.clean,
+            reviewed * 100 / summary.files
+        ));
+    }
+    out.push('\n');
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_empty() {
-        let md = export_markdown(&[]);
+        let md = export_markdown(&[], None);
         assert!(md.contains("No annotations found"));
     }
 
@@ -49,7 +242,7 @@ mod tests {
             Annotation::new("src/a.rs".into(), 5, 5, "fix bug".into()),
             Annotation::new("src/a.rs".into(), 15, 18, "add tests".into()),
         ];
-        let md = export_markdown(&anns);
+        let md = export_markdown(&anns, None);
         assert!(md.contains("## `src/a.rs`"));
         assert!(md.contains("## `src/b.rs`"));
         assert!(md.contains("**Line 5**: fix bug"));
@@ -64,4 +257,80 @@ mod tests {
         let line5_pos = md.find("Line 5").unwrap();
         assert!(line15_pos < line5_pos);
     }
+
+    #[test]
+    fn test_linked_annotations_cross_reference() {
+        let mut a = Annotation::new("a.rs".into(), 1, 1, "same leak".into());
+        let mut b = Annotation::new("b.rs".into(), 4, 6, "same leak".into());
+        a.links = vec![b.id, uuid::Uuid::new_v4()];
+        b.links = vec![a.id];
+        let md = export_markdown(&[a, b], None);
+        assert!(md.contains("- **Line 1**: same leak (see also `b.rs:4-6`)\n"), "{}", md);
+        assert!(md.contains("- **Lines 4-6**: same leak (see also `a.rs:1`)\n"));
+    }
+
+    #[test]
+    fn test_paint_severities() {
+        let anns = vec![
+            Annotation::new("a.rs".into(), 1, 1, "Blocker: leaks fd".into()),
+            Annotation::new("a.rs".into(), 4, 6, "no label: here".into()),
+        ];
+        let md = export_markdown(&anns, None);
+        let painted = paint_severities(&md, |s, label| format!("<{}>{}</>", s.as_str(), label));
+        assert!(painted.contains("- **Line 1**: <blocker>Blocker:</> leaks fd\n"), "{}", painted);
+        assert!(painted.contains("- **Lines 4-6**: no label: here\n"));
+        assert_eq!(painted.len(), md.len() + "<blocker></>".len());
+    }
+
+    #[test]
+    fn test_checklist_section() {
+        let checklist = Completion {
+            items: vec!["Error handling".into(), "Input validation".into()],
+            files: [("src/a.rs".to_string(), vec!["Input validation".to_string()])].into(),
+        };
+        let context = crate::export::ExportContext {
+            annotations: &[],
+            summary: None,
+            commit: None,
+            checklist: Some(&checklist),
+            repo_root: None,
+        };
+        let md = crate::export::Exporter::render(&Markdown, &context).unwrap();
+        assert!(md.ends_with("## Checklist\n\n- `src/a.rs` (1/2): [ ] Error handling, [x] Input validation\n"), "{}", md);
+    }
+
+    #[test]
+    fn test_metadata_line() {
+        let mut a = Annotation::new("a.rs".into(), 1, 1, "fix".into());
+        a.created_at = "2026-03-01T09:30:00Z".parse().unwrap();
+        a.updated_at = a.created_at;
+        a.commit = Some("0123456789abcdef".into());
+        let md = export_markdown(std::slice::from_ref(&a), None);
+        assert!(md.contains("- **Line 1**: fix\n  <sub>created 2026-03-01 09:30 UTC at `0123456`</sub>\n"));
+
+        a.updated_at = "2026-03-02T10:00:00Z".parse().unwrap();
+        a.commit = None;
+        assert!(metadata_line(&a).contains("created 2026-03-01 09:30 UTC, updated 2026-03-02 10:00 UTC"));
+    }
+
+    #[test]
+    fn test_summary_section() {
+        let anns = vec![
+            Annotation::new("a.rs".into(), 1, 1, "blocker: leak".into()),
+            Annotation::new("a.rs".into(), 2, 2, "nit: name".into()),
+            Annotation::new("b.rs".into(), 3, 3, "why?".into()),
+        ];
+        let summary = ReviewSummary {
+            reviewers: vec!["Ann".into()],
+            commit: Some("0123456789abcdef".into()),
+            files: 4,
+            annotated: 2,
+            clean: 1,
+        };
+        let md = export_markdown(&anns, Some(&summary));
+        assert!(md.starts_with("# Annotations\n\n## Summary\n\n- **Reviewers**: Ann\n- **Commit**: `0123456`\n"));
+        assert!(md.contains("- **Annotations**: 3 (1 blocker, 1 nit, 1 without severity)\n"));
+        assert!(md.contains("- **Files**: 3 of 4 reviewed (2 annotated, 1 clean), 75%\n"));
+        assert!(md.find("## Summary").unwrap() < md.find("## `a.rs`").unwrap());
+    }
 }
@@ -1,2 +1,139 @@
+//! Export formats. Each format is an [`Exporter`] in [`EXPORTERS`]; the CLI,
+//! config and the TUI's export picker take their names from there, so a new
+//! format only needs a module and an entry in the list. Optional formats sit
+//! behind a cargo feature, like `csv`.
+
+use crate::core::annotation::Annotation;
+use crate::core::checklist::Completion;
+use crate::core::index::AnnotationIndex;
+use std::cmp::Reverse;
+use std::path::Path;
+
+pub mod blame;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod html;
 pub mod json;
 pub mod markdown;
+pub mod redact;
+pub mod sarif;
+
+/// Everything an export may draw on.
+pub struct ExportContext<'a> {
+    pub annotations: &'a [Annotation],
+    /// Review context for formats that print a header.
+    pub summary: Option<&'a markdown::ReviewSummary>,
+    /// Commit the line numbers refer to.
+    pub commit: Option<&'a str>,
+    /// Checklist items ticked off per file, when the repository has a checklist.
+    pub checklist: Option<&'a Completion>,
+    /// Repository the annotated files are read from, for formats that quote
+    /// code; at `commit` when set, else from the working tree.
+    pub repo_root: Option<&'a Path>,
+}
+
+/// A format annotations can be exported to.
+pub trait Exporter: Sync {
+    /// Name used by `--format` and `export.format`.
+    fn name(&self) -> &'static str;
+    /// File extension of exported files, without the dot.
+    fn extension(&self) -> &'static str;
+    fn render(&self, context: &ExportContext) -> anyhow::Result<String>;
+    /// Writes the export to `out`; formats that can produce it piece by
+    /// piece override this to stream large reports.
+    fn write(&self, context: &ExportContext, out: &mut dyn std::io::Write) -> anyhow::Result<()> {
+        out.write_all(self.render(context)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Every available format; the first is the default.
+pub static EXPORTERS: &[&dyn Exporter] = &[
+    &markdown::Markdown,
+    &json::Json,
+    &html::Html,
+    &sarif::Sarif,
+    #[cfg(feature = "csv")]
+    &csv::Csv,
+];
+
+/// Default for `export.format`.
+pub const DEFAULT_FORMAT: &str = "markdown";
+
+/// The exporter called `name`.
+pub fn find(name: &str) -> anyhow::Result<&'static dyn Exporter> {
+    EXPORTERS
+        .iter()
+        .copied()
+        .find(|e| e.name() == name)
+        .ok_or_else(|| anyhow::anyhow!("unknown export format '{}', expected one of: {}", name, names().join(", ")))
+}
+
+pub fn names() -> Vec<&'static str> {
+    EXPORTERS.iter().map(|e| e.name()).collect()
+}
+
+/// File the TUI writes an export to, at the repository root.
+pub fn file_name(exporter: &dyn Exporter) -> String {
+    format!("annotations.{}", exporter.extension())
+}
+
+/// Annotated files with their annotations in report order: highest
+/// priority first, then files by path and annotations bottom-up, so the
+/// order is unchanged while nothing has a priority.
+pub fn report_order(annotations: &[Annotation]) -> Vec<(&str, Vec<&Annotation>)> {
+    let index = AnnotationIndex::build(annotations);
+    let mut files: Vec<(&str, Vec<&Annotation>)> = index
+        .files()
+        .into_iter()
+        .map(|file| {
+            let mut anns = index.for_file(annotations, file);
+            anns.sort_by_key(|a| (Reverse(a.priority), Reverse(a.start_line)));
+            (anns[0].file_path.as_str(), anns)
+        })
+        .collect();
+    files.sort_by_key(|(_, anns)| Reverse(anns[0].priority));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup() {
+        assert_eq!(EXPORTERS[0].name(), DEFAULT_FORMAT);
+        let mut unique = names();
+        unique.dedup();
+        assert_eq!(unique, names());
+        assert_eq!(find("json").unwrap().extension(), "json");
+        let err = find("pdf").err().unwrap().to_string();
+        assert!(err.contains("markdown, json, html, sarif"), "{}", err);
+    }
+
+    #[test]
+    fn test_report_order_puts_priority_first() {
+        let mut anns = vec![
+            Annotation::new("a.rs".into(), 1, 1, "x".into()),
+            Annotation::new("a.rs".into(), 9, 9, "y".into()),
+            Annotation::new("b.rs".into(), 3, 3, "z".into()),
+            Annotation::new("c.rs".into(), 3, 3, "w".into()),
+        ];
+        let lines = |order: Vec<(&str, Vec<&Annotation>)>| -> Vec<(String, u32)> {
+            order
+                .into_iter()
+                .flat_map(|(file, anns)| anns.into_iter().map(move |a| (file.to_string(), a.start_line)))
+                .collect()
+        };
+        assert_eq!(
+            lines(report_order(&anns)),
+            vec![("a.rs".into(), 9), ("a.rs".into(), 1), ("b.rs".into(), 3), ("c.rs".into(), 3)]
+        );
+        anns[0].priority = 1;
+        anns[3].priority = 2;
+        assert_eq!(
+            lines(report_order(&anns)),
+            vec![("c.rs".into(), 3), ("a.rs".into(), 1), ("a.rs".into(), 9), ("b.rs".into(), 3)]
+        );
+    }
+}
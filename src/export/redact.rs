@@ -0,0 +1,133 @@
+use crate::core::annotation::Annotation;
+use anyhow::{Result, bail};
+
+/// How to anonymize annotations before they leave the team.
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    /// Replace authors, `@handle` mentions and email addresses with stable pseudonyms.
+    pub authors: bool,
+    /// `(prefix, replacement)` pairs; the first matching prefix is rewritten.
+    pub paths: Vec<(String, String)>,
+}
+
+impl Redaction {
+    /// Parses `--redact-paths` values of the form `PREFIX` or `PREFIX=REPLACEMENT`.
+    pub fn parse_path_rule(rule: &str) -> Result<(String, String)> {
+        let (prefix, replacement) = rule.split_once('=').unwrap_or((rule, ""));
+        if prefix.is_empty() {
+            bail!("--redact-paths needs a prefix, got '{}'", rule);
+        }
+        Ok((prefix.to_string(), replacement.to_string()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.authors && self.paths.is_empty()
+    }
+
+    /// `path` with the first matching prefix rewritten.
+    pub fn apply_path(&self, path: &str) -> String {
+        match self.paths.iter().find(|(prefix, _)| path.starts_with(prefix.as_str())) {
+            Some((prefix, replacement)) => format!("{}{}", replacement, &path[prefix.len()..]),
+            None => path.to_string(),
+        }
+    }
+
+    /// Returns anonymized copies; snippets are dropped since they quote the code.
+    pub fn apply(&self, annotations: &[Annotation]) -> Vec<Annotation> {
+        annotations
+            .iter()
+            .map(|a| {
+                let mut a = a.clone();
+                a.file_path = self.apply_path(&a.file_path);
+                if self.authors {
+                    a.text = redact_identities(&a.text);
+                    a.author = a.author.as_deref().map(author_pseudonym);
+                }
+                a.snippet = None;
+                a
+            })
+            .collect()
+    }
+}
+
+/// Replaces `@handle` mentions and email addresses with `@reviewer-<hash>`, so
+/// the same person maps to the same pseudonym throughout a report.
+fn redact_identities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '+');
+        let start = rest[..at]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(*c))
+            .last()
+            .map_or(at, |(i, _)| i);
+        let end = at + 1 + rest[at + 1..].find(|c: char| !is_word(c)).unwrap_or(rest.len() - at - 1);
+        let identity = rest[start..end].trim_end_matches('.');
+        let end = start + identity.len();
+
+        out.push_str(&rest[..start]);
+        if identity.len() > 1 && (start == at || identity[at - start + 1..].contains('.')) {
+            out.push('@');
+            out.push_str(&pseudonym(identity));
+        } else {
+            out.push_str(identity);
+        }
+        rest = &rest[end.max(at + 1)..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Pseudonym for an `[author]` identity; `Name <email>` hashes its email, so
+/// it matches mentions of that address in annotation text.
+fn author_pseudonym(author: &str) -> String {
+    let email = author
+        .split_once('<')
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .map_or(author, |(email, _)| email);
+    pseudonym(email.trim())
+}
+
+fn pseudonym(identity: &str) -> String {
+    let hash = crate::core::location::fnv1a(identity.to_lowercase().as_bytes());
+    format!("reviewer-{:06x}", hash & 0xff_ffff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_mentions_and_emails() {
+        let text = redact_identities("ask @alice or bob@example.com, cc @Alice. x@y");
+        let pseudonym = &text[4..20];
+        assert!(pseudonym.starts_with("@reviewer-"));
+        assert_eq!(text.matches(pseudonym).count(), 2, "{}", text);
+        assert!(!text.contains("alice") && !text.contains("bob"));
+        assert!(text.ends_with(". x@y"), "{}", text);
+        assert_eq!(redact_identities("no identities @ all"), "no identities @ all");
+    }
+
+    #[test]
+    fn test_path_rules() {
+        let mut a = Annotation::new("internal/billing/src/lib.rs".into(), 1, 1, "ask carol@example.com".into());
+        a.snippet = Some("fn secret()".into());
+        a.author = Some("Carol <Carol@example.com>".into());
+        let redaction = Redaction {
+            authors: true,
+            paths: vec![
+                Redaction::parse_path_rule("internal/billing/=svc/").unwrap(),
+                Redaction::parse_path_rule("internal/").unwrap(),
+            ],
+        };
+        let out = redaction.apply(&[a]);
+        assert_eq!(out[0].file_path, "svc/src/lib.rs");
+        let author = out[0].author.clone().unwrap();
+        assert!(author.starts_with("reviewer-"));
+        assert_eq!(out[0].text, format!("ask @{}", author));
+        assert_eq!(out[0].snippet, None);
+        assert!(Redaction::parse_path_rule("=x").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::core::annotation::{Annotation, Severity, is_zero};
+use serde::Serialize;
+use uuid::Uuid;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule of annotations without a severity.
+const UNLABELED_RULE: &str = "annotation";
+
+#[derive(Serialize)]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct Run<'a> {
+    tool: Tool,
+    results: Vec<SarifResult<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<RunProperties<'a>>,
+}
+
+#[derive(Serialize)]
+struct RunProperties<'a> {
+    /// Commit the line numbers refer to.
+    commit: &'a str,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message<'static>,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    text: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+    rule_id: &'static str,
+    level: &'static str,
+    message: Message<'a>,
+    locations: [Location<'a>; 1],
+    /// Keeps a result matched to its annotation across line moves.
+    partial_fingerprints: Fingerprints,
+    properties: ResultProperties<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location<'a> {
+    physical_location: PhysicalLocation<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation<'a> {
+    artifact_location: ArtifactLocation<'a>,
+    region: Region,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation<'a> {
+    uri: &'a str,
+    uri_base_id: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: u32,
+    end_line: u32,
+}
+
+#[derive(Serialize)]
+struct Fingerprints {
+    #[serde(rename = "annotatorId/v1")]
+    id: Uuid,
+}
+
+#[derive(Serialize)]
+struct ResultProperties<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "is_zero")]
+    priority: u32,
+}
+
+/// SARIF 2.1.0 log for code scanning tools, one result per annotation with
+/// the severity as its rule.
+pub struct Sarif;
+
+impl super::Exporter for Sarif {
+    fn name(&self) -> &'static str {
+        "sarif"
+    }
+
+    fn extension(&self) -> &'static str {
+        "sarif"
+    }
+
+    fn render(&self, context: &super::ExportContext) -> anyhow::Result<String> {
+        let results: Vec<SarifResult> = super::report_order(context.annotations)
+            .into_iter()
+            .flat_map(|(_, anns)| anns)
+            .map(result)
+            .collect();
+        let mut rules: Vec<Rule> = Severity::ALL
+            .iter()
+            .map(|s| Some(*s))
+            .chain([None])
+            .filter(|s| context.annotations.iter().any(|a| a.severity == *s))
+            .map(|s| Rule {
+                id: rule_id(s),
+                short_description: Message { text: rule_description(s) },
+            })
+            .collect();
+        rules.sort_by_key(|r| r.id);
+
+        let log = Log {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: [Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                        rules,
+                    },
+                },
+                results,
+                properties: context.commit.map(|commit| RunProperties { commit }),
+            }],
+        };
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+}
+
+fn result(a: &Annotation) -> SarifResult<'_> {
+    SarifResult {
+        rule_id: rule_id(a.severity),
+        level: level(a.severity),
+        message: Message { text: &a.text },
+        locations: [Location {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: &a.file_path,
+                    uri_base_id: "%SRCROOT%",
+                },
+                region: Region {
+                    start_line: a.start_line,
+                    end_line: a.end_line,
+                },
+            },
+        }],
+        partial_fingerprints: Fingerprints { id: a.id },
+        properties: ResultProperties {
+            author: a.author.as_deref(),
+            tags: &a.tags,
+            priority: a.priority,
+        },
+    }
+}
+
+fn rule_id(severity: Option<Severity>) -> &'static str {
+    severity.map_or(UNLABELED_RULE, |s| s.as_str())
+}
+
+fn rule_description(severity: Option<Severity>) -> &'static str {
+    match severity {
+        Some(Severity::Blocker) => "Must be fixed before merging",
+        Some(Severity::Major) => "Significant problem found in review",
+        Some(Severity::Minor) => "Minor problem found in review",
+        Some(Severity::Nit) => "Nitpick",
+        Some(Severity::Info) => "Informational review note",
+        None => "Review annotation",
+    }
+}
+
+/// SARIF result level: blockers and major findings fail code scanning
+/// checks, minor ones warn, the rest are notes.
+fn level(severity: Option<Severity>) -> &'static str {
+    match severity {
+        Some(Severity::Blocker | Severity::Major) => "error",
+        Some(Severity::Minor) => "warning",
+        Some(Severity::Nit | Severity::Info) | None => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportContext, Exporter};
+
+    #[test]
+    fn test_annotations_become_results() {
+        let mut blocker = Annotation::new("src/a.rs".into(), 3, 5, "blocker: leaks #security".into());
+        blocker.priority = 2;
+        let note = Annotation::new("b.rs".into(), 7, 7, "why?".into());
+        let sarif = Sarif
+            .render(&ExportContext {
+                annotations: &[note, blocker.clone()],
+                summary: None,
+                commit: Some("abc123"),
+                checklist: None,
+                repo_root: None,
+            })
+            .unwrap();
+        let log: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["properties"]["commit"], "abc123");
+        let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, vec!["annotation", "blocker"]);
+
+        // The prioritized blocker comes first
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "blocker");
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["message"]["text"], "blocker: leaks #security");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/a.rs");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["endLine"], 5);
+        assert_eq!(result["partialFingerprints"]["annotatorId/v1"], blocker.id.to_string());
+        assert_eq!(result["properties"]["tags"][0], "security");
+        assert_eq!(run["results"][1]["level"], "note");
+    }
+}
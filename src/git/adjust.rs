@@ -1,9 +1,60 @@
 use crate::core::annotation::{AdjustResult, Annotation};
-use crate::git::diff::{FileDiff, FileDiffStatus};
+use crate::core::config::AdjustConfig;
+use crate::git::diff::{by_path, FileDiff, FileDiffStatus};
 use anyhow::Result;
 use git2::Repository;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Minimum alphanumeric characters a deleted block needs before it is matched
+/// as moved, mirroring git's `--color-moved` rule that ignores trivial lines.
+const MIN_MOVED_ALNUM: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdjustOptions {
+    /// Follow annotated blocks that were deleted and re-added verbatim.
+    pub follow_moves: bool,
+    /// Detect copies, and look for moved blocks in other files as well.
+    pub detect_copies: bool,
+    /// Treat lines differing only in whitespace as unchanged.
+    pub ignore_whitespace: bool,
+    /// Similarity percentage (0-100) for a delete/add pair to count as a rename or copy.
+    pub rename_threshold: u16,
+    /// Duplicate annotations from a copy's source into the copied file.
+    pub copy_annotations: bool,
+}
+
+impl Default for AdjustOptions {
+    fn default() -> Self {
+        Self {
+            follow_moves: true,
+            detect_copies: false,
+            ignore_whitespace: false,
+            rename_threshold: 50,
+            copy_annotations: false,
+        }
+    }
+}
+
+impl From<&AdjustConfig> for AdjustOptions {
+    fn from(config: &AdjustConfig) -> Self {
+        Self {
+            follow_moves: config.follow_moves,
+            detect_copies: config.detect_copies,
+            ignore_whitespace: config.ignore_whitespace,
+            rename_threshold: config.rename_threshold,
+            copy_annotations: config.copy_annotations,
+        }
+    }
+}
 
-pub fn compute_diffs(repo: &Repository, from_commit: &str, to_commit: &str) -> Result<Vec<FileDiff>> {
+pub fn compute_diffs(
+    repo: &Repository,
+    from_commit: &str,
+    to_commit: &str,
+    options: &AdjustOptions,
+) -> Result<Vec<FileDiff>> {
     let from_oid = repo.revparse_single(from_commit)?.peel_to_commit()?.id();
     let to_oid = repo.revparse_single(to_commit)?.peel_to_commit()?.id();
 
@@ -11,11 +62,19 @@ pub fn compute_diffs(repo: &Repository, from_commit: &str, to_commit: &str) -> R
     let to_tree = repo.find_commit(to_oid)?.tree()?;
 
     let mut diff_opts = git2::DiffOptions::new();
+    diff_opts.ignore_whitespace(options.ignore_whitespace);
+    // Unmodified files must be in the diff to be considered as copy sources
+    diff_opts.include_unmodified(options.detect_copies);
+    // Otherwise a file replaced by a symlink shows up as deleted and re-added
+    diff_opts.include_typechange(true);
     let diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut diff_opts))?;
 
     let mut find_opts = git2::DiffFindOptions::new();
     find_opts.renames(true);
-    find_opts.copies(false);
+    find_opts.rename_threshold(options.rename_threshold);
+    find_opts.copies(options.detect_copies);
+    find_opts.copies_from_unmodified(options.detect_copies);
+    find_opts.copy_threshold(options.rename_threshold);
     let diff = {
         let mut d = diff;
         d.find_similar(Some(&mut find_opts))?;
@@ -31,6 +90,9 @@ pub fn compute_diffs(repo: &Repository, from_commit: &str, to_commit: &str) -> R
             git2::Delta::Deleted => FileDiffStatus::Deleted,
             git2::Delta::Modified => FileDiffStatus::Modified,
             git2::Delta::Renamed => FileDiffStatus::Renamed,
+            git2::Delta::Copied => FileDiffStatus::Copied,
+            git2::Delta::Typechange => FileDiffStatus::TypeChanged,
+            // Mode-only changes are Modified without hunks, so they leave ranges alone
             _ => continue,
         };
 
@@ -39,35 +101,10 @@ pub fn compute_diffs(repo: &Repository, from_commit: &str, to_commit: &str) -> R
 
         let mut hunks = Vec::new();
 
-        if let Ok(patch) = git2::Patch::from_diff(&diff, delta_idx)
+        if status != FileDiffStatus::TypeChanged
+            && let Ok(patch) = git2::Patch::from_diff(&diff, delta_idx)
             && let Some(patch) = patch {
-                for hunk_idx in 0..patch.num_hunks() {
-                    let (hunk_header, _) = patch.hunk(hunk_idx)?;
-                    let mut lines = Vec::new();
-
-                    for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
-                        let line = patch.line_in_hunk(hunk_idx, line_idx)?;
-                        let origin = match line.origin() {
-                            '+' => crate::git::diff::DiffLineType::Addition,
-                            '-' => crate::git::diff::DiffLineType::Deletion,
-                            _ => crate::git::diff::DiffLineType::Context,
-                        };
-                        lines.push(crate::git::diff::DiffLine {
-                            origin,
-                            old_lineno: line.old_lineno(),
-                            new_lineno: line.new_lineno(),
-                            content: String::from_utf8_lossy(line.content()).to_string(),
-                        });
-                    }
-
-                    hunks.push(crate::git::diff::Hunk {
-                        old_start: hunk_header.old_start(),
-                        old_lines: hunk_header.old_lines(),
-                        new_start: hunk_header.new_start(),
-                        new_lines: hunk_header.new_lines(),
-                        lines,
-                    });
-                }
+                hunks = patch_hunks(&patch)?;
             }
 
         file_diffs.push(FileDiff {
@@ -81,19 +118,126 @@ pub fn compute_diffs(repo: &Repository, from_commit: &str, to_commit: &str) -> R
     Ok(file_diffs)
 }
 
+/// Diff of `path` between two of its versions, given by blob id.
+pub fn diff_blobs(repo: &Repository, old_blob: &str, new_blob: &str, path: &str, options: &AdjustOptions) -> Result<FileDiff> {
+    let old = repo.find_blob(git2::Oid::from_str(old_blob)?)?;
+    let new = repo.find_blob(git2::Oid::from_str(new_blob)?)?;
+    let mut diff_opts = git2::DiffOptions::new();
+    diff_opts.ignore_whitespace(options.ignore_whitespace);
+    let patch = git2::Patch::from_blobs(&old, Some(Path::new(path)), &new, Some(Path::new(path)), Some(&mut diff_opts))?;
+    Ok(FileDiff {
+        old_path: Some(path.to_string()),
+        new_path: Some(path.to_string()),
+        hunks: patch_hunks(&patch)?,
+        status: FileDiffStatus::Modified,
+    })
+}
+
+/// Diff between two versions of `path` given as text.
+pub fn diff_texts(old: &str, new: &str, path: &str, options: &AdjustOptions) -> Result<FileDiff> {
+    let mut diff_opts = git2::DiffOptions::new();
+    diff_opts.ignore_whitespace(options.ignore_whitespace);
+    let path_ref = Some(Path::new(path));
+    let patch = git2::Patch::from_buffers(old.as_bytes(), path_ref, new.as_bytes(), path_ref, Some(&mut diff_opts))?;
+    Ok(FileDiff {
+        old_path: Some(path.to_string()),
+        new_path: Some(path.to_string()),
+        hunks: patch_hunks(&patch)?,
+        status: FileDiffStatus::Modified,
+    })
+}
+
+fn patch_hunks(patch: &git2::Patch) -> Result<Vec<crate::git::diff::Hunk>> {
+    let mut hunks = Vec::new();
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk_header, _) = patch.hunk(hunk_idx)?;
+        let mut lines = Vec::new();
+
+        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let origin = match line.origin() {
+                '+' => crate::git::diff::DiffLineType::Addition,
+                '-' => crate::git::diff::DiffLineType::Deletion,
+                _ => crate::git::diff::DiffLineType::Context,
+            };
+            lines.push(crate::git::diff::DiffLine {
+                origin,
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content()).to_string(),
+            });
+        }
+
+        hunks.push(crate::git::diff::Hunk {
+            old_start: hunk_header.old_start(),
+            old_lines: hunk_header.old_lines(),
+            new_start: hunk_header.new_start(),
+            new_lines: hunk_header.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Where each hunk of a diff ends in the old file and the offset the hunks
+/// before it add, so an annotation finds the first hunk that can touch it by
+/// binary search instead of walking the ones above it.
+pub struct HunkIndex {
+    old_ends: Vec<u32>,
+    /// One entry per hunk plus the total.
+    offsets_before: Vec<i64>,
+}
+
+impl HunkIndex {
+    pub fn new(diff: &FileDiff) -> Self {
+        let mut offsets_before = Vec::with_capacity(diff.hunks.len() + 1);
+        let mut offset = 0;
+        offsets_before.push(offset);
+        for hunk in &diff.hunks {
+            offset += hunk.net_offset();
+            offsets_before.push(offset);
+        }
+        Self {
+            old_ends: diff.hunks.iter().map(|h| h.old_end()).collect(),
+            offsets_before,
+        }
+    }
+
+    /// The first hunk not entirely above `line`, and the offset of the ones above.
+    /// Hunks are ordered and don't overlap, so their ends are sorted.
+    fn skip_to(&self, line: u32) -> (usize, i64) {
+        let first = self.old_ends.partition_point(|&end| end < line);
+        (first, self.offsets_before[first])
+    }
+}
+
 pub fn adjust_annotation(annotation: &Annotation, file_diff: &FileDiff) -> AdjustResult {
+    adjust_indexed(annotation, file_diff, &HunkIndex::new(file_diff))
+}
+
+/// `adjust_annotation` with the diff's hunks indexed once for all its annotations.
+pub fn adjust_indexed(annotation: &Annotation, file_diff: &FileDiff, index: &HunkIndex) -> AdjustResult {
+    let (first, offset) = index.skip_to(annotation.start_line);
+    adjust_from(annotation, file_diff, first, offset)
+}
+
+/// Adjusts `annotation` walking the hunks from `first`, with `offset` the
+/// net change of the hunks before it.
+fn adjust_from(annotation: &Annotation, file_diff: &FileDiff, first: usize, mut offset: i64) -> AdjustResult {
     match file_diff.status {
         FileDiffStatus::Deleted => return AdjustResult::Deleted,
-        FileDiffStatus::Added => return AdjustResult::Unchanged,
+        FileDiffStatus::Added | FileDiffStatus::Copied | FileDiffStatus::TypeChanged => {
+            return AdjustResult::Unchanged;
+        }
         _ => {}
     }
 
-    let mut offset: i64 = 0;
     let mut deleted_in_range = Vec::new();
+    let mut added_in_range: i64 = 0;
     let start = annotation.start_line;
     let end = annotation.end_line;
 
-    for hunk in &file_diff.hunks {
+    for hunk in &file_diff.hunks[first..] {
         let hunk_old_end = hunk.old_end();
 
         if hunk_old_end < start {
@@ -103,62 +247,35 @@ pub fn adjust_annotation(annotation: &Annotation, file_diff: &FileDiff) -> Adjus
             // Hunk entirely after annotation — stop accumulating
             break;
         } else {
-            // Hunk overlaps annotation
-            // Check for deleted lines within annotation range
-            for deleted_line in hunk.deleted_old_lines() {
-                if deleted_line >= start && deleted_line <= end {
-                    deleted_in_range.push(deleted_line);
+            // Hunk overlaps annotation: deleted lines before the range shift
+            // it, those inside are collected. Added lines go where the old
+            // line they precede is: up to the first annotated line they
+            // shift the range, inside it they widen it
+            use crate::git::diff::DiffLineType;
+            let mut added: i64 = 0;
+            // Lines added at the end of the hunk precede the line after it
+            let after_hunk = (DiffLineType::Context, Some(hunk_old_end + 1));
+            let lines = hunk.lines.iter().map(|l| (l.origin, l.old_lineno)).chain([after_hunk]);
+            for (origin, old) in lines {
+                if origin == DiffLineType::Addition {
+                    added += 1;
+                    continue;
                 }
-            }
-
-            // Calculate offset contribution from lines before annotation start
-            let mut pre_offset: i64 = 0;
-            for line in &hunk.lines {
-                match line.origin {
-                    crate::git::diff::DiffLineType::Deletion => {
-                        if let Some(old) = line.old_lineno
-                            && old < start {
-                                pre_offset -= 1;
-                            }
-                    }
-                    crate::git::diff::DiffLineType::Addition => {
-                        if let Some(new) = line.new_lineno {
-                            let effective_old = (new as i64 - offset - pre_offset) as u32;
-                            if effective_old < start {
-                                pre_offset += 1;
-                            }
-                        }
-                    }
-                    _ => {}
+                let Some(old) = old else { continue };
+                if old <= start {
+                    offset += added;
+                } else if old <= end {
+                    added_in_range += added;
                 }
-            }
-            offset += pre_offset;
-
-            // Calculate size change within annotation range
-            let mut range_offset: i64 = 0;
-            for line in &hunk.lines {
-                match line.origin {
-                    crate::git::diff::DiffLineType::Deletion => {
-                        if let Some(old) = line.old_lineno
-                            && old >= start && old <= end {
-                                range_offset -= 1;
-                            }
+                added = 0;
+                if origin == DiffLineType::Deletion {
+                    if old < start {
+                        offset -= 1;
+                    } else if old <= end {
+                        deleted_in_range.push(old);
                     }
-                    crate::git::diff::DiffLineType::Addition => {
-                        if let Some(new) = line.new_lineno {
-                            let effective_old = (new as i64 - offset) as u32;
-                            if effective_old >= start && effective_old <= end {
-                                range_offset += 1;
-                            }
-                        }
-                    }
-                    _ => {}
                 }
             }
-
-            // After-annotation offset from this hunk
-            let total_hunk_offset = hunk.net_offset();
-            let _post_offset = total_hunk_offset - pre_offset - range_offset;
         }
     }
 
@@ -173,7 +290,7 @@ pub fn adjust_annotation(annotation: &Annotation, file_diff: &FileDiff) -> Adjus
     }
 
     let new_start = (start as i64 + offset) as u32;
-    let new_end = (end as i64 + offset) as u32;
+    let new_end = (end as i64 + offset + added_in_range) as u32;
 
     if new_start == start && new_end == end {
         AdjustResult::Unchanged
@@ -187,21 +304,109 @@ pub fn adjust_annotation(annotation: &Annotation, file_diff: &FileDiff) -> Adjus
     }
 }
 
+/// Looks for the deleted content of `annotation` among the added lines of the
+/// same file (and of other files when copy detection is on).
+/// Returns the new path and line range of the block.
+pub fn find_moved_block(
+    annotation: &Annotation,
+    file_diff: &FileDiff,
+    diffs: &[FileDiff],
+    options: &AdjustOptions,
+) -> Option<(String, u32, u32)> {
+    let block = deleted_block(annotation, file_diff, options)?;
+    let alnum: usize = block
+        .iter()
+        .map(|l| l.chars().filter(|c| c.is_alphanumeric()).count())
+        .sum();
+    if alnum < MIN_MOVED_ALNUM {
+        return None;
+    }
+
+    let same_file = std::iter::once(file_diff);
+    let other_files = diffs
+        .iter()
+        .filter(|d| options.detect_copies && !std::ptr::eq(*d, file_diff));
+
+    same_file
+        .chain(other_files)
+        .filter(|d| d.status != FileDiffStatus::Deleted)
+        .find_map(|d| {
+            let path = d.new_path.clone()?;
+            let (start, end) = find_added_block(&block, d, options)?;
+            Some((path, start, end))
+        })
+}
+
+/// Line content used when matching moved blocks.
+fn comparable_content(content: &str, options: &AdjustOptions) -> String {
+    if options.ignore_whitespace {
+        content.chars().filter(|c| !c.is_whitespace()).collect()
+    } else {
+        content.trim_end_matches(['\n', '\r']).to_string()
+    }
+}
+
+fn deleted_block(annotation: &Annotation, file_diff: &FileDiff, options: &AdjustOptions) -> Option<Vec<String>> {
+    annotation
+        .line_range()
+        .map(|line_no| {
+            file_diff
+                .hunks
+                .iter()
+                .flat_map(|h| &h.lines)
+                .find(|l| l.origin == crate::git::diff::DiffLineType::Deletion && l.old_lineno == Some(line_no))
+                .map(|l| comparable_content(&l.content, options))
+        })
+        .collect()
+}
+
+fn find_added_block(block: &[String], file_diff: &FileDiff, options: &AdjustOptions) -> Option<(u32, u32)> {
+    let added: Vec<(u32, String)> = file_diff
+        .hunks
+        .iter()
+        .flat_map(|h| &h.lines)
+        .filter(|l| l.origin == crate::git::diff::DiffLineType::Addition)
+        .filter_map(|l| Some((l.new_lineno?, comparable_content(&l.content, options))))
+        .collect();
+
+    added
+        .windows(block.len())
+        .find(|window| {
+            window.iter().zip(block).all(|((_, added), deleted)| added == deleted)
+                && window.windows(2).all(|pair| pair[1].0 == pair[0].0 + 1)
+        })
+        .map(|window| (window[0].0, window[window.len() - 1].0))
+}
+
 pub fn adjust_annotations(
     annotations: &[Annotation],
     diffs: &[FileDiff],
+    options: &AdjustOptions,
 ) -> Vec<(Annotation, AdjustResult)> {
     let mut results = Vec::new();
+    let diffs_by_path = by_path(diffs);
+    // Built once per file, on its first annotation
+    let mut indexes: HashMap<&str, HunkIndex> = HashMap::new();
 
     for annotation in annotations {
         // Find diff for this file
-        let file_diff = diffs.iter().find(|d| {
-            d.old_path.as_deref() == Some(&annotation.file_path)
-                || d.new_path.as_deref() == Some(&annotation.file_path)
-        });
+        let path = annotation.file_path.as_str();
+        let file_diff = diffs_by_path.get(path).copied();
 
         let result = match file_diff {
-            Some(diff) => adjust_annotation(annotation, diff),
+            Some(diff) => match adjust_indexed(annotation, diff, indexes.entry(path).or_insert_with(|| HunkIndex::new(diff))) {
+                AdjustResult::Deleted if options.follow_moves => {
+                    match find_moved_block(annotation, diff, diffs, options) {
+                        Some((new_path, new_start, new_end)) => AdjustResult::Moved {
+                            new_path,
+                            new_start,
+                            new_end,
+                        },
+                        None => AdjustResult::Deleted,
+                    }
+                }
+                result => result,
+            },
             None => AdjustResult::Unchanged,
         };
 
@@ -212,17 +417,27 @@ pub fn adjust_annotations(
 }
 
 pub fn apply_adjustments(annotations: &mut Vec<Annotation>, results: &[(Annotation, AdjustResult)]) {
+    let positions: HashMap<Uuid, usize> = annotations.iter().enumerate().map(|(i, a)| (a.id, i)).collect();
+    let mut deleted = HashSet::new();
     for (original, result) in results {
         match result {
             AdjustResult::Shifted { new_start, new_end, .. } => {
-                if let Some(a) = annotations.iter_mut().find(|a| a.id == original.id) {
+                if let Some(a) = positions.get(&original.id).map(|&i| &mut annotations[i]) {
+                    a.start_line = *new_start;
+                    a.end_line = *new_end;
+                    a.updated_at = chrono::Utc::now();
+                }
+            }
+            AdjustResult::Moved { new_path, new_start, new_end } => {
+                if let Some(a) = positions.get(&original.id).map(|&i| &mut annotations[i]) {
+                    a.file_path = new_path.clone();
                     a.start_line = *new_start;
                     a.end_line = *new_end;
                     a.updated_at = chrono::Utc::now();
                 }
             }
             AdjustResult::Deleted => {
-                annotations.retain(|a| a.id != original.id);
+                deleted.insert(original.id);
             }
             AdjustResult::Conflict { .. } => {
                 // Conflicts are handled separately by the UI
@@ -230,6 +445,9 @@ pub fn apply_adjustments(annotations: &mut Vec<Annotation>, results: &[(Annotati
             AdjustResult::Unchanged => {}
         }
     }
+    if !deleted.is_empty() {
+        annotations.retain(|a| !deleted.contains(&a.id));
+    }
 }
 
 #[cfg(test)]
@@ -370,6 +588,61 @@ mod tests {
         }
     }
 
+    fn content_line(origin: DiffLineType, old: Option<u32>, new: Option<u32>, content: &str) -> DiffLine {
+        DiffLine {
+            origin,
+            old_lineno: old,
+            new_lineno: new,
+            content: format!("{content}\n"),
+        }
+    }
+
+    #[test]
+    fn test_moved_block_followed() {
+        // Lines 2-3 moved to the end of the file (after old line 5)
+        let a = make_annotation(2, 3);
+        let diff = FileDiff {
+            old_path: Some("test.rs".into()),
+            new_path: Some("test.rs".into()),
+            hunks: vec![
+                make_hunk(2, 2, 1, 0, vec![
+                    content_line(DiffLineType::Deletion, Some(2), None, "let total = compute_total(items);"),
+                    content_line(DiffLineType::Deletion, Some(3), None, "println!(\"{total}\");"),
+                ]),
+                make_hunk(5, 0, 4, 2, vec![
+                    content_line(DiffLineType::Addition, None, Some(4), "let total = compute_total(items);"),
+                    content_line(DiffLineType::Addition, None, Some(5), "println!(\"{total}\");"),
+                ]),
+            ],
+            status: FileDiffStatus::Modified,
+        };
+        let results = adjust_annotations(&[a], std::slice::from_ref(&diff), &AdjustOptions::default());
+        assert_eq!(
+            results[0].1,
+            AdjustResult::Moved {
+                new_path: "test.rs".into(),
+                new_start: 4,
+                new_end: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_trivial_block_not_followed() {
+        let a = make_annotation(2, 2);
+        let diff = FileDiff {
+            old_path: Some("test.rs".into()),
+            new_path: Some("test.rs".into()),
+            hunks: vec![
+                make_hunk(2, 1, 1, 0, vec![content_line(DiffLineType::Deletion, Some(2), None, "}")]),
+                make_hunk(5, 0, 4, 1, vec![content_line(DiffLineType::Addition, None, Some(4), "}")]),
+            ],
+            status: FileDiffStatus::Modified,
+        };
+        let results = adjust_annotations(&[a], std::slice::from_ref(&diff), &AdjustOptions::default());
+        assert_eq!(results[0].1, AdjustResult::Deleted);
+    }
+
     #[test]
     fn test_added_file_unchanged() {
         let a = make_annotation(1, 5);
@@ -381,4 +654,50 @@ mod tests {
         };
         assert_eq!(adjust_annotation(&a, &diff), AdjustResult::Unchanged);
     }
+
+    #[test]
+    fn test_added_lines_are_placed_by_the_line_they_precede() {
+        let old = "a\nb\nc\nd\ne\n";
+        // Added above the first line, in a hunk whose context overlaps the annotation
+        let diff = diff_texts(old, "x\ny\na\nb\nc\nd\ne\n", "f", &AdjustOptions::default()).unwrap();
+        assert_eq!(
+            adjust_annotation(&make_annotation(1, 1), &diff),
+            AdjustResult::Shifted { old_start: 1, old_end: 1, new_start: 3, new_end: 3 }
+        );
+        // A replaced line before the annotation leaves it in place
+        let diff = diff_texts(old, "A\nb\nc\nd\ne\n", "f", &AdjustOptions::default()).unwrap();
+        assert_eq!(adjust_annotation(&make_annotation(2, 3), &diff), AdjustResult::Unchanged);
+        // Added inside the range widens it
+        let diff = diff_texts(old, "a\nb\nx\nc\nd\ne\n", "f", &AdjustOptions::default()).unwrap();
+        assert_eq!(
+            adjust_annotation(&make_annotation(2, 3), &diff),
+            AdjustResult::Shifted { old_start: 2, old_end: 3, new_start: 2, new_end: 4 }
+        );
+    }
+
+    #[test]
+    fn test_indexed_pass_matches_full_walk() {
+        let scenario = crate::git::synthetic::Scenario::generate(3, 60, 400, 7);
+        let results = adjust_annotations(&scenario.annotations, &scenario.diffs, &AdjustOptions::default());
+        let mut changed = 0;
+        for (a, result) in &results {
+            let diff = scenario.diffs.iter().find(|d| d.applies_to(&a.file_path)).unwrap();
+            let walked = adjust_from(a, diff, 0, 0);
+            if walked != AdjustResult::Deleted {
+                assert_eq!(*result, walked, "{}:{}-{}", a.file_path, a.start_line, a.end_line);
+            }
+            changed += usize::from(walked != AdjustResult::Unchanged);
+        }
+        assert!(changed > 1000, "{}", changed);
+
+        let mut annotations = scenario.annotations.clone();
+        apply_adjustments(&mut annotations, &results);
+        let deleted = results.iter().filter(|(_, r)| *r == AdjustResult::Deleted).count();
+        assert_eq!(annotations.len(), scenario.annotations.len() - deleted);
+        for (a, result) in &results {
+            if let AdjustResult::Shifted { new_start, .. } = result {
+                assert_eq!(annotations.iter().find(|b| b.id == a.id).unwrap().start_line, *new_start);
+            }
+        }
+    }
 }
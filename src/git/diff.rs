@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hunk {
@@ -38,6 +39,25 @@ pub enum FileDiffStatus {
     Deleted,
     Modified,
     Renamed,
+    Copied,
+    /// Turned into a symlink or back; the two sides' lines aren't comparable.
+    TypeChanged,
+}
+
+/// The diff `applies_to` each old path, for lookups across many annotations.
+/// Earlier diffs win, matching a linear `find` over the same list.
+pub fn by_path(diffs: &[FileDiff]) -> HashMap<&str, &FileDiff> {
+    let mut map = HashMap::new();
+    for diff in diffs {
+        let paths = match diff.status {
+            FileDiffStatus::Copied => [None, diff.new_path.as_deref()],
+            _ => [diff.old_path.as_deref(), diff.new_path.as_deref()],
+        };
+        for path in paths.into_iter().flatten() {
+            map.entry(path).or_insert(diff);
+        }
+    }
+    map
 }
 
 impl Hunk {
@@ -61,3 +81,104 @@ impl Hunk {
             .collect()
     }
 }
+
+impl FileDiff {
+    /// Whether annotations on `path` (in the old version) are affected by this diff.
+    /// A copy leaves its source untouched, so it only matches its own new path.
+    pub fn applies_to(&self, path: &str) -> bool {
+        match self.status {
+            FileDiffStatus::Copied => self.new_path.as_deref() == Some(path),
+            _ => self.old_path.as_deref() == Some(path) || self.new_path.as_deref() == Some(path),
+        }
+    }
+
+    /// Maps a line number in the old version of the file to its number in the
+    /// new version. Returns `None` if the line was deleted.
+    pub fn map_old_line(&self, line: u32) -> Option<u32> {
+        let mut offset: i64 = 0;
+        for hunk in &self.hunks {
+            if hunk.old_lines == 0 {
+                // Pure insertion after `old_start`
+                if hunk.old_start < line {
+                    offset += hunk.net_offset();
+                    continue;
+                }
+                break;
+            }
+            if hunk.old_end() < line {
+                offset += hunk.net_offset();
+                continue;
+            }
+            if hunk.old_start > line {
+                break;
+            }
+            if let Some(diff_line) = hunk.lines.iter().find(|l| l.old_lineno == Some(line)) {
+                return match diff_line.origin {
+                    DiffLineType::Deletion => None,
+                    _ => diff_line.new_lineno,
+                };
+            }
+            break;
+        }
+        Some((line as i64 + offset) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(origin: DiffLineType, old: Option<u32>, new: Option<u32>) -> DiffLine {
+        DiffLine {
+            origin,
+            old_lineno: old,
+            new_lineno: new,
+            content: String::new(),
+        }
+    }
+
+    fn modified(hunks: Vec<Hunk>) -> FileDiff {
+        FileDiff {
+            old_path: Some("f.rs".into()),
+            new_path: Some("f.rs".into()),
+            hunks,
+            status: FileDiffStatus::Modified,
+        }
+    }
+
+    #[test]
+    fn test_map_old_line_insertion_before() {
+        let diff = modified(vec![Hunk {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: 2,
+            lines: vec![
+                line(DiffLineType::Addition, None, Some(1)),
+                line(DiffLineType::Addition, None, Some(2)),
+            ],
+        }]);
+        assert_eq!(diff.map_old_line(1), Some(3));
+        assert_eq!(diff.map_old_line(10), Some(12));
+    }
+
+    #[test]
+    fn test_map_old_line_inside_hunk() {
+        let diff = modified(vec![Hunk {
+            old_start: 4,
+            old_lines: 3,
+            new_start: 4,
+            new_lines: 2,
+            lines: vec![
+                line(DiffLineType::Context, Some(4), Some(4)),
+                line(DiffLineType::Deletion, Some(5), None),
+                line(DiffLineType::Context, Some(6), Some(5)),
+            ],
+        }]);
+        assert_eq!(diff.map_old_line(3), Some(3));
+        assert_eq!(diff.map_old_line(4), Some(4));
+        assert_eq!(diff.map_old_line(5), None);
+        assert_eq!(diff.map_old_line(6), Some(5));
+        assert_eq!(diff.map_old_line(20), Some(19));
+    }
+}
@@ -0,0 +1,601 @@
+use crate::core::annotation::{AdjustResult, Annotation};
+use crate::core::progress::Progress;
+use crate::core::repair::find_snippet;
+use crate::core::tombstone::Tombstone;
+use crate::git::adjust::{AdjustOptions, adjust_annotations, apply_adjustments, compute_diffs, diff_blobs};
+use crate::git::diff::{FileDiff, FileDiffStatus};
+use crate::git::rename::{apply_renames, copies_of};
+use anyhow::{Result, bail};
+use git2::{Oid, Repository};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Histories longer than this are adjusted with a single two-tree diff.
+const MAX_STEPS: usize = 256;
+
+/// Outcome of adjusting annotations across a commit range.
+#[derive(Debug, Default)]
+pub struct HistoryAdjustment {
+    pub steps: usize,
+    pub renames: Vec<(String, String)>,
+    /// Annotations duplicated into copied files (already added to the annotation list).
+    pub copies: Vec<Annotation>,
+    /// One entry per input annotation, relative to its position before the adjustment.
+    pub results: Vec<(Annotation, AdjustResult)>,
+    /// Deleted annotations whose file was removed, with the lines they covered.
+    pub tombstones: Vec<Tombstone>,
+}
+
+/// Plans the sequence of `(from, to)` commit pairs to diff when moving from
+/// `from` to `to`.
+///
+/// - `to` descends from `from` along first parents: one step per commit, merges
+///   diffed against their first parent.
+/// - History was rewritten (rebase): the rebased copy of `from` is located in
+///   the new history by patch-id, then the remaining commits are stepped through.
+/// - Anything else falls back to a direct two-tree diff.
+pub fn plan_steps(repo: &Repository, from: &str, to: &str) -> Result<Vec<(String, String)>> {
+    let from_oid = repo.revparse_single(from)?.peel_to_commit()?.id();
+    let to_oid = repo.revparse_single(to)?.peel_to_commit()?.id();
+
+    if from_oid == to_oid {
+        return Ok(Vec::new());
+    }
+
+    let direct = vec![(from_oid.to_string(), to_oid.to_string())];
+
+    if repo.graph_descendant_of(to_oid, from_oid)? {
+        return Ok(match first_parent_chain(repo, to_oid, from_oid)? {
+            Some(chain) => chain_steps(from_oid, &chain),
+            None => direct,
+        });
+    }
+
+    if repo.graph_descendant_of(from_oid, to_oid)? {
+        return Ok(direct);
+    }
+
+    let Ok(base) = repo.merge_base(from_oid, to_oid) else {
+        return Ok(direct);
+    };
+    let Some(new_chain) = first_parent_chain(repo, to_oid, base)? else {
+        return Ok(direct);
+    };
+
+    let from_patch = patch_id(repo, from_oid)?;
+    let mut equivalent = None;
+    for (i, oid) in new_chain.iter().enumerate().rev() {
+        if patch_id(repo, *oid)? == from_patch {
+            equivalent = Some(i);
+            break;
+        }
+    }
+
+    match equivalent {
+        Some(i) => {
+            let mut steps = vec![(from_oid.to_string(), new_chain[i].to_string())];
+            steps.extend(chain_steps(new_chain[i], &new_chain[i + 1..]));
+            Ok(steps)
+        }
+        None => Ok(direct),
+    }
+}
+
+/// Adjusts `annotations` in place from `from` to `to`, stepping through the
+/// planned history. Conflicted annotations are moved onto their surviving lines
+/// so later steps keep tracking them.
+pub fn adjust_across(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+    annotations: &mut Vec<Annotation>,
+    options: &AdjustOptions,
+) -> Result<HistoryAdjustment> {
+    adjust_across_with(repo, from, to, annotations, options, &Progress::new())
+}
+
+/// Like `adjust_across`, reporting each step to `progress`. Once it is
+/// cancelled, `annotations` are put back as they were and an error returned.
+pub fn adjust_across_with(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+    annotations: &mut Vec<Annotation>,
+    options: &AdjustOptions,
+    progress: &Progress,
+) -> Result<HistoryAdjustment> {
+    let originals = annotations.clone();
+    let steps = plan_steps(repo, from, to)?;
+    progress.set_total(steps.len());
+    let mut renames = Vec::new();
+    let mut copies = Vec::new();
+    let mut conflicts: HashMap<Uuid, Vec<u32>> = HashMap::new();
+    let mut moved: HashSet<Uuid> = HashSet::new();
+    let mut tombstones = Vec::new();
+
+    // Annotations written against another version of their file than the one
+    // at `from`, e.g. before it was committed, are diffed straight from that
+    // version; stepping through history would shift them by changes they
+    // already saw
+    let from_tree = repo.revparse_single(from)?.peel_to_tree()?;
+    let to_tree = repo.revparse_single(to)?.peel_to_tree()?;
+    let mut direct = Vec::new();
+    for mut a in std::mem::take(annotations) {
+        let Some((old_blob, new_blob)) = direct_blobs(repo, &from_tree, &to_tree, &a) else {
+            // Written on a version since pruned from the object database, so
+            // only the snippet can tell where the lines went
+            if written_on_lost_version(repo, &from_tree, &a)
+                && let Some(start) = snippet_start_at(repo, to, &a)?
+            {
+                a.end_line = start + a.end_line.saturating_sub(a.start_line);
+                a.start_line = start;
+                direct.push(a);
+            } else {
+                annotations.push(a);
+            }
+            continue;
+        };
+        let diff = diff_blobs(repo, &old_blob, &new_blob, &a.file_path, options)?;
+        let diffs = std::slice::from_ref(&diff);
+        let results = adjust_annotations(std::slice::from_ref(&a), diffs, options);
+        let mut adjusted = vec![a];
+        apply_adjustments(&mut adjusted, &results);
+        for (ann, result) in &results {
+            match result {
+                AdjustResult::Conflict { deleted_lines } => {
+                    conflicts.insert(ann.id, deleted_lines.clone());
+                    relocate_conflict(&mut adjusted, ann, deleted_lines, diffs);
+                }
+                AdjustResult::Moved { .. } => {
+                    moved.insert(ann.id);
+                }
+                _ => {}
+            }
+        }
+        direct.extend(adjusted);
+    }
+
+    for (step_from, step_to) in &steps {
+        if progress.is_cancelled() {
+            *annotations = originals;
+            bail!("Adjustment cancelled");
+        }
+        let diffs = compute_diffs(repo, step_from, step_to, options)?;
+        // Copied from where the annotations were before this step, and
+        // added after it so they aren't shifted a second time
+        let step_copies = if options.copy_annotations { copies_of(annotations, &diffs) } else { Vec::new() };
+        renames.extend(apply_renames(annotations, &diffs));
+
+        let results = adjust_annotations(annotations, &diffs, options);
+        apply_adjustments(annotations, &results);
+
+        for (ann, result) in &results {
+            match result {
+                AdjustResult::Conflict { deleted_lines } => {
+                    conflicts.entry(ann.id).or_insert_with(|| deleted_lines.clone());
+                    relocate_conflict(annotations, ann, deleted_lines, &diffs);
+                }
+                AdjustResult::Moved { .. } => {
+                    moved.insert(ann.id);
+                }
+                AdjustResult::Deleted if file_deleted(&diffs, &ann.file_path) => {
+                    let old_content = crate::git::repo::read_file_at(repo, step_from, &ann.file_path)?;
+                    tombstones.push(Tombstone::new(ann.clone(), step_to, &old_content.unwrap_or_default()));
+                }
+                _ => {}
+            }
+        }
+
+        annotations.extend(step_copies.iter().cloned());
+        copies.extend(step_copies);
+        progress.advance();
+    }
+    annotations.extend(direct);
+    let order: HashMap<Uuid, usize> = originals.iter().enumerate().map(|(i, a)| (a.id, i)).collect();
+    annotations.sort_by_key(|a| order.get(&a.id).copied().unwrap_or(usize::MAX));
+
+    // Annotations on files that changed now refer to the version at `to`
+    let original_paths: HashMap<Uuid, &str> = originals.iter().map(|a| (a.id, a.file_path.as_str())).collect();
+    for a in annotations.iter_mut() {
+        let new_blob = blob_in(&to_tree, &a.file_path);
+        let changed = match original_paths.get(&a.id) {
+            Some(path) => blob_in(&from_tree, path) != new_blob,
+            None => true,
+        };
+        if changed {
+            a.blob = new_blob;
+        }
+    }
+
+    let results = originals
+        .into_iter()
+        .map(|orig| match annotations.iter().find(|a| a.id == orig.id) {
+            None => (orig, AdjustResult::Deleted),
+            Some(current) => {
+                let result = if let Some(deleted_lines) = conflicts.remove(&orig.id) {
+                    AdjustResult::Conflict { deleted_lines }
+                } else if moved.contains(&orig.id) {
+                    AdjustResult::Moved {
+                        new_path: current.file_path.clone(),
+                        new_start: current.start_line,
+                        new_end: current.end_line,
+                    }
+                } else if current.start_line == orig.start_line && current.end_line == orig.end_line {
+                    AdjustResult::Unchanged
+                } else {
+                    AdjustResult::Shifted {
+                        old_start: orig.start_line,
+                        old_end: orig.end_line,
+                        new_start: current.start_line,
+                        new_end: current.end_line,
+                    }
+                };
+                let mut reported = orig;
+                reported.file_path = current.file_path.clone();
+                (reported, result)
+            }
+        })
+        .collect();
+
+    Ok(HistoryAdjustment {
+        steps: steps.len(),
+        renames,
+        copies,
+        results,
+        tombstones,
+    })
+}
+
+/// The blob `a` was written against and the one its file has in `to_tree`,
+/// when the file changed between the trees and `a` doesn't refer to its
+/// version in `from_tree`.
+fn direct_blobs(repo: &Repository, from_tree: &git2::Tree, to_tree: &git2::Tree, a: &Annotation) -> Option<(String, String)> {
+    let blob = a.blob.as_deref()?;
+    let from_blob = blob_in(from_tree, &a.file_path);
+    let to_blob = blob_in(to_tree, &a.file_path)?;
+    let known = Oid::from_str(blob).is_ok_and(|oid| repo.find_blob(oid).is_ok());
+    (known && from_blob.as_deref() != Some(blob) && from_blob.as_deref() != Some(to_blob.as_str()))
+        .then(|| (blob.to_string(), to_blob))
+}
+
+/// Whether `a` refers to a version of its file other than the one in
+/// `from_tree` that is no longer in the object database.
+fn written_on_lost_version(repo: &Repository, from_tree: &git2::Tree, a: &Annotation) -> bool {
+    let Some(blob) = a.blob.as_deref() else {
+        return false;
+    };
+    blob_in(from_tree, &a.file_path).as_deref() != Some(blob)
+        && !Oid::from_str(blob).is_ok_and(|oid| repo.find_blob(oid).is_ok())
+}
+
+/// 1-based line where the snippet of `a` occurs, once, in its file at `rev`.
+fn snippet_start_at(repo: &Repository, rev: &str, a: &Annotation) -> Result<Option<u32>> {
+    let (Some(snippet), Some(content)) = (&a.snippet, crate::git::repo::read_file_at(repo, rev, &a.file_path)?) else {
+        return Ok(None);
+    };
+    let lines: Vec<String> = content.lines().map(String::from).collect();
+    Ok(find_snippet(&lines, snippet))
+}
+
+fn blob_in(tree: &git2::Tree, path: &str) -> Option<String> {
+    tree.get_path(std::path::Path::new(path)).ok().map(|entry| entry.id().to_string())
+}
+
+fn file_deleted(diffs: &[FileDiff], path: &str) -> bool {
+    diffs
+        .iter()
+        .any(|d| d.status == FileDiffStatus::Deleted && d.old_path.as_deref() == Some(path))
+}
+
+fn relocate_conflict(annotations: &mut [Annotation], ann: &Annotation, deleted_lines: &[u32], diffs: &[FileDiff]) {
+    let Some(diff) = diffs.iter().find(|d| d.applies_to(&ann.file_path)) else {
+        return;
+    };
+    let surviving: Vec<u32> = ann
+        .line_range()
+        .filter(|l| !deleted_lines.contains(l))
+        .filter_map(|l| diff.map_old_line(l))
+        .collect();
+    if let (Some(&start), Some(&end)) = (surviving.iter().min(), surviving.iter().max())
+        && let Some(a) = annotations.iter_mut().find(|a| a.id == ann.id)
+    {
+        a.start_line = start;
+        a.end_line = end;
+    }
+}
+
+/// Commits on the first-parent chain from `tip` back to (excluding) `stop`,
+/// oldest first. `None` if `stop` is not reached within `MAX_STEPS`.
+fn first_parent_chain(repo: &Repository, tip: Oid, stop: Oid) -> Result<Option<Vec<Oid>>> {
+    let mut chain = Vec::new();
+    let mut current = repo.find_commit(tip)?;
+    while current.id() != stop {
+        if chain.len() >= MAX_STEPS || current.parent_count() == 0 {
+            return Ok(None);
+        }
+        chain.push(current.id());
+        current = current.parent(0)?;
+    }
+    chain.reverse();
+    Ok(Some(chain))
+}
+
+fn chain_steps(start: Oid, chain: &[Oid]) -> Vec<(String, String)> {
+    let mut steps = Vec::new();
+    let mut prev = start;
+    for oid in chain {
+        steps.push((prev.to_string(), oid.to_string()));
+        prev = *oid;
+    }
+    steps
+}
+
+fn patch_id(repo: &Repository, oid: Oid) -> Result<Oid> {
+    let commit = repo.find_commit(oid)?;
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(diff.patchid(None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let out = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q", "-b", "main"]);
+        git(dir.path(), &["config", "user.email", "test@test.com"]);
+        git(dir.path(), &["config", "user.name", "Test"]);
+        dir
+    }
+
+    fn write_lines(dir: &Path, file: &str, lines: &[&str]) {
+        std::fs::write(dir.join(file), lines.join("\n") + "\n").unwrap();
+    }
+
+    fn commit_all(dir: &Path, msg: &str) -> String {
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "-q", "-m", msg]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_linear_history_steps_per_commit() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c"]);
+        let c1 = commit_all(dir.path(), "c1");
+        write_lines(dir.path(), "f.txt", &["x", "a", "b", "c"]);
+        commit_all(dir.path(), "c2");
+        write_lines(dir.path(), "f.txt", &["y", "x", "a", "b", "c"]);
+        let c3 = commit_all(dir.path(), "c3");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let steps = plan_steps(&repo, &c1, &c3).unwrap();
+        assert_eq!(steps.len(), 2);
+
+        let mut anns = vec![Annotation::new("f.txt".into(), 3, 3, "on c".into())];
+        let outcome = adjust_across(&repo, &c1, &c3, &mut anns, &AdjustOptions::default()).unwrap();
+        assert_eq!(anns[0].start_line, 5);
+        assert!(matches!(outcome.results[0].1, AdjustResult::Shifted { new_start: 5, .. }));
+    }
+
+    #[test]
+    fn test_copy_maps_lines_from_before_the_step() {
+        let dir = init_repo();
+        let lines = ["one", "two", "three", "four", "five", "six", "seven", "eight"];
+        write_lines(dir.path(), "f.txt", &lines);
+        let c1 = commit_all(dir.path(), "c1");
+        // f.txt gets two lines on top while its old text is copied to g.txt
+        std::fs::copy(dir.path().join("f.txt"), dir.path().join("g.txt")).unwrap();
+        let shifted: Vec<&str> = ["new", "new"].into_iter().chain(lines).collect();
+        write_lines(dir.path(), "f.txt", &shifted);
+        let c2 = commit_all(dir.path(), "c2");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut anns = vec![Annotation::new("f.txt".into(), 5, 5, "on five".into())];
+        let options = AdjustOptions { detect_copies: true, copy_annotations: true, ..AdjustOptions::default() };
+        let outcome = adjust_across(&repo, &c1, &c2, &mut anns, &options).unwrap();
+        assert_eq!(outcome.copies.len(), 1);
+        let lines: Vec<(&str, u32)> = anns.iter().map(|a| (a.file_path.as_str(), a.start_line)).collect();
+        assert_eq!(lines, vec![("f.txt", 7), ("g.txt", 5)]);
+    }
+
+    #[test]
+    fn test_cancelled_adjustment_keeps_annotations() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c"]);
+        let c1 = commit_all(dir.path(), "c1");
+        write_lines(dir.path(), "f.txt", &["x", "a", "b", "c"]);
+        let c2 = commit_all(dir.path(), "c2");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut anns = vec![Annotation::new("f.txt".into(), 3, 3, "on c".into())];
+        let progress = Progress::new();
+        progress.cancel();
+        let err = adjust_across_with(&repo, &c1, &c2, &mut anns, &AdjustOptions::default(), &progress).unwrap_err();
+        assert_eq!(err.to_string(), "Adjustment cancelled");
+        assert_eq!(anns[0].start_line, 3);
+
+        let progress = Progress::new();
+        adjust_across_with(&repo, &c1, &c2, &mut anns, &AdjustOptions::default(), &progress).unwrap();
+        assert_eq!(anns[0].start_line, 4);
+        assert_eq!(progress.percent(), Some(100));
+    }
+
+    #[test]
+    fn test_annotation_on_uncommitted_version_diffed_from_its_blob() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c"]);
+        let c1 = commit_all(dir.path(), "c1");
+        // Written on the working tree before the insertion was committed
+        write_lines(dir.path(), "f.txt", &["x", "a", "b", "c"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut early = Annotation::new("f.txt".into(), 4, 4, "on c".into());
+        early.blob = crate::git::repo::worktree_blob(&repo, "f.txt");
+        let mut legacy = Annotation::new("f.txt".into(), 3, 3, "on c".into());
+        legacy.blob = None;
+        write_lines(dir.path(), "f.txt", &["x", "a", "y", "b", "c"]);
+        let c2 = commit_all(dir.path(), "c2");
+
+        let mut anns = vec![early, legacy];
+        adjust_across(&repo, &c1, &c2, &mut anns, &AdjustOptions::default()).unwrap();
+        assert_eq!((anns[0].start_line, anns[1].start_line), (5, 5));
+        let head_blob = crate::git::repo::blob_at(&repo, &c2, "f.txt").unwrap();
+        assert!(anns.iter().all(|a| a.blob == head_blob));
+    }
+
+    #[test]
+    fn test_annotation_on_pruned_version_reanchored_by_snippet() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c", "d"]);
+        let c1 = commit_all(dir.path(), "c1");
+        write_lines(dir.path(), "f.txt", &["x", "a", "b", "c", "d"]);
+        let c2 = commit_all(dir.path(), "c2");
+
+        // Written on a working tree version that never reached the object database
+        let repo = Repository::open(dir.path()).unwrap();
+        let lost = Oid::hash_object(git2::ObjectType::Blob, b"a\nw\nb\nc\nd\n").unwrap();
+        let mut a = Annotation::new("f.txt".into(), 4, 5, "on c".into());
+        a.snippet = Some("c\nd".into());
+        a.blob = Some(lost.to_string());
+
+        let mut anns = vec![a];
+        adjust_across(&repo, &c1, &c2, &mut anns, &AdjustOptions::default()).unwrap();
+        assert_eq!((anns[0].start_line, anns[0].end_line), (4, 5));
+    }
+
+    #[test]
+    fn test_rebase_matches_by_patch_id() {
+        let dir = init_repo();
+        let base_lines: Vec<String> = (1..=10).map(|i| format!("line {i}")).collect();
+        let base: Vec<&str> = base_lines.iter().map(|s| s.as_str()).collect();
+        write_lines(dir.path(), "f.txt", &base);
+        commit_all(dir.path(), "base");
+
+        git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+        let mut feature = base.clone();
+        feature[7] = "feature change";
+        write_lines(dir.path(), "f.txt", &feature);
+        let old_tip = commit_all(dir.path(), "feature");
+
+        git(dir.path(), &["checkout", "-q", "main"]);
+        let mut upstream = vec!["new header 1", "new header 2"];
+        upstream.extend(base.iter());
+        write_lines(dir.path(), "f.txt", &upstream);
+        commit_all(dir.path(), "upstream");
+
+        git(dir.path(), &["checkout", "-q", "feature"]);
+        git(dir.path(), &["rebase", "-q", "main"]);
+        write_lines(dir.path(), "g.txt", &["later"]);
+        let new_tip = commit_all(dir.path(), "after rebase");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let steps = plan_steps(&repo, &old_tip, &new_tip).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].0, old_tip);
+
+        let mut anns = vec![Annotation::new("f.txt".into(), 8, 8, "feature note".into())];
+        adjust_across(&repo, &old_tip, &new_tip, &mut anns, &AdjustOptions::default()).unwrap();
+        assert_eq!(anns[0].start_line, 10);
+    }
+
+    #[test]
+    fn test_ignore_whitespace_reindent() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["fn f() {", "let a = 1;", "let b = 2;", "}"]);
+        let c1 = commit_all(dir.path(), "c1");
+        write_lines(dir.path(), "f.txt", &["// header", "fn f() {", "    let a = 1;", "    let b = 2;", "}"]);
+        let c2 = commit_all(dir.path(), "reformat");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let options = AdjustOptions {
+            ignore_whitespace: true,
+            ..Default::default()
+        };
+        let mut anns = vec![Annotation::new("f.txt".into(), 2, 3, "body".into())];
+        let outcome = adjust_across(&repo, &c1, &c2, &mut anns, &options).unwrap();
+        assert!(matches!(outcome.results[0].1, AdjustResult::Shifted { .. }));
+        assert_eq!((anns[0].start_line, anns[0].end_line), (3, 4));
+
+        let mut anns = vec![Annotation::new("f.txt".into(), 2, 3, "body".into())];
+        let outcome = adjust_across(&repo, &c1, &c2, &mut anns, &AdjustOptions::default()).unwrap();
+        assert!(!matches!(outcome.results[0].1, AdjustResult::Shifted { .. }));
+    }
+
+    #[test]
+    fn test_conflict_relocated_to_surviving_lines() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c", "d", "e"]);
+        let c1 = commit_all(dir.path(), "c1");
+        write_lines(dir.path(), "f.txt", &["a", "c", "d", "e"]);
+        commit_all(dir.path(), "drop b");
+        write_lines(dir.path(), "f.txt", &["top", "a", "c", "d", "e"]);
+        let c3 = commit_all(dir.path(), "add top");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut anns = vec![Annotation::new("f.txt".into(), 2, 3, "b and c".into())];
+        let outcome = adjust_across(&repo, &c1, &c3, &mut anns, &AdjustOptions::default()).unwrap();
+        assert!(matches!(outcome.results[0].1, AdjustResult::Conflict { .. }));
+        assert_eq!((anns[0].start_line, anns[0].end_line), (3, 3));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_mode_and_type_changes_keep_annotations() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c"]);
+        write_lines(dir.path(), "g.txt", &["x", "y"]);
+        let c1 = commit_all(dir.path(), "c1");
+        std::fs::set_permissions(dir.path().join("f.txt"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_file(dir.path().join("g.txt")).unwrap();
+        std::os::unix::fs::symlink("f.txt", dir.path().join("g.txt")).unwrap();
+        let c2 = commit_all(dir.path(), "chmod and symlink");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut anns = vec![
+            Annotation::new("f.txt".into(), 2, 3, "mode".into()),
+            Annotation::new("g.txt".into(), 2, 2, "type".into()),
+        ];
+        let outcome = adjust_across(&repo, &c1, &c2, &mut anns, &AdjustOptions::default()).unwrap();
+        assert!(outcome.results.iter().all(|(_, r)| *r == AdjustResult::Unchanged));
+        assert_eq!(anns.len(), 2);
+        assert_eq!((anns[1].file_path.as_str(), anns[1].start_line), ("g.txt", 2));
+    }
+
+    #[test]
+    fn test_deleted_file_annotations_become_tombstones() {
+        let dir = init_repo();
+        write_lines(dir.path(), "f.txt", &["a", "b", "c"]);
+        write_lines(dir.path(), "g.txt", &["x", "y"]);
+        let c1 = commit_all(dir.path(), "c1");
+        std::fs::remove_file(dir.path().join("g.txt")).unwrap();
+        write_lines(dir.path(), "f.txt", &["a", "c"]);
+        let c2 = commit_all(dir.path(), "remove g and a line of f");
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let mut anns = vec![
+            Annotation::new("f.txt".into(), 2, 2, "line".into()),
+            Annotation::new("g.txt".into(), 2, 2, "file".into()),
+        ];
+        let outcome = adjust_across(&repo, &c1, &c2, &mut anns, &AdjustOptions::default()).unwrap();
+        assert!(anns.is_empty());
+        assert_eq!(outcome.tombstones.len(), 1);
+        let tombstone = &outcome.tombstones[0];
+        assert_eq!(tombstone.annotation.text, "file");
+        assert_eq!(tombstone.deleted_in, c2);
+        assert_eq!(tombstone.content, vec!["y"]);
+    }
+}
@@ -0,0 +1,153 @@
+//! What a correct adjustment guarantees, checked against the text of the
+//! file before and after: an annotation that survives starts and ends on
+//! the lines it was written on and still covers every line it did, in order,
+//! with only added lines in between. A conflict names deleted lines inside
+//! the range, and not all of them.
+
+use crate::core::annotation::{AdjustResult, Annotation};
+
+/// Why `result` is not a faithful adjustment of `annotation`, or `None` when
+/// it is. `old` is the annotated file's text, `new` the text of the file it
+/// ends up in (after a rename or move), if that exists.
+pub fn check(annotation: &Annotation, result: &AdjustResult, old: &str, new: Option<&str>, ignore_whitespace: bool) -> Option<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let (start, end) = (annotation.start_line, annotation.end_line);
+    if start == 0 || end < start || end as usize > old_lines.len() {
+        // Out of range before adjusting; that is for `repair`
+        return None;
+    }
+    let (new_start, new_end) = match result {
+        AdjustResult::Unchanged => (start, end),
+        AdjustResult::Shifted { new_start, new_end, .. } | AdjustResult::Moved { new_start, new_end, .. } => {
+            (*new_start, *new_end)
+        }
+        AdjustResult::Deleted => return None,
+        AdjustResult::Conflict { deleted_lines } => {
+            if let Some(line) = deleted_lines.iter().find(|l| !(start..=end).contains(*l)) {
+                return Some(format!("conflict names line {}, outside the annotated range", line));
+            }
+            return match deleted_lines.len() {
+                0 => Some("conflict without deleted lines".to_string()),
+                n if n as u32 > end - start => Some("every annotated line deleted, reported as a conflict".to_string()),
+                _ => None,
+            };
+        }
+    };
+
+    let Some(new) = new else {
+        return Some("its file is gone after adjusting".to_string());
+    };
+    let new_lines: Vec<&str> = new.lines().collect();
+    if new_start == 0 || new_end < new_start || new_end as usize > new_lines.len() {
+        return Some(format!("lands on {}-{}, outside the file's {} lines", new_start, new_end, new_lines.len()));
+    }
+    let same = |a: &str, b: &str| {
+        if ignore_whitespace {
+            a.split_whitespace().eq(b.split_whitespace())
+        } else {
+            a == b
+        }
+    };
+    let before = &old_lines[start as usize - 1..end as usize];
+    let after = &new_lines[new_start as usize - 1..new_end as usize];
+    if !same(before[0], after[0]) {
+        return Some(format!("starts on {:?} instead of the annotated {:?}", after[0], before[0]));
+    }
+    if !same(before[before.len() - 1], after[after.len() - 1]) {
+        return Some(format!(
+            "ends on {:?} instead of the annotated {:?}",
+            after[after.len() - 1],
+            before[before.len() - 1]
+        ));
+    }
+    let mut rest = after.iter();
+    if !before.iter().all(|b| rest.any(|a| same(a, b))) {
+        return Some("no longer covers every annotated line".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::adjust::{AdjustOptions, adjust_annotation, diff_texts};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_check_catches_a_wrong_shift() {
+        let text = "a\nb\nc\nd\n";
+        let a = Annotation::new("f.rs".into(), 2, 3, "note".into());
+        let shifted = |new_start, new_end| AdjustResult::Shifted {
+            old_start: 2,
+            old_end: 3,
+            new_start,
+            new_end,
+        };
+        assert_eq!(check(&a, &AdjustResult::Unchanged, text, Some(text), false), None);
+        assert_eq!(check(&a, &shifted(3, 4), "x\na\nb\nc\nd\n", Some(text), false), Some("starts on \"c\" instead of the annotated \"a\"".into()));
+        assert_eq!(check(&a, &shifted(3, 4), text, Some("x\na\nb\nc\nd\n"), false), None);
+        assert!(check(&a, &shifted(4, 5), text, Some(text), false).unwrap().contains("outside"));
+        assert_eq!(check(&a, &AdjustResult::Unchanged, text, Some("a\n b\n c\nd\n"), true), None);
+        let conflict = AdjustResult::Conflict { deleted_lines: vec![2, 3] };
+        assert!(check(&a, &conflict, text, Some(text), false).is_some());
+    }
+
+    /// Insertions, deletions and replacements as (kind, position, removed, added).
+    type Edit = (u8, usize, usize, usize);
+
+    /// Applies `edits` to distinct lines, adding distinct lines, so every
+    /// line can be told apart by its content.
+    fn apply(lines: &mut Vec<String>, edits: &[Edit]) {
+        let mut fresh = 0;
+        for &(kind, at, removed, added) in edits {
+            let at = at % (lines.len() + 1);
+            let removed = if kind == 0 { 0 } else { removed.min(lines.len() - at) };
+            let added = if kind == 1 { 0 } else { added };
+            let inserted: Vec<String> = (0..added)
+                .map(|_| {
+                    fresh += 1;
+                    format!("added {}", fresh)
+                })
+                .collect();
+            lines.splice(at..at + removed, inserted);
+        }
+    }
+
+    fn text(lines: &[String]) -> String {
+        lines.iter().map(|l| format!("{}\n", l)).collect()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_adjusted_annotations_keep_their_lines(
+            len in 1usize..60,
+            edits in prop::collection::vec((0u8..3, any::<usize>(), 1usize..5, 1usize..5), 0..8),
+            ranges in prop::collection::vec((any::<usize>(), 0usize..6), 1..10),
+        ) {
+            let old: Vec<String> = (1..=len).map(|i| format!("line {}", i)).collect();
+            let mut new = old.clone();
+            apply(&mut new, &edits);
+            let (old_text, new_text) = (text(&old), text(&new));
+            let diff = diff_texts(&old_text, &new_text, "f.rs", &AdjustOptions::default()).unwrap();
+
+            for (at, extra) in ranges {
+                let start = at % len + 1;
+                let end = (start + extra).min(len);
+                let a = Annotation::new("f.rs".into(), start as u32, end as u32, "note".into());
+                let result = adjust_annotation(&a, &diff);
+                prop_assert_eq!(check(&a, &result, &old_text, Some(&new_text), false), None, "{:?} for {}-{}", result, start, end);
+
+                // Lines are distinct, so deletions can be told from content too
+                let kept: Vec<bool> = old[start - 1..end].iter().map(|l| new.contains(l)).collect();
+                match &result {
+                    AdjustResult::Deleted => prop_assert!(kept.iter().all(|k| !k)),
+                    AdjustResult::Conflict { deleted_lines } => {
+                        let gone: Vec<u32> = (start..=end).filter(|l| !kept[l - start]).map(|l| l as u32).collect();
+                        prop_assert_eq!(deleted_lines, &gone);
+                    }
+                    _ => prop_assert!(kept.iter().all(|k| *k)),
+                }
+            }
+        }
+    }
+}
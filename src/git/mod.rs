@@ -1,4 +1,12 @@
 pub mod adjust;
 pub mod diff;
+pub mod history;
+pub mod invariants;
 pub mod rename;
 pub mod repo;
+pub mod report;
+pub mod stale;
+pub mod submodule;
+#[cfg(any(test, feature = "bench"))]
+pub mod synthetic;
+pub mod sync;
@@ -22,6 +22,33 @@ pub fn apply_renames(annotations: &mut [Annotation], diffs: &[FileDiff]) -> Vec<
     renames
 }
 
+/// Duplicates of the annotations on the source of each copied file, in the
+/// copy, their line ranges mapped through the copy's diff. `annotations`
+/// must still have the positions of the diffs' old side.
+pub fn copies_of(annotations: &[Annotation], diffs: &[FileDiff]) -> Vec<Annotation> {
+    let mut copies = Vec::new();
+
+    for diff in diffs {
+        if diff.status == FileDiffStatus::Copied
+            && let (Some(source), Some(target)) = (&diff.old_path, &diff.new_path) {
+                for annotation in annotations.iter().filter(|a| a.file_path == *source) {
+                    let mapped: Vec<u32> = annotation
+                        .line_range()
+                        .filter_map(|l| diff.map_old_line(l))
+                        .collect();
+                    if let (Some(&start), Some(&end)) = (mapped.iter().min(), mapped.iter().max()) {
+                        let mut copy = Annotation::new(target.clone(), start, end, annotation.text.clone());
+                        copy.snippet = annotation.snippet.clone();
+                        copy.author = annotation.author.clone();
+                        copies.push(copy);
+                    }
+                }
+            }
+    }
+
+    copies
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,6 +73,23 @@ mod tests {
         assert_eq!(annotations[1].file_path, "other.rs");
     }
 
+    #[test]
+    fn test_copies_of() {
+        let annotations = vec![Annotation::new("src.rs".into(), 3, 4, "note".into())];
+        let diffs = vec![FileDiff {
+            old_path: Some("src.rs".into()),
+            new_path: Some("copy.rs".into()),
+            hunks: vec![],
+            status: FileDiffStatus::Copied,
+        }];
+
+        let copies = copies_of(&annotations, &diffs);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].file_path, "copy.rs");
+        assert_eq!((copies[0].start_line, copies[0].end_line), (3, 4));
+        assert_ne!(annotations[0].id, copies[0].id);
+    }
+
     #[test]
     fn test_no_renames() {
         let mut annotations = vec![
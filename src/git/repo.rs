@@ -12,6 +12,78 @@ pub fn head_commit_id(repo: &Repository) -> Result<String> {
     Ok(commit.id().to_string())
 }
 
+/// Abbreviates a commit id for display.
+pub fn short_id(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+/// Resolves any revision expression (sha, branch, tag, `HEAD~3`, ...) to a full commit id.
+pub fn resolve_commit(repo: &Repository, rev: &str) -> Result<String> {
+    let commit = repo
+        .revparse_single(rev)
+        .with_context(|| format!("resolving revision '{rev}'"))?
+        .peel_to_commit()
+        .with_context(|| format!("'{rev}' does not point to a commit"))?;
+    Ok(commit.id().to_string())
+}
+
+/// Reads a file's content as of the given revision. `None` if the file does not exist there.
+pub fn read_file_at(repo: &Repository, rev: &str, path: &str) -> Result<Option<String>> {
+    let tree = repo.revparse_single(rev)?.peel_to_commit()?.tree()?;
+    let entry = match tree.get_path(Path::new(path)) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+}
+
+/// Id of the blob `path` has at `rev`. `None` if the file does not exist there.
+pub fn blob_at(repo: &Repository, rev: &str, path: &str) -> Result<Option<String>> {
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    Ok(tree.get_path(Path::new(path)).ok().map(|entry| entry.id().to_string()))
+}
+
+/// Id of the blob for `path` as it is in the working tree, after the same
+/// filters `git add` runs (line endings, `ident` and clean drivers), so an
+/// unchanged file has the blob it has in HEAD. The content is written to the
+/// object database, so adjust can diff from it even if this version is never
+/// committed. Runs the git CLI, since libgit2 skips filter drivers.
+pub fn worktree_blob(repo: &Repository, path: &str) -> Option<String> {
+    let root = repo.workdir()?;
+    let output = std::process::Command::new("git")
+        .args(["hash-object", "-w", &format!("--path={}", path), "--"])
+        .arg(root.join(path))
+        .current_dir(root)
+        .output()
+        .ok()?;
+    let id = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && git2::Oid::from_str(id.trim()).is_ok()).then(|| id.trim().to_string())
+}
+
+/// First lines of the hunks where `content` differs from `path` at `base`,
+/// in order. A file missing at `base` is a single hunk from line 1.
+pub fn changed_hunk_starts(repo: &Repository, base: &str, path: &str, content: &str) -> Result<Vec<u32>> {
+    let tree = repo.revparse_single(base)?.peel_to_commit()?.tree()?;
+    let blob = match tree.get_path(Path::new(path)) {
+        Ok(entry) => entry.to_object(repo)?.peel_to_blob()?,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(vec![1]),
+        Err(e) => return Err(e.into()),
+    };
+    let mut opts = git2::DiffOptions::new();
+    opts.context_lines(0);
+    let patch = git2::Patch::from_blob_and_buffer(&blob, Some(Path::new(path)), content.as_bytes(), Some(Path::new(path)), Some(&mut opts))?;
+    let mut starts = Vec::new();
+    for i in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(i)?;
+        // Pure deletions report the line before the gap
+        let after_gap = hunk.new_lines() == 0 && hunk.new_start() > 0;
+        starts.push(hunk.new_start().max(1) + u32::from(after_gap));
+    }
+    Ok(starts)
+}
+
 pub fn find_repo_root(start: &Path) -> Result<std::path::PathBuf> {
     let repo = Repository::discover(start)
         .with_context(|| format!("finding git repo from {}", start.display()))?;
@@ -20,3 +92,64 @@ pub fn find_repo_root(start: &Path) -> Result<std::path::PathBuf> {
         .context("bare repositories are not supported")?;
     Ok(workdir.to_path_buf())
 }
+
+/// Like `find_repo_root`, but accepts bare repositories, returning their git directory.
+pub fn find_repo_dir(start: &Path) -> Result<std::path::PathBuf> {
+    let repo = Repository::discover(start)
+        .with_context(|| format!("finding git repo from {}", start.display()))?;
+    Ok(repo.workdir().unwrap_or(repo.path()).to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let out = Command::new("git").args(args).current_dir(dir).output().unwrap();
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_worktree_blob_runs_filters() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "t@t"]);
+        git(dir.path(), &["config", "user.name", "t"]);
+        git(dir.path(), &["config", "core.autocrlf", "true"]);
+        git(dir.path(), &["config", "filter.upper.clean", "tr a-z A-Z"]);
+        std::fs::write(dir.path().join(".gitattributes"), "upper.txt filter=upper\n").unwrap();
+        std::fs::write(dir.path().join("crlf.txt"), "one\r\ntwo\r\n").unwrap();
+        std::fs::write(dir.path().join("upper.txt"), "one\ntwo\n").unwrap();
+        git(dir.path(), &["add", "-A"]);
+        git(dir.path(), &["commit", "-q", "-m", "init"]);
+
+        let repo = Repository::open(dir.path()).unwrap();
+        for file in ["crlf.txt", "upper.txt"] {
+            assert_eq!(worktree_blob(&repo, file), blob_at(&repo, "HEAD", file).unwrap(), "{}", file);
+        }
+        assert_eq!(worktree_blob(&repo, "missing.txt"), None);
+    }
+
+    #[test]
+    fn test_changed_hunk_starts() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "t@t"]);
+        git(dir.path(), &["config", "user.name", "t"]);
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\nfour\nfive\n").unwrap();
+        git(dir.path(), &["add", "-A"]);
+        git(dir.path(), &["commit", "-q", "-m", "init"]);
+
+        let repo = Repository::open(dir.path()).unwrap();
+        let starts = |content: &str| changed_hunk_starts(&repo, "HEAD", "a.txt", content).unwrap();
+        assert!(starts("one\ntwo\nthree\nfour\nfive\n").is_empty());
+        // A changed line, then an added one
+        assert_eq!(starts("one\nTWO\nthree\nfour\nfive\nsix\n"), vec![2, 6]);
+        // A deletion is reported at the line after the gap
+        assert_eq!(starts("one\nthree\nfour\nfive\n"), vec![2]);
+        assert_eq!(starts("two\nthree\nfour\nfive\n"), vec![1]);
+        assert_eq!(changed_hunk_starts(&repo, "HEAD", "new.txt", "x\n").unwrap(), vec![1]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Machine-readable summary of an `adjust` run, for `adjust --json`.
+
+use crate::core::annotation::{AdjustResult, Annotation};
+use crate::git::history::HistoryAdjustment;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct AdjustReport {
+    pub from: String,
+    pub to: String,
+    pub steps: usize,
+    pub renames: Vec<Rename>,
+    /// Annotations duplicated into copied files.
+    pub copies: Vec<Placement>,
+    pub annotations: Vec<AnnotationChange>,
+    pub submodules: Vec<SubmoduleReport>,
+    pub summary: Summary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Placement {
+    pub id: Uuid,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl Placement {
+    fn of(a: &Annotation) -> Self {
+        Self {
+            id: a.id,
+            file_path: a.file_path.clone(),
+            start_line: a.start_line,
+            end_line: a.end_line,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Range {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// What happened to one annotation; `old` is its range before the adjustment.
+#[derive(Debug, Serialize)]
+pub struct AnnotationChange {
+    pub id: Uuid,
+    pub old: Range,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Change {
+    Unchanged,
+    Shifted { new: Range },
+    Moved { new: Range },
+    Deleted,
+    Conflict { deleted_lines: Vec<u32> },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubmoduleReport {
+    pub path: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub steps: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub unchanged: usize,
+    pub shifted: usize,
+    pub moved: usize,
+    pub deleted: usize,
+    pub conflicts: usize,
+}
+
+impl AdjustReport {
+    pub fn new(from: &str, to: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            steps: 0,
+            renames: Vec::new(),
+            copies: Vec::new(),
+            annotations: Vec::new(),
+            submodules: Vec::new(),
+            summary: Summary::default(),
+        }
+    }
+
+    /// Adds the outcome of one history walk.
+    pub fn add(&mut self, outcome: &HistoryAdjustment) {
+        self.steps += outcome.steps;
+        self.renames.extend(outcome.renames.iter().map(|(from, to)| Rename {
+            from: from.clone(),
+            to: to.clone(),
+        }));
+        self.copies.extend(outcome.copies.iter().map(Placement::of));
+        for (a, result) in &outcome.results {
+            let range = |file_path: &str, start_line, end_line| Range {
+                file_path: file_path.to_string(),
+                start_line,
+                end_line,
+            };
+            let change = match result {
+                AdjustResult::Unchanged => Change::Unchanged,
+                AdjustResult::Shifted { new_start, new_end, .. } => Change::Shifted {
+                    new: range(&a.file_path, *new_start, *new_end),
+                },
+                AdjustResult::Moved { new_path, new_start, new_end } => Change::Moved {
+                    new: range(new_path, *new_start, *new_end),
+                },
+                AdjustResult::Deleted => Change::Deleted,
+                AdjustResult::Conflict { deleted_lines } => Change::Conflict {
+                    deleted_lines: deleted_lines.clone(),
+                },
+            };
+            let counter = match change {
+                Change::Unchanged => &mut self.summary.unchanged,
+                Change::Shifted { .. } => &mut self.summary.shifted,
+                Change::Moved { .. } => &mut self.summary.moved,
+                Change::Deleted => &mut self.summary.deleted,
+                Change::Conflict { .. } => &mut self.summary.conflicts,
+            };
+            *counter += 1;
+            self.annotations.push(AnnotationChange {
+                id: a.id,
+                old: range(&a.file_path, a.start_line, a.end_line),
+                change,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_json() {
+        let a = Annotation::new("a.rs".into(), 3, 4, "x".into());
+        let b = Annotation::new("b.rs".into(), 1, 1, "y".into());
+        let outcome = HistoryAdjustment {
+            steps: 2,
+            renames: vec![("old.rs".into(), "b.rs".into())],
+            copies: Vec::new(),
+            tombstones: Vec::new(),
+            results: vec![
+                (a, AdjustResult::Shifted { old_start: 3, old_end: 4, new_start: 5, new_end: 6 }),
+                (b, AdjustResult::Conflict { deleted_lines: vec![1] }),
+            ],
+        };
+        let mut report = AdjustReport::new("c1", "c2");
+        report.add(&outcome);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["steps"], 2);
+        assert_eq!(json["renames"][0]["to"], "b.rs");
+        assert_eq!(json["annotations"][0]["result"], "shifted");
+        assert_eq!(json["annotations"][0]["old"]["start_line"], 3);
+        assert_eq!(json["annotations"][0]["new"]["start_line"], 5);
+        assert_eq!(json["annotations"][1]["result"], "conflict");
+        assert_eq!(json["annotations"][1]["deleted_lines"][0], 1);
+        assert_eq!(json["summary"]["conflicts"], 1);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::core::annotation::Annotation;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use git2::{Repository, Sort};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// An annotation due for re-validation.
+#[derive(Debug)]
+pub struct StaleAnnotation<'a> {
+    pub annotation: &'a Annotation,
+    /// Whole days since the annotation's text was last edited.
+    pub age_days: i64,
+    /// Commits on HEAD's first-parent history that changed its file since then.
+    pub changes: usize,
+}
+
+/// Annotations last edited at least `max_age_days` ago, or whose file has
+/// changed in at least `max_changes` commits since; `None` disables a limit.
+pub fn find_stale<'a>(
+    repo: &Repository,
+    annotations: &'a [Annotation],
+    now: DateTime<Utc>,
+    max_age_days: Option<i64>,
+    max_changes: Option<usize>,
+) -> Result<Vec<StaleAnnotation<'a>>> {
+    let changes = match max_changes {
+        Some(_) => change_counts(repo, annotations)?,
+        None => HashMap::new(),
+    };
+    let mut stale: Vec<StaleAnnotation> = annotations
+        .iter()
+        .map(|a| StaleAnnotation {
+            annotation: a,
+            age_days: (now - a.last_edited()).num_days(),
+            changes: changes.get(&a.id).copied().unwrap_or(0),
+        })
+        .filter(|s| {
+            max_age_days.is_some_and(|max| s.age_days >= max) || max_changes.is_some_and(|max| s.changes >= max)
+        })
+        .collect();
+    stale.sort_by(|a, b| {
+        (&a.annotation.file_path, a.annotation.start_line).cmp(&(&b.annotation.file_path, b.annotation.start_line))
+    });
+    Ok(stale)
+}
+
+/// For each annotation, the number of commits that touched its file after
+/// it was last edited. Walks first parents from HEAD back to the oldest edit.
+fn change_counts(repo: &Repository, annotations: &[Annotation]) -> Result<HashMap<Uuid, usize>> {
+    let mut counts = HashMap::new();
+    let Some(oldest) = annotations.iter().map(|a| a.last_edited().timestamp()).min() else {
+        return Ok(counts);
+    };
+    let mut by_file: HashMap<&str, Vec<&Annotation>> = HashMap::new();
+    for a in annotations {
+        by_file.entry(a.file_path.as_str()).or_default().push(a);
+    }
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TIME)?;
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let time = commit.time().seconds();
+        if time < oldest {
+            break;
+        }
+        let parent_tree = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().and_then(|p| p.to_str()) else {
+                continue;
+            };
+            for a in by_file.get(path).into_iter().flatten() {
+                if time > a.last_edited().timestamp() {
+                    *counts.entry(a.id).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn commit(dir: &Path, file: &str, content: &str, date: &str) {
+        std::fs::write(dir.join(file), content).unwrap();
+        for args in [&["add", "-A"][..], &["commit", "-q", "-m", content]] {
+            let status = Command::new("git")
+                .args(["-c", "user.email=t@t", "-c", "user.name=t"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .current_dir(dir)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    #[test]
+    fn test_age_and_change_limits() {
+        let dir = TempDir::new().unwrap();
+        Command::new("git").args(["init", "-q"]).current_dir(dir.path()).status().unwrap();
+        commit(dir.path(), "a.rs", "1\n", "2026-01-01T00:00:00Z");
+        commit(dir.path(), "b.rs", "1\n", "2026-01-02T00:00:00Z");
+        commit(dir.path(), "a.rs", "2\n", "2026-02-01T00:00:00Z");
+        commit(dir.path(), "a.rs", "3\n", "2026-02-02T00:00:00Z");
+        let repo = Repository::open(dir.path()).unwrap();
+
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let mut old = Annotation::new("a.rs".into(), 1, 1, "old".into());
+        old.text_edited_at = Some(at("2026-01-15T00:00:00Z"));
+        // Moved by an adjustment since, which doesn't make it any fresher
+        old.updated_at = at("2026-02-19T00:00:00Z");
+        // Written before edits were timed apart from other changes
+        let mut fresh = Annotation::new("b.rs".into(), 1, 1, "fresh".into());
+        fresh.text_edited_at = None;
+        fresh.updated_at = at("2026-02-10T00:00:00Z");
+        let annotations = vec![old, fresh];
+        let now = at("2026-02-20T00:00:00Z");
+
+        let stale = find_stale(&repo, &annotations, now, None, Some(2)).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!((stale[0].annotation.text.as_str(), stale[0].changes), ("old", 2));
+        assert_eq!(stale[0].age_days, 36);
+
+        let stale = find_stale(&repo, &annotations, now, Some(10), None).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert!(find_stale(&repo, &annotations, now, Some(60), Some(3)).unwrap().is_empty());
+    }
+}
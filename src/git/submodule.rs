@@ -0,0 +1,162 @@
+use crate::core::annotation::Annotation;
+use crate::core::file_list::submodule_paths;
+use crate::git::adjust::AdjustOptions;
+use crate::git::history::{HistoryAdjustment, adjust_across};
+use crate::git::repo::{head_commit_id, open_repo};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Adjusts annotations on submodule files against each submodule's own
+/// history, from the commit recorded in `commits` to the submodule's HEAD.
+/// Submodules seen for the first time are only recorded. Returned outcomes
+/// use repo-relative paths, like the annotations themselves.
+pub fn adjust_submodules(
+    repo_root: &Path,
+    annotations: &mut Vec<Annotation>,
+    commits: &mut BTreeMap<String, String>,
+    options: &AdjustOptions,
+) -> Result<Vec<(String, HistoryAdjustment)>> {
+    let paths = submodule_paths(repo_root)?;
+    commits.retain(|path, _| paths.contains(path));
+
+    let mut outcomes = Vec::new();
+    for path in &paths {
+        let repo = open_repo(&repo_root.join(path))?;
+        let Ok(head) = head_commit_id(&repo) else {
+            continue;
+        };
+        let from = match commits.get(path) {
+            Some(from) if *from != head => from.clone(),
+            Some(_) => continue,
+            None => {
+                commits.insert(path.clone(), head);
+                continue;
+            }
+        };
+
+        let prefix = format!("{}/", path);
+        let mut owned: Vec<Annotation> = annotations
+            .iter()
+            .filter(|a| owner(&a.file_path, &paths) == Some(path.as_str()))
+            .cloned()
+            .collect();
+        for a in &mut owned {
+            a.file_path.drain(..prefix.len());
+        }
+
+        let mut outcome = adjust_across(&repo, &from, &head, &mut owned, options)?;
+
+        for a in owned.iter_mut().chain(&mut outcome.copies) {
+            a.file_path.insert_str(0, &prefix);
+        }
+        for (a, _) in &mut outcome.results {
+            a.file_path.insert_str(0, &prefix);
+        }
+        for (old, new) in &mut outcome.renames {
+            old.insert_str(0, &prefix);
+            new.insert_str(0, &prefix);
+        }
+        merge_adjusted(annotations, owned, |a| owner(&a.file_path, &paths) == Some(path.as_str()));
+
+        commits.insert(path.clone(), head);
+        outcomes.push((path.clone(), outcome));
+    }
+    Ok(outcomes)
+}
+
+/// Deepest submodule containing `file_path`; `paths` is ordered deepest first.
+fn owner<'a>(file_path: &str, paths: &'a [String]) -> Option<&'a str> {
+    paths
+        .iter()
+        .find(|p| file_path.strip_prefix(p.as_str()).is_some_and(|rest| rest.starts_with('/')))
+        .map(|p| p.as_str())
+}
+
+/// Puts adjusted annotations back in their original positions, dropping
+/// deleted ones and appending copies.
+fn merge_adjusted(annotations: &mut Vec<Annotation>, adjusted: Vec<Annotation>, is_owned: impl Fn(&Annotation) -> bool) {
+    let original: HashSet<Uuid> = annotations.iter().filter(|a| is_owned(a)).map(|a| a.id).collect();
+    let (updated, copies): (Vec<_>, Vec<_>) = adjusted.into_iter().partition(|a| original.contains(&a.id));
+    let mut updated: HashMap<Uuid, Annotation> = updated.into_iter().map(|a| (a.id, a)).collect();
+    let mut merged = Vec::with_capacity(annotations.len() + copies.len());
+    for a in annotations.drain(..) {
+        if !is_owned(&a) {
+            merged.push(a);
+        } else if let Some(a) = updated.remove(&a.id) {
+            merged.push(a);
+        }
+    }
+    merged.extend(copies);
+    *annotations = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.email=t@t", "-c", "user.name=t", "-c", "protocol.file.allow=always"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn setup() -> (TempDir, std::path::PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let lib = dir.path().join("lib");
+        let app = dir.path().join("app");
+        std::fs::create_dir_all(&lib).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        git(&lib, &["init", "-q"]);
+        std::fs::write(lib.join("lib.rs"), "a\nb\nc\n").unwrap();
+        git(&lib, &["add", "."]);
+        git(&lib, &["commit", "-q", "-m", "init"]);
+        git(&app, &["init", "-q"]);
+        std::fs::write(app.join("main.rs"), "x\ny\nz\n").unwrap();
+        git(&app, &["submodule", "add", "-q", lib.to_str().unwrap(), "vendor/lib"]);
+        git(&app, &["add", "."]);
+        git(&app, &["commit", "-q", "-m", "init"]);
+        (dir, app)
+    }
+
+    #[test]
+    fn test_adjusts_against_submodule_history() {
+        let (_dir, app) = setup();
+        let mut annotations = vec![
+            Annotation::new("main.rs".into(), 3, 3, "main".into()),
+            Annotation::new("vendor/lib/lib.rs".into(), 3, 3, "lib".into()),
+        ];
+        let mut commits = BTreeMap::new();
+        let options = AdjustOptions::default();
+
+        let outcomes = adjust_submodules(&app, &mut annotations, &mut commits, &options).unwrap();
+        assert!(outcomes.is_empty());
+        assert!(commits.contains_key("vendor/lib"));
+
+        let sub = app.join("vendor/lib");
+        std::fs::write(sub.join("lib.rs"), "new1\nnew2\na\nb\nc\n").unwrap();
+        git(&sub, &["commit", "-q", "-am", "grow"]);
+
+        let outcomes = adjust_submodules(&app, &mut annotations, &mut commits, &options).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].1.results[0].0.file_path, "vendor/lib/lib.rs");
+        assert_eq!((annotations[0].file_path.as_str(), annotations[0].start_line), ("main.rs", 3));
+        assert_eq!((annotations[1].file_path.as_str(), annotations[1].start_line), ("vendor/lib/lib.rs", 5));
+    }
+
+    #[test]
+    fn test_owner_picks_deepest_submodule() {
+        let paths = vec!["a/b".to_string(), "a".to_string()];
+        assert_eq!(owner("a/b/x.rs", &paths), Some("a/b"));
+        assert_eq!(owner("a/x.rs", &paths), Some("a"));
+        assert_eq!(owner("ab/x.rs", &paths), None);
+    }
+}
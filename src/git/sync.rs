@@ -0,0 +1,585 @@
+//! Exchanges review data through a dedicated git ref.
+//!
+//! Each sync records the store as a commit whose tree holds
+//! `annotations.jsonl`, `file_status.jsonl` and the `base` commit, on
+//! `refs/annotator/reviews/<name>`. The ref is pushed and fetched like any
+//! other, so reviewers collaborate without committing `.annotator/`.
+//!
+//! `changes.jsonl` carries the annotations deleted and the labels added or
+//! removed since each reviewer's previous sync, so a merge can tell an
+//! annotation deleted on one side from one the other side never had.
+
+use crate::core::annotation::{Annotation, FileReviewState, FileStatus};
+use crate::core::crypto::{self, StoreKey};
+use crate::git::adjust::AdjustOptions;
+use crate::git::history::{HistoryAdjustment, adjust_across};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use git2::{Oid, Repository, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::process::Command;
+use uuid::Uuid;
+
+const ANNOTATIONS_FILE: &str = "annotations.jsonl";
+const FILE_STATUS_FILE: &str = "file_status.jsonl";
+const CHANGES_FILE: &str = "changes.jsonl";
+const BASE_FILE: &str = "base";
+
+/// Ref the store is recorded on after each change when `review.history` is on.
+pub const HISTORY_REF: &str = "refs/annotator/history";
+
+/// Review data as stored on a sync ref.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub annotations: Vec<Annotation>,
+    pub file_statuses: Vec<FileReviewState>,
+    /// Commit the annotation line numbers refer to, when known.
+    pub base: Option<String>,
+    /// Deletions and label changes noted by `note_changes`, the latest per
+    /// annotation or label.
+    pub changes: Vec<Change>,
+}
+
+/// Something a reviewer removed or relabeled, noted when syncing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    DeletedAnnotation { id: Uuid, at: DateTime<Utc> },
+    Label { file_path: String, label: String, present: bool, at: DateTime<Utc> },
+}
+
+impl Change {
+    fn at(&self) -> DateTime<Utc> {
+        match self {
+            Change::DeletedAnnotation { at, .. } | Change::Label { at, .. } => *at,
+        }
+    }
+
+    /// What the change is about; a later change to the same thing replaces it.
+    fn key(&self) -> (Option<Uuid>, Option<(String, String)>) {
+        match self {
+            Change::DeletedAnnotation { id, .. } => (Some(*id), None),
+            Change::Label { file_path, label, .. } => (None, Some((file_path.clone(), label.clone()))),
+        }
+    }
+}
+
+/// Keeps the latest of each annotation's or label's changes, oldest first.
+fn latest_changes(changes: impl IntoIterator<Item = Change>) -> Vec<Change> {
+    let mut changes: Vec<Change> = changes.into_iter().collect();
+    changes.sort_by_key(|c| std::cmp::Reverse(c.at()));
+    let mut seen = std::collections::HashSet::new();
+    changes.retain(|c| seen.insert(c.key()));
+    changes.reverse();
+    changes
+}
+
+/// Notes in `current` what was deleted or relabeled since `previous`, the
+/// data as last synced, on top of the changes `previous` already carried.
+pub fn note_changes(previous: &Snapshot, current: &mut Snapshot, now: DateTime<Utc>) {
+    let mut changes = previous.changes.clone();
+    let ids: BTreeSet<Uuid> = current.annotations.iter().map(|a| a.id).collect();
+    for a in previous.annotations.iter().filter(|a| !ids.contains(&a.id)) {
+        changes.push(Change::DeletedAnnotation { id: a.id, at: now });
+    }
+    let labels = |snapshot: &Snapshot| -> BTreeSet<(String, String)> {
+        snapshot
+            .file_statuses
+            .iter()
+            .flat_map(|s| s.labels.iter().map(|l| (s.file_path.clone(), l.clone())))
+            .collect()
+    };
+    let (before, after) = (labels(previous), labels(current));
+    for ((file_path, label), present) in before
+        .difference(&after)
+        .map(|l| (l, false))
+        .chain(after.difference(&before).map(|l| (l, true)))
+    {
+        changes.push(Change::Label { file_path: file_path.clone(), label: label.clone(), present, at: now });
+    }
+    current.changes = latest_changes(changes);
+}
+
+/// Local ref holding the review `name`.
+pub fn review_ref(name: &str) -> Result<String> {
+    let refname = format!("refs/annotator/reviews/{}", name);
+    if !git2::Reference::is_valid_name(&refname) {
+        bail!("'{}' is not a valid review name", name);
+    }
+    Ok(refname)
+}
+
+/// Ref the review `name` is fetched into from `remote`.
+pub fn tracking_ref(remote: &str, name: &str) -> Result<String> {
+    let refname = format!("refs/annotator/remotes/{}/{}", remote, name);
+    if !git2::Reference::is_valid_name(&refname) {
+        bail!("'{}' is not a valid remote name", remote);
+    }
+    Ok(refname)
+}
+
+/// Commits `snapshot` onto `refname` with the given parents, encrypting the
+/// data when the store is encrypted. Returns None when there is a single
+/// parent with identical data, leaving the ref as is.
+pub fn write_snapshot(
+    repo: &Repository,
+    refname: &str,
+    snapshot: &Snapshot,
+    parents: &[Oid],
+    key: Option<&StoreKey>,
+) -> Result<Option<Oid>> {
+    let mut builder = repo.treebuilder(None)?;
+    for (name, content) in [
+        (ANNOTATIONS_FILE, to_jsonl(&snapshot.annotations)?),
+        (FILE_STATUS_FILE, to_jsonl(&snapshot.file_statuses)?),
+        (CHANGES_FILE, to_jsonl(&snapshot.changes)?),
+        (BASE_FILE, snapshot.base.clone().unwrap_or_default()),
+    ] {
+        // Snapshots without changes read the same without the file
+        if name == CHANGES_FILE && content.is_empty() {
+            continue;
+        }
+        let blob = repo.blob(&crypto::encode(&content, key)?)?;
+        builder.insert(name, blob, 0o100644)?;
+    }
+    let tree = repo.find_tree(builder.write()?)?;
+
+    let parents = parents
+        .iter()
+        .map(|oid| repo.find_commit(*oid))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Encryption is randomized, so encrypted data is compared after decrypting
+    if let [parent] = parents.as_slice()
+        && (parent.tree_id() == tree.id()
+            || (key.is_some() && read_commit(repo, parent, refname, key)? == *snapshot))
+    {
+        return Ok(None);
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("annotator", "annotator@localhost"))?;
+    let parent_refs: Vec<_> = parents.iter().collect();
+    let message = format!(
+        "Review data: {} annotation(s), {} file state(s)",
+        snapshot.annotations.len(),
+        snapshot.file_statuses.len()
+    );
+    let oid = repo.commit(None, &signature, &signature, &message, &tree, &parent_refs)?;
+    repo.reference(refname, oid, true, "annotator sync")?;
+    Ok(Some(oid))
+}
+
+/// Adds `snapshot` to the review history unless it matches the latest entry.
+pub fn record_history(repo: &Repository, snapshot: &Snapshot, key: Option<&StoreKey>) -> Result<Option<Oid>> {
+    let parents: Vec<_> = repo.refname_to_id(HISTORY_REF).into_iter().collect();
+    write_snapshot(repo, HISTORY_REF, snapshot, &parents, key)
+}
+
+/// Reads the snapshot at `refname`, or None if the ref doesn't exist.
+pub fn read_snapshot(repo: &Repository, refname: &str, key: Option<&StoreKey>) -> Result<Option<(Oid, Snapshot)>> {
+    let reference = match repo.find_reference(refname) {
+        Ok(r) => r,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let commit = reference.peel_to_commit()?;
+    let snapshot = read_commit(repo, &commit, refname, key)?;
+    Ok(Some((commit.id(), snapshot)))
+}
+
+/// Reads the snapshot recorded by the commit `rev`, such as
+/// `refs/annotator/reviews/team~1`.
+pub fn read_revision(repo: &Repository, rev: &str, key: Option<&StoreKey>) -> Result<Snapshot> {
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|o| o.peel_to_commit())
+        .with_context(|| format!("'{}' is not a commit", rev))?;
+    read_commit(repo, &commit, rev, key)
+}
+
+fn read_commit(repo: &Repository, commit: &git2::Commit, refname: &str, key: Option<&StoreKey>) -> Result<Snapshot> {
+    let tree = commit.tree()?;
+    let read = |name: &str| -> Result<String> {
+        let Some(entry) = tree.get_name(name) else {
+            return Ok(String::new());
+        };
+        let blob = entry.to_object(repo)?.peel_to_blob()?;
+        if !crypto::is_encrypted(blob.content()) {
+            return Ok(String::from_utf8_lossy(blob.content()).into_owned());
+        }
+        let key = key.with_context(|| {
+            format!("{} is encrypted; set ANNOTATOR_IDENTITY_FILE or ANNOTATOR_PASSPHRASE", refname)
+        })?;
+        crypto::decrypt_text(blob.content(), key)
+    };
+    Ok(Snapshot {
+        annotations: from_jsonl(&read(ANNOTATIONS_FILE)?).with_context(|| format!("reading {} on {}", ANNOTATIONS_FILE, refname))?,
+        file_statuses: from_jsonl(&read(FILE_STATUS_FILE)?).with_context(|| format!("reading {} on {}", FILE_STATUS_FILE, refname))?,
+        base: Some(read(BASE_FILE)?.trim().to_string()).filter(|b| !b.is_empty()),
+        changes: from_jsonl(&read(CHANGES_FILE)?).with_context(|| format!("reading {} on {}", CHANGES_FILE, refname))?,
+    })
+}
+
+/// Combines local and remote review data. Annotations are matched by id and
+/// the copy whose text was edited last wins; annotations only one side has
+/// are kept, unless the other side deleted them after their last edit. A file
+/// state from the remote is taken only where the local one is unreviewed,
+/// and labels are combined, leaving out those last noted as removed. The
+/// local base is kept.
+pub fn merge(local: Snapshot, remote: Snapshot) -> Snapshot {
+    let changes = latest_changes(local.changes.into_iter().chain(remote.changes));
+    let mut deleted: HashMap<Uuid, DateTime<Utc>> = HashMap::new();
+    let mut removed_labels: BTreeSet<(&str, &str)> = BTreeSet::new();
+    for change in &changes {
+        match change {
+            Change::DeletedAnnotation { id, at } => {
+                deleted.insert(*id, *at);
+            }
+            Change::Label { file_path, label, present: false, .. } => {
+                removed_labels.insert((file_path.as_str(), label.as_str()));
+            }
+            Change::Label { .. } => {}
+        }
+    }
+
+    let mut remote_annotations: HashMap<_, _> = remote.annotations.iter().map(|a| (a.id, a)).collect();
+    let mut annotations = Vec::with_capacity(local.annotations.len());
+    for a in local.annotations {
+        match remote_annotations.remove(&a.id) {
+            Some(theirs) if theirs.last_edited() > a.last_edited() => annotations.push(theirs.clone()),
+            _ => annotations.push(a),
+        }
+    }
+    annotations.extend(
+        remote
+            .annotations
+            .iter()
+            .filter(|a| remote_annotations.contains_key(&a.id))
+            .cloned(),
+    );
+    // An edit made after the deletion brings the annotation back; lines
+    // shifted by adjusting don't count as one
+    annotations.retain(|a| deleted.get(&a.id).is_none_or(|at| a.last_edited() > *at));
+
+    let mut file_statuses = local.file_statuses;
+    for theirs in remote.file_statuses {
+        match file_statuses.iter_mut().find(|s| s.file_path == theirs.file_path) {
+            Some(ours) => {
+                if ours.status == FileStatus::Unreviewed && theirs.status != FileStatus::Unreviewed {
+                    ours.set_status(theirs.status);
+                }
+                // Either side may have labeled the file
+                ours.labels.extend(theirs.labels);
+                ours.labels.sort();
+                ours.labels.dedup();
+            }
+            None => file_statuses.push(theirs),
+        }
+    }
+    for state in &mut file_statuses {
+        state.labels.retain(|l| !removed_labels.contains(&(state.file_path.as_str(), l.as_str())));
+    }
+
+    Snapshot {
+        annotations,
+        file_statuses,
+        base: local.base.or(remote.base),
+        changes,
+    }
+}
+
+/// Annotations of `snapshot` that `local` doesn't have yet, adjusted from
+/// `base` to `to`. Annotations whose lines were deleted in between are dropped.
+pub fn copy_from(
+    repo: &Repository,
+    snapshot: &Snapshot,
+    local: &[Annotation],
+    base: &str,
+    to: &str,
+    options: &AdjustOptions,
+) -> Result<(Vec<Annotation>, HistoryAdjustment)> {
+    let mut copied: Vec<Annotation> = snapshot
+        .annotations
+        .iter()
+        .filter(|a| !local.iter().any(|l| l.id == a.id))
+        .cloned()
+        .collect();
+    let outcome = adjust_across(repo, base, to, &mut copied, options)?;
+    Ok((copied, outcome))
+}
+
+/// Pushes the local review ref to `remote`. Runs the git CLI so the user's
+/// credential setup applies.
+pub fn push(repo_root: &Path, remote: &str, refname: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["push", "--quiet", remote, &format!("{0}:{0}", refname)])
+        .current_dir(repo_root)
+        .output()
+        .context("running git push")?;
+    if !output.status.success() {
+        bail!(
+            "pushing {} to {} failed (run 'annotator sync pull' first if it was rejected):\n{}",
+            refname,
+            remote,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Fetches `refname` from `remote` into `tracking`. Returns false when the
+/// remote has no such ref yet.
+pub fn fetch(repo_root: &Path, remote: &str, refname: &str, tracking: &str) -> Result<bool> {
+    // --exit-code makes ls-remote exit with 2 when nothing matches
+    let listed = Command::new("git")
+        .args(["ls-remote", "--exit-code", remote, refname])
+        .current_dir(repo_root)
+        .output()
+        .context("running git ls-remote")?;
+    match listed.status.code() {
+        Some(0) => {}
+        Some(2) => return Ok(false),
+        _ => bail!(
+            "listing {} on {} failed:\n{}",
+            refname,
+            remote,
+            String::from_utf8_lossy(&listed.stderr).trim()
+        ),
+    }
+    let output = Command::new("git")
+        .args(["fetch", "--quiet", remote, &format!("+{}:{}", refname, tracking)])
+        .current_dir(repo_root)
+        .output()
+        .context("running git fetch")?;
+    if !output.status.success() {
+        bail!(
+            "fetching {} from {} failed:\n{}",
+            refname,
+            remote,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(true)
+}
+
+fn to_jsonl<T: Serialize>(items: &[T]) -> Result<String> {
+    let mut out = String::new();
+    for item in items {
+        out.push_str(&serde_json::to_string(item)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn from_jsonl<T: serde::de::DeserializeOwned>(content: &str) -> Result<Vec<T>> {
+    content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .enumerate()
+        .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("parsing line {}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.email=t@t", "-c", "user.name=t"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn snapshot(annotations: Vec<Annotation>) -> Snapshot {
+        Snapshot {
+            annotations,
+            file_statuses: vec![FileReviewState::new("a.rs".into(), FileStatus::Annotated)],
+            base: None,
+            changes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let refname = review_ref("team").unwrap();
+        assert!(read_snapshot(&repo, &refname, None).unwrap().is_none());
+
+        let mut data = snapshot(vec![Annotation::new("a.rs".into(), 1, 2, "x".into())]);
+        data.base = Some("0123abcd".into());
+        data.changes = vec![Change::DeletedAnnotation { id: uuid::Uuid::new_v4(), at: Utc::now() }];
+        let first = write_snapshot(&repo, &refname, &data, &[], None).unwrap().unwrap();
+        let (oid, read) = read_snapshot(&repo, &refname, None).unwrap().unwrap();
+        assert_eq!((oid, read), (first, data.clone()));
+
+        // Unchanged data adds no commit
+        assert_eq!(write_snapshot(&repo, &refname, &data, &[first], None).unwrap(), None);
+        assert_eq!(read_revision(&repo, &refname, None).unwrap(), data);
+        assert!(read_revision(&repo, &format!("{}~1", refname), None).is_err());
+        assert!(review_ref("bad..name").is_err());
+
+        let first = record_history(&repo, &data, None).unwrap().unwrap();
+        assert_eq!(record_history(&repo, &data, None).unwrap(), None);
+        let second = record_history(&repo, &snapshot(Vec::new()), None).unwrap().unwrap();
+        assert_eq!(repo.find_commit(second).unwrap().parent_id(0).unwrap(), first);
+        assert_eq!(read_revision(&repo, &format!("{}~1", HISTORY_REF), None).unwrap(), data);
+    }
+
+    #[test]
+    fn test_merge_prefers_newer_annotation() {
+        let mut ours = Annotation::new("a.rs".into(), 1, 1, "old".into());
+        let mut theirs = ours.clone();
+        theirs.text = "new".into();
+        theirs.updated_at = ours.updated_at + chrono::Duration::seconds(1);
+        theirs.text_edited_at = Some(theirs.updated_at);
+        let only_ours = Annotation::new("a.rs".into(), 2, 2, "mine".into());
+        let only_theirs = Annotation::new("b.rs".into(), 3, 3, "theirs".into());
+
+        let mut local = snapshot(vec![ours.clone(), only_ours.clone()]);
+        local.file_statuses.push(FileReviewState::new("b.rs".into(), FileStatus::Unreviewed));
+        local.file_statuses[1].labels = vec!["security".into()];
+        let mut remote = snapshot(vec![theirs.clone(), only_theirs.clone()]);
+        remote.file_statuses = vec![
+            FileReviewState::new("a.rs".into(), FileStatus::Clean),
+            FileReviewState::new("b.rs".into(), FileStatus::Clean),
+        ];
+        remote.file_statuses[1].labels = vec!["needs-second-opinion".into(), "security".into()];
+
+        let merged = merge(local, remote);
+        assert_eq!(merged.annotations, vec![theirs.clone(), only_ours, only_theirs]);
+        assert_eq!(merged.file_statuses[0].status, FileStatus::Annotated);
+        assert_eq!(merged.file_statuses[1].status, FileStatus::Clean);
+        assert_eq!(merged.file_statuses[1].labels, vec!["needs-second-opinion", "security"]);
+
+        ours.updated_at = theirs.updated_at + chrono::Duration::seconds(1);
+        ours.text_edited_at = Some(ours.updated_at);
+        let merged = merge(snapshot(vec![ours.clone()]), snapshot(vec![theirs]));
+        assert_eq!(merged.annotations, vec![ours]);
+    }
+
+    #[test]
+    fn test_merge_ignores_updates_from_adjusting() {
+        let original = Annotation::new("a.rs".into(), 1, 1, "old".into());
+        let mut edited = original.clone();
+        edited.text = "new".into();
+        edited.updated_at = original.updated_at + chrono::Duration::seconds(1);
+        edited.text_edited_at = Some(edited.updated_at);
+        // Adjusted later on the other side, which only moves its lines
+        let mut shifted = original.clone();
+        shifted.start_line = 3;
+        shifted.end_line = 3;
+        shifted.updated_at = edited.updated_at + chrono::Duration::seconds(1);
+
+        let merged = merge(snapshot(vec![edited.clone()]), snapshot(vec![shifted.clone()]));
+        assert_eq!(merged.annotations, vec![edited]);
+
+        // A deletion isn't undone by the other side's adjustment
+        let mut local = snapshot(Vec::new());
+        local.changes = vec![Change::DeletedAnnotation {
+            id: original.id,
+            at: original.updated_at + chrono::Duration::seconds(1),
+        }];
+        let merged = merge(local, snapshot(vec![shifted]));
+        assert!(merged.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_deletions_and_label_removals() {
+        let kept = Annotation::new("a.rs".into(), 1, 1, "kept".into());
+        let deleted = Annotation::new("a.rs".into(), 2, 2, "deleted".into());
+        let mut synced = snapshot(vec![kept.clone(), deleted.clone()]);
+        synced.file_statuses[0].labels = vec!["security".into(), "slow".into()];
+        let now = kept.updated_at + chrono::Duration::seconds(10);
+
+        // We deleted one annotation and a label since the last sync; they
+        // still have both
+        let mut local = snapshot(vec![kept.clone()]);
+        local.file_statuses[0].labels = vec!["slow".into()];
+        note_changes(&synced, &mut local, now);
+        assert_eq!(local.changes.len(), 2);
+        let merged = merge(local.clone(), synced.clone());
+        assert_eq!(merged.annotations, vec![kept.clone()]);
+        assert_eq!(merged.file_statuses[0].labels, vec!["slow"]);
+
+        // The deletions reach them through the merged data, even where they
+        // still have the old copies
+        let merged = merge(synced.clone(), merged);
+        assert_eq!(merged.annotations, vec![kept.clone()]);
+        assert_eq!(merged.file_statuses[0].labels, vec!["slow"]);
+
+        // Labeling the file again later, or editing the deleted annotation
+        // after the deletion, wins
+        let mut relabeled = merged.clone();
+        relabeled.file_statuses[0].labels = vec!["security".into(), "slow".into()];
+        note_changes(&merged, &mut relabeled, now + chrono::Duration::seconds(1));
+        let mut edited = deleted.clone();
+        edited.updated_at = now + chrono::Duration::seconds(1);
+        edited.text_edited_at = Some(edited.updated_at);
+        let merged = merge(relabeled, merge(local, snapshot(vec![kept.clone(), edited.clone()])));
+        assert_eq!(merged.annotations, vec![kept, edited]);
+        assert_eq!(merged.file_statuses[0].labels, vec!["security", "slow"]);
+    }
+
+    #[test]
+    fn test_copy_from_adjusts_to_current_commit() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        std::fs::write(dir.path().join("a.rs"), "one\ntwo\nthree\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "c1"]);
+        let repo = Repository::open(dir.path()).unwrap();
+        let base = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+        std::fs::write(dir.path().join("a.rs"), "zero\none\ntwo\n").unwrap();
+        git(dir.path(), &["commit", "-qam", "c2"]);
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id().to_string();
+
+        let kept = Annotation::new("a.rs".into(), 2, 2, "on two".into());
+        let dropped = Annotation::new("a.rs".into(), 3, 3, "on three".into());
+        let present = Annotation::new("a.rs".into(), 1, 1, "already here".into());
+        let mut theirs = snapshot(vec![kept.clone(), dropped, present.clone()]);
+        theirs.base = Some(base.clone());
+
+        let (copied, outcome) = copy_from(&repo, &theirs, &[present], &base, &head, &AdjustOptions::default()).unwrap();
+        assert_eq!(copied.len(), 1);
+        assert_eq!((copied[0].id, copied[0].start_line), (kept.id, 3));
+        assert_eq!(outcome.results.len(), 2);
+    }
+
+    #[test]
+    fn test_push_and_fetch_through_remote() {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        let alice = dir.path().join("alice");
+        let bob = dir.path().join("bob");
+        git(dir.path(), &["init", "-q", "--bare", remote.to_str().unwrap()]);
+        for clone in [&alice, &bob] {
+            std::fs::create_dir(clone).unwrap();
+            git(clone, &["init", "-q"]);
+            git(clone, &["remote", "add", "origin", remote.to_str().unwrap()]);
+        }
+        let refname = review_ref("default").unwrap();
+        let tracking = tracking_ref("origin", "default").unwrap();
+        assert!(!fetch(&bob, "origin", &refname, &tracking).unwrap());
+
+        let alice_repo = Repository::open(&alice).unwrap();
+        let data = snapshot(vec![Annotation::new("a.rs".into(), 1, 1, "from alice".into())]);
+        write_snapshot(&alice_repo, &refname, &data, &[], None).unwrap();
+        push(&alice, "origin", &refname).unwrap();
+
+        assert!(fetch(&bob, "origin", &refname, &tracking).unwrap());
+        let bob_repo = Repository::open(&bob).unwrap();
+        let (_, fetched) = read_snapshot(&bob_repo, &tracking, None).unwrap().unwrap();
+        assert_eq!(fetched, data);
+    }
+}
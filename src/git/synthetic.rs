@@ -0,0 +1,117 @@
+//! Synthetic diffs and annotations for benchmarking and testing the adjust
+//! algorithm at sizes real repositories rarely provide. Generation is
+//! deterministic for a seed.
+
+use crate::core::annotation::Annotation;
+use crate::git::diff::{DiffLine, DiffLineType, FileDiff, FileDiffStatus, Hunk};
+
+/// Files changed by a commit and annotations on their old versions.
+pub struct Scenario {
+    pub diffs: Vec<FileDiff>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl Scenario {
+    /// `files` modified files of `hunks` hunks each, carrying `annotations`
+    /// annotations each.
+    pub fn generate(files: usize, hunks: usize, annotations: usize, seed: u64) -> Self {
+        let mut rng = Rng(seed.max(1));
+        let mut scenario = Scenario {
+            diffs: Vec::with_capacity(files),
+            annotations: Vec::with_capacity(files * annotations),
+        };
+        for file in 0..files {
+            let path = format!("src/file{}.rs", file);
+            let (diff, old_lines) = file_diff(&path, hunks, &mut rng);
+            for _ in 0..annotations {
+                let start = rng.range(1, old_lines);
+                let end = (start + rng.range(0, 7)).min(old_lines);
+                scenario
+                    .annotations
+                    .push(Annotation::new(path.clone(), start, end, format!("note {}", start)));
+            }
+            scenario.diffs.push(diff);
+        }
+        scenario
+    }
+}
+
+/// A diff of `hunks` hunks spread over a file, with its old length. Most
+/// hunks have three lines of context; some have none, down to pure
+/// insertions.
+fn file_diff(path: &str, hunks: usize, rng: &mut Rng) -> (FileDiff, u32) {
+    let (mut old, mut new) = (1u32, 1u32);
+    let mut out = Vec::with_capacity(hunks);
+    for _ in 0..hunks {
+        let gap = rng.range(4, 40);
+        old += gap;
+        new += gap;
+        let context = if rng.range(0, 4) == 0 { 0 } else { 3 };
+        let deleted = rng.range(0, 5);
+        let added = rng.range(u32::from(deleted == 0), 5);
+
+        let (old_start, new_start) = (old, new);
+        let mut lines = Vec::new();
+        let push_context = |lines: &mut Vec<DiffLine>, old: &mut u32, new: &mut u32| {
+            for _ in 0..context {
+                lines.push(line(DiffLineType::Context, Some(*old), Some(*new)));
+                *old += 1;
+                *new += 1;
+            }
+        };
+        push_context(&mut lines, &mut old, &mut new);
+        for _ in 0..deleted {
+            lines.push(line(DiffLineType::Deletion, Some(old), None));
+            old += 1;
+        }
+        for _ in 0..added {
+            lines.push(line(DiffLineType::Addition, None, Some(new)));
+            new += 1;
+        }
+        push_context(&mut lines, &mut old, &mut new);
+
+        let old_lines = 2 * context + deleted;
+        out.push(Hunk {
+            // A pure insertion is placed after the line it follows
+            old_start: if old_lines == 0 { old_start - 1 } else { old_start },
+            old_lines,
+            new_start,
+            new_lines: 2 * context + added,
+            lines,
+        });
+    }
+    let diff = FileDiff {
+        old_path: Some(path.to_string()),
+        new_path: Some(path.to_string()),
+        hunks: out,
+        status: FileDiffStatus::Modified,
+    };
+    (diff, old + 20)
+}
+
+fn line(origin: DiffLineType, old_lineno: Option<u32>, new_lineno: Option<u32>) -> DiffLine {
+    let number = old_lineno.or(new_lineno).unwrap_or_default();
+    let content = match origin {
+        DiffLineType::Addition => format!("added line {}\n", number),
+        _ => format!("original line {}\n", number),
+    };
+    DiffLine {
+        origin,
+        old_lineno,
+        new_lineno,
+        content,
+    }
+}
+
+/// xorshift64; enough for spreading hunks and annotations around.
+struct Rng(u64);
+
+impl Rng {
+    /// A number in `low..=high`.
+    fn range(&mut self, low: u32, high: u32) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        low + (self.0 % u64::from(high - low + 1)) as u32
+    }
+}
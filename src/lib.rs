@@ -4,4 +4,5 @@ pub mod cli;
 pub mod core;
 pub mod export;
 pub mod git;
+pub mod publish;
 pub mod tui;
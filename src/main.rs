@@ -1,505 +1,1766 @@
 #![allow(dead_code)]
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 mod cli;
 mod core;
 mod export;
 mod git;
+mod publish;
 mod tui;
 
-use cli::{Cli, Command, ExportFormat};
+use cli::{
+    AdjustArgs, CheckCondition, Cli, ColorChoice, Command, ConfigCommand, GcArgs, GithubArgs, PublishCommand, SyncArgs,
+    SyncCommand, paint,
+};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Review { path } => cmd_review(&path),
-        Command::Adjust { path, auto_resolve } => cmd_adjust(&path, auto_resolve),
-        Command::Export { path, format } => cmd_export(&path, format),
-        Command::Status { path } => cmd_status(&path),
+        Command::Review {
+            paths,
+            include,
+            mine,
+            script,
+            rev,
+        } => cmd_review(&paths, &include, mine, script.as_deref(), rev.as_deref(), cli.color),
+        Command::Adjust(args) => cmd_adjust(&args, cli.color.enabled(std::io::stdout().is_terminal())),
+        Command::Export {
+            path,
+            format,
+            redact_authors,
+            redact_paths,
+            tags,
+            pager,
+        } => {
+            let pager = pager && std::io::stdout().is_terminal();
+            cmd_export(&path, format, redact_authors, &redact_paths, &tags, pager, cli.color.enabled(pager))
+        }
+        Command::Import { file, path } => cmd_import(&path, &file, cli.color.enabled(std::io::stdout().is_terminal())),
+        Command::Gc(args) => cmd_gc(&args),
+        Command::Compact { path } => cmd_compact(&path),
+        Command::Dedupe { path, dry_run } => cmd_dedupe(&path, dry_run),
+        Command::Repair { path, reanchor, dry_run } => cmd_repair(&path, reanchor, dry_run),
+        Command::Verify { path } => cmd_verify(&path),
+        Command::Sync(SyncCommand::Push(args)) => cmd_sync_push(&args),
+        Command::Sync(SyncCommand::Pull(args)) => cmd_sync_pull(&args),
+        Command::Publish(PublishCommand::Github(args)) => cmd_publish_github(&args),
+        Command::Copy { from_review, base, path } => {
+            cmd_copy(&path, &from_review, base.as_deref(), cli.color.enabled(std::io::stdout().is_terminal()))
+        }
+        Command::Diff { path, review, rev, store } => cmd_diff(
+            &path,
+            review.as_deref(),
+            rev.as_deref(),
+            store.as_deref(),
+            cli.color.enabled(std::io::stdout().is_terminal()),
+        ),
+        Command::Restore { rev, path } => cmd_restore(&path, &rev),
+        Command::List { deleted, path } => cmd_list(&path, deleted, cli.color.enabled(std::io::stdout().is_terminal())),
+        Command::Search { query, path } => cmd_search(&path, &query, cli.color.enabled(std::io::stdout().is_terminal())),
+        Command::Label { file, add, remove, path } => cmd_label(&path, &file, &add, &remove),
+        Command::Undelete { id, to, lines, path } => cmd_undelete(&path, &id, to.as_deref(), lines.as_deref()),
+        Command::Stale { path, older_than, changes } => {
+            cmd_stale(&path, older_than, changes, cli.color.enabled(std::io::stdout().is_terminal()))
+        }
+        Command::Stats { path } => cmd_stats(&path),
+        Command::Blame { file } => cmd_blame(&file, cli.color.enabled(std::io::stdout().is_terminal())),
+        Command::Config(ConfigCommand::Get { key, path }) => cmd_config_get(&path, &key),
+        Command::Config(ConfigCommand::Set { key, value, global, path }) => cmd_config_set(&path, &key, &value, global),
+        Command::Config(ConfigCommand::Check { path }) => cmd_config_check(&path),
+        Command::Status { check, fail_on, path } => {
+            let conditions = match (check, fail_on.is_empty()) {
+                (false, _) => Vec::new(),
+                (true, true) => vec![CheckCondition::Unreviewed],
+                (true, false) => fail_on,
+            };
+            cmd_status(&path, &conditions)
+        }
     }
 }
 
-fn cmd_review(path: &Path) -> Result<()> {
-    let repo_root = git::repo::find_repo_root(path)?;
+fn cmd_review(
+    paths: &[PathBuf],
+    include: &[String],
+    mine: bool,
+    script: Option<&Path>,
+    rev: Option<&str>,
+    color: ColorChoice,
+) -> Result<()> {
+    if let Some(rev) = rev {
+        return cmd_review_revision(paths, include, mine, script, rev, color);
+    }
+    let repo_root = git::repo::find_repo_root(&paths[0])?;
+    let mut scope = review_scope(&repo_root, paths, include)?;
+    if mine {
+        scope = assigned_scope(&repo_root, scope)?;
+    }
     let mut app = tui::app::App::new(repo_root)?;
+    app.set_scope(scope);
+    // The TUI always owns a terminal, so only the flag and environment matter
+    app.monochrome = !color.enabled(true);
 
     // Check for pending adjustments
-    if let Some(ref last_commit) = app.session.last_adjust_commit.clone() {
+    if let Some(ref last_commit) = app.adjust_base.commit.clone() {
         let repo = git::repo::open_repo(&app.repo_root)?;
         let head = git::repo::head_commit_id(&repo)?;
         if head != *last_commit {
-            run_adjustment(&mut app, last_commit, &head)?;
+            app.run_adjustment(last_commit, &head)?;
+        } else if app.config.review.submodules {
+            app.adjust_submodules()?;
         }
     } else {
         // Set initial commit
         let repo = git::repo::open_repo(&app.repo_root)?;
         if let Ok(head) = git::repo::head_commit_id(&repo) {
-            app.session.last_adjust_commit = Some(head);
+            app.set_adjust_base(head)?;
+        }
+        if app.config.review.submodules {
+            app.adjust_submodules()?;
         }
     }
 
-    run_tui(app)
+    match script {
+        Some(script) => run_script(app, script),
+        None => run_tui(app),
+    }
 }
 
+/// Reviews a commit straight from git objects. Annotations are adjusted to
+/// that commit first, so their lines match what is shown; when that means
+/// moving them away from the commit they were last adjusted to, the
+/// adjustment stays in memory and the review is read-only.
+fn cmd_review_revision(
+    paths: &[PathBuf],
+    include: &[String],
+    mine: bool,
+    script: Option<&Path>,
+    rev: &str,
+    color: ColorChoice,
+) -> Result<()> {
+    let repo_root = git::repo::find_repo_dir(&paths[0])?;
+    let mut scope = review_scope(&repo_root, paths, include)?;
+    if mine {
+        scope = assigned_scope(&repo_root, scope)?;
+    }
+    let commit = git::repo::resolve_commit(&git::repo::open_repo(&repo_root)?, rev)?;
+    let mut app = tui::app::App::with_revision(repo_root, Some(commit.clone()))?;
+    app.set_scope(scope);
+    app.monochrome = !color.enabled(true);
+
+    match app.adjust_base.commit.clone() {
+        Some(last_commit) if last_commit != commit => app.adjust_for_viewing(&last_commit, &commit)?,
+        Some(_) => {}
+        None => app.set_adjust_base(commit)?,
+    }
+
+    match script {
+        Some(script) => run_script(app, script),
+        None => run_tui(app),
+    }
+}
+
+fn run_script(mut app: tui::app::App, script: &Path) -> Result<()> {
+    let source = if script == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("Failed to read script from stdin")?
+    } else {
+        std::fs::read_to_string(script)
+            .with_context(|| format!("Failed to read script {}", script.display()))?
+    };
+    let commands = tui::script::parse_script(&source)?;
+    app.finish_file_listing();
+    let result = tui::script::run_script(&mut app, &commands);
+    // Keep whatever the script managed to do, even if a later command failed
+    app.retry_pending_writes();
+    app.save_session();
+    app.record_history()?;
+    result?;
+    if let Some(error) = &app.pending_writes.last_error {
+        bail!("{} change(s) could not be saved: {}", app.pending_writes.len(), error);
+    }
+
+    if let Some(msg) = &app.status_message {
+        println!("{}", msg);
+    }
+    Ok(())
+}
+
+/// How often the TUI checks whether HEAD moved underneath it.
+const HEAD_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// Quiet time after a file switch, new annotation or cursor move before the
+/// session is saved.
+const SESSION_SAVE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often tracked review time is written out, bounding what a crash loses.
+const TIME_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How often store writes that failed are tried again.
+const WRITE_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 fn run_tui(mut app: tui::app::App) -> Result<()> {
     use crossterm::{
         event::Event,
         execute,
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+        terminal::{EnterAlternateScreen, enable_raw_mode},
     };
     use ratatui::Terminal;
     use ratatui::backend::CrosstermBackend;
     use std::io;
+    use std::panic::AssertUnwindSafe;
     use std::time::Duration;
 
+    install_panic_hook();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let highlighter = tui::highlight::Highlighter::new();
+    let highlighter = tui::highlight::Highlighter::with_theme(&app.config.ui.theme)?;
+    app.syntax_names = highlighter.syntax_names();
+    if !app.open_setup() {
+        app.open_resume_summary();
+    }
+    let mut last_head_check = std::time::Instant::now();
+    let mut last_time_flush = std::time::Instant::now();
+    let mut last_write_retry = std::time::Instant::now();
 
-    loop {
-        terminal.draw(|f| {
-            let size = f.area();
-            app.viewport_height = size.height.saturating_sub(3);
-            app.viewport_width = size.width;
-            tui::render::render(f, &app, &highlighter);
-        })?;
+    let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| -> Result<()> {
+        loop {
+            terminal.draw(|f| {
+                let size = f.area();
+                app.viewport_height = size.height.saturating_sub(3 + app.visible_panel_height() + app.banner_height());
+                app.viewport_width = if app.annotation_list {
+                    size.width - tui::annotation_list::width(size.width)
+                } else {
+                    size.width
+                };
+                tui::render::render(f, &app, &highlighter);
+            })?;
 
-        if app.should_quit {
-            break;
+            if app.should_quit {
+                break;
+            }
+
+            if let Some(Event::Key(key)) = tui::event::poll_event(Duration::from_millis(100))? {
+                app.track_input();
+                let position = (app.current_file_index, app.cursor_line);
+                tui::handler::handle_key(&mut app, key);
+                if position != (app.current_file_index, app.cursor_line) {
+                    app.note_session_change();
+                }
+            }
+            app.save_session_debounced(SESSION_SAVE_DELAY);
+            app.poll_file_listing();
+            app.poll_adjustment();
+            if last_write_retry.elapsed() >= WRITE_RETRY_INTERVAL {
+                app.retry_pending_writes();
+                last_write_retry = std::time::Instant::now();
+            }
+
+            if last_head_check.elapsed() >= HEAD_CHECK_INTERVAL {
+                app.check_head();
+                last_head_check = std::time::Instant::now();
+            }
+            if last_time_flush.elapsed() >= TIME_FLUSH_INTERVAL {
+                app.flush_time();
+                last_time_flush = std::time::Instant::now();
+            }
         }
+        Ok(())
+    }));
 
-        if let Some(Event::Key(key)) = tui::event::poll_event(Duration::from_millis(100))? {
-            handle_key(&mut app, key);
+    match outcome {
+        Ok(result) => {
+            app.save_session();
+            app.flush_time();
+            restore_terminal();
+            if let Some(error) = &app.pending_writes.last_error {
+                eprintln!("{} change(s) could not be saved: {}", app.pending_writes.len(), error);
+            }
+            // Back to the default hook, which prints without touching the terminal
+            drop(std::panic::take_hook());
+            result?;
+        }
+        Err(panic) => {
+            // The hook has already restored the terminal and printed the panic
+            save_after_panic(&mut app);
+            std::panic::resume_unwind(panic);
         }
     }
 
-    app.save_session();
+    app.record_history()
+}
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+/// Leaves raw mode and the alternate screen and shows the cursor. Errors are
+/// ignored, as this also runs from the panic hook.
+fn restore_terminal() {
+    use crossterm::{cursor::Show, execute, terminal::LeaveAlternateScreen, terminal::disable_raw_mode};
+    let _ = disable_raw_mode();
+    let _ = execute!(std::io::stdout(), LeaveAlternateScreen, Show);
+}
 
-    Ok(())
+/// Restores the terminal before the default hook prints a panic, so the
+/// message lands on the normal screen and the shell stays usable.
+fn install_panic_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default(info);
+    }));
 }
 
-fn handle_key(app: &mut tui::app::App, key: crossterm::event::KeyEvent) {
-    use tui::app::AppMode;
-    use tui::keymap::*;
+/// Last attempt to write unsaved changes, tracked time and the session
+/// after the TUI panicked, reporting what was kept.
+fn save_after_panic(app: &mut tui::app::App) {
+    let saved = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        app.retry_pending_writes();
+        app.flush_time();
+        app.save_session();
+        app.pending_writes.len()
+    }));
+    match saved {
+        Ok(0) => eprintln!("annotator crashed; annotations and the review session were saved."),
+        Ok(lost) => eprintln!("annotator crashed; {} change(s) could not be saved.", lost),
+        Err(_) => eprintln!("annotator crashed; saving annotations and the session failed too."),
+    }
+}
 
-    match app.mode {
-        AppMode::Viewing => {
-            if let Some(action) = map_key_viewing(key) {
-                handle_viewing_action(app, action);
-            }
+fn cmd_adjust(args: &AdjustArgs, color: bool) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(&args.path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let session_path = core::location::session_dir(&repo_root, &annotator_dir).join("session.json");
+    let session = core::session::Session::load(&session_path)?;
+    let mut base = core::session::AdjustBase::load(&annotator_dir, &session)?;
+    let repo = git::repo::open_repo(&repo_root)?;
+
+    let last_commit = match &args.from {
+        Some(rev) => git::repo::resolve_commit(&repo, rev)?,
+        None => base
+            .commit
+            .clone()
+            .context("No previous adjust commit recorded. Run 'annotator review' first or pass --from.")?,
+    };
+
+    let head = match &args.to {
+        Some(rev) => git::repo::resolve_commit(&repo, rev)?,
+        None => git::repo::head_commit_id(&repo)?,
+    };
+
+    let config = core::config::Config::load_for(&annotator_dir)?;
+    let options = args.adjust_options(&git::adjust::AdjustOptions::from(&config.adjust));
+    let submodules = config.review.submodules;
+    let mut report = git::report::AdjustReport::new(&last_commit, &head);
+    if head == last_commit && !submodules {
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("Already up to date.");
         }
-        AppMode::AnnotationInput | AppMode::AnnotationEdit => {
-            if let Some(action) = map_key_input(key) {
-                handle_input_action(app, action);
-            }
+        return Ok(());
+    }
+    let text = !args.json;
+
+    let originals = store.load_annotations()?;
+    let mut annotations = originals.clone();
+    let outcome = git::history::adjust_across(&repo, &last_commit, &head, &mut annotations, &options)?;
+    report.add(&outcome);
+    if args.self_check {
+        return self_check(&repo, &last_commit, &head, &annotations, &outcome, options.ignore_whitespace, color);
+    }
+
+    if text {
+        for (old, new) in &outcome.renames {
+            println!("{}", paint(color, "36", &format!("Renamed: {} -> {}", old, new)));
         }
-        AppMode::FileList => {
-            if let Some(action) = map_key_file_list(key) {
-                handle_file_list_action(app, action);
-            }
+    }
+
+    if args.interactive {
+        if !approve_adjustments(&repo, &last_commit, &head, &originals, &mut annotations, &outcome, color)? {
+            println!("Aborted, nothing written.");
+            return Ok(());
+        }
+    } else if text {
+        for copy in &outcome.copies {
+            let line = format!("Copied: {}:{}-{}", copy.file_path, copy.start_line, copy.end_line);
+            println!("{}", paint(color, "36", &line));
         }
-        AppMode::TreeView => {
-            if let Some(action) = map_key_tree(key) {
-                handle_tree_action(app, action);
+        for (ann, result) in &outcome.results {
+            if let Some(line) = describe_adjustment(ann, result) {
+                println!("{}", paint(color, adjustment_color(result), &line));
             }
         }
-        AppMode::ConflictResolution => {
-            if let Some(action) = map_key_conflict(key) {
-                handle_conflict_action(app, action);
+    }
+
+    let mut new_session = session;
+    new_session.add_conflicts(&outcome.results);
+    if submodules {
+        let before = base.submodules.clone();
+        let outcomes = git::submodule::adjust_submodules(
+            &repo_root,
+            &mut annotations,
+            &mut base.submodules,
+            &options,
+        )?;
+        for (path, sub) in outcomes {
+            if text {
+                println!("{}", paint(color, "36", &format!("Submodule {}: {} step(s)", path, sub.steps)));
+                for (old, new) in &sub.renames {
+                    println!("{}", paint(color, "36", &format!("Renamed: {} -> {}", old, new)));
+                }
+                for (ann, result) in &sub.results {
+                    if let Some(line) = describe_adjustment(ann, result) {
+                        println!("{}", paint(color, adjustment_color(result), &line));
+                    }
+                }
             }
+            report.add(&sub);
+            new_session.add_conflicts(&sub.results);
+            report.submodules.push(git::report::SubmoduleReport {
+                from: before.get(&path).cloned(),
+                to: base.submodules.get(&path).cloned(),
+                steps: sub.steps,
+                path,
+            });
         }
     }
-}
 
-fn handle_viewing_action(app: &mut tui::app::App, action: tui::keymap::Action) {
-    use tui::keymap::Action;
-    use tui::selection::Selection;
+    // Archive first so a failed rewrite never loses annotations
+    let tombstones: Vec<_> = outcome
+        .tombstones
+        .iter()
+        .filter(|t| !annotations.iter().any(|a| a.id == t.annotation.id))
+        .cloned()
+        .collect();
+    store.archive_tombstones(&tombstones)?;
+    store.save_annotations(&annotations)?;
 
-    app.status_message = None;
+    base.commit = Some(head);
+    base.save(&annotator_dir)?;
+    new_session.save(&session_path)?;
+    record_history(&repo_root, &annotator_dir)?;
 
-    match action {
-        Action::CursorUp => {
-            app.cursor_line = app.cursor_line.saturating_sub(1).max(1);
-            app.selection = None;
-            app.ensure_cursor_visible();
-        }
-        Action::CursorDown => {
-            app.cursor_line = (app.cursor_line + 1).min(app.total_lines().max(1));
-            app.selection = None;
-            app.ensure_cursor_visible();
-        }
-        Action::CursorLeft => {
-            app.cursor_col = app.cursor_col.saturating_sub(1);
-            app.selection = None;
+    if text {
+        let summary = &report.summary;
+        println!(
+            "\nAdjusted over {} step(s): {} shifted, {} moved, {} deleted, {} conflicts",
+            report.steps, summary.shifted, summary.moved, summary.deleted, summary.conflicts
+        );
+        if !tombstones.is_empty() {
+            println!(
+                "{} annotation(s) on deleted files were archived, see 'annotator list --deleted'",
+                tombstones.len()
+            );
         }
-        Action::CursorRight => {
-            app.cursor_col += 1;
-            app.selection = None;
-        }
-        Action::PageUp => {
-            let page = app.viewport_height as u32;
-            app.cursor_line = app.cursor_line.saturating_sub(page).max(1);
-            app.scroll_offset = app.scroll_offset.saturating_sub(page);
-            app.selection = None;
-        }
-        Action::PageDown => {
-            let page = app.viewport_height as u32;
-            let max = app.total_lines().max(1);
-            app.cursor_line = (app.cursor_line + page).min(max);
-            app.scroll_offset = (app.scroll_offset + page).min(max.saturating_sub(1));
-            app.selection = None;
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    Ok(())
+}
+
+/// `adjust --self-check`: compares the lines each adjusted annotation lands
+/// on with the ones it was written on, writing nothing.
+fn self_check(
+    repo: &git2::Repository,
+    from: &str,
+    to: &str,
+    adjusted: &[core::annotation::Annotation],
+    outcome: &git::history::HistoryAdjustment,
+    ignore_whitespace: bool,
+    color: bool,
+) -> Result<()> {
+    use core::annotation::AdjustResult;
+
+    let mut violations = 0;
+    for (original, result) in &outcome.results {
+        // The line numbers refer to the recorded version when there is one
+        let recorded = original
+            .blob
+            .as_deref()
+            .and_then(|blob| git2::Oid::from_str(blob).ok())
+            .and_then(|oid| repo.find_blob(oid).ok())
+            .map(|blob| String::from_utf8_lossy(blob.content()).into_owned());
+        let old = match recorded {
+            Some(text) => text,
+            None => match git::repo::read_file_at(repo, from, &original.file_path)? {
+                Some(text) => text,
+                None => continue,
+            },
+        };
+        let new_path = match result {
+            AdjustResult::Moved { new_path, .. } => Some(new_path.as_str()),
+            _ => adjusted.iter().find(|a| a.id == original.id).map(|a| a.file_path.as_str()),
+        };
+        let new = match new_path {
+            Some(path) => git::repo::read_file_at(repo, to, path)?,
+            None => None,
+        };
+        if let Some(problem) = git::invariants::check(original, result, &old, new.as_deref(), ignore_whitespace) {
+            let line = format!("{}:{}-{}: {}", original.file_path, original.start_line, original.end_line, problem);
+            println!("{}", paint(color, "31", &line));
+            violations += 1;
         }
-        Action::Home => {
-            app.cursor_col = 0;
+    }
+    println!("Checked {} annotation(s) over {} step(s), nothing written", outcome.results.len(), outcome.steps);
+    if violations > 0 {
+        bail!("{} adjustment(s) broke an invariant", violations);
+    }
+    Ok(())
+}
+
+fn describe_adjustment(ann: &core::annotation::Annotation, result: &core::annotation::AdjustResult) -> Option<String> {
+    use core::annotation::AdjustResult;
+
+    match result {
+        AdjustResult::Shifted { old_start, old_end, new_start, new_end } => Some(format!(
+            "Shifted: {}:{}-{} -> {}-{}",
+            ann.file_path, old_start, old_end, new_start, new_end
+        )),
+        AdjustResult::Moved { new_path, new_start, new_end } => Some(format!(
+            "Moved: {}:{}-{} -> {}:{}-{}",
+            ann.file_path, ann.start_line, ann.end_line, new_path, new_start, new_end
+        )),
+        AdjustResult::Deleted => Some(format!("Deleted: {}:{}-{}", ann.file_path, ann.start_line, ann.end_line)),
+        AdjustResult::Conflict { deleted_lines } => Some(format!(
+            "CONFLICT: {}:{}-{} (deleted lines: {:?})",
+            ann.file_path, ann.start_line, ann.end_line, deleted_lines
+        )),
+        AdjustResult::Unchanged => None,
+    }
+}
+
+/// SGR color for an adjustment line: conflicts red, deletions yellow, moves cyan.
+fn adjustment_color(result: &core::annotation::AdjustResult) -> &'static str {
+    use core::annotation::AdjustResult;
+
+    match result {
+        AdjustResult::Conflict { .. } => "1;31",
+        AdjustResult::Deleted => "33",
+        AdjustResult::Moved { .. } => "36",
+        AdjustResult::Shifted { .. } | AdjustResult::Unchanged => "0",
+    }
+}
+
+/// Walks through each proposed adjustment like `git add -p`, reverting skipped
+/// changes and applying edited ranges in `annotations`.
+/// Returns false if the user aborted.
+fn approve_adjustments(
+    repo: &git2::Repository,
+    from: &str,
+    to: &str,
+    originals: &[core::annotation::Annotation],
+    annotations: &mut Vec<core::annotation::Annotation>,
+    outcome: &git::history::HistoryAdjustment,
+    color: bool,
+) -> Result<bool> {
+    use std::io::{BufRead, Write};
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut accept_rest = false;
+    let mut skip_rest = false;
+    let pending: Vec<_> = outcome
+        .results
+        .iter()
+        .filter(|(_, r)| *r != core::annotation::AdjustResult::Unchanged)
+        .collect();
+
+    for (i, (ann, result)) in pending.iter().enumerate() {
+        let Some(original) = originals.iter().find(|a| a.id == ann.id) else {
+            continue;
+        };
+        if accept_rest {
+            continue;
         }
-        Action::End => {
-            if let Some(line) = app.file_content.get(app.cursor_line as usize - 1) {
-                app.cursor_col = line.len() as u32;
-            }
+        if skip_rest {
+            revert_adjustment(annotations, original);
+            continue;
         }
-        Action::SelectUp => {
-            let new_line = app.cursor_line.saturating_sub(1).max(1);
-            if app.selection.is_none() {
-                app.selection = Some(Selection::new(app.cursor_line, app.cursor_col));
-            }
-            app.cursor_line = new_line;
-            app.selection.as_mut().unwrap().extend_to(app.cursor_line, app.cursor_col);
-            app.ensure_cursor_visible();
-        }
-        Action::SelectDown => {
-            let new_line = (app.cursor_line + 1).min(app.total_lines().max(1));
-            if app.selection.is_none() {
-                app.selection = Some(Selection::new(app.cursor_line, app.cursor_col));
-            }
-            app.cursor_line = new_line;
-            app.selection.as_mut().unwrap().extend_to(app.cursor_line, app.cursor_col);
-            app.ensure_cursor_visible();
-        }
-        Action::SelectLeft => {
-            let new_col = app.cursor_col.saturating_sub(1);
-            if app.selection.is_none() {
-                app.selection = Some(Selection::new(app.cursor_line, app.cursor_col));
-            }
-            app.cursor_col = new_col;
-            app.selection.as_mut().unwrap().extend_to(app.cursor_line, app.cursor_col);
+
+        let summary = describe_adjustment(ann, result).unwrap_or_default();
+        println!("\n({}/{}) {}", i + 1, pending.len(), paint(color, adjustment_color(result), &summary));
+        println!("  \"{}\"", original.text.replace('\n', " "));
+        print_range(repo, from, &original.file_path, original.start_line, original.end_line, '-', color);
+        if let Some(current) = annotations.iter().find(|a| a.id == ann.id) {
+            print_range(repo, to, &current.file_path, current.start_line, current.end_line, '+', color);
         }
-        Action::SelectRight => {
-            if app.selection.is_none() {
-                app.selection = Some(Selection::new(app.cursor_line, app.cursor_col));
-            }
-            app.cursor_col += 1;
-            app.selection.as_mut().unwrap().extend_to(app.cursor_line, app.cursor_col);
-        }
-        Action::CreateAnnotation => {
-            // If cursor is on an annotated line, edit it; otherwise create new
-            let file = app.current_file().map(|s| s.to_string());
-            if let Some(file) = file {
-                let line = app.cursor_line;
-                if let Some(ann) = app.annotations.iter().find(|a| a.file_path == file && a.contains_line(line)) {
-                    app.editing_annotation_id = Some(ann.id);
-                    app.annotation_input = ann.text.clone();
-                    app.annotation_input_cursor = ann.text.len();
-                    app.mode = tui::app::AppMode::AnnotationEdit;
-                } else {
-                    app.mode = tui::app::AppMode::AnnotationInput;
-                    app.annotation_input.clear();
-                    app.annotation_input_cursor = 0;
+
+        loop {
+            print!("Apply this change [y,n,e,a,d,q,?]? ");
+            std::io::stdout().flush()?;
+            let answer = match lines.next() {
+                Some(line) => line?,
+                None => return Ok(false),
+            };
+            match answer.trim() {
+                "y" => break,
+                "n" => {
+                    revert_adjustment(annotations, original);
+                    break;
                 }
-            }
-        }
-        Action::EditAnnotation => {
-            let file = app.current_file().map(|s| s.to_string());
-            if let Some(file) = file {
-                let line = app.cursor_line;
-                if let Some(ann) = app.annotations.iter().find(|a| a.file_path == file && a.contains_line(line)) {
-                    app.editing_annotation_id = Some(ann.id);
-                    app.annotation_input = ann.text.clone();
-                    app.annotation_input_cursor = ann.text.len();
-                    app.mode = tui::app::AppMode::AnnotationEdit;
+                "e" => {
+                    print!("New range for {} (start-end): ", original.file_path);
+                    std::io::stdout().flush()?;
+                    let input = match lines.next() {
+                        Some(line) => line?,
+                        None => return Ok(false),
+                    };
+                    match core::annotation::parse_line_range(&input) {
+                        Some((start, end)) => {
+                            let path = annotations
+                                .iter()
+                                .find(|a| a.id == original.id)
+                                .map_or_else(|| original.file_path.clone(), |a| a.file_path.clone());
+                            revert_adjustment(annotations, original);
+                            if let Some(a) = annotations.iter_mut().find(|a| a.id == original.id) {
+                                a.file_path = path;
+                                a.start_line = start;
+                                a.end_line = end;
+                                a.updated_at = chrono::Utc::now();
+                            }
+                            break;
+                        }
+                        None => println!("Invalid range, expected e.g. 12-15"),
+                    }
+                }
+                "a" => {
+                    accept_rest = true;
+                    break;
+                }
+                "d" => {
+                    revert_adjustment(annotations, original);
+                    skip_rest = true;
+                    break;
+                }
+                "q" => return Ok(false),
+                _ => {
+                    println!("y - apply this change");
+                    println!("n - keep the annotation where it was");
+                    println!("e - enter the new line range manually");
+                    println!("a - apply this and all remaining changes");
+                    println!("d - skip this and all remaining changes");
+                    println!("q - quit without writing anything");
                 }
             }
         }
-        Action::DeleteAnnotation => {
-            let had = app.annotations.len();
-            app.delete_annotation_at_cursor();
-            if app.annotations.len() < had {
-                app.status_message = Some("Annotation deleted".into());
-            }
-        }
-        Action::MarkClean => app.mark_file_clean(),
-        Action::NextUnreviewed => app.next_unreviewed_file(),
-        Action::OpenFileList => {
-            app.mode = tui::app::AppMode::FileList;
-            app.file_list_filter.clear();
-            app.file_list_selected = 0;
+    }
+
+    for copy in &outcome.copies {
+        if skip_rest {
+            annotations.retain(|a| a.id != copy.id);
+            continue;
         }
-        Action::OpenTreeView => {
-            app.mode = tui::app::AppMode::TreeView;
-            app.tree_selected = 0;
+        if accept_rest {
+            continue;
         }
-        Action::Undo => app.apply_undo(),
-        Action::Redo => app.apply_redo(),
-        Action::Quit => {
-            app.should_quit = true;
+        print!("Keep copied annotation {}:{}-{} [y,n]? ", copy.file_path, copy.start_line, copy.end_line);
+        std::io::stdout().flush()?;
+        let answer = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(false),
+        };
+        if answer.trim() != "y" {
+            annotations.retain(|a| a.id != copy.id);
         }
-        _ => {}
     }
+
+    Ok(true)
 }
 
-fn handle_input_action(app: &mut tui::app::App, action: tui::keymap::Action) {
-    use tui::keymap::Action;
+fn revert_adjustment(annotations: &mut Vec<core::annotation::Annotation>, original: &core::annotation::Annotation) {
+    match annotations.iter_mut().find(|a| a.id == original.id) {
+        Some(a) => *a = original.clone(),
+        None => annotations.push(original.clone()),
+    }
+}
 
-    match action {
-        Action::Confirm => {
-            if app.mode == tui::app::AppMode::AnnotationEdit {
-                app.update_annotation();
-            } else {
-                app.create_annotation();
-            }
-        }
-        Action::Cancel => {
-            app.mode = tui::app::AppMode::Viewing;
-            app.annotation_input.clear();
-            app.annotation_input_cursor = 0;
-            app.editing_annotation_id = None;
-        }
-        Action::InputChar(c) => {
-            app.annotation_input.insert(app.annotation_input_cursor, c);
-            app.annotation_input_cursor += c.len_utf8();
-        }
-        Action::InputBackspace => {
-            if app.annotation_input_cursor > 0 {
-                let prev = app.annotation_input[..app.annotation_input_cursor]
-                    .chars()
-                    .last()
-                    .map(|c| c.len_utf8())
-                    .unwrap_or(0);
-                app.annotation_input_cursor -= prev;
-                app.annotation_input.remove(app.annotation_input_cursor);
-            }
+fn print_range(repo: &git2::Repository, rev: &str, file: &str, start: u32, end: u32, marker: char, color: bool) {
+    let sgr = if marker == '-' { "31" } else { "32" };
+    let Ok(Some(content)) = git::repo::read_file_at(repo, rev, file) else {
+        return;
+    };
+    for (i, line) in content.lines().enumerate().skip(start.saturating_sub(1) as usize) {
+        let line_no = i as u32 + 1;
+        if line_no > end {
+            break;
         }
-        Action::InputDelete => {
-            if app.annotation_input_cursor < app.annotation_input.len() {
-                app.annotation_input.remove(app.annotation_input_cursor);
+        println!("{}", paint(color, sgr, &format!("  {}{:>5} {}", marker, line_no, line)));
+    }
+}
+
+/// Turns the review paths into a scope of repo-relative prefixes; paths
+/// naming the repository itself don't restrict anything.
+fn review_scope(repo_root: &Path, paths: &[PathBuf], include: &[String]) -> Result<core::scope::Scope> {
+    let root = repo_root.canonicalize()?;
+    let mut prefixes = Vec::new();
+    for path in paths {
+        let full = path
+            .canonicalize()
+            .with_context(|| format!("Path not found: {}", path.display()))?;
+        let relative = full
+            .strip_prefix(&root)
+            .with_context(|| format!("{} is outside the repository", path.display()))?;
+        if relative.as_os_str().is_empty() {
+            // The whole repository is in scope unless --include or
+            // `review.include` narrows it
+            if include.is_empty() {
+                let config = core::config::Config::load_for(&core::location::annotator_dir(repo_root)?)?;
+                return core::scope::Scope::new(Vec::new(), &config.review.include);
             }
+            continue;
         }
-        _ => {}
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        prefixes.push(components.join("/"));
+    }
+    core::scope::Scope::new(prefixes, include)
+}
+
+/// Narrows `scope` to the paths `REVIEWERS.toml` assigns to the current user.
+fn assigned_scope(repo_root: &Path, scope: core::scope::Scope) -> Result<core::scope::Scope> {
+    use core::reviewers::{FILE_NAME, Reviewers};
+    let reviewers = Reviewers::load(repo_root)?
+        .with_context(|| format!("--mine needs a {} at the repository root", FILE_NAME))?;
+    let config = core::config::Config::load_for(&core::location::annotator_dir(repo_root)?)?;
+    let identities = core::reviewers::current_identities(repo_root, &config.author);
+    let patterns = reviewers.patterns_for(&identities);
+    if patterns.is_empty() {
+        bail!(
+            "No paths in {} are assigned to {}",
+            FILE_NAME,
+            identities.last().map_or("you (set author.name or git user.name)", |s| s.as_str())
+        );
     }
+    scope.restrict(&patterns)
 }
 
-fn handle_file_list_action(app: &mut tui::app::App, action: tui::keymap::Action) {
-    use tui::keymap::Action;
+/// Files under review: tracked files, plus submodule files when enabled in the
+/// config, minus those matching `review.ignore`.
+fn review_files(repo_root: &Path, annotator_dir: &Path) -> Result<Vec<String>> {
+    let config = core::config::Config::load_for(annotator_dir)?;
+    config
+        .review
+        .filter_ignored(core::file_list::list_files(repo_root, config.review.submodules)?)
+}
 
-    let popup = tui::file_list_popup::FileListPopup {
-        files: &app.files,
-        filter: &app.file_list_filter,
-        selected: app.file_list_selected,
-        store: &app.store,
+fn cmd_export(
+    path: &Path,
+    format: Option<String>,
+    redact_authors: bool,
+    redact_paths: &[String],
+    tags: &[String],
+    pager: bool,
+    color: bool,
+) -> Result<()> {
+    let redaction = export::redact::Redaction {
+        authors: redact_authors,
+        paths: redact_paths
+            .iter()
+            .map(|rule| export::redact::Redaction::parse_path_rule(rule))
+            .collect::<Result<_>>()?,
     };
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let mut annotations = store.load_annotations()?;
+    if !tags.is_empty() {
+        annotations.retain(|a| tags.iter().any(|tag| a.has_tag(tag)));
+    }
+    if !redaction.is_empty() {
+        annotations = redaction.apply(&annotations);
+    }
 
-    match action {
-        Action::Cancel => {
-            app.mode = tui::app::AppMode::Viewing;
+    let config = core::config::Config::load_for(&annotator_dir)?;
+    let exporter = export::find(format.as_deref().unwrap_or(&config.export.format))?;
+    let summary = review_summary(&repo_root, &annotator_dir, &store, &annotations, &config, redaction.authors)?;
+    let checklist = match core::checklist::Checklist::load(&repo_root)? {
+        Some(checklist) => {
+            let mut completion = checklist.completion(&store.load_file_statuses()?);
+            completion.files = completion
+                .files
+                .into_iter()
+                .map(|(file, checked)| (redaction.apply_path(&file), checked))
+                .collect();
+            Some(completion)
         }
-        Action::Confirm => {
-            let filtered = popup.filtered_files();
-            if let Some((orig_idx, _)) = filtered.get(app.file_list_selected) {
-                app.switch_to_file(*orig_idx);
+        None => None,
+    };
+    let context = export::ExportContext {
+        annotations: &annotations,
+        summary: Some(&summary),
+        commit: summary.commit.as_deref(),
+        checklist: checklist.as_ref(),
+        // Redacted reports must not quote the code
+        repo_root: redaction.is_empty().then_some(repo_root.as_path()),
+    };
+
+    if !pager {
+        use std::io::Write;
+        let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+        exporter.write(&context, &mut out)?;
+        writeln!(out)?;
+        out.flush()?;
+        return Ok(());
+    }
+    let output = exporter.render(&context)?;
+    let output = if exporter.name() == "markdown" {
+        export::markdown::paint_severities(&output, |s, label| paint(color, cli::severity_sgr(s), label))
+    } else {
+        output
+    };
+    cli::page(&output)
+}
+
+fn cmd_import(path: &Path, file: &Path, color: bool) -> Result<()> {
+    use core::annotation::{AdjustResult, FileStatus};
+    use std::io::Read;
+
+    let data = if file == Path::new("-") {
+        let mut data = String::new();
+        std::io::stdin().read_to_string(&mut data)?;
+        data
+    } else {
+        std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?
+    };
+    let mut imported = export::json::import_json(&data)?;
+
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+
+    // Line numbers are relative to the exported commit; bring them to ours
+    let mut dropped = 0;
+    if let Some(from) = &imported.commit {
+        let repo = git::repo::open_repo(&repo_root)?;
+        let target = match adjusted_commit(&repo_root, &annotator_dir)? {
+            Some(commit) => commit,
+            None => git::repo::head_commit_id(&repo)?,
+        };
+        let from = git::repo::resolve_commit(&repo, from)
+            .with_context(|| format!("The export was made at {}, which is not in this repository", from))?;
+        let config = core::config::Config::load_for(&annotator_dir)?;
+        let options = git::adjust::AdjustOptions::from(&config.adjust);
+        let outcome = git::history::adjust_across(&repo, &from, &target, &mut imported.annotations, &options)?;
+        for (ann, result) in &outcome.results {
+            if let Some(line) = describe_adjustment(ann, result) {
+                println!("{}", paint(color, adjustment_color(result), &line));
             }
-            app.mode = tui::app::AppMode::Viewing;
-        }
-        Action::CursorUp => {
-            app.file_list_selected = app.file_list_selected.saturating_sub(1);
         }
-        Action::CursorDown => {
-            let filtered = popup.filtered_files();
-            app.file_list_selected = (app.file_list_selected + 1).min(filtered.len().saturating_sub(1));
+        dropped = outcome.results.iter().filter(|(_, r)| matches!(r, AdjustResult::Deleted)).count();
+    }
+
+    let mut annotations = store.load_annotations()?;
+    let files: Vec<String> = imported.annotations.iter().map(|a| a.file_path.clone()).collect();
+    let counts = export::json::apply_import(&mut annotations, imported.annotations);
+    store.save_annotations(&annotations)?;
+    for file in files {
+        if store.get_file_status(&file)? == FileStatus::Unreviewed {
+            store.set_file_status(&file, FileStatus::Annotated)?;
         }
-        Action::InputChar(c) => {
-            app.file_list_filter.push(c);
-            app.file_list_selected = 0;
+    }
+    record_history(&repo_root, &annotator_dir)?;
+
+    println!(
+        "Imported: {} added, {} updated, {} unchanged, {} dropped with their lines",
+        counts.added, counts.updated, counts.unchanged, dropped
+    );
+    Ok(())
+}
+
+/// The commit the annotations in the store were last adjusted to, if any.
+fn adjusted_commit(repo_root: &Path, annotator_dir: &Path) -> Result<Option<String>> {
+    let session_path = core::location::session_dir(repo_root, annotator_dir).join("session.json");
+    let session = core::session::Session::load(&session_path)?;
+    Ok(core::session::AdjustBase::load(annotator_dir, &session)?.commit)
+}
+
+/// Who reviewed what and how far the review got, for the export header.
+fn review_summary(
+    repo_root: &Path,
+    annotator_dir: &Path,
+    store: &core::store::Store,
+    annotations: &[core::annotation::Annotation],
+    config: &core::config::Config,
+    redact_authors: bool,
+) -> Result<export::markdown::ReviewSummary> {
+    Ok(export::markdown::ReviewSummary::new(
+        annotations,
+        (!redact_authors).then(|| config.author.identity()).flatten(),
+        adjusted_commit(repo_root, annotator_dir)?,
+        review_files(repo_root, annotator_dir)?.len(),
+        &store.load_file_statuses()?,
+    ))
+}
+
+/// Numbers of annotated and clean files.
+fn count_statuses(statuses: &[core::annotation::FileReviewState]) -> (usize, usize) {
+    use core::annotation::FileStatus;
+    let count = |status| statuses.iter().filter(|s| s.status == status).count();
+    (count(FileStatus::Annotated), count(FileStatus::Clean))
+}
+
+fn cmd_gc(args: &GcArgs) -> Result<()> {
+    use std::collections::HashSet;
+    use std::io::Write;
+
+    let repo_root = git::repo::find_repo_root(&args.path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let tracked: HashSet<String> = review_files(&repo_root, &annotator_dir)?
+        .into_iter()
+        .collect();
+    let is_tracked = |f: &str| tracked.contains(f);
+
+    let stale_statuses: Vec<_> = store
+        .load_file_statuses()?
+        .into_iter()
+        .filter(|s| !is_tracked(&s.file_path))
+        .collect();
+    for s in &stale_statuses {
+        println!("Status: {}", s.file_path);
+    }
+
+    let orphans: Vec<_> = store
+        .load_annotations()?
+        .into_iter()
+        .filter(|a| !is_tracked(&a.file_path))
+        .collect();
+    if args.annotations {
+        for a in &orphans {
+            println!("Annotation: {}:{}-{} \"{}\"", a.file_path, a.start_line, a.end_line, a.text.replace('\n', " "));
         }
-        Action::InputBackspace => {
-            app.file_list_filter.pop();
-            app.file_list_selected = 0;
+    }
+
+    if args.dry_run {
+        println!(
+            "\nWould remove {} status record(s) and {} annotation(s)",
+            stale_statuses.len(),
+            if args.annotations { orphans.len() } else { 0 }
+        );
+        return Ok(());
+    }
+
+    let pruned_statuses = store.retain_file_statuses(is_tracked)?.len();
+
+    let mut pruned_annotations = 0;
+    if args.annotations && !orphans.is_empty() {
+        let confirmed = args.yes || {
+            print!("Delete {} annotation(s) on untracked files [y/N]? ", orphans.len());
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            answer.trim().eq_ignore_ascii_case("y")
+        };
+        if confirmed {
+            pruned_annotations = store.retain_annotations(is_tracked)?.len();
         }
-        _ => {}
     }
+
+    record_history(&repo_root, &annotator_dir)?;
+    println!(
+        "\nRemoved {} status record(s) and {} annotation(s)",
+        pruned_statuses, pruned_annotations
+    );
+    if !args.annotations && !orphans.is_empty() {
+        println!(
+            "{} annotation(s) still point at untracked files; rerun with --annotations to remove them",
+            orphans.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_compact(path: &Path) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+
+    let report = store.compact()?;
+    for path in &report.removed_tmp {
+        println!("Removed stale temp file {}", path.display());
+    }
+    for path in &report.dropped_partial {
+        println!("Dropped partial last line in {}", path.display());
+    }
+    println!(
+        "Store size: {} -> {} bytes (saved {})",
+        report.bytes_before,
+        report.bytes_after,
+        report.bytes_before.saturating_sub(report.bytes_after)
+    );
+    Ok(())
+}
+
+fn cmd_dedupe(path: &Path, dry_run: bool) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+
+    let (kept, removed) = core::dedupe::dedupe(store.load_annotations()?);
+    for a in &removed {
+        println!("Duplicate: {}:{}-{} \"{}\"", a.file_path, a.start_line, a.end_line, a.text.replace('\n', " "));
+    }
+    if dry_run {
+        println!("\nWould merge {} duplicate(s)", removed.len());
+        return Ok(());
+    }
+    if !removed.is_empty() {
+        store.save_annotations(&kept)?;
+        record_history(&repo_root, &annotator_dir)?;
+    }
+    println!("\nMerged {} duplicate(s)", removed.len());
+    Ok(())
 }
 
-fn handle_tree_action(app: &mut tui::app::App, action: tui::keymap::Action) {
-    use tui::keymap::Action;
-    use tui::tree_view::TreeNode;
+fn cmd_repair(path: &Path, reanchor: bool, dry_run: bool) -> Result<()> {
+    use core::repair::{Repair, apply_repair, plan_repair};
+    use std::collections::{HashMap, HashSet};
+
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let tracked: HashSet<String> = review_files(&repo_root, &annotator_dir)?
+        .into_iter()
+        .collect();
 
-    let tree = TreeNode::build(&app.files);
-    let items = tree.flatten(&app.tree_expanded, "");
+    // None for files that can't be read, whose annotations are left alone
+    let mut file_lines: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    let mut kept = Vec::new();
+    let mut quarantined = Vec::new();
+    let (mut clamped, mut reanchored, mut skipped) = (0, 0, 0);
 
-    match action {
-        Action::Cancel => {
-            app.mode = tui::app::AppMode::Viewing;
+    for mut ann in store.load_annotations()? {
+        // Annotations on untracked files are left for `gc`
+        if !tracked.contains(&ann.file_path) {
+            kept.push(ann);
+            continue;
         }
-        Action::Confirm => {
-            if let Some((_, path, is_dir)) = items.get(app.tree_selected) {
-                if *is_dir {
-                    if app.tree_expanded.contains(path) {
-                        app.tree_expanded.remove(path);
-                    } else {
-                        app.tree_expanded.insert(path.clone());
-                    }
-                } else {
-                    if let Some(idx) = app.files.iter().position(|f| f == path) {
-                        app.switch_to_file(idx);
-                    }
-                    app.mode = tui::app::AppMode::Viewing;
+        let lines = file_lines.entry(ann.file_path.clone()).or_insert_with(|| {
+            match std::fs::read_to_string(repo_root.join(&ann.file_path)) {
+                Ok(content) => Some(content.lines().map(|l| l.to_string()).collect()),
+                Err(e) => {
+                    eprintln!("Skipped: {}: {}", ann.file_path, e);
+                    None
                 }
             }
+        });
+        let Some(lines) = lines else {
+            skipped += 1;
+            kept.push(ann);
+            continue;
+        };
+        let Some(repair) = plan_repair(&ann, lines, reanchor) else {
+            kept.push(ann);
+            continue;
+        };
+
+        let location = format!("{}:{}-{}", ann.file_path, ann.start_line, ann.end_line);
+        match &repair {
+            Repair::Clamped { new_end } => {
+                println!("Clamped: {} -> {}-{}", location, ann.start_line, new_end);
+                clamped += 1;
+            }
+            Repair::Reanchored { new_start, new_end } => {
+                println!("Re-anchored: {} -> {}-{}", location, new_start, new_end);
+                reanchored += 1;
+            }
+            Repair::Quarantined => {
+                println!("Quarantined: {} \"{}\"", location, ann.text.replace('\n', " "));
+                quarantined.push(ann);
+                continue;
+            }
         }
-        Action::CursorUp => {
-            app.tree_selected = app.tree_selected.saturating_sub(1);
-        }
-        Action::CursorDown => {
-            app.tree_selected = (app.tree_selected + 1).min(items.len().saturating_sub(1));
-        }
-        _ => {}
+        apply_repair(&mut ann, &repair);
+        kept.push(ann);
+    }
+
+    let verb = if dry_run { "Would repair" } else { "Repaired" };
+    println!(
+        "\n{}: {} clamped, {} re-anchored, {} quarantined",
+        verb,
+        clamped,
+        reanchored,
+        quarantined.len()
+    );
+    if skipped > 0 {
+        println!("{} annotation(s) on unreadable files were left as they are", skipped);
     }
+    if dry_run || clamped + reanchored + quarantined.len() == 0 {
+        return Ok(());
+    }
+
+    // Quarantine first so a failed rewrite never loses annotations
+    store.quarantine_annotations(&quarantined)?;
+    store.save_annotations(&kept)?;
+    record_history(&repo_root, &annotator_dir)?;
+    if !quarantined.is_empty() {
+        println!("Quarantined annotations were moved to {}", annotator_dir.join("quarantine.jsonl").display());
+    }
+    Ok(())
 }
 
-fn handle_conflict_action(app: &mut tui::app::App, _action: tui::keymap::Action) {
-    app.mode = tui::app::AppMode::Viewing;
+fn cmd_verify(path: &Path) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let tracked = review_files(&repo_root, &annotator_dir)?.into_iter().collect();
+
+    let issues = core::verify::verify_store(&store, &repo_root, &tracked)?;
+    if issues.is_empty() {
+        println!("Store OK");
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    anyhow::bail!("{} problem(s) found", issues.len())
 }
 
-fn run_adjustment(app: &mut tui::app::App, from: &str, to: &str) -> Result<()> {
-    let repo = git::repo::open_repo(&app.repo_root)?;
-    let diffs = git::adjust::compute_diffs(&repo, from, to)?;
+/// Records the store on the history ref when `review.history` is on; called
+/// by commands that change the store.
+fn record_history(repo_root: &Path, annotator_dir: &Path) -> Result<()> {
+    if !core::config::Config::load_for(annotator_dir)?.review.history {
+        return Ok(());
+    }
+    let store = core::store::Store::open(annotator_dir)?;
+    let snapshot = local_snapshot(repo_root, annotator_dir, &store)?;
+    git::sync::record_history(&git::repo::open_repo(repo_root)?, &snapshot, store.key())?;
+    Ok(())
+}
 
-    git::rename::apply_renames(&mut app.annotations, &diffs);
+/// The local review data, based on the commit annotations were last adjusted to.
+fn local_snapshot(repo_root: &Path, annotator_dir: &Path, store: &core::store::Store) -> Result<git::sync::Snapshot> {
+    Ok(git::sync::Snapshot {
+        annotations: store.load_annotations()?,
+        file_statuses: store.load_file_statuses()?,
+        base: adjusted_commit(repo_root, annotator_dir)?,
+        changes: Vec::new(),
+    })
+}
 
-    let results = git::adjust::adjust_annotations(&app.annotations, &diffs);
-    git::adjust::apply_adjustments(&mut app.annotations, &results);
+/// The local review data, noting what was deleted or relabeled since it was
+/// last synced on `refname`, so merging it elsewhere doesn't bring that back.
+fn sync_snapshot(
+    repo_root: &Path,
+    annotator_dir: &Path,
+    store: &core::store::Store,
+    repo: &git2::Repository,
+    refname: &str,
+) -> Result<git::sync::Snapshot> {
+    let mut snapshot = local_snapshot(repo_root, annotator_dir, store)?;
+    let previous = git::sync::read_snapshot(repo, refname, store.key())?
+        .map(|(_, previous)| previous)
+        .unwrap_or_default();
+    git::sync::note_changes(&previous, &mut snapshot, chrono::Utc::now());
+    Ok(snapshot)
+}
 
-    app.store.save_annotations(&app.annotations)?;
+fn cmd_sync_push(args: &SyncArgs) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(&args.path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let repo = git::repo::open_repo(&repo_root)?;
+    let refname = git::sync::review_ref(&args.name)?;
 
-    app.session.last_adjust_commit = Some(to.to_string());
-    app.save_session();
+    let snapshot = sync_snapshot(&repo_root, &annotator_dir, &store, &repo, &refname)?;
+    let parents: Vec<_> = repo.refname_to_id(&refname).into_iter().collect();
+    match git::sync::write_snapshot(&repo, &refname, &snapshot, &parents, store.key())? {
+        Some(_) => println!("Recorded {} annotation(s) on {}", snapshot.annotations.len(), refname),
+        None => println!("No changes since the last sync"),
+    }
 
+    git::sync::push(&repo_root, &args.remote, &refname)?;
+    println!("Pushed {} to {}", refname, args.remote);
     Ok(())
 }
 
-fn cmd_adjust(path: &Path, _auto_resolve: bool) -> Result<()> {
-    let repo_root = git::repo::find_repo_root(path)?;
-    let annotator_dir = repo_root.join(".annotator");
-    let store = core::store::Store::new(&annotator_dir);
-    let session = core::session::Session::load(&annotator_dir.join("session.json"))?;
+fn cmd_sync_pull(args: &SyncArgs) -> Result<()> {
+    use std::collections::HashMap;
 
-    let last_commit = session
-        .last_adjust_commit
-        .clone()
-        .context("No previous adjust commit recorded. Run 'annotator review' first.")?;
+    let repo_root = git::repo::find_repo_root(&args.path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let repo = git::repo::open_repo(&repo_root)?;
+    let refname = git::sync::review_ref(&args.name)?;
+    let tracking = git::sync::tracking_ref(&args.remote, &args.name)?;
+
+    if !git::sync::fetch(&repo_root, &args.remote, &refname, &tracking)? {
+        println!("{} has no review '{}' yet", args.remote, args.name);
+        return Ok(());
+    }
+    let (fetched, remote) = git::sync::read_snapshot(&repo, &tracking, store.key())?
+        .with_context(|| format!("{} is missing after fetch", tracking))?;
+
+    let local = sync_snapshot(&repo_root, &annotator_dir, &store, &repo, &refname)?;
+    // Bring the remote lines to the commit the local ones refer to, so the
+    // merged data doesn't mix line numbers from different commits
+    let target = match &local.base {
+        Some(commit) => commit.clone(),
+        None => git::repo::head_commit_id(&repo)?,
+    };
+    let remote = match remote.base.clone() {
+        Some(base) if base != target => {
+            let config = core::config::Config::load_for(&annotator_dir)?;
+            let options = git::adjust::AdjustOptions::from(&config.adjust);
+            let (annotations, _) = git::sync::copy_from(&repo, &remote, &[], &base, &target, &options).with_context(|| {
+                format!(
+                    "Not pulling: the lines of review '{}' refer to {}, which can't be adjusted to {} here",
+                    args.name,
+                    git::repo::short_id(&base),
+                    git::repo::short_id(&target)
+                )
+            })?;
+            git::sync::Snapshot { annotations, base: Some(target), ..remote }
+        }
+        None if local.base.is_some() && !remote.annotations.is_empty() => bail!(
+            "Not pulling: review '{}' doesn't record the commit its lines refer to, so they can't be adjusted to {}",
+            args.name,
+            git::repo::short_id(&target)
+        ),
+        _ => remote,
+    };
+    let before: HashMap<_, _> = local.annotations.iter().map(|a| (a.id, a.updated_at)).collect();
+    let merged = git::sync::merge(local, remote);
+    store.save_all(&merged.annotations, &merged.file_statuses)?;
+    record_history(&repo_root, &annotator_dir)?;
+
+    // Build on the fetched commit so the next push fast-forwards
+    if git::sync::write_snapshot(&repo, &refname, &merged, &[fetched], store.key())?.is_none() {
+        repo.reference(&refname, fetched, true, "annotator sync pull")?;
+    }
+
+    let added = merged.annotations.iter().filter(|a| !before.contains_key(&a.id)).count();
+    let updated = merged
+        .annotations
+        .iter()
+        .filter(|a| before.get(&a.id).is_some_and(|t| *t != a.updated_at))
+        .count();
+    let deleted = before.len() + added - merged.annotations.len();
+    println!(
+        "Pulled {} from {}: {} new, {} updated, {} deleted annotation(s)",
+        refname, args.remote, added, updated, deleted
+    );
+    Ok(())
+}
 
+fn cmd_publish_github(args: &GithubArgs) -> Result<()> {
+    use publish::github::{Client, Review};
+
+    let repo_root = git::repo::find_repo_root(&args.path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
     let repo = git::repo::open_repo(&repo_root)?;
-    let head = git::repo::head_commit_id(&repo)?;
+    let name = match &args.repo {
+        Some(name) => name.clone(),
+        None => {
+            let remote = repo
+                .find_remote(&args.remote)
+                .with_context(|| format!("No remote '{}'; pass --repo OWNER/NAME", args.remote))?;
+            remote
+                .url()
+                .and_then(publish::github::repo_from_url)
+                .with_context(|| format!("Can't tell the repository from the URL of '{}'; pass --repo OWNER/NAME", args.remote))?
+        }
+    };
+    let token = publish::github::token_from_env();
+    if token.is_none() && !args.dry_run {
+        bail!("Set GITHUB_TOKEN or GH_TOKEN to a token allowed to review pull requests in {}", name);
+    }
+    let client = Client::new(&name, token);
+    let pr = client.pull_request(args.pr)?;
+
+    let find = |sha: &str| {
+        git::repo::resolve_commit(&repo, sha).with_context(|| {
+            format!(
+                "Commit {} of the pull request is not here; fetch it first, e.g. git fetch {} pull/{}/head",
+                git::repo::short_id(sha),
+                args.remote,
+                args.pr
+            )
+        })
+    };
+    let head = find(&pr.head.sha)?;
+    let base = find(&pr.base.sha)?;
+    // GitHub shows a pull request's changes since the merge base
+    let merge_base = repo.merge_base(git2::Oid::from_str(&base)?, git2::Oid::from_str(&head)?)?.to_string();
+    let config = core::config::Config::load_for(&annotator_dir)?;
+    let options = git::adjust::AdjustOptions::from(&config.adjust);
+    let diffs = git::adjust::compute_diffs(&repo, &merge_base, &head, &options)?;
 
-    if head == last_commit {
-        println!("Already up to date.");
+    // Comments go on the head's lines, so bring the annotations there
+    let at = match adjusted_commit(&repo_root, &annotator_dir)? {
+        Some(commit) => commit,
+        None => git::repo::head_commit_id(&repo)?,
+    };
+    let mut annotations = store.load_annotations()?;
+    let before = annotations.len();
+    if at != head {
+        git::history::adjust_across(&repo, &at, &head, &mut annotations, &options)?;
+    }
+    let gone = before - annotations.len();
+
+    let published = client.published_ids(args.pr)?;
+    let plan = publish::plan(&annotations, &diffs, &published);
+    if gone > 0 {
+        println!("{} annotation(s) are on lines deleted by {}", gone, git::repo::short_id(&head));
+    }
+    if plan.published > 0 {
+        println!("{} annotation(s) are already on the pull request", plan.published);
+    }
+    if plan.comments.is_empty() && plan.outside.is_empty() {
+        println!("Nothing new to publish");
         return Ok(());
     }
 
-    let diffs = git::adjust::compute_diffs(&repo, &last_commit, &head)?;
+    if args.dry_run {
+        for (a, start, end) in &plan.comments {
+            let text = a.text.lines().next().unwrap_or_default();
+            println!("Comment on {}:{}-{}: {}", a.file_path, start, end, text);
+        }
+        for a in &plan.outside {
+            let text = a.text.lines().next().unwrap_or_default();
+            println!("Outside the diff, listed in the review: {}:{}-{}: {}", a.file_path, a.start_line, a.end_line, text);
+        }
+        println!("Dry run: nothing posted to {}", pr.html_url);
+        return Ok(());
+    }
+
+    let url = client.post_review(args.pr, &Review::new(&head, &plan))?;
+    println!(
+        "Posted {} comment(s), and {} annotation(s) outside the diff in the review: {}",
+        plan.comments.len(),
+        plan.outside.len(),
+        url
+    );
+    Ok(())
+}
+
+fn cmd_copy(path: &Path, name: &str, base: Option<&str>, color: bool) -> Result<()> {
+    use core::annotation::{AdjustResult, FileStatus};
+
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let repo = git::repo::open_repo(&repo_root)?;
+    let refname = git::sync::review_ref(name)?;
+    let (_, snapshot) = git::sync::read_snapshot(&repo, &refname, store.key())?.with_context(|| {
+        format!("No review '{}' here; run 'annotator sync pull --name {}' to fetch it", name, name)
+    })?;
+
+    let base = match base {
+        Some(rev) => git::repo::resolve_commit(&repo, rev)?,
+        None => snapshot
+            .base
+            .clone()
+            .with_context(|| format!("Review '{}' doesn't record its commit; pass --base", name))?,
+    };
+    let target = match adjusted_commit(&repo_root, &annotator_dir)? {
+        Some(commit) => commit,
+        None => git::repo::head_commit_id(&repo)?,
+    };
+
+    let config = core::config::Config::load_for(&annotator_dir)?;
     let mut annotations = store.load_annotations()?;
+    let options = git::adjust::AdjustOptions::from(&config.adjust);
+    let (copied, outcome) = git::sync::copy_from(&repo, &snapshot, &annotations, &base, &target, &options)?;
+    let present = snapshot.annotations.iter().filter(|a| annotations.iter().any(|l| l.id == a.id)).count();
+    for (ann, result) in &outcome.results {
+        if let Some(line) = describe_adjustment(ann, result) {
+            println!("{}", paint(color, adjustment_color(result), &line));
+        }
+    }
 
-    let renames = git::rename::apply_renames(&mut annotations, &diffs);
-    for (old, new) in &renames {
-        println!("Renamed: {} -> {}", old, new);
+    for a in &copied {
+        if store.get_file_status(&a.file_path)? == FileStatus::Unreviewed {
+            store.set_file_status(&a.file_path, FileStatus::Annotated)?;
+        }
     }
+    annotations.extend(copied.iter().cloned());
+    store.save_annotations(&annotations)?;
+    record_history(&repo_root, &annotator_dir)?;
+
+    let dropped = outcome.results.iter().filter(|(_, r)| matches!(r, AdjustResult::Deleted)).count();
+    println!(
+        "Copied {} annotation(s) from review '{}' ({} dropped with their lines, {} already present)",
+        copied.len(),
+        name,
+        dropped,
+        present
+    );
+    Ok(())
+}
 
-    let results = git::adjust::adjust_annotations(&annotations, &diffs);
-    let mut conflicts = Vec::new();
-    let mut shifted = 0;
-    let mut deleted = 0;
+fn cmd_diff(path: &Path, review: Option<&str>, rev: Option<&str>, store_dir: Option<&Path>, color: bool) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let store = core::store::Store::open(&core::location::annotator_dir(&repo_root)?)?;
+    let current = store.load_annotations()?;
 
-    for (ann, result) in &results {
-        match result {
-            core::annotation::AdjustResult::Shifted { old_start, old_end, new_start, new_end } => {
-                println!(
-                    "Shifted: {}:{}-{} -> {}-{}",
-                    ann.file_path, old_start, old_end, new_start, new_end
-                );
-                shifted += 1;
-            }
-            core::annotation::AdjustResult::Deleted => {
-                println!("Deleted: {}:{}-{}", ann.file_path, ann.start_line, ann.end_line);
-                deleted += 1;
-            }
-            core::annotation::AdjustResult::Conflict { deleted_lines } => {
-                println!(
-                    "CONFLICT: {}:{}-{} (deleted lines: {:?})",
-                    ann.file_path, ann.start_line, ann.end_line, deleted_lines
-                );
-                conflicts.push(ann.clone());
+    let (label, earlier) = if let Some(dir) = store_dir {
+        if !dir.is_dir() {
+            bail!("{} is not a directory", dir.display());
+        }
+        // The open store's key, rather than deriving a passphrase key again
+        let old = core::store::Store::with_key(dir, store.key().cloned());
+        (dir.display().to_string(), old.load_annotations()?)
+    } else {
+        let repo = git::repo::open_repo(&repo_root)?;
+        match rev {
+            Some(rev) => (rev.to_string(), git::sync::read_revision(&repo, rev, store.key())?.annotations),
+            None => {
+                let name = review.unwrap_or("default");
+                let refname = git::sync::review_ref(name)?;
+                let (_, snapshot) = git::sync::read_snapshot(&repo, &refname, store.key())?
+                    .with_context(|| format!("No review '{}' recorded yet; run 'annotator sync push' or 'sync pull'", name))?;
+                (refname, snapshot.annotations)
             }
-            core::annotation::AdjustResult::Unchanged => {}
         }
+    };
+
+    let changes = core::changes::compare(&earlier, &current);
+    let location = |a: &core::annotation::Annotation| format!("{}:{}-{}", a.file_path, a.start_line, a.end_line);
+    let first_line = |a: &core::annotation::Annotation| a.text.lines().next().unwrap_or("").to_string();
+    for a in &changes.added {
+        println!("{}  {}", paint(color, "32", &format!("+ {}", location(a))), first_line(a));
+    }
+    for a in &changes.removed {
+        println!("{}  {}", paint(color, "31", &format!("- {}", location(a))), first_line(a));
+    }
+    for (old, new) in &changes.modified {
+        let moved = if location(old) == location(new) {
+            location(new)
+        } else {
+            format!("{} -> {}", location(old), location(new))
+        };
+        let fields = core::changes::changed_fields(old, new).join(", ");
+        println!("{}  {} ({})", paint(color, "33", &format!("~ {}", moved)), first_line(new), fields);
     }
+    if !changes.is_empty() {
+        println!();
+    }
+    println!(
+        "Since {}: {} added, {} removed, {} modified",
+        label,
+        changes.added.len(),
+        changes.removed.len(),
+        changes.modified.len()
+    );
+    Ok(())
+}
 
-    git::adjust::apply_adjustments(&mut annotations, &results);
-    store.save_annotations(&annotations)?;
+fn cmd_restore(path: &Path, rev: &str) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let repo = git::repo::open_repo(&repo_root)?;
+    let snapshot = git::sync::read_revision(&repo, rev, store.key())?;
+    let config = core::config::Config::load_for(&annotator_dir)?;
 
-    let mut new_session = session;
-    new_session.last_adjust_commit = Some(head);
-    new_session.save(&annotator_dir.join("session.json"))?;
+    // Keep the data being replaced recoverable, which only the history can do
+    let has_data = !store.load_annotations()?.is_empty() || !store.load_file_statuses()?.is_empty();
+    if has_data && !config.review.history {
+        bail!(
+            "Not restoring: review.history is off, so the current review data could not be recorded first \
+             and would be lost. Run 'annotator config set review.history true' and restore again."
+        );
+    }
+    record_history(&repo_root, &annotator_dir)?;
+
+    // Bring the recorded lines up to the commit the local annotations are at
+    let annotations = match (&snapshot.base, adjusted_commit(&repo_root, &annotator_dir)?) {
+        (Some(base), Some(target)) if *base != target => {
+            let options = git::adjust::AdjustOptions::from(&config.adjust);
+            git::sync::copy_from(&repo, &snapshot, &[], base, &target, &options)?.0
+        }
+        _ => snapshot.annotations.clone(),
+    };
+    store.save_all(&annotations, &snapshot.file_statuses)?;
+    record_history(&repo_root, &annotator_dir)?;
 
     println!(
-        "\nAdjusted: {} shifted, {} deleted, {} conflicts",
-        shifted,
-        deleted,
-        conflicts.len()
+        "Restored {} annotation(s) and {} file state(s) from {}",
+        annotations.len(),
+        snapshot.file_statuses.len(),
+        rev
     );
+    Ok(())
+}
+
+fn cmd_stale(path: &Path, older_than: Option<i64>, changes: Option<usize>, color: bool) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let annotations = store.load_annotations()?;
+    let repo = git::repo::open_repo(&repo_root)?;
+
+    // Flags replace both configured limits, so `--changes 3` alone ignores age
+    let (max_age, max_changes) = if older_than.is_some() || changes.is_some() {
+        (older_than, changes)
+    } else {
+        let config = core::config::Config::load_for(&annotator_dir)?;
+        (Some(config.stale.max_age_days), Some(config.stale.max_changes))
+    };
+    let stale = git::stale::find_stale(&repo, &annotations, chrono::Utc::now(), max_age, max_changes)?;
+
+    for s in &stale {
+        let a = s.annotation;
+        let location = format!("{}:{}-{}", a.file_path, a.start_line, a.end_line);
+        let text: String = a.text.replace('\n', " ").chars().take(60).collect();
+        println!(
+            "{}  {} day(s) old, {} change(s)  {}",
+            paint(color, "33", &location),
+            s.age_days,
+            s.changes,
+            text
+        );
+    }
+    println!("\n{} of {} annotation(s) may need re-validation", stale.len(), annotations.len());
+    Ok(())
+}
+
+fn cmd_list(path: &Path, deleted: bool, color: bool) -> Result<()> {
+    use export::blame::short_id;
+
+    let repo_root = git::repo::find_repo_root(path)?;
+    let store = core::store::Store::open(&core::location::annotator_dir(&repo_root)?)?;
+
+    if !deleted {
+        let mut annotations = store.load_annotations()?;
+        annotations.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        for a in &annotations {
+            let location = format!("{}:{}-{}", a.file_path, a.start_line, a.end_line);
+            println!("{} {} {}", paint(color, "33", &short_id(a)), location, a.text.replace('\n', " "));
+        }
+        return Ok(());
+    }
+
+    let tombstones = store.load_tombstones()?;
+    if tombstones.is_empty() {
+        println!("No annotations were archived.");
+    }
+    for t in &tombstones {
+        let a = &t.annotation;
+        println!(
+            "{} {}:{}-{} (file deleted in {}, {})",
+            paint(color, "33", &short_id(a)),
+            a.file_path,
+            a.start_line,
+            a.end_line,
+            &t.deleted_in[..t.deleted_in.len().min(8)],
+            t.archived_at.format("%Y-%m-%d")
+        );
+        for line in a.text.lines() {
+            println!("    {}", line);
+        }
+        for (n, line) in (a.start_line..).zip(&t.content) {
+            println!("    {}", paint(color, "2", &format!("{:>4} | {}", n, line)));
+        }
+    }
+    Ok(())
+}
+
+fn cmd_search(path: &Path, query: &str, color: bool) -> Result<()> {
+    use export::blame::short_id;
 
+    let repo_root = git::repo::find_repo_root(path)?;
+    let store = core::store::Store::open(&core::location::annotator_dir(&repo_root)?)?;
+    let found = store.search(query)?;
+    if found.is_empty() {
+        println!("No annotations match {:?}.", query);
+    }
+    for a in &found {
+        let location = format!("{}:{}-{}", a.file_path, a.start_line, a.end_line);
+        println!("{} {} {}", paint(color, "33", &short_id(a)), location, a.text.replace('\n', " "));
+    }
+    Ok(())
+}
+
+fn cmd_label(path: &Path, file: &str, add: &[String], remove: &[String]) -> Result<()> {
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    if let Some(bad) = add.iter().find(|l| l.is_empty() || l.contains(char::is_whitespace)) {
+        bail!("Labels can't be empty or contain spaces: {:?}", bad);
+    }
+    if !add.is_empty() && !review_files(&repo_root, &annotator_dir)?.iter().any(|f| f == file) {
+        bail!("{} is not a file of this review", file);
+    }
+
+    let mut labels = store.labels(file)?;
+    if !add.is_empty() || !remove.is_empty() {
+        labels.retain(|l| !remove.contains(l));
+        labels.extend(add.iter().cloned());
+        store.set_labels(file, &labels)?;
+        labels = store.labels(file)?;
+    }
+    if labels.is_empty() {
+        println!("{} has no labels", file);
+    } else {
+        println!("{}: {}", file, labels.join(", "));
+    }
     Ok(())
 }
 
-fn cmd_export(path: &Path, format: ExportFormat) -> Result<()> {
+fn cmd_undelete(path: &Path, id: &str, to: Option<&str>, lines: Option<&str>) -> Result<()> {
+    use core::annotation::{FileStatus, parse_line_range};
+
     let repo_root = git::repo::find_repo_root(path)?;
-    let store = core::store::Store::new(&repo_root.join(".annotator"));
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let tombstones = store.load_tombstones()?;
+    let mut annotation = core::tombstone::find_by_id(&tombstones, id)?.annotation.clone();
+
+    if let Some(to) = to {
+        annotation.file_path = to.trim_start_matches("./").to_string();
+    }
+    if let Some(range) = lines {
+        let (start, end) = parse_line_range(range).with_context(|| format!("Invalid line range '{}'", range))?;
+        annotation.start_line = start;
+        annotation.end_line = end;
+    }
+    let content: Vec<String> = match std::fs::read_to_string(repo_root.join(&annotation.file_path)) {
+        Ok(text) => text.lines().map(String::from).collect(),
+        Err(_) => bail!("{} does not exist; pass --to to place the annotation on another file", annotation.file_path),
+    };
+    if annotation.end_line as usize > content.len() {
+        bail!(
+            "{} has {} lines, lines {}-{} don't fit; pass --lines",
+            annotation.file_path,
+            content.len(),
+            annotation.start_line,
+            annotation.end_line
+        );
+    }
+    annotation.capture_snippet(&content);
+    annotation.blob = git::repo::open_repo(&repo_root)
+        .ok()
+        .and_then(|repo| git::repo::worktree_blob(&repo, &annotation.file_path));
+    annotation.updated_at = chrono::Utc::now();
+
+    store.restore_tombstone(&annotation)?;
+    if store.get_file_status(&annotation.file_path)? == FileStatus::Unreviewed {
+        store.set_file_status(&annotation.file_path, FileStatus::Annotated)?;
+    }
+    record_history(&repo_root, &annotator_dir)?;
+    println!("Restored to {}:{}-{}", annotation.file_path, annotation.start_line, annotation.end_line);
+    Ok(())
+}
+
+fn cmd_blame(file: &Path, color: bool) -> Result<()> {
+    let full = file
+        .canonicalize()
+        .with_context(|| format!("File not found: {}", file.display()))?;
+    let repo_root = git::repo::find_repo_root(full.parent().unwrap_or(&full))?;
+    let relative = full
+        .strip_prefix(repo_root.canonicalize()?)
+        .with_context(|| format!("{} is outside the repository", file.display()))?;
+    let file_path: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let file_path = file_path.join("/");
+
+    let content: Vec<String> = std::fs::read_to_string(&full)
+        .with_context(|| format!("Failed to read {}", file.display()))?
+        .lines()
+        .map(String::from)
+        .collect();
+    let store = core::store::Store::open(&core::location::annotator_dir(&repo_root)?)?;
     let annotations = store.load_annotations()?;
+    let covering: Vec<&core::annotation::Annotation> =
+        annotations.iter().filter(|a| a.file_path == file_path).collect();
+
+    print!("{}", export::blame::export_blame(&content, &covering, |ids| paint(color, "33", ids)));
+    Ok(())
+}
+
+fn cmd_stats(path: &Path) -> Result<()> {
+    use core::timing::format_duration;
+
+    let repo_root = git::repo::find_repo_root(path)?;
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
+    let summary = core::timing::summarize(&store.load_time_entries()?);
+
+    println!("Time Spent");
+    println!("==========");
+    if summary.files.is_empty() {
+        println!("No time recorded yet.");
+        return Ok(());
+    }
+    println!("Total:         {} over {} day(s)", format_duration(summary.total), summary.days);
+    println!("Files:         {}", summary.files.len());
+    println!();
+    for (file, duration) in &summary.files {
+        println!("{:>9}  {}", format_duration(*duration), file);
+    }
+    Ok(())
+}
 
-    let output = match format {
-        ExportFormat::Markdown => export::markdown::export_markdown(&annotations),
-        ExportFormat::Json => export::json::export_json(&annotations)?,
+/// The settings in effect at `path`.
+fn effective_config(path: &Path) -> Result<core::config::Config> {
+    match git::repo::find_repo_root(path) {
+        Ok(repo_root) => core::config::Config::load_for(&core::location::annotator_dir(&repo_root)?),
+        // Outside a repository only the user config applies
+        Err(_) => core::config::Config::load(&core::location::user_config_path().unwrap_or_default()),
+    }
+}
+
+fn cmd_config_get(path: &Path, key: &str) -> Result<()> {
+    let config = effective_config(path)?;
+    match config.get(key)? {
+        Some(toml::Value::String(s)) => println!("{}", s),
+        Some(toml::Value::Table(t)) => print!("{}", toml::to_string(&t)?),
+        Some(value) => println!("{}", value),
+        None => bail!("'{}' is not set", key),
+    }
+    Ok(())
+}
+
+fn cmd_config_set(path: &Path, key: &str, value: &str, global: bool) -> Result<()> {
+    let file = if global {
+        core::location::user_config_path().context("cannot locate the user config directory; HOME is not set")?
+    } else {
+        let repo_root = git::repo::find_repo_root(path)?;
+        core::location::annotator_dir(&repo_root)?.join("config.toml")
     };
+    core::config::Config::set(&file, key, value)?;
+    println!("Set {} in {}", key, file.display());
+    Ok(())
+}
 
-    println!("{}", output);
+fn cmd_config_check(path: &Path) -> Result<()> {
+    let config = effective_config(path)?;
+    let (bindings, errors) = tui::keymap::KeyBindings::parse(&config.ui.key_layers());
+    for error in &errors {
+        println!("error: {}", error);
+    }
+    for conflict in bindings.conflicts(config.ui.keymap) {
+        println!("conflict: {}", conflict);
+    }
+    if !errors.is_empty() {
+        bail!("{} invalid key binding(s) in ui.keys", errors.len());
+    }
+    println!("Settings OK ({} key binding(s))", bindings.len());
     Ok(())
 }
 
-fn cmd_status(path: &Path) -> Result<()> {
+fn cmd_status(path: &Path, check: &[CheckCondition]) -> Result<()> {
     let repo_root = git::repo::find_repo_root(path)?;
-    let store = core::store::Store::new(&repo_root.join(".annotator"));
+    let annotator_dir = core::location::annotator_dir(&repo_root)?;
+    let store = core::store::Store::open(&annotator_dir)?;
     let annotations = store.load_annotations()?;
-    let files = core::file_list::list_tracked_files(&repo_root)?;
+    let files = review_files(&repo_root, &annotator_dir)?;
     let statuses = store.load_file_statuses()?;
 
     let total = files.len();
-    let clean = statuses
-        .iter()
-        .filter(|s| s.status == core::annotation::FileStatus::Clean)
-        .count();
-    let annotated = statuses
-        .iter()
-        .filter(|s| s.status == core::annotation::FileStatus::Annotated)
-        .count();
+    let (annotated, clean) = count_statuses(&statuses);
     let unreviewed = total - clean - annotated;
 
     println!("Review Progress");
@@ -509,11 +1770,100 @@ fn cmd_status(path: &Path) -> Result<()> {
     println!("Annotated:     {}", annotated);
     println!("Clean:         {}", clean);
     println!("Annotations:   {}", annotations.len());
+    println!("Store:         {}", annotator_dir.display());
 
     if total > 0 {
         let pct = ((clean + annotated) as f64 / total as f64 * 100.0) as u32;
         println!("Progress:      {}%", pct);
     }
 
+    if let Some(reviewers) = core::reviewers::Reviewers::load(&repo_root)? {
+        let reviewed: std::collections::HashSet<&str> = statuses
+            .iter()
+            .filter(|s| s.status != core::annotation::FileStatus::Unreviewed)
+            .map(|s| s.file_path.as_str())
+            .collect();
+        println!();
+        println!("Reviewers");
+        println!("=========");
+        for name in reviewers.names() {
+            let assigned: Vec<&String> = files.iter().filter(|f| reviewers.is_assigned(name, f)).collect();
+            let done = assigned.iter().filter(|f| reviewed.contains(f.as_str())).count();
+            let pct = if assigned.is_empty() { 100 } else { done * 100 / assigned.len() };
+            println!("{}: {}/{} files ({}%)", name, done, assigned.len(), pct);
+        }
+    }
+
+    let mut labeled: std::collections::BTreeMap<&str, (usize, usize)> = std::collections::BTreeMap::new();
+    for s in statuses.iter().filter(|s| files.contains(&s.file_path)) {
+        for label in &s.labels {
+            let (count, unreviewed) = labeled.entry(label.as_str()).or_default();
+            *count += 1;
+            if s.status == core::annotation::FileStatus::Unreviewed {
+                *unreviewed += 1;
+            }
+        }
+    }
+    if !labeled.is_empty() {
+        println!();
+        println!("Labels");
+        println!("======");
+        for (label, (count, unreviewed)) in labeled {
+            println!("{}: {} file(s), {} unreviewed", label, count, unreviewed);
+        }
+    }
+
+    let mut failures = Vec::new();
+    for condition in check {
+        match condition {
+            CheckCondition::Unreviewed if unreviewed > 0 => {
+                failures.push(format!("{} unreviewed file(s)", unreviewed));
+            }
+            CheckCondition::OpenAnnotations if !annotations.is_empty() => {
+                failures.push(format!("{} open annotation(s)", annotations.len()));
+            }
+            CheckCondition::Conflicts => {
+                let conflicts = count_conflicts(&repo_root, &annotator_dir, annotations.clone())?;
+                if conflicts > 0 {
+                    failures.push(format!("{} conflicted annotation(s)", conflicts));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !failures.is_empty() {
+        bail!("Review check failed: {}", failures.join(", "));
+    }
+
     Ok(())
 }
+
+/// Annotations in conflict: those an earlier adjustment left in conflict and
+/// haven't been visited yet, and those adjusting from the last adjusted
+/// commit to HEAD would leave in conflict, computed without writing anything.
+fn count_conflicts(repo_root: &Path, annotator_dir: &Path, mut annotations: Vec<core::annotation::Annotation>) -> Result<usize> {
+    let session_path = core::location::session_dir(repo_root, annotator_dir).join("session.json");
+    let session = core::session::Session::load(&session_path)?;
+    let mut conflicts: std::collections::HashSet<uuid::Uuid> = session
+        .conflicts
+        .iter()
+        .filter(|id| annotations.iter().any(|a| a.id == **id))
+        .copied()
+        .collect();
+    let repo = git::repo::open_repo(repo_root)?;
+    let head = git::repo::head_commit_id(&repo)?;
+    let base = core::session::AdjustBase::load(annotator_dir, &session)?;
+    if let Some(from) = base.commit.filter(|from| *from != head) {
+        let config = core::config::Config::load_for(annotator_dir)?;
+        let options = git::adjust::AdjustOptions::from(&config.adjust);
+        let outcome = git::history::adjust_across(&repo, &from, &head, &mut annotations, &options)?;
+        conflicts.extend(
+            outcome
+                .results
+                .iter()
+                .filter(|(_, r)| matches!(r, core::annotation::AdjustResult::Conflict { .. }))
+                .map(|(a, _)| a.id),
+        );
+    }
+    Ok(conflicts.len())
+}
@@ -0,0 +1,264 @@
+use super::Plan;
+use anyhow::{Context, Result, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+
+const DEFAULT_API: &str = "https://api.github.com";
+/// Environment variables a token is read from, in order.
+pub const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+/// Largest page the list endpoints return.
+const PAGE_SIZE: usize = 100;
+/// Times a rate-limited request is tried again.
+const RATE_LIMIT_RETRIES: u32 = 3;
+/// Longest wait for a rate limit to reset before giving up.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    pub html_url: String,
+    pub head: Branch,
+    pub base: Branch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Branch {
+    pub sha: String,
+}
+
+/// A review with line comments, as the reviews endpoint takes it.
+#[derive(Debug, Serialize)]
+pub struct Review<'a> {
+    pub commit_id: &'a str,
+    pub event: &'static str,
+    pub body: String,
+    pub comments: Vec<ReviewComment<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewComment<'a> {
+    pub path: &'a str,
+    pub body: String,
+    pub line: u32,
+    pub side: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_side: Option<&'static str>,
+}
+
+impl<'a> Review<'a> {
+    /// A plain comment review of `plan` on the pull request's head commit.
+    pub fn new(head: &'a str, plan: &Plan<'a>) -> Self {
+        let comments = plan
+            .comments
+            .iter()
+            .map(|(a, start, end)| ReviewComment {
+                path: &a.file_path,
+                body: super::comment_body(a),
+                line: *end,
+                side: "RIGHT",
+                start_line: (start < end).then_some(*start),
+                start_side: (start < end).then_some("RIGHT"),
+            })
+            .collect();
+        Self {
+            commit_id: head,
+            event: "COMMENT",
+            body: super::review_body(plan),
+            comments,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Posted {
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    #[serde(default)]
+    body: Option<String>,
+}
+
+/// GitHub REST API client for one repository.
+pub struct Client {
+    agent: ureq::Agent,
+    api: String,
+    /// `OWNER/NAME`.
+    repo: String,
+    token: Option<String>,
+}
+
+impl Client {
+    /// Talks to `GITHUB_API_URL` when set (GitHub Enterprise, Actions),
+    /// else api.github.com. Without a token only public data can be read.
+    pub fn new(repo: &str, token: Option<String>) -> Self {
+        let api = std::env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API.to_string());
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+            api: api.trim_end_matches('/').to_string(),
+            repo: repo.to_string(),
+            token,
+        }
+    }
+
+    pub fn pull_request(&self, number: u64) -> Result<PullRequest> {
+        let response = self.send("GET", &format!("pulls/{}", number), None)?;
+        response.into_json().context("Unexpected pull request response from GitHub")
+    }
+
+    /// Ids of the annotations already posted on the pull request, in line
+    /// comments or review bodies.
+    pub fn published_ids(&self, number: u64) -> Result<HashSet<Uuid>> {
+        let comments: Vec<Comment> = self.get_pages(&format!("pulls/{}/comments", number))?;
+        let reviews: Vec<Comment> = self.get_pages(&format!("pulls/{}/reviews", number))?;
+        Ok(comments
+            .iter()
+            .chain(&reviews)
+            .filter_map(|c| c.body.as_deref())
+            .flat_map(super::marked_ids)
+            .collect())
+    }
+
+    /// Posts `review`, returning its URL.
+    pub fn post_review(&self, number: u64, review: &Review) -> Result<String> {
+        let body = serde_json::to_value(review)?;
+        let response = self.send("POST", &format!("pulls/{}/reviews", number), Some(&body))?;
+        let posted: Posted = response.into_json().context("Unexpected review response from GitHub")?;
+        Ok(posted.html_url)
+    }
+
+    fn get_pages<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        for page in 1.. {
+            let response = self.send("GET", &format!("{}?per_page={}&page={}", path, PAGE_SIZE, page), None)?;
+            let batch: Vec<T> = response.into_json().with_context(|| format!("Unexpected response from GitHub for {}", path))?;
+            let last = batch.len() < PAGE_SIZE;
+            items.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Sends a request to `path` under the repository, waiting out rate
+    /// limits a few times.
+    fn send(&self, method: &str, path: &str, body: Option<&serde_json::Value>) -> Result<ureq::Response> {
+        let url = format!("{}/repos/{}/{}", self.api, self.repo, path);
+        let mut attempt = 0;
+        loop {
+            let mut request = self
+                .agent
+                .request(method, &url)
+                .set("Accept", "application/vnd.github+json")
+                .set("X-GitHub-Api-Version", "2022-11-28")
+                .set("User-Agent", concat!("annotator/", env!("CARGO_PKG_VERSION")));
+            if let Some(token) = &self.token {
+                request = request.set("Authorization", &format!("Bearer {}", token));
+            }
+            let result = match body {
+                Some(body) => request.send_json(body),
+                None => request.call(),
+            };
+            let response = match result {
+                Ok(response) => return Ok(response),
+                Err(ureq::Error::Status(_, response)) => response,
+                Err(e) => return Err(e).with_context(|| format!("{} {} failed", method, url)),
+            };
+            let status = response.status();
+            let wait = rate_limit_wait(
+                status,
+                response.header("retry-after"),
+                response.header("x-ratelimit-remaining"),
+                response.header("x-ratelimit-reset"),
+                chrono::Utc::now().timestamp(),
+            );
+            match wait {
+                Some(wait) if attempt < RATE_LIMIT_RETRIES && wait <= MAX_RATE_LIMIT_WAIT => {
+                    eprintln!("Rate limited by GitHub, retrying in {}s", wait.as_secs());
+                    std::thread::sleep(wait);
+                    attempt += 1;
+                }
+                Some(wait) => bail!("GitHub rate limit hit; try again in {} minute(s)", wait.as_secs().div_ceil(60)),
+                None => {
+                    let message = response
+                        .into_json::<serde_json::Value>()
+                        .ok()
+                        .and_then(|v| v["message"].as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    bail!("{} {} failed with {}: {}", method, url, status, message);
+                }
+            }
+        }
+    }
+}
+
+/// The first token set in `TOKEN_VARS`.
+pub fn token_from_env() -> Option<String> {
+    TOKEN_VARS.iter().find_map(|var| std::env::var(var).ok().filter(|t| !t.is_empty()))
+}
+
+/// `OWNER/NAME` from a remote URL such as `git@github.com:owner/name.git`
+/// or `https://github.com/owner/name`.
+pub fn repo_from_url(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let mut parts = url.rsplit(['/', ':']);
+    let name = parts.next().filter(|s| !s.is_empty())?;
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!("{}/{}", owner, name))
+}
+
+/// How long to wait before retrying a request refused with `status`, from
+/// the rate limit headers; `None` when it wasn't refused for rate limiting.
+/// Secondary limits may come without headers, and then call for a minute.
+fn rate_limit_wait(
+    status: u16,
+    retry_after: Option<&str>,
+    remaining: Option<&str>,
+    reset: Option<&str>,
+    now: i64,
+) -> Option<Duration> {
+    if status != 403 && status != 429 {
+        return None;
+    }
+    if let Some(seconds) = retry_after.and_then(|s| s.trim().parse().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+    if remaining.map(str::trim) == Some("0")
+        && let Some(reset) = reset.and_then(|s| s.trim().parse::<i64>().ok())
+    {
+        return Some(Duration::from_secs((reset - now).max(1) as u64));
+    }
+    (status == 429).then_some(Duration::from_secs(60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_from_url() {
+        assert_eq!(repo_from_url("git@github.com:siy/annotator.git").as_deref(), Some("siy/annotator"));
+        assert_eq!(repo_from_url("https://github.com/siy/annotator").as_deref(), Some("siy/annotator"));
+        assert_eq!(repo_from_url("ssh://git@github.com/siy/annotator.git/").as_deref(), Some("siy/annotator"));
+        assert_eq!(repo_from_url("annotator"), None);
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let wait = |status, retry_after, remaining, reset| rate_limit_wait(status, retry_after, remaining, reset, 1000);
+        assert_eq!(wait(403, Some("30"), None, None), Some(Duration::from_secs(30)));
+        assert_eq!(wait(403, None, Some("0"), Some("1090")), Some(Duration::from_secs(90)));
+        assert_eq!(wait(403, None, Some("0"), Some("900")), Some(Duration::from_secs(1)));
+        assert_eq!(wait(429, None, None, None), Some(Duration::from_secs(60)));
+        // Plain permission errors are not retried
+        assert_eq!(wait(403, None, Some("4999"), Some("1090")), None);
+        assert_eq!(wait(404, Some("30"), None, None), None);
+    }
+}
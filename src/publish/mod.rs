@@ -0,0 +1,142 @@
+//! Posting annotations to code hosts as review comments. Hosts only take
+//! comments on lines shown in a pull request's diff, so annotations are
+//! first placed on the diff's hunks; the rest go into the review's body.
+
+use crate::core::annotation::Annotation;
+use crate::git::diff::{FileDiff, FileDiffStatus};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub mod github;
+
+/// Prefix of the hidden marker naming the annotation a comment came from,
+/// so publishing again skips what is already posted.
+const MARKER_PREFIX: &str = "<!-- annotator:";
+
+/// What to post for a set of annotations.
+#[derive(Debug, Default)]
+pub struct Plan<'a> {
+    /// Annotations with the lines of the diff they are commented on.
+    pub comments: Vec<(&'a Annotation, u32, u32)>,
+    /// Annotations on lines the diff does not show.
+    pub outside: Vec<&'a Annotation>,
+    /// Annotations posted before.
+    pub published: usize,
+}
+
+/// Places `annotations`, at the commit the diff leads to, on `diffs`,
+/// leaving out the ids in `published`.
+pub fn plan<'a>(annotations: &'a [Annotation], diffs: &[FileDiff], published: &HashSet<Uuid>) -> Plan<'a> {
+    let mut plan = Plan::default();
+    for (_, anns) in crate::export::report_order(annotations) {
+        for a in anns {
+            if published.contains(&a.id) {
+                plan.published += 1;
+                continue;
+            }
+            let diff = diffs
+                .iter()
+                .find(|d| d.status != FileDiffStatus::Deleted && d.new_path.as_deref() == Some(a.file_path.as_str()));
+            match diff.and_then(|d| place(d, a.start_line, a.end_line)) {
+                Some((start, end)) => plan.comments.push((a, start, end)),
+                None => plan.outside.push(a),
+            }
+        }
+    }
+    plan
+}
+
+/// The part of `start..=end` (new-side lines) inside the first hunk it
+/// overlaps; a comment can't span hunks.
+pub fn place(diff: &FileDiff, start: u32, end: u32) -> Option<(u32, u32)> {
+    diff.hunks.iter().filter(|h| h.new_lines > 0).find_map(|hunk| {
+        let hunk_end = hunk.new_start + hunk.new_lines - 1;
+        let (start, end) = (start.max(hunk.new_start), end.min(hunk_end));
+        (start <= end).then_some((start, end))
+    })
+}
+
+/// Comment text for an annotation, ending in its marker.
+pub fn comment_body(a: &Annotation) -> String {
+    format!("{}\n\n{}", a.text, marker(a.id))
+}
+
+/// Review body listing annotations outside the diff, each with its marker.
+pub fn review_body(plan: &Plan) -> String {
+    let mut body = format!("{} annotation(s) from annotator.", plan.comments.len() + plan.outside.len());
+    if !plan.outside.is_empty() {
+        body.push_str("\n\nOn lines outside this diff:\n");
+        for a in &plan.outside {
+            let text = a.text.lines().next().unwrap_or_default();
+            body.push_str(&format!("\n- `{}:{}` {} {}", a.file_path, line_range(a), text, marker(a.id)));
+        }
+    }
+    body
+}
+
+/// Ids of the annotations marked in `text`.
+pub fn marked_ids(text: &str) -> impl Iterator<Item = Uuid> + '_ {
+    text.split(MARKER_PREFIX)
+        .skip(1)
+        .filter_map(|rest| rest.split_once(" -->"))
+        .filter_map(|(id, _)| Uuid::parse_str(id).ok())
+}
+
+fn marker(id: Uuid) -> String {
+    format!("{}{} -->", MARKER_PREFIX, id)
+}
+
+fn line_range(a: &Annotation) -> String {
+    if a.start_line == a.end_line {
+        a.start_line.to_string()
+    } else {
+        format!("{}-{}", a.start_line, a.end_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::Hunk;
+
+    fn hunk(new_start: u32, new_lines: u32) -> Hunk {
+        Hunk {
+            old_start: new_start,
+            old_lines: 0,
+            new_start,
+            new_lines,
+            lines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_places_annotations_on_hunks() {
+        let diffs = vec![FileDiff {
+            old_path: Some("a.rs".into()),
+            new_path: Some("a.rs".into()),
+            hunks: vec![hunk(5, 4), hunk(20, 3)],
+            status: FileDiffStatus::Modified,
+        }];
+        let annotations = vec![
+            Annotation::new("a.rs".into(), 3, 6, "overlaps the first hunk".into()),
+            Annotation::new("a.rs".into(), 21, 30, "runs past the second".into()),
+            Annotation::new("a.rs".into(), 12, 12, "between hunks".into()),
+            Annotation::new("b.rs".into(), 1, 1, "unchanged file".into()),
+            Annotation::new("a.rs".into(), 7, 7, "already posted".into()),
+        ];
+        let published: HashSet<Uuid> = marked_ids(&comment_body(&annotations[4])).collect();
+
+        let plan = plan(&annotations, &diffs, &published);
+        let comments: Vec<(&str, u32, u32)> = plan.comments.iter().map(|(a, s, e)| (a.text.as_str(), *s, *e)).collect();
+        assert_eq!(comments, vec![("runs past the second", 21, 22), ("overlaps the first hunk", 5, 6)]);
+        let outside: Vec<&str> = plan.outside.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(outside, vec!["between hunks", "unchanged file"]);
+        assert_eq!(plan.published, 1);
+
+        let body = review_body(&plan);
+        assert!(body.starts_with("4 annotation(s)"));
+        assert!(body.contains("- `a.rs:12` between hunks"));
+        let listed: Vec<Uuid> = marked_ids(&body).collect();
+        assert_eq!(listed, vec![annotations[2].id, annotations[3].id]);
+    }
+}
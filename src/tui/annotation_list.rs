@@ -0,0 +1,90 @@
+use crate::core::annotation::Annotation;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Widest the list gets; it never takes more than a third of the screen.
+const MAX_WIDTH: u16 = 40;
+
+/// Width of the annotation list beside a viewer `total` columns wide.
+pub fn width(total: u16) -> u16 {
+    (total / 3).min(MAX_WIDTH)
+}
+
+/// Annotations of the current file by line, along the right edge of the viewer.
+pub struct AnnotationList<'a> {
+    pub annotations: &'a [&'a Annotation],
+    pub selected: usize,
+    /// Keys go to the list; otherwise it only follows along.
+    pub focused: bool,
+    pub palette: &'a Palette,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for AnnotationList<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(if self.focused { Color::Cyan } else { Color::DarkGray });
+
+        if area.width < 4 || area.height < 4 {
+            return;
+        }
+        for py in area.y..area.y + area.height {
+            for px in area.x..area.x + area.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(area.width);
+        let bottom = self.glyphs.frame_bottom(area.width);
+        buf.set_string(area.x, area.y, &top, border_style);
+        buf.set_string(area.x, area.y + area.height - 1, &bottom, border_style);
+        for py in area.y + 1..area.y + area.height - 1 {
+            buf.set_string(area.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(area.x + area.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let title = format!(" Annotations ({}) ", self.annotations.len());
+        let title: String = title.chars().take(area.width.saturating_sub(4) as usize).collect();
+        buf.set_string(area.x + 2, area.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = area.width.saturating_sub(4) as usize;
+        if self.annotations.is_empty() {
+            let empty: String = "No annotations in this file".chars().take(inner_width).collect();
+            buf.set_string(area.x + 2, area.y + 1, &empty, bg.fg(Color::DarkGray));
+        }
+        let max_items = area.height as usize - 2 - usize::from(self.focused);
+        let selected = self.selected.min(self.annotations.len().saturating_sub(1));
+        let scroll = (selected + 1).saturating_sub(max_items);
+        for (i, a) in self.annotations.iter().enumerate().skip(scroll).take(max_items) {
+            let range = if a.start_line == a.end_line {
+                a.start_line.to_string()
+            } else {
+                format!("{}-{}", a.start_line, a.end_line)
+            };
+            let text = a.text.lines().next().unwrap_or_default();
+            let display: String = format!("{:>7} {}", range, text).chars().take(inner_width).collect();
+            let style = if self.focused && i == selected {
+                bg.add_modifier(Modifier::REVERSED)
+            } else {
+                bg
+            };
+            let y = area.y + 1 + (i - scroll) as u16;
+            buf.set_string(area.x + 2, y, &display, style);
+            let range: String = format!("{:>7}", range).chars().take(inner_width).collect();
+            buf.set_string(area.x + 2, y, &range, style.fg(self.palette.accent(a)));
+        }
+
+        if self.focused {
+            let help: String = self
+                .glyphs
+                .localize("Enter: go to │ Esc: back │ Alt+A: hide")
+                .chars()
+                .take(inner_width)
+                .collect();
+            buf.set_string(area.x + 2, area.y + area.height - 2, &help, bg.fg(Color::DarkGray));
+        }
+    }
+}
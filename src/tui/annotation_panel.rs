@@ -0,0 +1,106 @@
+use crate::core::annotation::Annotation;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+pub const MIN_PANEL_HEIGHT: u16 = 3;
+
+/// Full text of the annotation under the cursor, shown below the viewer.
+pub struct AnnotationPanel<'a> {
+    pub annotation: Option<&'a Annotation>,
+    pub palette: &'a Palette,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for AnnotationPanel<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::DarkGray).bg(Color::Rgb(30, 34, 42));
+
+        for y in area.y..area.y + area.height {
+            for x in area.x..area.x + area.width {
+                buf.set_string(x, y, " ", bg);
+            }
+        }
+
+        // Top border with the annotated range as title
+        let sep = self.glyphs.rule(area.width);
+        buf.set_string(area.x, area.y, &sep, border_style);
+
+        let Some(annotation) = self.annotation else {
+            if area.height > 1 {
+                buf.set_string(
+                    area.x + 1,
+                    area.y + 1,
+                    "No annotation on this line",
+                    Style::default().fg(Color::DarkGray).bg(Color::Rgb(30, 34, 42)),
+                );
+            }
+            return;
+        };
+
+        let accent = self.palette.accent(annotation);
+        let mut title = format!(" Lines {}-{} ", annotation.start_line, annotation.end_line);
+        if let Some(severity) = annotation.severity {
+            title.push_str(&format!("{} {} ", self.glyphs.dot, severity.as_str()));
+        }
+        if annotation.priority > 0 {
+            title.push_str(&format!("{} priority {} ", self.glyphs.dot, annotation.priority));
+        }
+        buf.set_string(
+            area.x + 2,
+            area.y,
+            &title,
+            Style::default()
+                .fg(accent)
+                .bg(Color::Rgb(30, 34, 42))
+                .add_modifier(Modifier::BOLD),
+        );
+
+        // Body, wrapped at the panel width
+        let width = area.width.saturating_sub(2) as usize;
+        if width == 0 {
+            return;
+        }
+        let rows = area.height.saturating_sub(1) as usize;
+        let wrapped = wrap_text(&annotation.text, width);
+        for (i, line) in wrapped.iter().take(rows).enumerate() {
+            buf.set_string(area.x + 1, area.y + 1 + i as u16, line, bg);
+        }
+        if wrapped.len() > rows && rows > 0 {
+            let more = format!(" +{} more ", wrapped.len() - rows);
+            let x = (area.x + area.width).saturating_sub(more.len() as u16 + 1);
+            buf.set_string(x, area.y + area.height - 1, &more, border_style);
+        }
+    }
+}
+
+/// Splits text on newlines and wraps each line at `width` characters,
+/// preferring to break at the last space.
+pub(crate) fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    for line in text.split('\n') {
+        let mut rest: Vec<char> = line.chars().collect();
+        if rest.is_empty() {
+            out.push(String::new());
+        }
+        while !rest.is_empty() {
+            if rest.len() <= width {
+                out.push(rest.iter().collect());
+                break;
+            }
+            let split = rest[..width]
+                .iter()
+                .rposition(|c| *c == ' ')
+                .filter(|&p| p > 0)
+                .unwrap_or(width);
+            out.push(rest[..split].iter().collect());
+            let skip = if rest[split] == ' ' { split + 1 } else { split };
+            rest.drain(..skip);
+        }
+    }
+    out
+}
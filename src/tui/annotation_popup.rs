@@ -1,3 +1,5 @@
+use crate::tui::glyphs::Glyphs;
+use crate::tui::text_input;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
@@ -10,6 +12,7 @@ pub struct AnnotationPopup<'a> {
     pub scroll_offset: u32,
     pub viewport_height: u16,
     pub is_edit: bool,
+    pub glyphs: &'a Glyphs,
 }
 
 impl<'a> AnnotationPopup<'a> {
@@ -34,6 +37,9 @@ impl<'a> AnnotationPopup<'a> {
 impl<'a> Widget for AnnotationPopup<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let popup = self.popup_rect(area);
+        if popup.width < 4 || popup.height < 4 {
+            return;
+        }
         let border_style = Style::default().fg(Color::Cyan);
         let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
 
@@ -45,19 +51,13 @@ impl<'a> Widget for AnnotationPopup<'a> {
         }
 
         // Border
-        let top = format!(
-            "┌{}┐",
-            "─".repeat((popup.width.saturating_sub(2)) as usize)
-        );
-        let bottom = format!(
-            "└{}┘",
-            "─".repeat((popup.width.saturating_sub(2)) as usize)
-        );
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
         buf.set_string(popup.x, popup.y, &top, border_style);
         buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
         for y in popup.y + 1..popup.y + popup.height - 1 {
-            buf.set_string(popup.x, y, "│", border_style);
-            buf.set_string(popup.x + popup.width - 1, y, "│", border_style);
+            buf.set_string(popup.x, y, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, y, self.glyphs.vertical, border_style);
         }
 
         // Title
@@ -73,24 +73,28 @@ impl<'a> Widget for AnnotationPopup<'a> {
             border_style.add_modifier(Modifier::BOLD),
         );
 
-        // Text content
+        // Text content, scrolled to keep the cursor line in view
         let inner_width = (popup.width.saturating_sub(4)) as usize;
         let lines: Vec<&str> = self.text.split('\n').collect();
         let max_lines = (popup.height.saturating_sub(3)) as usize;
-        for (i, line) in lines.iter().take(max_lines).enumerate() {
+        let (cursor_line, cursor_col) = text_input::line_and_column(self.text, self.cursor_pos);
+        let scroll = (cursor_line + 1).saturating_sub(max_lines);
+        for (i, line) in lines.iter().skip(scroll).take(max_lines).enumerate() {
             let display: String = line.chars().take(inner_width).collect();
             buf.set_string(popup.x + 2, popup.y + 1 + i as u16, &display, bg);
         }
+        if lines.len() > max_lines {
+            let position = format!(" {}/{} ", cursor_line + 1, lines.len());
+            let x = (popup.x + popup.width).saturating_sub(position.len() as u16 + 2);
+            if x > popup.x + 2 + title.len() as u16 {
+                buf.set_string(x, popup.y, &position, border_style);
+            }
+        }
 
         // Cursor
-        let cursor_line = self.text[..self.cursor_pos].matches('\n').count();
-        let cursor_col = self.text[..self.cursor_pos]
-            .rfind('\n')
-            .map(|p| self.cursor_pos - p - 1)
-            .unwrap_or(self.cursor_pos);
-        if cursor_line < max_lines && cursor_col < inner_width {
+        if cursor_col < inner_width {
             let cx = popup.x + 2 + cursor_col as u16;
-            let cy = popup.y + 1 + cursor_line as u16;
+            let cy = popup.y + 1 + (cursor_line - scroll) as u16;
             if cx < popup.x + popup.width - 1 && cy < popup.y + popup.height - 1 {
                 buf.set_style(
                     Rect::new(cx, cy, 1, 1),
@@ -99,15 +103,59 @@ impl<'a> Widget for AnnotationPopup<'a> {
             }
         }
 
-        // Help text
-        let help = "Enter: confirm │ Esc: cancel";
+        // Help text, led by the tags the annotation will get
+        let tags: Vec<String> = crate::core::annotation::parse_tags(self.text)
+            .iter()
+            .map(|t| format!("#{}", t))
+            .collect();
+        let help = if tags.is_empty() {
+            "Enter: confirm │ Alt+Enter: new line │ Esc: cancel".to_string()
+        } else {
+            format!("{} │ Enter: confirm │ Alt+Enter: new line │ Esc: cancel", tags.join(" "))
+        };
+        let help: String = self.glyphs.localize(&help).chars().take(inner_width).collect();
         if popup.height >= 4 {
             buf.set_string(
                 popup.x + 2,
                 popup.y + popup.height - 2,
-                help,
+                &help,
                 Style::default().fg(Color::DarkGray).bg(Color::Rgb(30, 34, 42)),
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_screen(text: &str, cursor_pos: usize) -> String {
+        let popup = AnnotationPopup {
+            text,
+            cursor_pos,
+            selection_line: 1,
+            scroll_offset: 0,
+            viewport_height: 24,
+            is_edit: false,
+            glyphs: &crate::tui::glyphs::ASCII,
+        };
+        let area = Rect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        popup.render(area, &mut buf);
+        buf.content.iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn test_long_text_scrolls_to_cursor_line() {
+        let text = (1..=9).map(|n| format!("note {n}")).collect::<Vec<_>>().join("\n");
+        let screen = render_screen(&text, text.len());
+        assert!(screen.contains("note 9") && !screen.contains("note 1"));
+        assert!(screen.contains(" 9/9 "));
+
+        let screen = render_screen(&text, 0);
+        assert!(screen.contains("note 1") && !screen.contains("note 9"));
+        assert!(screen.contains(" 1/9 "));
+        // Text that fits shows no position
+        assert!(!render_screen("one\ntwo", 0).contains("1/2"));
+    }
+}
@@ -1,8 +1,30 @@
 use crate::core::annotation::{Annotation, FileStatus};
-use crate::core::session::Session;
+use crate::core::checklist::Checklist;
+use crate::core::config::{AfterAnnotate, AnnotationLayout, Config, ProgressWeight};
+use crate::core::heatmap::heatmap;
+use crate::core::index::AnnotationIndex;
+use crate::core::overlay::Overlay;
+use crate::core::progress::Progress;
+use crate::core::session::{AdjustBase, Session};
+use crate::core::scope::Scope;
+use crate::core::search::SearchIndex;
 use crate::core::store::Store;
+use crate::core::timing::TimeTracker;
 use crate::core::undo::{UndoAction, UndoStack};
+use crate::git::adjust::AdjustOptions;
+use crate::git::history::HistoryAdjustment;
+use crate::tui::annotation_panel::MIN_PANEL_HEIGHT;
+use crate::tui::cursor;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::keymap::KeyBindings;
+use crate::tui::macros::MacroRecorder;
+use crate::tui::messages::MessageLog;
+use crate::tui::palette::{HexColor, Palette};
+use crate::tui::pending_writes::{PendingWrite, PendingWrites};
 use crate::tui::selection::Selection;
+use crate::tui::setup::SetupForm;
+use crate::tui::viewer::OverlayNote;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,23 +35,104 @@ pub enum AppMode {
     FileList,
     TreeView,
     ConflictResolution,
+    /// Startup overlay summarizing where the previous session left off.
+    Resume,
+    /// Full text of the annotation under the cursor.
+    AnnotationView,
+    /// Files set aside with `SkipFile`, to revisit them.
+    SkippedList,
+    /// Choosing the syntax the current file is highlighted as.
+    SyntaxPicker,
+    /// Recent status messages.
+    MessageLog,
+    /// Quitting while some changes could not be saved.
+    UnsavedQuit,
+    /// First-run questions, asked when the repository had no store yet.
+    Setup,
+    /// Side panel of the team checklist for the current file.
+    Checklist,
+    /// Searching annotation text and paths across the whole store.
+    Search,
+    /// Keys go to the annotation list beside the viewer.
+    AnnotationList,
+    /// Every annotation of the review, to revisit one.
+    AnnotationBrowser,
+    /// Annotated files shaded by how much and how severely they are annotated.
+    Heatmap,
+    /// Export formats, to write the review to a file.
+    ExportPicker,
+}
+
+/// Order of the annotation browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrowserSort {
+    /// By path, then line.
+    #[default]
+    File,
+    /// Newest first.
+    Created,
+    /// Most severe first, unlabeled last.
+    Severity,
+}
+
+impl BrowserSort {
+    pub fn next(self) -> Self {
+        match self {
+            BrowserSort::File => BrowserSort::Created,
+            BrowserSort::Created => BrowserSort::Severity,
+            BrowserSort::Severity => BrowserSort::File,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BrowserSort::File => "file",
+            BrowserSort::Created => "date",
+            BrowserSort::Severity => "severity",
+        }
+    }
+}
+
+/// What the startup overlay reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumeSummary {
+    /// File and line the previous session stopped at.
+    pub stopped_at: Option<(String, u32)>,
+    pub conflicts: usize,
+    pub remaining: usize,
+    pub total: usize,
 }
 
 pub struct App {
     pub repo_root: PathBuf,
+    /// Commit under review when reading from git objects instead of the working tree.
+    pub rev: Option<String>,
+    /// Subtrees the review is restricted to; `files` only holds files inside it.
+    pub scope: Scope,
+    pub annotator_dir: PathBuf,
+    /// Where this checkout's session and undo history live.
+    pub session_dir: PathBuf,
     pub store: Store,
     pub session: Session,
+    /// Commits the annotations' lines refer to, shared by every worktree.
+    pub adjust_base: AdjustBase,
     pub mode: AppMode,
     pub files: Vec<String>,
     pub current_file_index: usize,
     pub file_content: Vec<String>,
     pub cursor_line: u32,
     pub cursor_col: u32,
+    /// Column vertical movement returns to, or `cursor::LINE_END` after End.
+    pub preferred_col: u32,
     pub scroll_offset: u32,
     pub viewport_height: u16,
     pub viewport_width: u16,
     pub selection: Option<Selection>,
     pub annotations: Vec<Annotation>,
+    /// Read-only annotations from other reviews, adjusted to what is shown.
+    pub overlays: Vec<Overlay>,
+    /// `annotations` by file, rebuilt by `reindex` after every change to the list.
+    index: AnnotationIndex,
     pub undo_stack: UndoStack,
     pub should_quit: bool,
     pub annotation_input: String,
@@ -37,20 +140,133 @@ pub struct App {
     pub editing_annotation_id: Option<uuid::Uuid>,
     pub file_list_filter: String,
     pub file_list_selected: usize,
+    /// Labels of every labeled file, read when the file list opens.
+    pub file_list_labels: HashMap<String, Vec<String>>,
     pub tree_expanded: std::collections::HashSet<String>,
     pub tree_selected: usize,
+    pub skipped_selected: usize,
+    /// Team checklist from `CHECKLIST.toml`, ticked off per file.
+    pub checklist: Option<Checklist>,
+    pub checklist_selected: usize,
+    /// Syntaxes the highlighter knows, offered by the syntax picker.
+    pub syntax_names: Vec<String>,
+    pub syntax_filter: String,
+    pub syntax_selected: usize,
+    /// Annotations of the current file are listed beside the viewer.
+    pub annotation_list: bool,
+    pub annotation_list_selected: usize,
+    pub browser_sort: BrowserSort,
+    pub browser_selected: usize,
+    pub heatmap_selected: usize,
+    /// Line count of each annotated file, loaded when the heatmap opens.
+    pub heatmap_lines: HashMap<String, usize>,
+    pub export_selected: usize,
+    pub search_query: String,
+    pub search_selected: usize,
+    /// Built when the search opens; annotations can't change while it is open.
+    pub search_index: SearchIndex,
+    /// File index, line and offset among the annotations overlapping that
+    /// line, set by cycling; other lines start at the first annotation.
+    pub annotation_cycle: (usize, u32, usize),
     pub status_message: Option<String>,
+    /// Every status message of the session, for the message log.
+    pub messages: MessageLog,
+    /// Store writes that failed, retried until they go through.
+    pub pending_writes: PendingWrites,
+    pub palette: Palette,
+    pub config: Config,
+    /// `config.ui.keys`, read when the review opens; bad entries are skipped
+    /// and reported in the message log.
+    pub key_bindings: KeyBindings,
+    /// Current height of the bottom annotation panel, adjustable at runtime.
+    pub panel_height: u16,
+    pub macros: MacroRecorder,
+    /// `z` was pressed and the next key places the cursor line on screen.
+    pub scroll_prefix: bool,
+    /// Reduced-color mode for `--color never`, `NO_COLOR` and dumb terminals.
+    pub monochrome: bool,
+    /// HEAD commit seen during the session that annotations were not yet adjusted to.
+    pub pending_head: Option<String>,
+    /// Exact duplicate annotations found on startup, offered for merging.
+    pub pending_duplicates: usize,
+    /// Line count per file, loaded when progress is weighted by lines.
+    pub line_counts: Option<HashMap<String, usize>>,
+    /// Time spent per file, when `review.track_time` is on.
+    pub time_tracker: Option<TimeTracker>,
+    /// Annotation picked with ^O, waiting for the range to move it to.
+    pub reanchoring: Option<uuid::Uuid>,
+    /// Annotation picked with ^B, waiting for the one to link it to.
+    pub linking: Option<uuid::Uuid>,
+    /// Only annotations with this tag are shown and acted on.
+    pub tag_filter: Option<String>,
+    /// Annotation copied with ^C, placed again with ^V.
+    pub clipboard: Option<Annotation>,
+    /// Last change to the session not yet saved, see `save_session_debounced`.
+    pub session_changed: Option<std::time::Instant>,
+    /// The annotations were adjusted in memory to show another revision, so
+    /// nothing may be edited or written back.
+    pub read_only: bool,
+    /// The store didn't exist before this review opened.
+    pub first_run: bool,
+    /// Answers being filled in, in `AppMode::Setup`.
+    pub setup: Option<SetupForm>,
+    /// Shown in `AppMode::Resume`.
+    pub resume: Option<ResumeSummary>,
+    /// Tracked files left out by `review.ignore`.
+    pub ignored_files: usize,
+    /// Background search for binary files among `files`, which stay listed
+    /// until it finishes, see `poll_file_listing`.
+    pub binary_check: Option<std::thread::JoinHandle<HashSet<String>>>,
+    pub listing_progress: Progress,
+    /// Adjustment started with ^R, see `poll_adjustment`.
+    pub adjusting: Option<AdjustJob>,
+    /// The current file was found to be binary before `binary_check` finished.
+    pub current_is_binary: bool,
 }
 
 impl App {
     pub fn new(repo_root: PathBuf) -> anyhow::Result<Self> {
-        let annotator_dir = repo_root.join(".annotator");
-        let store = Store::new(&annotator_dir);
+        Self::with_revision(repo_root, None)
+    }
+
+    /// Opens a review of commit `rev` (a full commit id), reading file lists
+    /// and contents from git objects, or of the working tree when `None`.
+    pub fn with_revision(repo_root: PathBuf, rev: Option<String>) -> anyhow::Result<Self> {
+        let annotator_dir = crate::core::location::annotator_dir(&repo_root)?;
+        let first_run = !annotator_dir.exists();
+        let store = Store::open(&annotator_dir)?;
         store.ensure_dir()?;
 
-        let session = Session::load(&annotator_dir.join("session.json"))?;
-        let files = crate::core::file_list::list_tracked_files(&repo_root)?;
+        let session_dir = crate::core::location::session_dir(&repo_root, &annotator_dir);
+        let session = Session::load(&session_dir.join("session.json"))?;
+        // A corrupt undo file should never prevent the review from opening
+        let adjust_base = AdjustBase::load(&annotator_dir, &session)?;
+        let undo_stack = UndoStack::load(&session_dir.join("undo.json"), adjust_base.commit.as_deref(), store.key())
+            .unwrap_or_default();
+        let palette = Palette::load(&annotator_dir.join("palette.json")).unwrap_or_default();
+        let config = Config::load_for(&annotator_dir)?;
+        let (key_bindings, key_errors) = KeyBindings::parse(&config.ui.key_layers());
+        let files = match &rev {
+            Some(rev) => crate::core::file_list::list_files_at(&repo_root, rev)?,
+            // Reading every file is slow in large repos, so binary content
+            // is looked for in the background once the review is open
+            None => crate::core::file_list::list_candidate_files(&repo_root, config.review.submodules)?,
+        };
+        let tracked = files.len();
+        let files = config.review.filter_ignored(files)?;
+        // A few bad lines should not keep the whole review from opening
+        let set_aside = store.set_aside_malformed()?;
+        let ignored_files = tracked - files.len();
+        let listing_progress = Progress::new();
+        let binary_check = rev.is_none().then(|| spawn_binary_check(&repo_root, &files, &listing_progress));
         let annotations = store.load_annotations()?;
+        let pending_duplicates = crate::core::dedupe::dedupe(annotations.clone()).1.len();
+        let line_counts = (config.ui.progress == ProgressWeight::Lines)
+            .then(|| count_lines(&repo_root, rev.as_deref(), &files));
+        let time_tracker = config
+            .review
+            .track_time
+            .then(|| TimeTracker::new(std::time::Duration::from_secs(config.review.idle_timeout)));
 
         let current_file_index = session
             .current_file
@@ -59,13 +275,17 @@ impl App {
             .unwrap_or(0);
 
         let file_content = if !files.is_empty() {
-            load_file_content(&repo_root, &files[current_file_index])
+            load_file_content(&repo_root, rev.as_deref(), &files[current_file_index])
         } else {
             Vec::new()
         };
 
-        Ok(Self {
+        let mut app = Self {
             repo_root,
+            rev,
+            scope: Scope::default(),
+            annotator_dir,
+            session_dir,
             store,
             mode: AppMode::Viewing,
             files,
@@ -73,23 +293,183 @@ impl App {
             file_content,
             cursor_line: session.current_line.max(1),
             cursor_col: session.current_col,
+            preferred_col: session.current_col,
             scroll_offset: session.scroll_offset,
             viewport_height: 24,
             viewport_width: 80,
             selection: None,
+            index: AnnotationIndex::build(&annotations),
             annotations,
-            undo_stack: UndoStack::default(),
+            overlays: Vec::new(),
+            undo_stack,
             should_quit: false,
             annotation_input: String::new(),
             annotation_input_cursor: 0,
             editing_annotation_id: None,
             file_list_filter: String::new(),
             file_list_selected: 0,
+            file_list_labels: HashMap::new(),
             tree_expanded: std::collections::HashSet::new(),
             tree_selected: 0,
+            skipped_selected: 0,
+            checklist: None,
+            checklist_selected: 0,
+            syntax_names: Vec::new(),
+            syntax_filter: String::new(),
+            syntax_selected: 0,
+            annotation_list: false,
+            annotation_list_selected: 0,
+            browser_sort: BrowserSort::default(),
+            browser_selected: 0,
+            heatmap_selected: 0,
+            heatmap_lines: HashMap::new(),
+            export_selected: 0,
+            search_query: String::new(),
+            search_selected: 0,
+            search_index: SearchIndex::default(),
+            annotation_cycle: (0, 0, 0),
             status_message: None,
+            messages: MessageLog::default(),
+            pending_writes: PendingWrites::default(),
+            palette,
+            panel_height: config.ui.panel_height.max(MIN_PANEL_HEIGHT),
+            key_bindings,
+            config,
+            macros: MacroRecorder::default(),
+            scroll_prefix: false,
+            monochrome: false,
+            pending_head: None,
+            pending_duplicates,
+            line_counts,
+            time_tracker,
+            reanchoring: None,
+            linking: None,
+            tag_filter: None,
+            clipboard: None,
+            session_changed: None,
+            read_only: false,
+            first_run,
+            setup: None,
+            resume: None,
+            ignored_files,
+            binary_check,
+            listing_progress,
+            adjusting: None,
+            current_is_binary: false,
             session,
-        })
+            adjust_base,
+        };
+        app.load_overlays();
+        match Checklist::load(&app.repo_root) {
+            Ok(checklist) => app.checklist = checklist,
+            Err(e) => app.set_status(format!("Checklist not shown: {:#}", e)),
+        }
+        if let Some(message) = set_aside_message(&set_aside) {
+            app.set_status(message);
+        }
+        for error in key_errors {
+            app.set_status(format!("Key binding ignored: {}; see `annotator config check`", error));
+        }
+        Ok(app)
+    }
+
+    /// Reads the configured overlays, reporting those that can't be shown.
+    pub fn load_overlays(&mut self) {
+        self.overlays.clear();
+        for source in self.config.overlays.clone() {
+            match self.load_overlay(&source) {
+                Ok(overlay) => self.overlays.push(overlay),
+                Err(e) => self.set_status(format!("Overlay '{}' not shown: {:#}", source.name, e)),
+            }
+        }
+    }
+
+    /// Loads `source` and brings its line numbers from the commit it was
+    /// written at to the one under review.
+    fn load_overlay(&self, source: &crate::core::config::OverlayConfig) -> anyhow::Result<Overlay> {
+        if let Some(color) = &source.color {
+            HexColor::try_from(color.clone()).map_err(anyhow::Error::msg)?;
+        }
+        let mut overlay = crate::core::overlay::load(&self.repo_root, source, self.store.key())?;
+        if overlay.base.is_none() {
+            return Ok(overlay);
+        }
+        let repo = crate::git::repo::open_repo(&self.repo_root)?;
+        let target = match &self.rev {
+            Some(rev) => rev.clone(),
+            None => crate::git::repo::head_commit_id(&repo)?,
+        };
+        overlay.adjust_to(&repo, &target, &AdjustOptions::from(&self.config.adjust))?;
+        Ok(overlay)
+    }
+
+    /// Overlaid annotations on the current file, with their source's accent.
+    pub fn current_file_overlays(&self) -> Vec<OverlayNote<'_>> {
+        let Some(file) = self.current_file() else {
+            return Vec::new();
+        };
+        self.overlays
+            .iter()
+            .flat_map(|overlay| {
+                let color = crate::tui::palette::overlay_color(overlay);
+                overlay
+                    .annotations
+                    .iter()
+                    .filter(move |a| a.file_path == file)
+                    .map(move |annotation| OverlayNote {
+                        annotation,
+                        source: &overlay.name,
+                        color,
+                    })
+            })
+            .collect()
+    }
+
+    /// Shows `message` in the status bar and keeps it in the message log.
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.messages.push(message.clone());
+        self.status_message = Some(message);
+    }
+
+    /// Queues `write` for retrying if `result` is a failed store write.
+    fn record_write(&mut self, result: anyhow::Result<()>, write: PendingWrite) {
+        if let Err(e) = result {
+            self.set_status(format!("Could not save: {:#}", e));
+            self.pending_writes.push(write, &e);
+        }
+    }
+
+    /// Tries the store writes that failed earlier again.
+    pub fn retry_pending_writes(&mut self) {
+        if self.pending_writes.is_empty() {
+            return;
+        }
+        let mut pending = std::mem::take(&mut self.pending_writes);
+        let saved = pending.retry(&self.store, &self.annotations, || self.write_session());
+        self.pending_writes = pending;
+        if saved {
+            self.set_status("Saved the changes that failed to write earlier");
+        }
+    }
+
+    /// Quits, unless changes are still unsaved after one more try; then asks first.
+    pub fn request_quit(&mut self) {
+        self.retry_pending_writes();
+        if self.pending_writes.is_empty() {
+            self.should_quit = true;
+        } else {
+            self.mode = AppMode::UnsavedQuit;
+        }
+    }
+
+    /// Rows taken by the unsaved changes banner above the viewer.
+    pub fn banner_height(&self) -> u16 {
+        u16::from(!self.pending_writes.is_empty())
+    }
+
+    pub fn open_message_log(&mut self) {
+        self.mode = AppMode::MessageLog;
     }
 
     pub fn current_file(&self) -> Option<&str> {
@@ -97,32 +477,161 @@ impl App {
     }
 
     pub fn current_file_annotations(&self) -> Vec<&Annotation> {
-        let file = match self.current_file() {
-            Some(f) => f,
-            None => return Vec::new(),
+        match self.current_file() {
+            Some(file) => self
+                .index
+                .for_file(&self.annotations, file)
+                .into_iter()
+                .filter(|a| self.passes_tag_filter(a))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn passes_tag_filter(&self, annotation: &Annotation) -> bool {
+        self.tag_filter.as_ref().is_none_or(|tag| annotation.has_tag(tag))
+    }
+
+    /// Shows only annotations with the next tag used in the review, then
+    /// all of them again.
+    pub fn cycle_tag_filter(&mut self) {
+        if self.annotations.iter().all(|a| a.tags.is_empty()) {
+            self.tag_filter = None;
+            self.set_status("No tagged annotations — add #tags to their text");
+            return;
+        }
+        self.tag_filter = crate::core::annotation::next_tag(&self.annotations, self.tag_filter.as_deref());
+        match &self.tag_filter {
+            Some(tag) => {
+                let count = self.annotations.iter().filter(|a| a.has_tag(tag)).count();
+                self.set_status(format!("Showing #{} only ({} in the review) — Alt+T for the next tag", tag, count));
+            }
+            None => self.set_status("Showing all annotations"),
+        }
+    }
+
+    /// Position in `annotations` of the first annotation under the cursor.
+    pub fn cursor_annotation(&self) -> Option<usize> {
+        let covering = self.annotations_at_cursor();
+        let (file, line, offset) = self.annotation_cycle;
+        let offset = if (file, line) == (self.current_file_index, self.cursor_line) { offset } else { 0 };
+        covering.get(offset % covering.len().max(1)).copied()
+    }
+
+    /// Positions in `annotations` of those covering the cursor line.
+    pub fn annotations_at_cursor(&self) -> Vec<usize> {
+        let Some(file) = self.current_file() else {
+            return Vec::new();
         };
-        self.annotations
+        self.index
+            .positions(file)
             .iter()
-            .filter(|a| a.file_path == file)
+            .copied()
+            .filter(|&i| self.annotations[i].contains_line(self.cursor_line))
+            .filter(|&i| self.passes_tag_filter(&self.annotations[i]))
             .collect()
     }
 
+    /// Makes the next annotation overlapping the cursor line the one shown
+    /// and acted on.
+    pub fn cycle_annotation(&mut self) {
+        let count = self.annotations_at_cursor().len();
+        if count < 2 {
+            self.set_status(match count {
+                0 => "No annotation on this line",
+                _ => "Only one annotation on this line",
+            });
+            return;
+        }
+        let here = (self.current_file_index, self.cursor_line);
+        let offset = if (self.annotation_cycle.0, self.annotation_cycle.1) == here { self.annotation_cycle.2 } else { 0 };
+        self.annotation_cycle = (here.0, here.1, (offset + 1) % count);
+    }
+
+    /// Refreshes the per-file index after annotations were added, removed or moved between files.
+    pub fn reindex(&mut self) {
+        self.index = AnnotationIndex::build(&self.annotations);
+    }
+
+    /// Restricts the review to `scope`, staying on the current file if it is inside.
+    pub fn set_scope(&mut self, scope: Scope) {
+        let current = self.current_file().map(|s| s.to_string());
+        self.files = scope.filter(std::mem::take(&mut self.files));
+        self.scope = scope;
+        match current.and_then(|f| self.files.iter().position(|x| *x == f)) {
+            Some(idx) => self.current_file_index = idx,
+            None => {
+                self.current_file_index = 0;
+                self.cursor_line = 1;
+                self.cursor_col = 0;
+                self.preferred_col = 0;
+                self.scroll_offset = 0;
+                self.load_current_file();
+            }
+        }
+    }
+
     pub fn load_current_file(&mut self) {
-        if let Some(file) = self.current_file() {
-            self.file_content = load_file_content(&self.repo_root, file);
+        self.current_is_binary = match (self.current_file(), &self.binary_check) {
+            (Some(file), Some(_)) => crate::core::file_list::is_binary_path(&self.repo_root.join(file)),
+            _ => false,
+        };
+        if self.current_is_binary {
+            self.file_content = Vec::new();
+        } else if let Some(file) = self.current_file() {
+            self.file_content = load_file_content(&self.repo_root, self.rev.as_deref(), file);
         } else {
             self.file_content = Vec::new();
         }
     }
 
+    /// Drops the binary files found by `binary_check` from the list once it
+    /// has finished, keeping the current file selected if it remains.
+    pub fn poll_file_listing(&mut self) {
+        if self.binary_check.as_ref().is_some_and(|h| h.is_finished()) {
+            self.finish_file_listing();
+        }
+    }
+
+    /// Like `poll_file_listing`, but waits for the check to finish.
+    pub fn finish_file_listing(&mut self) {
+        let Some(handle) = self.binary_check.take() else {
+            return;
+        };
+        let binary = handle.join().unwrap_or_default();
+        if binary.is_empty() {
+            self.current_is_binary = false;
+            return;
+        }
+        let current = self.current_file().map(|s| s.to_string());
+        self.files.retain(|f| !binary.contains(f));
+        match current.and_then(|f| self.files.iter().position(|x| *x == f)) {
+            Some(idx) => {
+                self.current_file_index = idx;
+                self.current_is_binary = false;
+            }
+            None => {
+                self.current_file_index = self.current_file_index.min(self.files.len().saturating_sub(1));
+                self.cursor_line = 1;
+                self.cursor_col = 0;
+                self.preferred_col = 0;
+                self.scroll_offset = 0;
+                self.selection = None;
+                self.load_current_file();
+            }
+        }
+    }
+
     pub fn switch_to_file(&mut self, index: usize) {
         if index < self.files.len() {
             self.current_file_index = index;
             self.cursor_line = 1;
             self.cursor_col = 0;
+            self.preferred_col = 0;
             self.scroll_offset = 0;
             self.selection = None;
             self.load_current_file();
+            self.note_session_change();
         }
     }
 
@@ -130,6 +639,9 @@ impl App {
         let start = self.current_file_index + 1;
         for i in 0..self.files.len() {
             let idx = (start + i) % self.files.len();
+            if self.session.skipped.contains(&self.files[idx]) {
+                continue;
+            }
             let status = self
                 .store
                 .get_file_status(&self.files[idx])
@@ -139,7 +651,406 @@ impl App {
                 return;
             }
         }
-        self.status_message = Some("All files reviewed!".into());
+        self.set_status(if self.session.skipped.is_empty() {
+            "All files reviewed!".into()
+        } else {
+            format!("Only skipped files left ({}) — ^L to revisit them", self.session.skipped.len())
+        });
+    }
+
+    /// Sets the current file aside until it is unskipped or reviewed, moving
+    /// on to the next unreviewed file; on a skipped file, unskips it.
+    pub fn toggle_skip_file(&mut self) {
+        let Some(file) = self.current_file().map(|s| s.to_string()) else {
+            return;
+        };
+        if let Some(pos) = self.session.skipped.iter().position(|f| *f == file) {
+            self.session.skipped.remove(pos);
+            self.set_status(format!("{} is no longer skipped", file));
+        } else if self.store.get_file_status(&file).unwrap_or_default() != FileStatus::Unreviewed {
+            self.set_status(format!("{} is already reviewed", file));
+            return;
+        } else {
+            self.session.skipped.push(file.clone());
+            self.set_status(format!("Skipped {} — ^L lists skipped files", file));
+            self.next_unreviewed_file();
+        }
+        self.note_session_change();
+    }
+
+    pub fn open_syntax_picker(&mut self) {
+        if self.current_file().is_none() {
+            return;
+        }
+        self.syntax_filter.clear();
+        self.syntax_selected = 0;
+        self.mode = AppMode::SyntaxPicker;
+    }
+
+    /// Shows the annotation list and moves the keys to it, selecting the
+    /// first annotation that doesn't end above the cursor.
+    pub fn open_annotation_list(&mut self) {
+        let entries = self.annotation_list_entries();
+        self.annotation_list_selected = entries
+            .iter()
+            .position(|&i| self.annotations[i].end_line >= self.cursor_line)
+            .unwrap_or(entries.len().saturating_sub(1));
+        self.annotation_list = true;
+        self.mode = AppMode::AnnotationList;
+    }
+
+    /// Positions in `annotations` of the current file's annotations, by line.
+    pub fn annotation_list_entries(&self) -> Vec<usize> {
+        let Some(file) = self.current_file() else {
+            return Vec::new();
+        };
+        let mut entries: Vec<usize> = self
+            .index
+            .positions(file)
+            .iter()
+            .copied()
+            .filter(|&i| self.passes_tag_filter(&self.annotations[i]))
+            .collect();
+        entries.sort_by_key(|&i| (self.annotations[i].start_line, self.annotations[i].end_line));
+        entries
+    }
+
+    /// Moves the cursor to the first line of the selected annotation in the list.
+    pub fn go_to_listed_annotation(&mut self) {
+        let Some(&idx) = self.annotation_list_entries().get(self.annotation_list_selected) else {
+            return;
+        };
+        self.move_cursor_to_line(self.annotations[idx].start_line);
+        self.selection = None;
+        self.ensure_cursor_visible();
+        if let Some(offset) = self.annotations_at_cursor().iter().position(|&i| i == idx) {
+            self.annotation_cycle = (self.current_file_index, self.cursor_line, offset);
+        }
+    }
+
+    pub fn open_annotation_browser(&mut self) {
+        if self.annotations.is_empty() {
+            self.set_status("No annotations yet");
+            return;
+        }
+        self.browser_selected = self.browser_selected.min(self.annotations.len() - 1);
+        self.mode = AppMode::AnnotationBrowser;
+    }
+
+    /// Positions in `annotations` in the browser's order.
+    pub fn browser_entries(&self) -> Vec<usize> {
+        crate::tui::browser_popup::sorted(&self.annotations, self.browser_sort)
+    }
+
+    /// Opens the file list, reading the files' labels once for filtering and
+    /// display while it is open.
+    pub fn open_file_list(&mut self) {
+        self.file_list_labels = match self.store.load_file_statuses() {
+            Ok(statuses) => statuses
+                .into_iter()
+                .filter(|s| !s.labels.is_empty())
+                .map(|s| (s.file_path, s.labels))
+                .collect(),
+            Err(e) => {
+                self.set_status(format!("Could not read file labels: {}", e));
+                HashMap::new()
+            }
+        };
+        self.file_list_filter.clear();
+        self.file_list_selected = 0;
+        self.mode = AppMode::FileList;
+    }
+
+    pub fn open_heatmap(&mut self) {
+        if self.annotations.is_empty() {
+            self.set_status("No annotations yet");
+            return;
+        }
+        let mut files: Vec<String> = self.annotations.iter().map(|a| a.file_path.clone()).collect();
+        files.sort();
+        files.dedup();
+        self.heatmap_lines = count_lines(&self.repo_root, self.rev.as_deref(), &files);
+        // Unreadable files count as empty; leave them out of the density ranking
+        self.heatmap_lines.retain(|_, lines| *lines > 0);
+        self.heatmap_selected = 0;
+        self.mode = AppMode::Heatmap;
+    }
+
+    /// Switches to the file selected in the heatmap.
+    pub fn go_to_heatmap_file(&mut self) {
+        let Some(path) = heatmap(&self.annotations, &self.heatmap_lines).get(self.heatmap_selected).map(|f| f.path.to_string()) else {
+            return;
+        };
+        match self.files.iter().position(|f| *f == path) {
+            Some(file_idx) => self.switch_to_file(file_idx),
+            None => self.set_status(format!("{} is not part of this review", path)),
+        }
+    }
+
+    /// Lists the export formats, starting at `export.format`.
+    pub fn open_export_picker(&mut self) {
+        if self.annotations.is_empty() {
+            self.set_status("No annotations to export");
+            return;
+        }
+        self.export_selected = crate::export::EXPORTERS
+            .iter()
+            .position(|e| e.name() == self.config.export.format)
+            .unwrap_or(0);
+        self.mode = AppMode::ExportPicker;
+    }
+
+    /// Writes the review in the format selected in the picker to the
+    /// repository root.
+    pub fn export_selected_format(&mut self) {
+        let Some(exporter) = crate::export::EXPORTERS.get(self.export_selected).copied() else {
+            return;
+        };
+        match self.write_export(exporter) {
+            Ok(path) => self.set_status(format!(
+                "Exported {} annotation(s) to {}",
+                self.annotations.len(),
+                path.display()
+            )),
+            Err(e) => self.set_status(format!("Export failed: {}", e)),
+        }
+    }
+
+    fn write_export(&self, exporter: &dyn crate::export::Exporter) -> anyhow::Result<PathBuf> {
+        let statuses = self.store.load_file_statuses()?;
+        let summary = crate::export::markdown::ReviewSummary::new(
+            &self.annotations,
+            self.config.author.identity(),
+            self.adjust_base.commit.clone(),
+            self.files.len(),
+            &statuses,
+        );
+        let completion = self.checklist.as_ref().map(|c| c.completion(&statuses));
+        let context = crate::export::ExportContext {
+            annotations: &self.annotations,
+            summary: Some(&summary),
+            commit: summary.commit.as_deref(),
+            checklist: completion.as_ref(),
+            repo_root: Some(&self.repo_root),
+        };
+        let path = self.repo_root.join(crate::export::file_name(exporter));
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        exporter.write(&context, &mut out)?;
+        std::io::Write::flush(&mut out)?;
+        Ok(path)
+    }
+
+    pub fn open_search(&mut self) {
+        if self.annotations.is_empty() {
+            self.set_status("No annotations to search");
+            return;
+        }
+        self.search_index = SearchIndex::build(&self.annotations);
+        self.search_query.clear();
+        self.search_selected = 0;
+        self.mode = AppMode::Search;
+    }
+
+    /// Positions in `annotations` of the ones matching the search query.
+    pub fn search_results(&self) -> Vec<usize> {
+        self.search_index.search(&self.search_query)
+    }
+
+    /// Opens the annotation at `idx` in `annotations`, on its first line.
+    pub fn go_to_annotation(&mut self, idx: usize) {
+        let (file, line) = (self.annotations[idx].file_path.clone(), self.annotations[idx].start_line);
+        let Some(file_idx) = self.files.iter().position(|f| *f == file) else {
+            self.set_status(format!("{} is not part of this review", file));
+            return;
+        };
+        self.switch_to_file(file_idx);
+        self.move_cursor_to_line(line);
+        self.ensure_cursor_visible();
+        if let Some(offset) = self.annotations_at_cursor().iter().position(|&i| i == idx) {
+            self.annotation_cycle = (self.current_file_index, self.cursor_line, offset);
+        }
+    }
+
+    /// Picker entries matching the filter, see `syntax_popup::matching`.
+    pub fn filtered_syntaxes(&self) -> Vec<Option<&str>> {
+        crate::tui::syntax_popup::matching(&self.syntax_names, &self.syntax_filter)
+    }
+
+    /// Highlights the current file as `syntax`, or as detected with `None`,
+    /// and remembers the choice in the repository's `config.toml`.
+    pub fn set_file_syntax(&mut self, syntax: Option<String>) {
+        let Some(file) = self.current_file().map(|s| s.to_string()) else {
+            return;
+        };
+        let path = self.annotator_dir.join("config.toml");
+        if let Err(e) = Config::set_syntax(&path, &file, syntax.as_deref()) {
+            self.set_status(format!("Could not save the syntax: {}", e));
+            return;
+        }
+        self.set_status(match &syntax {
+            Some(name) => format!("Highlighting {} as {}", file, name),
+            None => format!("Detecting the syntax of {} from its name", file),
+        });
+        match syntax {
+            Some(name) => self.config.ui.syntax.insert(file, name),
+            None => self.config.ui.syntax.remove(&file),
+        };
+    }
+
+    pub fn open_skipped_list(&mut self) {
+        if self.session.skipped.is_empty() {
+            self.set_status("No skipped files");
+            return;
+        }
+        self.skipped_selected = self.skipped_selected.min(self.session.skipped.len() - 1);
+        self.mode = AppMode::SkippedList;
+    }
+
+    pub fn open_checklist(&mut self) {
+        let Some(checklist) = &self.checklist else {
+            self.set_status(format!("No {} at the repository root", crate::core::checklist::FILE_NAME));
+            return;
+        };
+        if checklist.items.is_empty() || self.current_file().is_none() {
+            self.set_status("Nothing to check");
+            return;
+        }
+        self.checklist_selected = self.checklist_selected.min(checklist.items.len() - 1);
+        self.mode = AppMode::Checklist;
+    }
+
+    /// Checklist items ticked off for the current file.
+    pub fn current_checked_items(&self) -> Vec<String> {
+        self.current_file()
+            .and_then(|file| self.store.checked_items(file).ok())
+            .unwrap_or_default()
+    }
+
+    /// Ticks the selected checklist item off for the current file, or
+    /// unticks it.
+    pub fn toggle_checklist_item(&mut self) {
+        let Some(checklist) = self.checklist.clone() else {
+            return;
+        };
+        let items = &checklist.items;
+        let (Some(file), Some(item)) = (self.current_file().map(str::to_string), items.get(self.checklist_selected))
+        else {
+            return;
+        };
+        let checked = checklist.toggle(&self.current_checked_items(), item);
+        let done = items.iter().filter(|i| checked.contains(i)).count();
+        let write = PendingWrite::Checklist { file: file.clone(), checked: checked.clone() };
+        self.record_write(self.store.set_checked_items(&file, &checked), write);
+        self.set_status(format!("{}/{} checked for {}", done, items.len(), file));
+    }
+
+    /// Opens the startup overlay when there is a previous session to resume
+    /// or conflicts to look at.
+    pub fn open_resume_summary(&mut self) {
+        let annotations = &self.annotations;
+        self.session.conflicts.retain(|id| annotations.iter().any(|a| a.id == *id));
+        let stopped_at = self
+            .session
+            .current_file
+            .as_ref()
+            .filter(|f| self.current_file() == Some(f.as_str()))
+            .map(|f| (f.clone(), self.cursor_line));
+        if stopped_at.is_none() && self.session.conflicts.is_empty() {
+            return;
+        }
+        let (reviewed, total, _) = self.review_progress();
+        self.resume = Some(ResumeSummary {
+            stopped_at,
+            conflicts: self.session.conflicts.len(),
+            remaining: total - reviewed,
+            total,
+        });
+        self.mode = AppMode::Resume;
+    }
+
+    /// Asks the first-run setup questions if the store is new. Returns
+    /// whether the setup overlay was opened.
+    pub fn open_setup(&mut self) -> bool {
+        if !self.first_run {
+            return false;
+        }
+        let git_name = git2::Repository::open(&self.repo_root)
+            .and_then(|r| r.config())
+            .and_then(|c| c.get_string("user.name"))
+            .ok();
+        self.setup = Some(SetupForm::new(&self.config, git_name));
+        self.mode = AppMode::Setup;
+        true
+    }
+
+    /// Saves the setup answers to the store's config and applies them to
+    /// the open review. Invalid answers keep the overlay open with the error.
+    pub fn finish_setup(&mut self) {
+        let Some(form) = self.setup.clone() else {
+            return;
+        };
+        match self.apply_setup(&form) {
+            Ok(()) => {
+                self.setup = None;
+                self.mode = AppMode::Viewing;
+                self.set_status("Setup saved — `annotator config set` changes it later");
+            }
+            Err(e) => {
+                if let Some(form) = self.setup.as_mut() {
+                    form.error = Some(format!("{:#}", e));
+                }
+            }
+        }
+    }
+
+    fn apply_setup(&mut self, form: &SetupForm) -> anyhow::Result<()> {
+        let base = form.values[1].trim();
+        if !base.is_empty() {
+            crate::git::repo::resolve_commit(&crate::git::repo::open_repo(&self.repo_root)?, base)?;
+        }
+        form.save(&self.annotator_dir.join("config.toml"))?;
+        let config = Config::load_for(&self.annotator_dir)?;
+        self.files = config.review.filter_ignored(std::mem::take(&mut self.files))?;
+        // Paths or globs given on the command line win over the answer
+        let scope = if self.scope.is_empty() {
+            Scope::new(Vec::new(), &config.review.include)?
+        } else {
+            self.scope.clone()
+        };
+        self.config = config;
+        self.set_scope(scope);
+        Ok(())
+    }
+
+    /// Closes the setup overlay, keeping the defaults.
+    pub fn skip_setup(&mut self) {
+        self.setup = None;
+        self.mode = AppMode::Viewing;
+        self.set_status("Setup skipped — `annotator config set` changes settings later");
+    }
+
+    /// Moves to the next annotation an adjustment left in conflict and takes
+    /// it off the queue.
+    pub fn next_conflict(&mut self) {
+        while !self.session.conflicts.is_empty() {
+            let id = self.session.conflicts.remove(0);
+            let Some(a) = self.annotations.iter().find(|a| a.id == id) else {
+                continue;
+            };
+            let line = a.start_line;
+            let Some(idx) = self.files.iter().position(|f| *f == a.file_path) else {
+                continue;
+            };
+            self.switch_to_file(idx);
+            self.move_cursor_to_line(line);
+            self.ensure_cursor_visible();
+            self.set_status(format!(
+                "Lines under this annotation were deleted — {} more conflict(s)",
+                self.session.conflicts.len()
+            ));
+            return;
+        }
+        self.set_status("No conflicts left");
     }
 
     pub fn create_annotation(&mut self) {
@@ -148,36 +1059,163 @@ impl App {
             None => return,
         };
 
-        let (start, end) = if let Some(ref sel) = self.selection {
-            (sel.start_line, sel.end_line)
+        let (start, end) = self.annotated_range();
+        let annotation = Annotation::new(file, start, end, self.annotation_input.clone());
+        self.add_annotation(annotation);
+        self.annotation_input.clear();
+        self.annotation_input_cursor = 0;
+        self.selection = None;
+        self.mode = AppMode::Viewing;
+        self.advance_after_annotating(end);
+    }
+
+    /// Annotates the selection, or the cursor line, with the text configured
+    /// for function key `n`, without opening the input.
+    pub fn annotate_with_verdict(&mut self, n: u8) {
+        let Some(text) = self.config.ui.verdict(n).map(str::to_string) else {
+            self.set_status(format!("F{} has no verdict — set ui.verdicts.F{} in the config", n, n));
+            return;
+        };
+        let Some(file) = self.current_file().map(str::to_string) else {
+            return;
+        };
+        if self.total_lines() == 0 {
+            self.set_status("Nothing to annotate here");
+            return;
+        }
+        let (start, end) = self.annotated_range();
+        self.add_annotation(Annotation::new(file, start, end, text.clone()));
+        self.selection = None;
+        self.set_status(format!("Annotated lines {}-{}: {}", start, end, text));
+        self.advance_after_annotating(end);
+    }
+
+    /// Lines a new annotation covers: the selection, else the cursor line.
+    fn annotated_range(&self) -> (u32, u32) {
+        match &self.selection {
+            Some(sel) => (sel.start_line, sel.end_line),
+            None => (self.cursor_line, self.cursor_line),
+        }
+    }
+
+    /// Pops up the full text of the annotation under the cursor.
+    pub fn show_full_annotation(&mut self) {
+        if self.cursor_annotation().is_some() {
+            self.mode = AppMode::AnnotationView;
         } else {
-            (self.cursor_line, self.cursor_line)
+            self.set_status("No annotation on this line");
+        }
+    }
+
+    /// Moves the cursor as `ui.after_annotate` asks, past an annotation ending at `end`.
+    fn advance_after_annotating(&mut self, end: u32) {
+        let target = match self.config.ui.after_annotate {
+            AfterAnnotate::Stay => return,
+            AfterAnnotate::NextLine => end + 1,
+            AfterAnnotate::NextHunk => match self.changed_hunk_starts().into_iter().find(|&start| start > end) {
+                Some(start) => start,
+                None => {
+                    self.set_status("No more changes in this file");
+                    return;
+                }
+            },
+        };
+        self.move_cursor_to_line(target);
+        self.move_cursor_to_col(0);
+        self.ensure_cursor_visible();
+    }
+
+    /// Starts of the hunks where the current file differs from `review.base`.
+    pub fn changed_hunk_starts(&self) -> Vec<u32> {
+        let Some(file) = self.current_file() else {
+            return Vec::new();
+        };
+        let base = match (&self.config.review.base, &self.rev) {
+            (Some(base), _) => base.clone(),
+            (None, Some(rev)) => format!("{}^", rev),
+            (None, None) => "HEAD".to_string(),
         };
+        let mut content = self.file_content.join("\n");
+        content.push('\n');
+        crate::git::repo::open_repo(&self.repo_root)
+            .and_then(|repo| crate::git::repo::changed_hunk_starts(&repo, &base, file, &content))
+            .unwrap_or_default()
+    }
+
+    /// Commit under review: the revision with `--rev`, else HEAD.
+    fn reviewed_commit(&self) -> Option<String> {
+        self.rev.clone().or_else(|| {
+            crate::git::repo::open_repo(&self.repo_root)
+                .and_then(|repo| crate::git::repo::head_commit_id(&repo))
+                .ok()
+        })
+    }
 
-        let annotation = Annotation::new(file.clone(), start, end, self.annotation_input.clone());
+    /// Blob of the current file as reviewed, recorded whenever an annotation's
+    /// lines are set so adjust knows which version they refer to.
+    fn current_blob(&self) -> Option<String> {
+        let file = self.current_file()?;
+        let repo = crate::git::repo::open_repo(&self.repo_root).ok()?;
+        match &self.rev {
+            Some(rev) => crate::git::repo::blob_at(&repo, rev, file).ok().flatten(),
+            None => crate::git::repo::worktree_blob(&repo, file),
+        }
+    }
+
+    /// Stores a new annotation on the current file as one undo step.
+    fn add_annotation(&mut self, mut annotation: Annotation) {
+        annotation.capture_snippet(&self.file_content);
+        annotation.author = self.config.author.identity();
+        annotation.commit = self.reviewed_commit();
+        annotation.blob = self.current_blob();
+        let file = annotation.file_path.clone();
+        self.undo_stack.begin_group();
         self.undo_stack
             .push(UndoAction::Create(annotation.clone()));
         self.annotations.push(annotation.clone());
-        let _ = self.store.append_annotation(&annotation);
-        let _ = self
-            .store
-            .set_file_status(&file, FileStatus::Annotated);
-        self.annotation_input.clear();
-        self.annotation_input_cursor = 0;
+        self.reindex();
+        self.record_write(self.store.append_annotation(&annotation), PendingWrite::Annotations);
+        self.set_file_status_recorded(&file, FileStatus::Annotated);
+        self.undo_stack.end_group();
+        self.note_session_change();
+    }
+
+    pub fn copy_annotation_at_cursor(&mut self) {
+        if let Some(idx) = self.cursor_annotation() {
+            self.clipboard = Some(self.annotations[idx].clone());
+            self.set_status("Annotation copied — ^V to place it at the cursor or selection");
+        }
+    }
+
+    /// Places a new annotation with the copied text and severity at the
+    /// selection, or the cursor line.
+    pub fn paste_annotation(&mut self) {
+        let Some(copied) = &self.clipboard else {
+            self.set_status("Nothing copied — ^C on an annotation first");
+            return;
+        };
+        let Some(file) = self.current_file().map(|f| f.to_string()) else {
+            return;
+        };
+        let (start, end) = match &self.selection {
+            Some(sel) => (sel.start_line, sel.end_line),
+            None => (self.cursor_line, self.cursor_line),
+        };
+        let annotation = copied.copy_to(&file, start, end);
+        self.add_annotation(annotation);
         self.selection = None;
-        self.mode = AppMode::Viewing;
+        self.set_status(format!("Annotation placed at lines {}-{}", start, end));
     }
 
     pub fn update_annotation(&mut self) {
         if let Some(id) = self.editing_annotation_id
             && let Some(annotation) = self.annotations.iter_mut().find(|a| a.id == id) {
                 let old = annotation.clone();
-                annotation.text = self.annotation_input.clone();
-                annotation.updated_at = chrono::Utc::now();
+                annotation.set_text(self.annotation_input.clone());
                 let new = annotation.clone();
                 self.undo_stack
-                    .push(UndoAction::Update { old, new: new.clone() });
-                let _ = self.store.update_annotation(&new);
+                    .push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+                self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
             }
         self.editing_annotation_id = None;
         self.annotation_input.clear();
@@ -190,36 +1228,348 @@ impl App {
             Some(f) => f.to_string(),
             None => return,
         };
-        let line = self.cursor_line;
-        if let Some(idx) = self
-            .annotations
-            .iter()
-            .position(|a| a.file_path == file && a.contains_line(line))
-        {
+        if let Some(idx) = self.cursor_annotation() {
             let removed = self.annotations.remove(idx);
+            self.reindex();
+            self.undo_stack.begin_group();
             self.undo_stack
                 .push(UndoAction::Delete(removed.clone()));
-            let _ = self.store.delete_annotation(removed.id);
+            self.record_write(self.store.delete_annotation(removed.id), PendingWrite::Annotations);
 
-            let has_annotations = self.annotations.iter().any(|a| a.file_path == file);
-            if !has_annotations {
-                let _ = self
-                    .store
-                    .set_file_status(&file, FileStatus::Unreviewed);
+            if self.index.positions(&file).is_empty() {
+                self.set_file_status_recorded(&file, FileStatus::Unreviewed);
             }
+            self.undo_stack.end_group();
         }
     }
 
+    /// Splits the annotation under the cursor so the cursor line starts a new
+    /// annotation with the same text, then opens the new one for editing.
+    /// Cancelling the edit keeps the copied text.
+    pub fn split_annotation_at_cursor(&mut self) {
+        let line = self.cursor_line;
+        let Some(idx) = self.cursor_annotation() else {
+            return;
+        };
+        if line == self.annotations[idx].start_line {
+            self.set_status("Move below the first line of the annotation to split it");
+            return;
+        }
+
+        let blob = self.current_blob();
+        let first = &mut self.annotations[idx];
+        let old = first.clone();
+        first.blob = blob;
+        let second = first.split_at(line, &self.file_content);
+        let new = first.clone();
+
+        self.undo_stack.begin_group();
+        self.undo_stack.push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+        self.undo_stack.push(UndoAction::Create(second.clone()));
+        self.undo_stack.end_group();
+        self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+        self.record_write(self.store.append_annotation(&second), PendingWrite::Annotations);
+
+        self.editing_annotation_id = Some(second.id);
+        self.annotation_input = second.text.clone();
+        self.annotation_input_cursor = second.text.len();
+        self.set_status(format!(
+            "Split at line {} — edit the text of lines {}-{}, Esc keeps it",
+            line, second.start_line, second.end_line
+        ));
+        self.annotations.push(second);
+        self.reindex();
+        self.selection = None;
+        self.mode = AppMode::AnnotationEdit;
+    }
+
+    /// Merges the annotation under the cursor with the next one in the file
+    /// that overlaps it or starts right after it. The merged annotation covers
+    /// both ranges and keeps both texts, the first one's on top.
+    pub fn join_annotation_at_cursor(&mut self) {
+        let Some(file) = self.current_file().map(|f| f.to_string()) else {
+            return;
+        };
+        let Some(first) = self.cursor_annotation() else {
+            return;
+        };
+        let next = self
+            .index
+            .positions(&file)
+            .iter()
+            .copied()
+            .filter(|&i| self.annotations[first].joins(&self.annotations[i]))
+            .min_by_key(|&i| (self.annotations[i].start_line, self.annotations[i].end_line));
+        let Some(next) = next else {
+            self.set_status("No adjacent annotation below to merge with");
+            return;
+        };
+
+        let removed = self.annotations[next].clone();
+        let blob = self.current_blob();
+        let merged = &mut self.annotations[first];
+        let old = merged.clone();
+        merged.blob = blob;
+        merged.join(&removed, &self.file_content);
+        let new = merged.clone();
+
+        self.undo_stack.begin_group();
+        self.undo_stack.push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+        self.undo_stack.push(UndoAction::Delete(removed.clone()));
+        self.undo_stack.end_group();
+        self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+        self.record_write(self.store.delete_annotation(removed.id), PendingWrite::Annotations);
+        self.annotations.retain(|a| a.id != removed.id);
+        self.reindex();
+        self.set_status(format!("Merged into lines {}-{}", new.start_line, new.end_line));
+    }
+
+    /// Moves the start and end line of the annotation under the cursor by the
+    /// given deltas, keeping it inside the file and at least one line long.
+    /// The cursor follows so repeated moves keep acting on the same annotation.
+    pub fn move_annotation_at_cursor(&mut self, start_delta: i32, end_delta: i32) {
+        let line = self.cursor_line;
+        let total = self.total_lines();
+        let Some(idx) = self.cursor_annotation() else {
+            return;
+        };
+        let blob = self.current_blob();
+        let annotation = &mut self.annotations[idx];
+        let Some((start, end)) = annotation.moved_range(start_delta, end_delta, total) else {
+            return;
+        };
+
+        let old = annotation.clone();
+        annotation.start_line = start;
+        annotation.end_line = end;
+        annotation.blob = blob;
+        annotation.updated_at = chrono::Utc::now();
+        annotation.capture_snippet(&self.file_content);
+        let new = annotation.clone();
+        self.undo_stack.push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+        self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+
+        let target = if start_delta == end_delta {
+            line.saturating_add_signed(start_delta)
+        } else {
+            line
+        };
+        self.move_cursor_to_line(target.clamp(new.start_line, new.end_line));
+        self.selection = None;
+        self.ensure_cursor_visible();
+        self.set_status(format!("Annotation now covers lines {}-{}", new.start_line, new.end_line));
+    }
+
+    /// Raises or lowers the export priority of the annotation under the
+    /// cursor, never below 0.
+    pub fn bump_priority(&mut self, delta: i32) {
+        let Some(idx) = self.cursor_annotation() else {
+            self.set_status("No annotation on this line");
+            return;
+        };
+        let annotation = &mut self.annotations[idx];
+        let priority = annotation.priority.saturating_add_signed(delta);
+        if priority == annotation.priority {
+            self.set_status("This annotation has no priority");
+            return;
+        }
+        let old = annotation.clone();
+        annotation.priority = priority;
+        annotation.updated_at = chrono::Utc::now();
+        let new = annotation.clone();
+        self.undo_stack.push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+        self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+        self.set_status(match priority {
+            0 => "Priority cleared".to_string(),
+            n => format!("Priority {} — exported ahead of lower ones", n),
+        });
+    }
+
+    /// Moves an annotation to the selection, or to the cursor line.
+    ///
+    /// The annotation is the one picked by a previous call, or else the one
+    /// overlapping the selection. Without either, the annotation under the
+    /// cursor is picked so the new range can be chosen anywhere, in any file.
+    pub fn reanchor_annotation(&mut self) {
+        let Some(file) = self.current_file().map(|f| f.to_string()) else {
+            return;
+        };
+        let (start, end) = match &self.selection {
+            Some(sel) => (sel.start_line, sel.end_line),
+            None => (self.cursor_line, self.cursor_line),
+        };
+        let under_cursor = self.cursor_annotation().map(|i| self.annotations[i].id);
+
+        let id = match (self.reanchoring.take(), &self.selection) {
+            (Some(id), None) if under_cursor == Some(id) => {
+                self.set_status("Re-anchoring cancelled");
+                return;
+            }
+            (Some(id), _) => id,
+            (None, Some(_)) => {
+                let overlapping = self.index.for_file(&self.annotations, &file).into_iter().find(|a| a.overlaps(start, end));
+                match overlapping {
+                    Some(a) => a.id,
+                    None => return,
+                }
+            }
+            (None, None) => {
+                self.reanchoring = under_cursor;
+                return;
+            }
+        };
+        let blob = self.current_blob();
+        let Some(annotation) = self.annotations.iter_mut().find(|a| a.id == id) else {
+            return;
+        };
+
+        let old = annotation.clone();
+        annotation.blob = blob;
+        annotation.anchor_to(&file, start, end, &self.file_content);
+        let new = annotation.clone();
+        self.reindex();
+
+        self.undo_stack.begin_group();
+        self.undo_stack.push(UndoAction::Update { old: Box::new(old.clone()), new: Box::new(new.clone()) });
+        self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+        self.set_file_status_recorded(&file, FileStatus::Annotated);
+        if old.file_path != file && !self.annotations.iter().any(|a| a.file_path == old.file_path) {
+            self.set_file_status_recorded(&old.file_path, FileStatus::Unreviewed);
+        }
+        self.undo_stack.end_group();
+        self.selection = None;
+        self.set_status(format!("Annotation moved to lines {}-{}", start, end));
+    }
+
+    /// Links the annotation under the cursor to the one picked by a previous
+    /// call, or unlinks them if they already are. Without a picked one, the
+    /// annotation under the cursor is picked, so the other can be in any file.
+    pub fn link_annotation(&mut self) {
+        let Some(idx) = self.cursor_annotation() else {
+            self.set_status("No annotation on this line");
+            return;
+        };
+        let target = self.annotations[idx].id;
+        let Some(source) = self.linking.take() else {
+            self.linking = Some(target);
+            return;
+        };
+        if source == target {
+            self.set_status("Linking cancelled");
+            return;
+        }
+        let Some(source_idx) = self.annotations.iter().position(|a| a.id == source) else {
+            self.set_status("The picked annotation was deleted");
+            return;
+        };
+
+        let unlink = self.annotations[idx].links.contains(&source);
+        let now = chrono::Utc::now();
+        self.undo_stack.begin_group();
+        for (i, other) in [(source_idx, target), (idx, source)] {
+            let old = self.annotations[i].clone();
+            let annotation = &mut self.annotations[i];
+            annotation.links.retain(|l| *l != other);
+            if !unlink {
+                annotation.links.push(other);
+            }
+            annotation.updated_at = now;
+            let new = annotation.clone();
+            self.undo_stack.push(UndoAction::Update { old: Box::new(old), new: Box::new(new.clone()) });
+            self.record_write(self.store.update_annotation(&new), PendingWrite::Annotations);
+        }
+        self.undo_stack.end_group();
+        self.set_status(if unlink {
+            "Annotations unlinked"
+        } else {
+            "Annotations linked — Alt+Right jumps between them"
+        });
+    }
+
+    /// Jumps to the next annotation in the linked group of the one under the
+    /// cursor, ordered by file and line and wrapping around.
+    pub fn follow_link(&mut self) {
+        let Some(idx) = self.cursor_annotation() else {
+            self.set_status("No annotation on this line");
+            return;
+        };
+        let mut group = crate::core::annotation::linked_group(&self.annotations, idx);
+        if group.len() < 2 {
+            self.set_status("No linked annotations — ^B links two annotations");
+            return;
+        }
+        group.sort_by(|&a, &b| {
+            let (a, b) = (&self.annotations[a], &self.annotations[b]);
+            (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line))
+        });
+        let position = group.iter().position(|&i| i == idx).unwrap_or(0);
+        let target = group[(position + 1) % group.len()];
+        let (file, line) = (self.annotations[target].file_path.clone(), self.annotations[target].start_line);
+        let Some(file_idx) = self.files.iter().position(|f| *f == file) else {
+            self.set_status(format!("The linked annotation is on {}, outside this review", file));
+            return;
+        };
+        self.switch_to_file(file_idx);
+        self.move_cursor_to_line(line);
+        self.ensure_cursor_visible();
+        // Where annotations overlap, preview the linked one
+        if let Some(offset) = self.annotations_at_cursor().iter().position(|&i| i == target) {
+            self.annotation_cycle = (self.current_file_index, self.cursor_line, offset);
+        }
+        self.set_status(format!("Linked annotation {}/{} — {}:{}", (position + 1) % group.len() + 1, group.len(), file, line));
+    }
+
+    /// Sets a file's review status and records the change for undo.
+    fn set_file_status_recorded(&mut self, file: &str, status: FileStatus) {
+        let old = self.store.get_file_status(file).unwrap_or_default();
+        if old == status {
+            return;
+        }
+        let write = PendingWrite::FileStatus { file: file.to_string(), status };
+        self.record_write(self.store.set_file_status(file, status), write);
+        if status != FileStatus::Unreviewed {
+            self.session.skipped.retain(|f| f != file);
+        }
+        self.undo_stack.push(UndoAction::FileStatus {
+            file_path: file.to_string(),
+            old,
+            new: status,
+        });
+    }
+
     pub fn mark_file_clean(&mut self) {
-        if let Some(file) = self.current_file() {
-            let _ = self
-                .store
-                .set_file_status(file, FileStatus::Clean);
-            self.status_message = Some(format!("Marked {} as clean", file));
+        if let Some(file) = self.current_file().map(|s| s.to_string()) {
+            self.set_file_status_recorded(&file, FileStatus::Clean);
+            self.set_status(format!("Marked {} as clean", file));
             self.next_unreviewed_file();
         }
     }
 
+    /// Marks a single file clean without moving to it.
+    pub fn mark_path_clean(&mut self, file: &str) {
+        self.set_file_status_recorded(file, FileStatus::Clean);
+        self.set_status(format!("Marked {} as clean", file));
+    }
+
+    /// Marks every file under `dir` clean as a single undo step.
+    pub fn mark_directory_clean(&mut self, dir: &str) {
+        let prefix = format!("{}/", dir);
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .filter(|f| f.starts_with(&prefix))
+            .cloned()
+            .collect();
+
+        self.undo_stack.begin_group();
+        for file in &files {
+            self.set_file_status_recorded(file, FileStatus::Clean);
+        }
+        self.undo_stack.end_group();
+
+        self.set_status(format!("Marked {} files in {}/ as clean", files.len(), dir));
+    }
+
     pub fn apply_undo(&mut self) {
         if let Some(action) = self.undo_stack.undo() {
             self.apply_undo_action(&action);
@@ -236,31 +1586,388 @@ impl App {
         match action {
             UndoAction::Create(a) => {
                 self.annotations.push(a.clone());
-                let _ = self.store.append_annotation(a);
+                self.record_write(self.store.append_annotation(a), PendingWrite::Annotations);
             }
             UndoAction::Delete(a) => {
                 self.annotations.retain(|x| x.id != a.id);
-                let _ = self.store.delete_annotation(a.id);
+                self.record_write(self.store.delete_annotation(a.id), PendingWrite::Annotations);
             }
             UndoAction::Update { new, .. } => {
                 if let Some(existing) = self.annotations.iter_mut().find(|a| a.id == new.id) {
-                    *existing = new.clone();
-                    let _ = self.store.update_annotation(new);
+                    *existing = (**new).clone();
+                    self.record_write(self.store.update_annotation(new), PendingWrite::Annotations);
+                }
+            }
+            UndoAction::FileStatus { file_path, new, .. } => {
+                let write = PendingWrite::FileStatus { file: file_path.clone(), status: *new };
+                self.record_write(self.store.set_file_status(file_path, *new), write);
+            }
+            UndoAction::Group { actions } => {
+                for action in actions {
+                    self.apply_undo_action(action);
                 }
             }
         }
+        self.reindex();
     }
 
-    pub fn save_session(&self) {
+    /// Adjusts all annotations from `from` to `to` and persists the result.
+    pub fn run_adjustment(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        let repo = crate::git::repo::open_repo(&self.repo_root)?;
+        let options = AdjustOptions::from(&self.config.adjust);
+        let outcome = crate::git::history::adjust_across(&repo, from, to, &mut self.annotations, &options)?;
+        self.apply_adjustment(to, outcome)
+    }
+
+    /// Adjusts all annotations from `from` to `to` for viewing only. The store
+    /// and session keep the lines they had, and the review becomes read-only.
+    pub fn adjust_for_viewing(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        let repo = crate::git::repo::open_repo(&self.repo_root)?;
+        let options = AdjustOptions::from(&self.config.adjust);
+        crate::git::history::adjust_across(&repo, from, to, &mut self.annotations, &options)?;
+        self.read_only = true;
+        self.time_tracker = None;
+        self.reindex();
+        Ok(())
+    }
+
+    /// Refuses an edit with a status message when the review is read-only.
+    pub fn refuse_if_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.set_status("Read-only: annotations are adjusted to this revision for viewing only");
+        }
+        self.read_only
+    }
+
+    /// Records an adjustment to `to` already applied to `self.annotations`.
+    fn apply_adjustment(&mut self, to: &str, outcome: HistoryAdjustment) -> anyhow::Result<()> {
+        self.store.archive_tombstones(&outcome.tombstones)?;
+        self.forget_undo_if_moved(&outcome.results);
+        self.session.add_conflicts(&outcome.results);
+        self.adjust_base.commit = Some(to.to_string());
+        self.reindex();
+        if !self.overlays.is_empty() {
+            self.load_overlays();
+        }
+        self.adjust_submodules()
+    }
+
+    /// Clears the undo history when an adjustment moved any annotation, since
+    /// undoing would put it back on the lines of the old commit.
+    fn forget_undo_if_moved(&mut self, results: &[(Annotation, crate::core::annotation::AdjustResult)]) {
+        if results.iter().any(|(_, r)| !matches!(r, crate::core::annotation::AdjustResult::Unchanged)) {
+            self.undo_stack.clear();
+        }
+    }
+
+    /// Adjusts annotations on submodule files to each submodule's HEAD, when
+    /// submodules are part of the review.
+    pub fn adjust_submodules(&mut self) -> anyhow::Result<()> {
+        if self.config.review.submodules && self.rev.is_none() {
+            let outcomes = crate::git::submodule::adjust_submodules(
+                &self.repo_root,
+                &mut self.annotations,
+                &mut self.adjust_base.submodules,
+                &AdjustOptions::from(&self.config.adjust),
+            )?;
+            for (_, outcome) in &outcomes {
+                self.session.add_conflicts(&outcome.results);
+                self.forget_undo_if_moved(&outcome.results);
+            }
+            self.reindex();
+        }
+        self.store.save_annotations(&self.annotations)?;
+        self.adjust_base.save(&self.annotator_dir)?;
+        self.save_session();
+        Ok(())
+    }
+
+    /// Records that the annotations' lines refer to `commit`.
+    pub fn set_adjust_base(&mut self, commit: String) -> anyhow::Result<()> {
+        self.adjust_base.commit = Some(commit);
+        self.adjust_base.save(&self.annotator_dir)
+    }
+
+    /// Records a pending adjustment if HEAD no longer matches the last adjust commit.
+    pub fn check_head(&mut self) {
+        // A pinned revision doesn't follow the checkout
+        if self.rev.is_some() {
+            return;
+        }
+        let Ok(repo) = crate::git::repo::open_repo(&self.repo_root) else {
+            return;
+        };
+        let Ok(head) = crate::git::repo::head_commit_id(&repo) else {
+            return;
+        };
+        if self.adjust_base.commit.as_deref() == Some(head.as_str()) {
+            self.pending_head = None;
+        } else {
+            self.pending_head = Some(head);
+        }
+    }
+
+    /// Starts adjusting to the HEAD detected by `check_head` on another
+    /// thread; `poll_adjustment` picks up the result.
+    pub fn adjust_to_pending_head(&mut self) {
+        let Some(head) = self.pending_head.take() else {
+            self.set_status("Annotations are up to date");
+            return;
+        };
+        let Some(from) = self.adjust_base.commit.clone() else {
+            if let Err(e) = self.set_adjust_base(head) {
+                self.set_status(format!("Could not save: {:#}", e));
+            }
+            return;
+        };
+
+        let progress = Progress::new();
+        let options = AdjustOptions::from(&self.config.adjust);
+        let (root, mut annotations) = (self.repo_root.clone(), self.annotations.clone());
+        let (to, job_progress) = (head.clone(), progress.clone());
+        let handle = std::thread::spawn(move || {
+            let repo = crate::git::repo::open_repo(&root)?;
+            let outcome = crate::git::history::adjust_across_with(&repo, &from, &to, &mut annotations, &options, &job_progress)?;
+            Ok((annotations, outcome))
+        });
+        self.adjusting = Some(AdjustJob { head, progress, handle });
+    }
+
+    /// Applies the adjustment started by `adjust_to_pending_head` once it has
+    /// finished.
+    pub fn poll_adjustment(&mut self) {
+        if self.adjusting.as_ref().is_some_and(|job| job.handle.is_finished()) {
+            self.finish_adjustment();
+        }
+    }
+
+    /// Like `poll_adjustment`, but waits for the adjustment to finish, then
+    /// reloads the file list and current file since the checkout changed on disk.
+    pub fn finish_adjustment(&mut self) {
+        let Some(job) = self.adjusting.take() else {
+            return;
+        };
+        let head = job.head;
+        let result = job
+            .handle
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("the adjustment stopped unexpectedly")))
+            .and_then(|(annotations, outcome)| {
+                self.annotations = annotations;
+                self.apply_adjustment(&head, outcome)
+            });
+        match result {
+            Ok(()) => {
+                let current = self.current_file().map(|s| s.to_string());
+                if let Ok(files) = crate::core::file_list::list_candidate_files(&self.repo_root, self.config.review.submodules) {
+                    self.files = self.scope.filter(files);
+                    self.listing_progress.cancel();
+                    self.listing_progress = Progress::new();
+                    self.binary_check = Some(spawn_binary_check(&self.repo_root, &self.files, &self.listing_progress));
+                }
+                self.current_file_index = current
+                    .and_then(|f| self.files.iter().position(|x| *x == f))
+                    .unwrap_or(0)
+                    .min(self.files.len().saturating_sub(1));
+                self.load_current_file();
+                self.move_cursor_to_line(self.cursor_line);
+                self.set_status(format!("Annotations adjusted to {}", crate::git::repo::short_id(&head)));
+            }
+            Err(_) if job.progress.is_cancelled() => {
+                self.pending_head = Some(head);
+                self.set_status("Adjustment cancelled — ^R to run it again");
+            }
+            Err(e) => {
+                self.pending_head = Some(head);
+                self.set_status(format!("Adjust failed: {e}"));
+            }
+        }
+    }
+
+    /// Stops the adjustment or binary file check running in the background,
+    /// the adjustment first; false when neither is.
+    pub fn cancel_background(&mut self) -> bool {
+        if let Some(job) = &self.adjusting {
+            job.progress.cancel();
+            return true;
+        }
+        if self.binary_check.is_some() {
+            self.listing_progress.cancel();
+            self.finish_file_listing();
+            self.set_status("Binary file check cancelled — unchecked files stay listed");
+            return true;
+        }
+        false
+    }
+
+    /// Merges exact duplicate annotations, keeping the earliest of each, as one undo step.
+    pub fn merge_duplicates(&mut self) {
+        let (kept, removed) = crate::core::dedupe::dedupe(self.annotations.clone());
+        self.pending_duplicates = 0;
+        if removed.is_empty() {
+            self.set_status("No duplicate annotations");
+            return;
+        }
+
+        self.undo_stack.begin_group();
+        for a in &removed {
+            // Copies sharing an id with a kept annotation are byte-for-byte
+            // identical, and undoing them by id would remove the kept one too
+            if !kept.iter().any(|k| k.id == a.id) {
+                self.undo_stack.push(UndoAction::Delete(a.clone()));
+            }
+        }
+        self.undo_stack.end_group();
+
+        self.record_write(self.store.save_annotations(&kept), PendingWrite::Annotations);
+        self.annotations = kept;
+        self.reindex();
+        self.set_status(format!("Merged {} duplicate annotation(s)", removed.len()));
+    }
+
+    pub fn toggle_macro_recording(&mut self) {
+        if self.macros.is_recording() {
+            let len = self.macros.stop();
+            self.set_status(format!("Recorded macro ({} keys) — ^P to replay", len));
+        } else {
+            self.macros.start();
+            self.set_status("Recording macro — ^K to stop");
+        }
+    }
+
+    /// Title and instructions shown instead of the viewer when there is no
+    /// file to review or the current file is empty.
+    pub fn placeholder(&self) -> Option<(String, Vec<String>)> {
+        let Some(file) = self.current_file() else {
+            let mut lines = Vec::new();
+            if !self.scope.is_empty() {
+                lines.push("No tracked file matches the paths or --include globs given.".to_string());
+            }
+            if self.ignored_files > 0 {
+                lines.push(format!(
+                    "{} tracked file(s) are left out by review.ignore; change it with `annotator config set review.ignore`.",
+                    self.ignored_files
+                ));
+            }
+            lines.push("Files are listed from the git index; `git add` new files to include them (untracked files are not reviewed).".to_string());
+            lines.push("In a new directory, run `git init` and `git add` your code first.".to_string());
+            lines.push(String::new());
+            lines.push("^Q to quit".to_string());
+            return Some(("No files to review".to_string(), lines));
+        };
+        if self.current_is_binary {
+            return Some((
+                format!("{} is binary", file),
+                vec![
+                    "Binary files are left out of the review; this one drops out of the list once all files are checked.".to_string(),
+                    String::new(),
+                    "^N next unreviewed file · ^F file list".to_string(),
+                ],
+            ));
+        }
+        self.file_content.is_empty().then(|| {
+            (
+                format!("{} is empty", file),
+                vec![
+                    "There is nothing to annotate in this file.".to_string(),
+                    String::new(),
+                    "^M mark it clean · ^N next unreviewed file · ^F file list".to_string(),
+                ],
+            )
+        })
+    }
+
+    pub fn glyphs(&self) -> &'static Glyphs {
+        Glyphs::get(self.config.ui.accessible)
+    }
+
+    /// Rows taken by the bottom annotation panel, zero when the layout doesn't use it.
+    pub fn visible_panel_height(&self) -> u16 {
+        match self.config.ui.annotation_layout {
+            AnnotationLayout::BottomPanel => self.panel_height,
+            AnnotationLayout::StatusBar => 0,
+        }
+    }
+
+    /// Grows or shrinks the bottom annotation panel, keeping room for the viewer.
+    pub fn resize_panel(&mut self, delta: i16) {
+        let available = self.viewport_height + self.visible_panel_height();
+        let max = available.saturating_sub(1).max(MIN_PANEL_HEIGHT);
+        self.panel_height = self
+            .panel_height
+            .saturating_add_signed(delta)
+            .clamp(MIN_PANEL_HEIGHT, max);
+    }
+
+    /// Saves the session and undo stack, queueing them to be tried again
+    /// when the write fails.
+    pub fn save_session(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let result = self.write_session();
+        self.record_write(result, PendingWrite::Session);
+    }
+
+    /// Writes the session, with the current position, and the undo stack.
+    fn write_session(&self) -> anyhow::Result<()> {
         let session = Session {
             current_file: self.current_file().map(|s| s.to_string()),
             current_line: self.cursor_line,
             current_col: self.cursor_col,
             scroll_offset: self.scroll_offset,
-            last_adjust_commit: self.session.last_adjust_commit.clone(),
+            ..self.session.clone()
+        };
+        session.save(&self.session_dir.join("session.json"))?;
+        let base = self.adjust_base.commit.as_deref();
+        self.undo_stack.save(&self.session_dir.join("undo.json"), base, self.store.key())
+    }
+
+    /// Notes a change worth saving the session for, such as a file switch.
+    pub fn note_session_change(&mut self) {
+        self.session_changed = Some(std::time::Instant::now());
+    }
+
+    /// Saves the session once no change was noted for `delay`, so a killed
+    /// terminal loses at most the last few moments without writing on every key.
+    pub fn save_session_debounced(&mut self, delay: std::time::Duration) {
+        if self.session_changed.is_some_and(|changed| changed.elapsed() >= delay) {
+            self.save_session();
+            self.session_changed = None;
+        }
+    }
+
+    /// Records the review data on the history ref when `review.history` is on.
+    pub fn record_history(&self) -> anyhow::Result<()> {
+        if !self.config.review.history || self.read_only {
+            return Ok(());
+        }
+        let snapshot = crate::git::sync::Snapshot {
+            annotations: self.store.load_annotations()?,
+            file_statuses: self.store.load_file_statuses()?,
+            base: self.adjust_base.commit.clone(),
+            changes: Vec::new(),
         };
-        let path = self.repo_root.join(".annotator/session.json");
-        let _ = session.save(&path);
+        let repo = crate::git::repo::open_repo(&self.repo_root)?;
+        crate::git::sync::record_history(&repo, &snapshot, self.store.key())?;
+        Ok(())
+    }
+
+    /// Notes user input for time tracking; call before handling the event.
+    pub fn track_input(&mut self) {
+        let file = self.current_file().map(|f| f.to_string());
+        if let Some(tracker) = &mut self.time_tracker {
+            tracker.input(std::time::Instant::now(), file.as_deref());
+        }
+    }
+
+    /// Writes tracked time to the store.
+    pub fn flush_time(&mut self) {
+        if let Some(tracker) = &mut self.time_tracker {
+            let entries = tracker.take_entries();
+            let result = self.store.append_time_entries(&entries);
+            self.record_write(result, PendingWrite::TimeEntries(entries));
+        }
     }
 
     pub fn ensure_cursor_visible(&mut self) {
@@ -275,23 +1982,110 @@ impl App {
         }
     }
 
+    /// Scrolls the view by `delta` lines without moving the cursor, unless it
+    /// would leave the view; then it stays on the first or last visible line.
+    pub fn scroll_view(&mut self, delta: i32) {
+        let max = self.total_lines().saturating_sub(1);
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta).min(max);
+        let first = self.scroll_offset + 1;
+        let last = self.scroll_offset + (self.viewport_height as u32).max(1);
+        if !(first..=last).contains(&self.cursor_line) {
+            self.move_cursor_to_line(self.cursor_line.clamp(first, last));
+        }
+    }
+
+    /// Scrolls so the cursor line is `row` rows below the top of the view.
+    pub fn scroll_cursor_to_row(&mut self, row: u32) {
+        self.scroll_offset = self.cursor_line.saturating_sub(1 + row);
+    }
+
+    /// Moves the cursor to `line`, kept inside the file, at the preferred
+    /// column as far as that line allows.
+    pub fn move_cursor_to_line(&mut self, line: u32) {
+        self.cursor_line = line.clamp(1, self.total_lines().max(1));
+        self.cursor_col = cursor::clamp(self.preferred_col, self.cursor_line_width(), self.config.ui.virtual_edit);
+    }
+
+    /// Moves the cursor to `col` on its line and makes that the preferred column.
+    pub fn move_cursor_to_col(&mut self, col: u32) {
+        self.cursor_col = cursor::clamp(col, self.cursor_line_width(), self.config.ui.virtual_edit);
+        self.preferred_col = self.cursor_col;
+    }
+
+    /// Moves the cursor right after the last character of its line. Moving
+    /// up or down afterwards keeps it at the end of each line.
+    pub fn move_cursor_to_line_end(&mut self) {
+        self.cursor_col = self.cursor_line_width();
+        self.preferred_col = cursor::LINE_END;
+    }
+
+    fn cursor_line_width(&self) -> u32 {
+        self.file_content
+            .get(self.cursor_line.saturating_sub(1) as usize)
+            .map_or(0, |line| cursor::line_width(line))
+    }
+
     pub fn total_lines(&self) -> u32 {
         self.file_content.len() as u32
     }
 
-    pub fn review_progress(&self) -> (usize, usize) {
-        let total = self.files.len();
-        let reviewed = self.files.iter().filter(|f| {
-            self.store.get_file_status(f).unwrap_or(FileStatus::Unreviewed) != FileStatus::Unreviewed
-        }).count();
-        (reviewed, total)
+    /// Reviewed and total file counts, and the reviewed fraction weighted
+    /// as configured in `ui.progress`.
+    pub fn review_progress(&self) -> (usize, usize, f64) {
+        let statuses = self.store.load_file_statuses().unwrap_or_default();
+        crate::tui::status_bar::review_progress(&self.files, &statuses, self.line_counts.as_ref())
     }
 }
 
-fn load_file_content(repo_root: &Path, relative_path: &str) -> Vec<String> {
-    let full = repo_root.join(relative_path);
-    match std::fs::read_to_string(&full) {
-        Ok(content) => content.lines().map(|l| l.to_string()).collect(),
-        Err(_) => vec!["[Error reading file]".to_string()],
+/// Adjustment running on another thread, yielding the adjusted annotations.
+pub struct AdjustJob {
+    pub head: String,
+    pub progress: Progress,
+    handle: std::thread::JoinHandle<anyhow::Result<(Vec<Annotation>, HistoryAdjustment)>>,
+}
+
+/// Starts looking for binary files among `files` on another thread.
+fn spawn_binary_check(repo_root: &Path, files: &[String], progress: &Progress) -> std::thread::JoinHandle<HashSet<String>> {
+    let (root, files, progress) = (repo_root.to_path_buf(), files.to_vec(), progress.clone());
+    std::thread::spawn(move || crate::core::file_list::binary_files(&root, &files, &progress))
+}
+
+/// Line counts of `files`; unreadable files count as empty.
+fn count_lines(repo_root: &Path, rev: Option<&str>, files: &[String]) -> HashMap<String, usize> {
+    let repo = rev.and_then(|_| crate::git::repo::open_repo(repo_root).ok());
+    files
+        .iter()
+        .map(|f| {
+            let content = match (&repo, rev) {
+                (Some(repo), Some(rev)) => crate::git::repo::read_file_at(repo, rev, f).ok().flatten(),
+                _ => std::fs::read_to_string(repo_root.join(f)).ok(),
+            };
+            (f.clone(), content.map_or(0, |c| c.lines().count()))
+        })
+        .collect()
+}
+
+fn set_aside_message(set_aside: &[(PathBuf, usize)]) -> Option<String> {
+    let parts: Vec<String> = set_aside
+        .iter()
+        .map(|(path, count)| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            format!("{} malformed line(s) of {} moved to {}.corrupt", count, name, name)
+        })
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("; "))
+}
+
+fn load_file_content(repo_root: &Path, rev: Option<&str>, relative_path: &str) -> Vec<String> {
+    let content = match rev {
+        Some(rev) => crate::git::repo::open_repo(repo_root)
+            .and_then(|repo| crate::git::repo::read_file_at(&repo, rev, relative_path))
+            .ok()
+            .flatten(),
+        None => std::fs::read_to_string(repo_root.join(relative_path)).ok(),
+    };
+    match content {
+        Some(content) => content.lines().map(|l| l.to_string()).collect(),
+        None => vec!["[Error reading file]".to_string()],
     }
 }
@@ -0,0 +1,122 @@
+use crate::core::annotation::Annotation;
+use crate::tui::app::BrowserSort;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Every annotation of the review, in files inside or outside it, to jump
+/// back to one.
+pub struct BrowserPopup<'a> {
+    /// Annotations in the order they are listed.
+    pub annotations: &'a [&'a Annotation],
+    pub sort: BrowserSort,
+    pub selected: usize,
+    pub palette: &'a Palette,
+    pub glyphs: &'a Glyphs,
+}
+
+/// Positions in `annotations` in the order `sort` lists them.
+pub fn sorted(annotations: &[Annotation], sort: BrowserSort) -> Vec<usize> {
+    let mut entries: Vec<usize> = (0..annotations.len()).collect();
+    let by_location = |a: &Annotation| (a.file_path.clone(), a.start_line);
+    match sort {
+        BrowserSort::File => entries.sort_by_cached_key(|&i| by_location(&annotations[i])),
+        BrowserSort::Created => entries.sort_by_key(|&i| std::cmp::Reverse(annotations[i].created_at)),
+        BrowserSort::Severity => entries.sort_by_cached_key(|&i| {
+            let a = &annotations[i];
+            (a.severity.is_none(), a.severity, by_location(a))
+        }),
+    }
+    entries
+}
+
+impl<'a> Widget for BrowserPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(100);
+        let popup_height = area.height.min(self.annotations.len() as u16 + 4);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let title = format!(" Annotations ({}) by {} ", self.annotations.len(), self.sort.as_str());
+        let title: String = title.chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let max_items = popup.height as usize - 3;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, a) in self.annotations.iter().enumerate().skip(scroll).take(max_items) {
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let date = a.created_at.format("%Y-%m-%d").to_string();
+            let severity = a.severity.map_or("", |s| s.as_str());
+            let location = format!("{}:{}", a.file_path, a.start_line);
+            let text = a.text.lines().next().unwrap_or_default();
+            let display: String = format!("{} {:<7} {}  {}", date, severity, location, text)
+                .chars()
+                .take(inner_width)
+                .collect();
+            let row = popup.y + 1 + (i - scroll) as u16;
+            buf.set_string(popup.x + 2, row, &display, style);
+            // The severity in the annotation's color, after the date
+            let severity: String = severity.chars().take(inner_width.saturating_sub(date.len() + 1)).collect();
+            if !severity.is_empty() {
+                let column = popup.x + 2 + date.len() as u16 + 1;
+                buf.set_string(column, row, &severity, style.fg(self.palette.accent(a)));
+            }
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: open │ Tab: sort │ Esc: close")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_by_each_order() {
+        let mut annotations = vec![
+            Annotation::new("b.txt".into(), 2, 2, "nit: later".into()),
+            Annotation::new("a.txt".into(), 4, 4, "blocker: worst".into()),
+            Annotation::new("a.txt".into(), 1, 1, "plain".into()),
+            Annotation::new("a.txt".into(), 3, 3, "minor: typo".into()),
+        ];
+        for (i, a) in annotations.iter_mut().enumerate() {
+            a.created_at += chrono::Duration::seconds(i as i64);
+        }
+        assert_eq!(sorted(&annotations, BrowserSort::File), vec![2, 3, 1, 0]);
+        assert_eq!(sorted(&annotations, BrowserSort::Created), vec![3, 2, 1, 0]);
+        // Unlabeled annotations come last
+        assert_eq!(sorted(&annotations, BrowserSort::Severity), vec![1, 3, 0, 2]);
+        assert!(sorted(&[], BrowserSort::File).is_empty());
+    }
+}
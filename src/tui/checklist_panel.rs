@@ -0,0 +1,63 @@
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// The team checklist for the current file, along the right edge of the viewer.
+pub struct ChecklistPanel<'a> {
+    pub items: &'a [String],
+    pub checked: &'a [String],
+    pub selected: usize,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for ChecklistPanel<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let panel_width = area.width.min(44);
+        let panel_height = area.height.min(self.items.len() as u16 + 4);
+        if panel_width < 4 || panel_height < 4 {
+            return;
+        }
+        let panel = Rect::new(area.x + area.width - panel_width, area.y, panel_width, panel_height);
+
+        for py in panel.y..panel.y + panel.height {
+            for px in panel.x..panel.x + panel.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(panel.width);
+        let bottom = self.glyphs.frame_bottom(panel.width);
+        buf.set_string(panel.x, panel.y, &top, border_style);
+        buf.set_string(panel.x, panel.y + panel.height - 1, &bottom, border_style);
+        for py in panel.y + 1..panel.y + panel.height - 1 {
+            buf.set_string(panel.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(panel.x + panel.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let done = self.items.iter().filter(|item| self.checked.contains(item)).count();
+        let title = format!(" Checklist {}/{} ", done, self.items.len());
+        buf.set_string(panel.x + 2, panel.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = panel.width.saturating_sub(4) as usize;
+        let max_items = panel.height as usize - 3;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, item) in self.items.iter().enumerate().skip(scroll).take(max_items) {
+            let mark = if self.checked.contains(item) { "[x]" } else { "[ ]" };
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let display: String = format!("{} {}", mark, item).chars().take(inner_width).collect();
+            buf.set_string(panel.x + 2, panel.y + 1 + (i - scroll) as u16, &display, style);
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Space: tick │ Esc: close")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(panel.x + 2, panel.y + panel.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
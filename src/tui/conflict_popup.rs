@@ -1,3 +1,4 @@
+use crate::tui::glyphs::Glyphs;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
@@ -17,6 +18,7 @@ pub struct ConflictPopup<'a> {
     pub annotation_text: &'a str,
     pub deleted_lines: &'a [u32],
     pub selected_choice: ConflictChoice,
+    pub glyphs: &'a Glyphs,
 }
 
 impl<'a> Widget for ConflictPopup<'a> {
@@ -26,6 +28,10 @@ impl<'a> Widget for ConflictPopup<'a> {
 
         let popup_width = area.width.min(70);
         let popup_height = area.height.min(15);
+        // Rows are laid out down to y + 8, plus the bottom border
+        if popup_width < 8 || popup_height < 10 {
+            return;
+        }
         let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
         let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
         let popup = Rect::new(x, y, popup_width, popup_height);
@@ -38,13 +44,13 @@ impl<'a> Widget for ConflictPopup<'a> {
         }
 
         // Border
-        let top = format!("┌{}┐", "─".repeat(popup.width.saturating_sub(2) as usize));
-        let bottom = format!("└{}┘", "─".repeat(popup.width.saturating_sub(2) as usize));
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
         buf.set_string(popup.x, popup.y, &top, border_style);
         buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
         for py in popup.y + 1..popup.y + popup.height - 1 {
-            buf.set_string(popup.x, py, "│", border_style);
-            buf.set_string(popup.x + popup.width - 1, py, "│", border_style);
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
         }
 
         buf.set_string(
@@ -88,11 +94,11 @@ impl<'a> Widget for ConflictPopup<'a> {
             } else {
                 bg
             };
-            let prefix = if *choice == self.selected_choice { "▸ " } else { "  " };
+            let prefix = if *choice == self.selected_choice { self.glyphs.pointer } else { " " };
             buf.set_string(
                 popup.x + 2,
                 popup.y + 6 + i as u16,
-                format!("{}{}", prefix, label),
+                format!("{} {}", prefix, label),
                 style,
             );
         }
@@ -0,0 +1,41 @@
+//! Column rules of the viewer cursor, shared by plain movement and selection.
+
+/// Preferred column after End, so moving between lines keeps to their ends.
+pub const LINE_END: u32 = u32::MAX;
+
+/// Width of `line` in cursor columns, one per character as the viewer draws it.
+pub fn line_width(line: &str) -> u32 {
+    line.chars().count() as u32
+}
+
+/// Column the cursor lands on in a line `width` columns wide when it wants to
+/// be at `preferred`. Without virtual editing it stops right after the last
+/// character.
+pub fn clamp(preferred: u32, width: u32, virtual_edit: bool) -> u32 {
+    if preferred == LINE_END {
+        width
+    } else if virtual_edit {
+        preferred
+    } else {
+        preferred.min(width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_to_line() {
+        assert_eq!(clamp(10, 4, false), 4);
+        assert_eq!(clamp(2, 4, false), 2);
+        assert_eq!(clamp(10, 4, true), 10);
+        assert_eq!(clamp(LINE_END, 4, true), 4);
+    }
+
+    #[test]
+    fn test_width_counts_characters() {
+        assert_eq!(line_width("héllo"), 5);
+        assert_eq!(line_width(""), 0);
+    }
+}
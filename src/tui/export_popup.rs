@@ -0,0 +1,69 @@
+use crate::export::Exporter;
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Formats the review can be exported to, with the file each one writes.
+pub struct ExportPopup<'a> {
+    pub exporters: &'a [&'a dyn Exporter],
+    pub selected: usize,
+    /// Format set by `export.format`, marked in the list.
+    pub default: &'a str,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for ExportPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(48);
+        let popup_height = area.height.min(self.exporters.len() as u16 + 4);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let title: String = " Export ".chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let max_items = popup.height as usize - 3;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, exporter) in self.exporters.iter().enumerate().skip(scroll).take(max_items) {
+            let mark = if exporter.name() == self.default { self.glyphs.pointer } else { " " };
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let display: String = format!("{} {:<10} {}", mark, exporter.name(), crate::export::file_name(*exporter))
+                .chars()
+                .take(inner_width)
+                .collect();
+            buf.set_string(popup.x + 2, popup.y + 1 + (i - scroll) as u16, &display, style);
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: export │ Esc: close")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
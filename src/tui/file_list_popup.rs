@@ -1,30 +1,43 @@
 use crate::core::annotation::FileStatus;
 use crate::core::store::Store;
+use crate::tui::glyphs::Glyphs;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::Widget;
+use std::collections::HashMap;
 
 pub struct FileListPopup<'a> {
     pub files: &'a [String],
     pub filter: &'a str,
     pub selected: usize,
+    /// Labels of every labeled file, see `App::open_file_list`.
+    pub labels: &'a HashMap<String, Vec<String>>,
     pub store: &'a Store,
+    pub glyphs: &'a Glyphs,
 }
 
 impl<'a> FileListPopup<'a> {
+    /// Files matching the filter: a glob, or text the path contains, and
+    /// `@label` words the file must carry a label starting with.
     pub fn filtered_files(&self) -> Vec<(usize, &'a String)> {
+        let (wanted, rest): (Vec<&str>, Vec<&str>) = self.filter.split_whitespace().partition(|w| w.starts_with('@'));
+        let text = rest.join(" ");
         self.files
             .iter()
             .enumerate()
             .filter(|(_, f)| {
-                if self.filter.is_empty() {
+                let has = self.labels.get(f.as_str()).map_or(&[][..], |l| l.as_slice());
+                wanted.iter().all(|w| has.iter().any(|l| l.starts_with(&w[1..])))
+            })
+            .filter(|(_, f)| {
+                if text.is_empty() {
                     return true;
                 }
-                let pattern = glob::Pattern::new(self.filter);
+                let pattern = glob::Pattern::new(&text);
                 match pattern {
                     Ok(p) => p.matches(f),
-                    Err(_) => f.contains(self.filter),
+                    Err(_) => f.contains(&text),
                 }
             })
             .collect()
@@ -35,6 +48,9 @@ impl<'a> Widget for FileListPopup<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
         let border_style = Style::default().fg(Color::Cyan);
+        if area.width < 4 || area.height < 4 {
+            return;
+        }
 
         // Clear area
         for y in area.y..area.y + area.height {
@@ -44,13 +60,13 @@ impl<'a> Widget for FileListPopup<'a> {
         }
 
         // Border
-        let top = format!("┌{}┐", "─".repeat(area.width.saturating_sub(2) as usize));
-        let bottom = format!("└{}┘", "─".repeat(area.width.saturating_sub(2) as usize));
+        let top = self.glyphs.frame_top(area.width);
+        let bottom = self.glyphs.frame_bottom(area.width);
         buf.set_string(area.x, area.y, &top, border_style);
         buf.set_string(area.x, area.y + area.height - 1, &bottom, border_style);
         for y in area.y + 1..area.y + area.height - 1 {
-            buf.set_string(area.x, y, "│", border_style);
-            buf.set_string(area.x + area.width - 1, y, "│", border_style);
+            buf.set_string(area.x, y, self.glyphs.vertical, border_style);
+            buf.set_string(area.x + area.width - 1, y, self.glyphs.vertical, border_style);
         }
 
         // Title
@@ -96,20 +112,63 @@ impl<'a> Widget for FileListPopup<'a> {
             };
 
             let inner_width = area.width.saturating_sub(4) as usize;
-            let entry = format!("{} {}", icon, file);
+            let mut entry = format!("{} {}", icon, file);
+            for label in self.labels.get(file.as_str()).into_iter().flatten() {
+                entry.push_str(&format!(" @{}", label));
+            }
             let display: String = entry.chars().take(inner_width).collect();
             buf.set_string(area.x + 2, list_start + i as u16, &display, style);
         }
 
         // Help
         if area.height >= 5 {
-            let help = "Enter: open │ Esc: close │ Type to filter";
+            let help: String = self
+                .glyphs
+                .localize("Enter: open │ Esc: close │ Type to filter, @label for labels")
+                .chars()
+                .take(area.width.saturating_sub(4) as usize)
+                .collect();
             buf.set_string(
                 area.x + 2,
                 area.y + area.height - 2,
-                help,
+                &help,
                 Style::default().fg(Color::DarkGray).bg(Color::Rgb(30, 34, 42)),
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_by_text_and_labels() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Store::new(dir.path());
+        let files = vec!["src/auth.rs".to_string(), "src/main.rs".to_string(), "README.md".to_string()];
+        let labels = HashMap::from([
+            ("src/auth.rs".to_string(), vec!["security-sensitive".to_string()]),
+            ("README.md".to_string(), vec!["docs".to_string(), "security-sensitive".to_string()]),
+        ]);
+        let found = |filter: &str| -> Vec<usize> {
+            let popup = FileListPopup {
+                files: &files,
+                filter,
+                selected: 0,
+                labels: &labels,
+                store: &store,
+                glyphs: &crate::tui::glyphs::UNICODE,
+            };
+            popup.filtered_files().into_iter().map(|(i, _)| i).collect()
+        };
+        assert_eq!(found(""), vec![0, 1, 2]);
+        assert_eq!(found("*main*"), vec![1]);
+        assert_eq!(found("src/*"), vec![0, 1]);
+        // Labels match by prefix, and every one given must be carried
+        assert_eq!(found("@sec"), vec![0, 2]);
+        assert_eq!(found("@sec @doc"), vec![2]);
+        assert_eq!(found("@sec src/*"), vec![0]);
+        assert!(found("@none").is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+/// Characters used to draw UI chrome, so accessible mode can swap in plain ASCII.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    pub horizontal: &'static str,
+    pub vertical: &'static str,
+    pub top_left: &'static str,
+    pub top_right: &'static str,
+    pub bottom_left: &'static str,
+    pub bottom_right: &'static str,
+    pub expanded: &'static str,
+    pub collapsed: &'static str,
+    pub pointer: &'static str,
+    pub clean: &'static str,
+    pub dash: &'static str,
+    pub dot: &'static str,
+    pub bar_full: &'static str,
+    pub bar_empty: &'static str,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    horizontal: "─",
+    vertical: "│",
+    top_left: "┌",
+    top_right: "┐",
+    bottom_left: "└",
+    bottom_right: "┘",
+    expanded: "▾",
+    collapsed: "▸",
+    pointer: "▸",
+    clean: "✓",
+    dash: "—",
+    dot: "·",
+    bar_full: "█",
+    bar_empty: "░",
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    horizontal: "-",
+    vertical: "|",
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    expanded: "v",
+    collapsed: ">",
+    pointer: ">",
+    clean: "*",
+    dash: "-",
+    dot: "-",
+    bar_full: "#",
+    bar_empty: ".",
+};
+
+impl Glyphs {
+    pub fn get(ascii: bool) -> &'static Glyphs {
+        if ascii { &ASCII } else { &UNICODE }
+    }
+
+    pub fn rule(&self, width: u16) -> String {
+        self.horizontal.repeat(width as usize)
+    }
+
+    pub fn frame_top(&self, width: u16) -> String {
+        format!("{}{}{}", self.top_left, self.rule(width.saturating_sub(2)), self.top_right)
+    }
+
+    pub fn frame_bottom(&self, width: u16) -> String {
+        format!("{}{}{}", self.bottom_left, self.rule(width.saturating_sub(2)), self.bottom_right)
+    }
+
+    /// A bar `width` cells wide, filled to `fraction` (0.0 to 1.0).
+    pub fn progress_bar(&self, fraction: f64, width: usize) -> String {
+        let filled = ((fraction.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+        format!("{}{}", self.bar_full.repeat(filled), self.bar_empty.repeat(width - filled))
+    }
+
+    /// Rewrites UI text (status messages, hints) that was written with Unicode glyphs.
+    pub fn localize(&self, text: &str) -> String {
+        if *self == UNICODE {
+            return text.to_string();
+        }
+        text.replace(UNICODE.dash, self.dash)
+            .replace(UNICODE.vertical, self.vertical)
+            .replace(UNICODE.dot, self.dot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(ASCII.progress_bar(0.0, 4), "....");
+        assert_eq!(ASCII.progress_bar(0.5, 4), "##..");
+        assert_eq!(UNICODE.progress_bar(1.5, 3), "███");
+    }
+}
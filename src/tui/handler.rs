@@ -0,0 +1,634 @@
+//! Key handling shared by the interactive TUI and headless scripts.
+
+use crate::core::heatmap::heatmap;
+use crate::tui::app::{App, AppMode};
+use crate::tui::keymap::*;
+use crate::tui::selection::Selection;
+use crate::tui::text_input;
+use crate::tui::tree_view::TreeNode;
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    // The adjustment replaces the annotations when it finishes, so nothing
+    // may change them meanwhile
+    if app.adjusting.is_some() {
+        if key.code == KeyCode::Esc {
+            app.cancel_background();
+        }
+        return;
+    }
+    // Macro controls are handled before recording so they never end up in a macro
+    if app.mode == AppMode::Viewing {
+        match app.key_bindings.viewing_action(key, app.config.ui.keymap) {
+            Some(Action::RecordMacro) => {
+                app.toggle_macro_recording();
+                return;
+            }
+            Some(Action::ReplayMacro) => {
+                replay_macro(app);
+                return;
+            }
+            Some(Action::CountDigit(_)) => {}
+            _ => app.macros.count = None,
+        }
+    }
+    app.macros.record(key);
+
+    match app.mode {
+        AppMode::Viewing => {
+            let action = if std::mem::take(&mut app.scroll_prefix) {
+                map_key_after_scroll_prefix(key)
+            } else {
+                app.key_bindings.viewing_action(key, app.config.ui.keymap)
+            };
+            if let Some(action) = action {
+                handle_viewing_action(app, action);
+            }
+        }
+        AppMode::AnnotationInput | AppMode::AnnotationEdit => {
+            if let Some(action) = map_key_input(key) {
+                handle_input_action(app, action);
+            }
+        }
+        AppMode::FileList => {
+            if let Some(action) = map_key_file_list(key) {
+                handle_file_list_action(app, action);
+            }
+        }
+        AppMode::TreeView => {
+            if let Some(action) = map_key_tree(key) {
+                handle_tree_action(app, action);
+            }
+        }
+        AppMode::ConflictResolution => {
+            if let Some(action) = map_key_conflict(key) {
+                handle_conflict_action(app, action);
+            }
+        }
+        AppMode::Resume => handle_resume_action(app, map_key_resume(key)),
+        AppMode::UnsavedQuit => handle_unsaved_action(app, map_key_unsaved(key)),
+        AppMode::AnnotationView | AppMode::MessageLog => app.mode = AppMode::Viewing,
+        AppMode::SyntaxPicker => {
+            if let Some(action) = map_key_syntax(key) {
+                handle_syntax_action(app, action);
+            }
+        }
+        AppMode::SkippedList => {
+            if let Some(action) = map_key_skipped(key) {
+                handle_skipped_action(app, action);
+            }
+        }
+        AppMode::Checklist => {
+            if let Some(action) = map_key_checklist(key) {
+                handle_checklist_action(app, action);
+            }
+        }
+        AppMode::AnnotationBrowser => {
+            if let Some(action) = map_key_browser(key) {
+                handle_browser_action(app, action);
+            }
+        }
+        AppMode::Heatmap => {
+            if let Some(action) = map_key_heatmap(key) {
+                handle_heatmap_action(app, action);
+            }
+        }
+        AppMode::ExportPicker => {
+            if let Some(action) = map_key_export(key) {
+                handle_export_action(app, action);
+            }
+        }
+        AppMode::AnnotationList => {
+            if let Some(action) = map_key_annotation_list(key) {
+                handle_annotation_list_action(app, action);
+            }
+        }
+        AppMode::Search => {
+            if let Some(action) = map_key_search(key) {
+                handle_search_action(app, action);
+            }
+        }
+        AppMode::Setup => {
+            if let Some(action) = map_key_setup(key) {
+                handle_setup_action(app, action);
+            }
+        }
+    }
+}
+
+fn replay_macro(app: &mut App) {
+    let count = app.macros.take_count();
+    if app.macros.is_recording() {
+        app.set_status("Stop recording (^K) before replaying");
+        return;
+    }
+    let keys = app.macros.recorded().to_vec();
+    if keys.is_empty() {
+        app.set_status("No macro recorded — ^K to start recording");
+        return;
+    }
+
+    for _ in 0..count {
+        for key in &keys {
+            handle_key(app, *key);
+            if app.should_quit {
+                return;
+            }
+        }
+    }
+    app.set_status(format!("Replayed macro {} time(s)", count));
+}
+
+fn handle_viewing_action(app: &mut App, action: Action) {
+    if action.edits_review() && app.refuse_if_read_only() {
+        return;
+    }
+    app.status_message = None;
+
+    match action {
+        Action::CursorUp => {
+            app.move_cursor_to_line(app.cursor_line.saturating_sub(1));
+            app.selection = None;
+            app.ensure_cursor_visible();
+        }
+        Action::CursorDown => {
+            app.move_cursor_to_line(app.cursor_line + 1);
+            app.selection = None;
+            app.ensure_cursor_visible();
+        }
+        Action::CursorLeft => {
+            app.move_cursor_to_col(app.cursor_col.saturating_sub(1));
+            app.selection = None;
+        }
+        Action::CursorRight => {
+            app.move_cursor_to_col(app.cursor_col.saturating_add(1));
+            app.selection = None;
+        }
+        Action::PageUp => {
+            let page = app.viewport_height as u32;
+            app.move_cursor_to_line(app.cursor_line.saturating_sub(page));
+            app.scroll_offset = app.scroll_offset.saturating_sub(page);
+            app.selection = None;
+        }
+        Action::PageDown => {
+            let page = app.viewport_height as u32;
+            let max = app.total_lines().max(1);
+            app.move_cursor_to_line(app.cursor_line + page);
+            app.scroll_offset = (app.scroll_offset + page).min(max.saturating_sub(1));
+            app.selection = None;
+        }
+        Action::ScrollUp => app.scroll_view(-1),
+        Action::ScrollDown => app.scroll_view(1),
+        Action::ScrollPrefix => app.scroll_prefix = true,
+        Action::CenterCursorLine => app.scroll_cursor_to_row(app.viewport_height as u32 / 2),
+        Action::CursorLineToTop => app.scroll_cursor_to_row(0),
+        Action::CursorLineToBottom => app.scroll_cursor_to_row((app.viewport_height as u32).saturating_sub(1)),
+        Action::Home => app.move_cursor_to_col(0),
+        Action::End => app.move_cursor_to_line_end(),
+        Action::SelectUp => select_to(app, |app| app.move_cursor_to_line(app.cursor_line.saturating_sub(1))),
+        Action::SelectDown => select_to(app, |app| app.move_cursor_to_line(app.cursor_line + 1)),
+        Action::SelectLeft => select_to(app, |app| app.move_cursor_to_col(app.cursor_col.saturating_sub(1))),
+        Action::SelectRight => select_to(app, |app| app.move_cursor_to_col(app.cursor_col.saturating_add(1))),
+        Action::CreateAnnotation => {
+            // If cursor is on an annotated line, edit it; otherwise create new
+            if let Some(idx) = app.cursor_annotation() {
+                let ann = &app.annotations[idx];
+                app.editing_annotation_id = Some(ann.id);
+                app.annotation_input = ann.text.clone();
+                app.annotation_input_cursor = ann.text.len();
+                app.mode = AppMode::AnnotationEdit;
+            } else if app.total_lines() == 0 {
+                app.set_status("Nothing to annotate here");
+            } else {
+                app.mode = AppMode::AnnotationInput;
+                app.annotation_input.clear();
+                app.annotation_input_cursor = 0;
+            }
+        }
+        Action::EditAnnotation => {
+            if let Some(idx) = app.cursor_annotation() {
+                let ann = &app.annotations[idx];
+                app.editing_annotation_id = Some(ann.id);
+                app.annotation_input = ann.text.clone();
+                app.annotation_input_cursor = ann.text.len();
+                app.mode = AppMode::AnnotationEdit;
+            }
+        }
+        Action::DeleteAnnotation => {
+            let had = app.annotations.len();
+            app.delete_annotation_at_cursor();
+            if app.annotations.len() < had {
+                app.set_status("Annotation deleted");
+            }
+        }
+        Action::SplitAnnotation => app.split_annotation_at_cursor(),
+        Action::JoinAnnotation => app.join_annotation_at_cursor(),
+        Action::MoveAnnotation { start, end } => app.move_annotation_at_cursor(start, end),
+        Action::BumpPriority(delta) => app.bump_priority(delta),
+        Action::ReanchorAnnotation => app.reanchor_annotation(),
+        Action::LinkAnnotation => app.link_annotation(),
+        Action::FollowLink => app.follow_link(),
+        Action::CopyAnnotation => app.copy_annotation_at_cursor(),
+        Action::PasteAnnotation => app.paste_annotation(),
+        Action::MarkClean => app.mark_file_clean(),
+        Action::NextUnreviewed => app.next_unreviewed_file(),
+        Action::SkipFile => app.toggle_skip_file(),
+        Action::OpenSkippedList => app.open_skipped_list(),
+        Action::OpenSearch => app.open_search(),
+        Action::ToggleAnnotationList => app.open_annotation_list(),
+        Action::OpenBrowser => app.open_annotation_browser(),
+        Action::OpenHeatmap => app.open_heatmap(),
+        Action::OpenExport => app.open_export_picker(),
+        Action::Verdict(n) => app.annotate_with_verdict(n),
+        Action::OpenChecklist => app.open_checklist(),
+        Action::CycleTagFilter => app.cycle_tag_filter(),
+        Action::PickSyntax => app.open_syntax_picker(),
+        Action::OpenFileList => app.open_file_list(),
+        Action::OpenTreeView => {
+            app.mode = AppMode::TreeView;
+            app.tree_selected = 0;
+        }
+        Action::AdjustToHead => app.adjust_to_pending_head(),
+        Action::Cancel => {
+            app.cancel_background();
+        }
+        Action::MergeDuplicates => app.merge_duplicates(),
+        Action::ShowFullAnnotation => app.show_full_annotation(),
+        Action::ShowMessages => app.open_message_log(),
+        Action::CycleAnnotation => app.cycle_annotation(),
+        Action::CountDigit(digit) => {
+            app.macros.push_count_digit(digit);
+            if let Some(count) = app.macros.count {
+                app.set_status(format!("Replay count: {}", count));
+            }
+        }
+        Action::GrowPanel => app.resize_panel(1),
+        Action::ShrinkPanel => app.resize_panel(-1),
+        Action::Undo => app.apply_undo(),
+        Action::Redo => app.apply_redo(),
+        Action::Quit => app.request_quit(),
+        _ => {}
+    }
+}
+
+/// Moves the cursor with `step`, extending the selection from where the
+/// cursor was, or starting one there.
+fn select_to(app: &mut App, step: impl FnOnce(&mut App)) {
+    let anchor = Selection::new(app.cursor_line, app.cursor_col);
+    step(app);
+    let (line, col) = (app.cursor_line, app.cursor_col);
+    app.selection.get_or_insert(anchor).extend_to(line, col);
+    app.ensure_cursor_visible();
+}
+
+fn handle_input_action(app: &mut App, action: Action) {
+    match action {
+        Action::Confirm => {
+            if app.mode == AppMode::AnnotationEdit {
+                app.update_annotation();
+            } else {
+                app.create_annotation();
+            }
+        }
+        Action::Cancel => {
+            app.mode = AppMode::Viewing;
+            app.annotation_input.clear();
+            app.annotation_input_cursor = 0;
+            app.editing_annotation_id = None;
+        }
+        Action::InputChar(c) => {
+            app.annotation_input.insert(app.annotation_input_cursor, c);
+            app.annotation_input_cursor += c.len_utf8();
+        }
+        Action::InputBackspace if app.annotation_input_cursor > 0 => {
+            let prev = app.annotation_input[..app.annotation_input_cursor]
+                .chars()
+                .last()
+                .map(|c| c.len_utf8())
+                .unwrap_or(0);
+            app.annotation_input_cursor -= prev;
+            app.annotation_input.remove(app.annotation_input_cursor);
+        }
+        Action::InputDelete if app.annotation_input_cursor < app.annotation_input.len() => {
+            app.annotation_input.remove(app.annotation_input_cursor);
+        }
+        Action::InputNewline => {
+            app.annotation_input.insert(app.annotation_input_cursor, '\n');
+            app.annotation_input_cursor += 1;
+        }
+        Action::CursorLeft | Action::CursorRight | Action::CursorUp | Action::CursorDown | Action::Home | Action::End => {
+            let (text, pos) = (app.annotation_input.as_str(), app.annotation_input_cursor);
+            app.annotation_input_cursor = match action {
+                Action::CursorLeft => text_input::left(text, pos),
+                Action::CursorRight => text_input::right(text, pos),
+                Action::CursorUp => text_input::up(text, pos),
+                Action::CursorDown => text_input::down(text, pos),
+                Action::Home => text_input::line_start(text, pos),
+                _ => text_input::line_end(text, pos),
+            };
+        }
+        _ => {}
+    }
+}
+
+fn handle_file_list_action(app: &mut App, action: Action) {
+    let popup = crate::tui::file_list_popup::FileListPopup {
+        files: &app.files,
+        filter: &app.file_list_filter,
+        selected: app.file_list_selected,
+        labels: &app.file_list_labels,
+        store: &app.store,
+        glyphs: app.glyphs(),
+    };
+
+    match action {
+        Action::Cancel => {
+            app.mode = AppMode::Viewing;
+        }
+        Action::Confirm => {
+            let filtered = popup.filtered_files();
+            if let Some((orig_idx, _)) = filtered.get(app.file_list_selected) {
+                app.switch_to_file(*orig_idx);
+            }
+            app.mode = AppMode::Viewing;
+        }
+        Action::CursorUp => {
+            app.file_list_selected = app.file_list_selected.saturating_sub(1);
+        }
+        Action::CursorDown => {
+            let filtered = popup.filtered_files();
+            app.file_list_selected = (app.file_list_selected + 1).min(filtered.len().saturating_sub(1));
+        }
+        Action::InputChar(c) => {
+            app.file_list_filter.push(c);
+            app.file_list_selected = 0;
+        }
+        Action::InputBackspace => {
+            app.file_list_filter.pop();
+            app.file_list_selected = 0;
+        }
+        _ => {}
+    }
+}
+
+fn handle_tree_action(app: &mut App, action: Action) {
+    let tree = TreeNode::build(&app.files);
+    let items = tree.flatten(&app.tree_expanded, "", app.glyphs());
+
+    match action {
+        Action::Cancel => {
+            app.mode = AppMode::Viewing;
+        }
+        Action::Confirm => {
+            if let Some((_, path, is_dir)) = items.get(app.tree_selected) {
+                if *is_dir {
+                    if app.tree_expanded.contains(path) {
+                        app.tree_expanded.remove(path);
+                    } else {
+                        app.tree_expanded.insert(path.clone());
+                    }
+                } else {
+                    if let Some(idx) = app.files.iter().position(|f| f == path) {
+                        app.switch_to_file(idx);
+                    }
+                    app.mode = AppMode::Viewing;
+                }
+            }
+        }
+        Action::CursorUp => {
+            app.tree_selected = app.tree_selected.saturating_sub(1);
+        }
+        Action::CursorDown => {
+            app.tree_selected = (app.tree_selected + 1).min(items.len().saturating_sub(1));
+        }
+        Action::MarkClean | Action::Undo if app.refuse_if_read_only() => {}
+        Action::MarkClean => {
+            if let Some((_, path, is_dir)) = items.get(app.tree_selected) {
+                if *is_dir {
+                    app.mark_directory_clean(path);
+                } else {
+                    app.mark_path_clean(path);
+                }
+            }
+        }
+        Action::Undo => app.apply_undo(),
+        _ => {}
+    }
+}
+
+fn handle_syntax_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            let choice = app.filtered_syntaxes().get(app.syntax_selected).map(|s| s.map(str::to_string));
+            app.mode = AppMode::Viewing;
+            if let Some(syntax) = choice {
+                app.set_file_syntax(syntax);
+            }
+        }
+        Action::CursorUp => app.syntax_selected = app.syntax_selected.saturating_sub(1),
+        Action::CursorDown => {
+            app.syntax_selected = (app.syntax_selected + 1).min(app.filtered_syntaxes().len().saturating_sub(1));
+        }
+        Action::InputChar(c) => {
+            app.syntax_filter.push(c);
+            app.syntax_selected = 0;
+        }
+        Action::InputBackspace => {
+            app.syntax_filter.pop();
+            app.syntax_selected = 0;
+        }
+        _ => {}
+    }
+}
+
+fn handle_browser_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            let choice = app.browser_entries().get(app.browser_selected).copied();
+            app.mode = AppMode::Viewing;
+            if let Some(idx) = choice {
+                app.go_to_annotation(idx);
+            }
+        }
+        Action::CursorUp => app.browser_selected = app.browser_selected.saturating_sub(1),
+        Action::CursorDown => {
+            app.browser_selected = (app.browser_selected + 1).min(app.annotations.len().saturating_sub(1));
+        }
+        Action::PageUp => app.browser_selected = app.browser_selected.saturating_sub(app.viewport_height as usize),
+        Action::PageDown => {
+            let page = app.viewport_height as usize;
+            app.browser_selected = (app.browser_selected + page).min(app.annotations.len().saturating_sub(1));
+        }
+        Action::CycleSort => {
+            // Stay on the selected annotation in the new order
+            let selected = app.browser_entries().get(app.browser_selected).copied();
+            app.browser_sort = app.browser_sort.next();
+            app.browser_selected = selected
+                .and_then(|idx| app.browser_entries().iter().position(|&i| i == idx))
+                .unwrap_or(0);
+        }
+        _ => {}
+    }
+}
+
+fn handle_heatmap_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            app.mode = AppMode::Viewing;
+            app.go_to_heatmap_file();
+        }
+        Action::CursorUp => app.heatmap_selected = app.heatmap_selected.saturating_sub(1),
+        Action::CursorDown => {
+            let files = heatmap(&app.annotations, &app.heatmap_lines).len();
+            app.heatmap_selected = (app.heatmap_selected + 1).min(files.saturating_sub(1));
+        }
+        _ => {}
+    }
+}
+
+fn handle_export_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            app.mode = AppMode::Viewing;
+            app.export_selected_format();
+        }
+        Action::CursorUp => app.export_selected = app.export_selected.saturating_sub(1),
+        Action::CursorDown => {
+            app.export_selected = (app.export_selected + 1).min(crate::export::EXPORTERS.len() - 1);
+        }
+        _ => {}
+    }
+}
+
+fn handle_annotation_list_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::ToggleAnnotationList => {
+            app.annotation_list = false;
+            app.mode = AppMode::Viewing;
+        }
+        Action::Confirm => {
+            app.mode = AppMode::Viewing;
+            app.go_to_listed_annotation();
+        }
+        Action::CursorUp => app.annotation_list_selected = app.annotation_list_selected.saturating_sub(1),
+        Action::CursorDown => {
+            let count = app.annotation_list_entries().len();
+            app.annotation_list_selected = (app.annotation_list_selected + 1).min(count.saturating_sub(1));
+        }
+        _ => {}
+    }
+}
+
+fn handle_search_action(app: &mut App, action: Action) {
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            let choice = app.search_results().get(app.search_selected).copied();
+            if let Some(idx) = choice {
+                app.mode = AppMode::Viewing;
+                app.go_to_annotation(idx);
+            }
+        }
+        Action::CursorUp => app.search_selected = app.search_selected.saturating_sub(1),
+        Action::CursorDown => {
+            app.search_selected = (app.search_selected + 1).min(app.search_results().len().saturating_sub(1));
+        }
+        Action::InputChar(c) => {
+            app.search_query.push(c);
+            app.search_selected = 0;
+        }
+        Action::InputBackspace => {
+            app.search_query.pop();
+            app.search_selected = 0;
+        }
+        _ => {}
+    }
+}
+
+fn handle_skipped_action(app: &mut App, action: Action) {
+    let selected = app.session.skipped.get(app.skipped_selected).cloned();
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::Confirm => {
+            app.mode = AppMode::Viewing;
+            match selected.and_then(|file| app.files.iter().position(|f| *f == file)) {
+                Some(idx) => app.switch_to_file(idx),
+                None => app.set_status("That file is not part of this review"),
+            }
+        }
+        Action::CursorUp => app.skipped_selected = app.skipped_selected.saturating_sub(1),
+        Action::CursorDown => {
+            app.skipped_selected = (app.skipped_selected + 1).min(app.session.skipped.len().saturating_sub(1));
+        }
+        Action::SkipFile if app.refuse_if_read_only() => {}
+        Action::SkipFile => {
+            if let Some(file) = selected {
+                app.session.skipped.retain(|f| *f != file);
+                app.skipped_selected = app.skipped_selected.min(app.session.skipped.len().saturating_sub(1));
+                app.set_status(format!("{} is no longer skipped", file));
+                app.note_session_change();
+                if app.session.skipped.is_empty() {
+                    app.mode = AppMode::Viewing;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_resume_action(app: &mut App, action: Action) {
+    app.mode = AppMode::Viewing;
+    app.resume = None;
+    match action {
+        Action::NextUnreviewed => app.next_unreviewed_file(),
+        Action::NextConflict => app.next_conflict(),
+        _ => {}
+    }
+}
+
+fn handle_unsaved_action(app: &mut App, action: Action) {
+    app.mode = AppMode::Viewing;
+    match action {
+        Action::Confirm => app.request_quit(),
+        Action::Quit => app.should_quit = true,
+        _ => {}
+    }
+}
+
+fn handle_checklist_action(app: &mut App, action: Action) {
+    let count = app.checklist.as_ref().map_or(0, |c| c.items.len());
+    match action {
+        Action::Cancel => app.mode = AppMode::Viewing,
+        Action::CursorUp => app.checklist_selected = app.checklist_selected.saturating_sub(1),
+        Action::CursorDown => app.checklist_selected = (app.checklist_selected + 1).min(count.saturating_sub(1)),
+        Action::Confirm if app.refuse_if_read_only() => {}
+        Action::Confirm => app.toggle_checklist_item(),
+        _ => {}
+    }
+}
+
+fn handle_setup_action(app: &mut App, action: Action) {
+    let Some(form) = app.setup.as_mut() else {
+        app.mode = AppMode::Viewing;
+        return;
+    };
+    match action {
+        Action::InputChar(c) => form.input(c),
+        Action::InputBackspace => form.backspace(),
+        Action::CursorDown => form.next_field(),
+        Action::CursorUp => form.previous_field(),
+        Action::Confirm if !form.is_last_field() => form.next_field(),
+        Action::Confirm => app.finish_setup(),
+        Action::Cancel => app.skip_setup(),
+        _ => {}
+    }
+}
+
+fn handle_conflict_action(app: &mut App, _action: Action) {
+    app.mode = AppMode::Viewing;
+}
@@ -0,0 +1,96 @@
+use crate::core::heatmap::FileHeat;
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Cells of the bar drawn for the hottest file.
+const BAR_WIDTH: usize = 12;
+
+/// Annotated files, densest first, each with a bar as long and as red as its
+/// share of the densest file's heat per hundred lines, then its annotation
+/// count, heat and density.
+pub struct HeatmapPopup<'a> {
+    pub files: &'a [FileHeat<'a>],
+    pub selected: usize,
+    pub glyphs: &'a Glyphs,
+}
+
+/// From yellow for the coolest files to red for the hottest.
+fn shade(fraction: f64) -> Color {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let green = 220.0 - fraction * 190.0;
+    Color::Rgb(230, green as u8, 30)
+}
+
+impl<'a> Widget for HeatmapPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(90);
+        let popup_height = area.height.min(self.files.len() as u16 + 4);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let title = format!(" Heatmap ({} files; annotations, heat, per 100 lines) ", self.files.len());
+        let title: String = title.chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let densest = self.files.first().and_then(|f| f.density()).filter(|d| *d > 0.0);
+        let hottest = self.files.iter().map(|f| f.heat).max().unwrap_or(1).max(1);
+        let max_items = popup.height as usize - 3;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, file) in self.files.iter().enumerate().skip(scroll).take(max_items) {
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            // Files of unknown length are measured by heat alone
+            let fraction = match (file.density(), densest) {
+                (Some(density), Some(densest)) => density / densest,
+                _ => f64::from(file.heat) / f64::from(hottest),
+            };
+            let bar = self.glyphs.progress_bar(fraction, BAR_WIDTH);
+            let worst = file.worst.map_or("", |s| s.as_str());
+            let density = file.density().map_or("-".to_string(), |d| format!("{:.1}", d));
+            let display: String = format!(
+                "{} {:>3} {:>4} {:>6} {:<7} {}",
+                bar, file.annotations, file.heat, density, worst, file.path
+            )
+                .chars()
+                .take(inner_width)
+                .collect();
+            let row = popup.y + 1 + (i - scroll) as u16;
+            buf.set_string(popup.x + 2, row, &display, style);
+            // The bar in the file's shade
+            let bar: String = bar.chars().take(inner_width).collect();
+            buf.set_string(popup.x + 2, row, &bar, style.fg(shade(fraction)));
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: open │ Esc: close")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
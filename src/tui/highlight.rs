@@ -8,6 +8,7 @@ use syntect::util::LinesWithEndings;
 pub struct Highlighter {
     syntax_set: SyntaxSet,
     theme_set: ThemeSet,
+    theme: String,
 }
 
 impl Default for Highlighter {
@@ -21,18 +22,38 @@ impl Highlighter {
         Self {
             syntax_set: SyntaxSet::load_defaults_newlines(),
             theme_set: ThemeSet::load_defaults(),
+            theme: "base16-ocean.dark".into(),
         }
     }
 
-    pub fn highlight_lines(&self, content: &str, file_path: &str) -> Vec<Line<'static>> {
-        let syntax = self
-            .syntax_set
-            .find_syntax_for_file(file_path)
-            .ok()
-            .flatten()
+    /// Highlights with one of syntect's bundled themes.
+    pub fn with_theme(theme: &str) -> anyhow::Result<Self> {
+        let mut highlighter = Self::new();
+        if !highlighter.theme_set.themes.contains_key(theme) {
+            let available: Vec<&str> = highlighter.theme_set.themes.keys().map(|k| k.as_str()).collect();
+            anyhow::bail!("unknown theme '{}' (available: {})", theme, available.join(", "));
+        }
+        highlighter.theme = theme.to_string();
+        Ok(highlighter)
+    }
+
+    /// Names of the bundled syntaxes, sorted, for picking one by hand.
+    pub fn syntax_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.syntax_set.syntaxes().iter().map(|s| s.name.clone()).collect();
+        names.sort_by_key(|n| n.to_lowercase());
+        names.dedup();
+        names
+    }
+
+    /// Highlights `content` as the syntax named `syntax`, or as detected from
+    /// `file_path` when `None` or unknown.
+    pub fn highlight_lines(&self, content: &str, file_path: &str, syntax: Option<&str>) -> Vec<Line<'static>> {
+        let syntax = syntax
+            .and_then(|name| self.syntax_set.find_syntax_by_name(name))
+            .or_else(|| self.syntax_set.find_syntax_for_file(file_path).ok().flatten())
             .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
 
-        let theme = &self.theme_set.themes["base16-ocean.dark"];
+        let theme = &self.theme_set.themes[&self.theme];
         let mut highlighter = HighlightLines::new(syntax, theme);
         let mut result = Vec::new();
 
@@ -1,4 +1,7 @@
+use crate::core::config::KeymapProfile;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
@@ -11,6 +14,14 @@ pub enum Action {
     PageDown,
     Home,
     End,
+    /// Scroll the view a line, the cursor staying put while it is visible.
+    ScrollUp,
+    ScrollDown,
+    /// `z` pressed; the next key says where the cursor line goes on screen.
+    ScrollPrefix,
+    CenterCursorLine,
+    CursorLineToTop,
+    CursorLineToBottom,
 
     // Selection
     SelectUp,
@@ -22,12 +33,52 @@ pub enum Action {
     CreateAnnotation,
     EditAnnotation,
     DeleteAnnotation,
+    SplitAnnotation,
+    JoinAnnotation,
+    /// Moves the start and end line of the annotation under the cursor.
+    MoveAnnotation { start: i32, end: i32 },
+    /// Raises or lowers the export priority of the annotation under the cursor.
+    BumpPriority(i32),
+    ReanchorAnnotation,
+    LinkAnnotation,
+    FollowLink,
+    CopyAnnotation,
+    PasteAnnotation,
+    /// Annotates with the text configured for a function key.
+    Verdict(u8),
+    MergeDuplicates,
+    ShowFullAnnotation,
+    CycleTagFilter,
+    CycleAnnotation,
+    ShowMessages,
 
     // File management
     MarkClean,
     NextUnreviewed,
+    NextConflict,
+    SkipFile,
+    OpenSkippedList,
+    OpenChecklist,
+    OpenSearch,
+    ToggleAnnotationList,
+    OpenBrowser,
+    OpenHeatmap,
+    OpenExport,
+    /// Next order of the annotation browser.
+    CycleSort,
+    PickSyntax,
     OpenFileList,
     OpenTreeView,
+    AdjustToHead,
+
+    // Macros
+    RecordMacro,
+    ReplayMacro,
+    CountDigit(u32),
+
+    // Layout
+    GrowPanel,
+    ShrinkPanel,
 
     // Undo/Redo
     Undo,
@@ -45,19 +96,91 @@ pub enum Action {
     InputNewline,
 }
 
-pub fn map_key_viewing(key: KeyEvent) -> Option<Action> {
+impl Action {
+    /// Whether the action changes annotations, file statuses or the session
+    /// from the viewer, which a read-only review refuses.
+    pub fn edits_review(&self) -> bool {
+        matches!(
+            self,
+            Action::CreateAnnotation
+                | Action::EditAnnotation
+                | Action::DeleteAnnotation
+                | Action::SplitAnnotation
+                | Action::JoinAnnotation
+                | Action::MoveAnnotation { .. }
+                | Action::BumpPriority(_)
+                | Action::ReanchorAnnotation
+                | Action::LinkAnnotation
+                | Action::PasteAnnotation
+                | Action::Verdict(_)
+                | Action::MergeDuplicates
+                | Action::MarkClean
+                | Action::SkipFile
+                | Action::AdjustToHead
+                | Action::Undo
+                | Action::Redo
+        )
+    }
+}
+
+pub fn map_key_viewing(key: KeyEvent, profile: KeymapProfile) -> Option<Action> {
+    // Alt+arrows move the annotation under the cursor; with Shift they move
+    // its end line and with Ctrl its start line
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        let delta = match key.code {
+            KeyCode::Up => -1,
+            KeyCode::Down => 1,
+            KeyCode::Right => return Some(Action::FollowLink),
+            KeyCode::Char('c') => return Some(Action::OpenChecklist),
+            KeyCode::Char('t') => return Some(Action::CycleTagFilter),
+            KeyCode::Char('s') => return Some(Action::OpenSearch),
+            KeyCode::Char('a') => return Some(Action::ToggleAnnotationList),
+            KeyCode::Char('b') => return Some(Action::OpenBrowser),
+            KeyCode::Char('h') => return Some(Action::OpenHeatmap),
+            KeyCode::Char('o') => return Some(Action::OpenExport),
+            // Vim's Ctrl+E and Ctrl+Y, which here edit and redo
+            KeyCode::Char('e') => return Some(Action::ScrollDown),
+            KeyCode::Char('y') => return Some(Action::ScrollUp),
+            _ => return None,
+        };
+        return Some(if key.modifiers.contains(KeyModifiers::SHIFT) {
+            Action::MoveAnnotation { start: 0, end: delta }
+        } else if key.modifiers.contains(KeyModifiers::CONTROL) {
+            Action::MoveAnnotation { start: delta, end: 0 }
+        } else {
+            Action::MoveAnnotation { start: delta, end: delta }
+        });
+    }
+
     // Check Ctrl combinations first
     if key.modifiers.contains(KeyModifiers::CONTROL) {
         return match key.code {
             KeyCode::Char('q') => Some(Action::Quit),
             KeyCode::Char('e') => Some(Action::EditAnnotation),
             KeyCode::Char('d') => Some(Action::DeleteAnnotation),
+            KeyCode::Char('s') => Some(Action::SplitAnnotation),
+            KeyCode::Char('j') => Some(Action::JoinAnnotation),
+            KeyCode::Char('o') => Some(Action::ReanchorAnnotation),
+            KeyCode::Char('b') => Some(Action::LinkAnnotation),
+            KeyCode::Char('c') => Some(Action::CopyAnnotation),
+            KeyCode::Char('v') => Some(Action::PasteAnnotation),
             KeyCode::Char('z') => Some(Action::Undo),
             KeyCode::Char('y') => Some(Action::Redo),
             KeyCode::Char('m') => Some(Action::MarkClean),
             KeyCode::Char('n') => Some(Action::NextUnreviewed),
+            KeyCode::Char('w') => Some(Action::SkipFile),
+            KeyCode::Char('l') => Some(Action::OpenSkippedList),
+            KeyCode::Char('u') => Some(Action::PickSyntax),
             KeyCode::Char('f') => Some(Action::OpenFileList),
             KeyCode::Char('t') => Some(Action::OpenTreeView),
+            KeyCode::Char('r') => Some(Action::AdjustToHead),
+            KeyCode::Char('g') => Some(Action::MergeDuplicates),
+            KeyCode::Char('x') => Some(Action::ShowFullAnnotation),
+            KeyCode::Char('a') => Some(Action::ShowMessages),
+            KeyCode::Char('k') => Some(Action::RecordMacro),
+            KeyCode::Char('p') => Some(Action::ReplayMacro),
+            KeyCode::Up => Some(Action::GrowPanel),
+            KeyCode::Down => Some(Action::ShrinkPanel),
             _ => None,
         };
     }
@@ -69,6 +192,11 @@ pub fn map_key_viewing(key: KeyEvent) -> Option<Action> {
             KeyCode::Down => Some(Action::SelectDown),
             KeyCode::Left => Some(Action::SelectLeft),
             KeyCode::Right => Some(Action::SelectRight),
+            KeyCode::PageUp => Some(Action::ScrollUp),
+            KeyCode::PageDown => Some(Action::ScrollDown),
+            // Most layouts need Shift for `+`
+            KeyCode::Char('+') => Some(Action::BumpPriority(1)),
+            KeyCode::Char(c) if profile == KeymapProfile::Vim => map_vim(c.to_ascii_uppercase()),
             _ => None,
         };
     }
@@ -83,6 +211,41 @@ pub fn map_key_viewing(key: KeyEvent) -> Option<Action> {
         KeyCode::Home => Some(Action::Home),
         KeyCode::End => Some(Action::End),
         KeyCode::Enter => Some(Action::CreateAnnotation),
+        KeyCode::Tab => Some(Action::CycleAnnotation),
+        KeyCode::F(n) => Some(Action::Verdict(n)),
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Char(c) if c.is_ascii_digit() => c.to_digit(10).map(Action::CountDigit),
+        KeyCode::Char('z') => Some(Action::ScrollPrefix),
+        KeyCode::Char('+') => Some(Action::BumpPriority(1)),
+        KeyCode::Char('-') => Some(Action::BumpPriority(-1)),
+        KeyCode::Char(c) if profile == KeymapProfile::Vim => map_vim(c),
+        _ => None,
+    }
+}
+
+fn map_vim(c: char) -> Option<Action> {
+    match c {
+        'h' => Some(Action::CursorLeft),
+        'j' => Some(Action::CursorDown),
+        'k' => Some(Action::CursorUp),
+        'l' => Some(Action::CursorRight),
+        'H' => Some(Action::SelectLeft),
+        'J' => Some(Action::SelectDown),
+        'K' => Some(Action::SelectUp),
+        'L' => Some(Action::SelectRight),
+        '^' => Some(Action::Home),
+        '$' => Some(Action::End),
+        _ => None,
+    }
+}
+
+/// The key after `z`: `z` centers the cursor line, `t` puts it at the top
+/// of the view and `b` at the bottom.
+pub fn map_key_after_scroll_prefix(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Char('z') => Some(Action::CenterCursorLine),
+        KeyCode::Char('t') => Some(Action::CursorLineToTop),
+        KeyCode::Char('b') => Some(Action::CursorLineToBottom),
         _ => None,
     }
 }
@@ -96,11 +259,21 @@ pub fn map_key_input(key: KeyEvent) -> Option<Action> {
     }
 
     match key.code {
+        // Not every terminal reports Shift with Enter, so Alt+Enter works too
+        KeyCode::Enter if key.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) => {
+            Some(Action::InputNewline)
+        }
         KeyCode::Enter => Some(Action::Confirm),
         KeyCode::Esc => Some(Action::Cancel),
         KeyCode::Char(c) => Some(Action::InputChar(c)),
         KeyCode::Backspace => Some(Action::InputBackspace),
         KeyCode::Delete => Some(Action::InputDelete),
+        KeyCode::Left => Some(Action::CursorLeft),
+        KeyCode::Right => Some(Action::CursorRight),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        KeyCode::Home => Some(Action::Home),
+        KeyCode::End => Some(Action::End),
         _ => None,
     }
 }
@@ -128,6 +301,123 @@ pub fn map_key_tree(key: KeyEvent) -> Option<Action> {
     if key.modifiers.contains(KeyModifiers::CONTROL) {
         return match key.code {
             KeyCode::Char('q') | KeyCode::Char('t') => Some(Action::Cancel),
+            KeyCode::Char('m') => Some(Action::MarkClean),
+            KeyCode::Char('z') => Some(Action::Undo),
+            _ => None,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter => Some(Action::Confirm),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        _ => None,
+    }
+}
+
+/// Any key closes the resume overlay; `n` and `c` also jump somewhere.
+pub fn map_key_syntax(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') | KeyCode::Char('u') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+    map_key_file_list(key)
+}
+
+/// Tab changes the order.
+pub fn map_key_browser(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('b')).then_some(Action::Cancel);
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter => Some(Action::Confirm),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        KeyCode::PageUp => Some(Action::PageUp),
+        KeyCode::PageDown => Some(Action::PageDown),
+        KeyCode::Tab => Some(Action::CycleSort),
+        _ => None,
+    }
+}
+
+pub fn map_key_heatmap(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('h')).then_some(Action::Cancel);
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter => Some(Action::Confirm),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        _ => None,
+    }
+}
+
+pub fn map_key_export(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('o')).then_some(Action::Cancel);
+    }
+    map_key_heatmap(key)
+}
+
+/// Alt+A hides the list again; Esc leaves it shown.
+pub fn map_key_annotation_list(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('a')).then_some(Action::ToggleAnnotationList);
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter => Some(Action::Confirm),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        _ => None,
+    }
+}
+
+/// Typing edits the query; Enter opens the selected result.
+pub fn map_key_search(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('s')).then_some(Action::Cancel);
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+    map_key_file_list(key)
+}
+
+pub fn map_key_skipped(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') | KeyCode::Char('l') => Some(Action::Cancel),
+            KeyCode::Char('w') => Some(Action::SkipFile),
             _ => None,
         };
     }
@@ -141,6 +431,59 @@ pub fn map_key_tree(key: KeyEvent) -> Option<Action> {
     }
 }
 
+/// Enter or Space ticks the selected item off, or unticks it.
+pub fn map_key_checklist(key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match key.code {
+            KeyCode::Char('q') => Some(Action::Cancel),
+            _ => None,
+        };
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return (key.code == KeyCode::Char('c')).then_some(Action::Cancel);
+    }
+
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter | KeyCode::Char(' ') => Some(Action::Confirm),
+        KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Down => Some(Action::CursorDown),
+        _ => None,
+    }
+}
+
+pub fn map_key_resume(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('n') => Action::NextUnreviewed,
+        KeyCode::Char('c') => Action::NextConflict,
+        _ => Action::Cancel,
+    }
+}
+
+/// `r` retries the failed writes and quits if they go through, `q` quits
+/// anyway; any other key goes back to the review.
+pub fn map_key_unsaved(key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('r') => Action::Confirm,
+        KeyCode::Char('q') => Action::Quit,
+        _ => Action::Cancel,
+    }
+}
+
+/// Tab and the arrows move between the setup fields, Enter goes on to the
+/// next one and saves on the last; Esc skips the setup.
+pub fn map_key_setup(key: KeyEvent) -> Option<Action> {
+    match key.code {
+        KeyCode::Esc => Some(Action::Cancel),
+        KeyCode::Enter => Some(Action::Confirm),
+        KeyCode::Tab | KeyCode::Down => Some(Action::CursorDown),
+        KeyCode::BackTab | KeyCode::Up => Some(Action::CursorUp),
+        KeyCode::Backspace => Some(Action::InputBackspace),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(Action::InputChar(c)),
+        _ => None,
+    }
+}
+
 pub fn map_key_conflict(key: KeyEvent) -> Option<Action> {
     match key.code {
         KeyCode::Up => Some(Action::CursorUp),
@@ -150,3 +493,319 @@ pub fn map_key_conflict(key: KeyEvent) -> Option<Action> {
         _ => None,
     }
 }
+
+/// Parses a chord such as `enter`, `a`, `ctrl+m` or `shift+down`. With Ctrl
+/// alone a letter's case doesn't matter, as in `Ctrl+K`, since terminals
+/// send the same key either way.
+pub fn parse_key(chord: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut parts: Vec<&str> = chord.split('+').collect();
+    let key = parts.pop()?;
+    for m in parts {
+        modifiers |= match m.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "shift" => KeyModifiers::SHIFT,
+            "alt" => KeyModifiers::ALT,
+            _ => return None,
+        };
+    }
+    let code = match key.to_ascii_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pgup" => KeyCode::PageUp,
+        "pgdn" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        "plus" => KeyCode::Char('+'),
+        "tab" => KeyCode::Tab,
+        _ if let Some(n) = crate::core::config::function_key(key) => KeyCode::F(n),
+        _ => {
+            let mut chars = key.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            if modifiers == KeyModifiers::CONTROL {
+                KeyCode::Char(c.to_ascii_lowercase())
+            } else {
+                KeyCode::Char(c)
+            }
+        }
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+/// Names of the viewer actions chords can be bound to in `ui.keys`.
+pub const ACTION_NAMES: &[(&str, Action)] = &[
+    ("cursor_up", Action::CursorUp),
+    ("cursor_down", Action::CursorDown),
+    ("cursor_left", Action::CursorLeft),
+    ("cursor_right", Action::CursorRight),
+    ("page_up", Action::PageUp),
+    ("page_down", Action::PageDown),
+    ("line_start", Action::Home),
+    ("line_end", Action::End),
+    ("scroll_up", Action::ScrollUp),
+    ("scroll_down", Action::ScrollDown),
+    ("scroll_prefix", Action::ScrollPrefix),
+    ("select_up", Action::SelectUp),
+    ("select_down", Action::SelectDown),
+    ("select_left", Action::SelectLeft),
+    ("select_right", Action::SelectRight),
+    ("create_annotation", Action::CreateAnnotation),
+    ("edit_annotation", Action::EditAnnotation),
+    ("delete_annotation", Action::DeleteAnnotation),
+    ("split_annotation", Action::SplitAnnotation),
+    ("join_annotation", Action::JoinAnnotation),
+    ("move_annotation_up", Action::MoveAnnotation { start: -1, end: -1 }),
+    ("move_annotation_down", Action::MoveAnnotation { start: 1, end: 1 }),
+    ("move_annotation_start_up", Action::MoveAnnotation { start: -1, end: 0 }),
+    ("move_annotation_start_down", Action::MoveAnnotation { start: 1, end: 0 }),
+    ("move_annotation_end_up", Action::MoveAnnotation { start: 0, end: -1 }),
+    ("move_annotation_end_down", Action::MoveAnnotation { start: 0, end: 1 }),
+    ("raise_priority", Action::BumpPriority(1)),
+    ("lower_priority", Action::BumpPriority(-1)),
+    ("reanchor_annotation", Action::ReanchorAnnotation),
+    ("link_annotation", Action::LinkAnnotation),
+    ("follow_link", Action::FollowLink),
+    ("copy_annotation", Action::CopyAnnotation),
+    ("paste_annotation", Action::PasteAnnotation),
+    ("merge_duplicates", Action::MergeDuplicates),
+    ("show_full_annotation", Action::ShowFullAnnotation),
+    ("cycle_tag_filter", Action::CycleTagFilter),
+    ("cycle_annotation", Action::CycleAnnotation),
+    ("show_messages", Action::ShowMessages),
+    ("mark_clean", Action::MarkClean),
+    ("next_unreviewed", Action::NextUnreviewed),
+    ("skip_file", Action::SkipFile),
+    ("open_skipped_list", Action::OpenSkippedList),
+    ("open_checklist", Action::OpenChecklist),
+    ("open_search", Action::OpenSearch),
+    ("toggle_annotation_list", Action::ToggleAnnotationList),
+    ("open_browser", Action::OpenBrowser),
+    ("open_heatmap", Action::OpenHeatmap),
+    ("open_export", Action::OpenExport),
+    ("pick_syntax", Action::PickSyntax),
+    ("open_file_list", Action::OpenFileList),
+    ("open_tree_view", Action::OpenTreeView),
+    ("adjust_to_head", Action::AdjustToHead),
+    ("record_macro", Action::RecordMacro),
+    ("replay_macro", Action::ReplayMacro),
+    ("grow_panel", Action::GrowPanel),
+    ("shrink_panel", Action::ShrinkPanel),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("quit", Action::Quit),
+];
+
+/// Name of `action` in `ui.keys`, if it can be bound there.
+pub fn action_name(action: Action) -> Option<&'static str> {
+    ACTION_NAMES.iter().find(|(_, a)| *a == action).map(|(name, _)| *name)
+}
+
+/// A key as bindings compare it: Shift on a character is part of the
+/// character, since terminals differ in whether they report it.
+type Chord = (KeyCode, KeyModifiers);
+
+fn chord(key: KeyEvent) -> Chord {
+    match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            (KeyCode::Char(c.to_ascii_uppercase()), key.modifiers - KeyModifiers::SHIFT)
+        }
+        code => (code, key.modifiers),
+    }
+}
+
+/// Viewer bindings from `ui.keys`, consulted before the built-in ones. A
+/// chord bound to `"none"` does nothing. Keys of the popups, lists and the
+/// annotation input can't be rebound.
+#[derive(Debug, Clone, Default)]
+pub struct KeyBindings {
+    /// The chord as written, and its action.
+    keys: HashMap<Chord, (String, Option<Action>)>,
+}
+
+impl KeyBindings {
+    /// Bindings of `layers`, a later layer replacing an earlier one's binding
+    /// for the same key, such as `Ctrl+K` in the repository config replacing
+    /// `ctrl+k` in the user's. Also returns the entries that had to be left
+    /// out: chords that don't parse, unknown action names, and one key bound
+    /// twice in the same layer, such as `K` and `shift+k`.
+    pub fn parse(layers: &[&BTreeMap<String, String>]) -> (Self, Vec<String>) {
+        let mut bindings = Self::default();
+        let mut errors = Vec::new();
+        for keys in layers {
+            let layer = Self::parse_layer(keys, &mut errors);
+            bindings.keys.extend(layer.keys);
+        }
+        (bindings, errors)
+    }
+
+    fn parse_layer(keys: &BTreeMap<String, String>, errors: &mut Vec<String>) -> Self {
+        let mut bindings = Self::default();
+        for (written, name) in keys {
+            let Some(key) = parse_key(written) else {
+                errors.push(format!("'{}' is not a key", written));
+                continue;
+            };
+            let action = match ACTION_NAMES.iter().find(|(n, _)| n == name) {
+                Some((_, action)) => Some(*action),
+                None if name == "none" => None,
+                None => {
+                    errors.push(format!("{}: unknown action '{}'", written, name));
+                    continue;
+                }
+            };
+            match bindings.keys.entry(chord(key)) {
+                Entry::Occupied(first) => {
+                    errors.push(format!("{} and {} are the same key", first.get().0, written));
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((written.clone(), action));
+                }
+            }
+        }
+        bindings
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn viewing_action(&self, key: KeyEvent, profile: KeymapProfile) -> Option<Action> {
+        match self.keys.get(&chord(key)) {
+            Some((_, action)) => *action,
+            None => map_key_viewing(key, profile),
+        }
+    }
+
+    /// Built-in bindings the configured ones replace, such as
+    /// `ctrl+d: delete_annotation, now undo`, and actions left without any key.
+    pub fn conflicts(&self, profile: KeymapProfile) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        let mut bound: Vec<(&Chord, &(String, Option<Action>))> = self.keys.iter().collect();
+        bound.sort_by(|a, b| a.1.0.cmp(&b.1.0));
+        for ((code, modifiers), (written, action)) in bound {
+            let builtin = map_key_viewing(KeyEvent::new(*code, *modifiers), profile);
+            if builtin.is_some() && builtin != *action {
+                let was = builtin.and_then(action_name).unwrap_or("a built-in action");
+                let now = action.and_then(action_name).unwrap_or("nothing");
+                conflicts.push(format!("{}: {}, now {}", written, was, now));
+            }
+        }
+
+        for (name, action) in ACTION_NAMES {
+            let builtin = every_chord().any(|key| map_key_viewing(key, profile) == Some(*action));
+            if builtin && !every_chord().any(|key| self.viewing_action(key, profile) == Some(*action)) {
+                conflicts.push(format!("{} has no key left", name));
+            }
+        }
+        conflicts
+    }
+}
+
+/// Every key the built-in viewer bindings use, with every combination of modifiers.
+fn every_chord() -> impl Iterator<Item = KeyEvent> {
+    let codes = (' '..='~').map(KeyCode::Char).chain((1..=12).map(KeyCode::F)).chain([
+        KeyCode::Enter,
+        KeyCode::Esc,
+        KeyCode::Tab,
+        KeyCode::Up,
+        KeyCode::Down,
+        KeyCode::Left,
+        KeyCode::Right,
+        KeyCode::PageUp,
+        KeyCode::PageDown,
+        KeyCode::Home,
+        KeyCode::End,
+    ]);
+    let modifiers = [KeyModifiers::SHIFT, KeyModifiers::CONTROL, KeyModifiers::ALT];
+    codes.flat_map(move |code| {
+        (0..1 << modifiers.len()).map(move |mask: usize| {
+            let modifiers = (0..modifiers.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .fold(KeyModifiers::NONE, |all, i| all | modifiers[i]);
+            // Terminals send Shift+letter as the capital letter
+            match code {
+                KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                    KeyEvent::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers)
+                }
+                code => KeyEvent::new(code, modifiers),
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_bindings_and_conflicts() {
+        let keys: BTreeMap<String, String> = [
+            ("ctrl+d", "undo"),
+            ("alt+x", "nonsense"),
+            ("ctrl+nope", "quit"),
+            ("K", "delete_annotation"),
+            ("shift+k", "redo"),
+            ("ctrl+q", "none"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let (bindings, errors) = KeyBindings::parse(&[&keys]);
+        assert_eq!(
+            errors,
+            vec![
+                "alt+x: unknown action 'nonsense'",
+                "'ctrl+nope' is not a key",
+                "K and shift+k are the same key",
+            ]
+        );
+        assert_eq!(bindings.len(), 3);
+
+        let profile = KeymapProfile::Default;
+        let key = |chord| parse_key(chord).unwrap();
+        assert_eq!(bindings.viewing_action(key("ctrl+d"), profile), Some(Action::Undo));
+        assert_eq!(bindings.viewing_action(key("shift+k"), profile), Some(Action::DeleteAnnotation));
+        assert_eq!(bindings.viewing_action(key("ctrl+q"), profile), None);
+        assert_eq!(bindings.viewing_action(key("ctrl+z"), profile), Some(Action::Undo));
+        assert_eq!(
+            bindings.conflicts(profile),
+            vec!["ctrl+d: delete_annotation, now undo", "ctrl+q: quit, now nothing", "quit has no key left"]
+        );
+    }
+
+    #[test]
+    fn test_later_layer_replaces_binding() {
+        let user: BTreeMap<String, String> = [("ctrl+k".to_string(), "undo".to_string())].into();
+        let repo: BTreeMap<String, String> = [("Ctrl+K".to_string(), "redo".to_string())].into();
+        let (bindings, errors) = KeyBindings::parse(&[&user, &repo]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let key = parse_key("ctrl+k").unwrap();
+        assert_eq!(bindings.viewing_action(key, KeymapProfile::Default), Some(Action::Redo));
+    }
+
+    #[test]
+    fn test_line_scrolling_keeps_edit_and_redo() {
+        let key = |chord| parse_key(chord).unwrap();
+        for profile in [KeymapProfile::Default, KeymapProfile::Vim] {
+            assert_eq!(map_key_viewing(key("alt+e"), profile), Some(Action::ScrollDown));
+            assert_eq!(map_key_viewing(key("alt+y"), profile), Some(Action::ScrollUp));
+            assert_eq!(map_key_viewing(key("ctrl+e"), profile), Some(Action::EditAnnotation));
+            assert_eq!(map_key_viewing(key("ctrl+y"), profile), Some(Action::Redo));
+        }
+        assert_eq!(map_key_viewing(key("U"), KeymapProfile::Vim), None);
+    }
+}
@@ -0,0 +1,50 @@
+use crossterm::event::KeyEvent;
+
+/// Upper bound on a replay count prefix, so a typo can't hang the UI.
+pub const MAX_REPLAY_COUNT: usize = 1000;
+
+/// Records raw key presses so a review pass can be replayed verbatim.
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    recording: Option<Vec<KeyEvent>>,
+    recorded: Vec<KeyEvent>,
+    /// Digits typed before a replay, e.g. `5` then ^P replays five times.
+    pub count: Option<usize>,
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Stops recording and keeps the sequence for replay. Returns its length.
+    pub fn stop(&mut self) -> usize {
+        if let Some(keys) = self.recording.take() {
+            self.recorded = keys;
+        }
+        self.recorded.len()
+    }
+
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some(keys) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    pub fn recorded(&self) -> &[KeyEvent] {
+        &self.recorded
+    }
+
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.count.unwrap_or(0) * 10 + digit as usize;
+        self.count = Some(count.min(MAX_REPLAY_COUNT));
+    }
+
+    pub fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1).max(1)
+    }
+}
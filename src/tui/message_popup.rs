@@ -0,0 +1,61 @@
+use crate::tui::glyphs::Glyphs;
+use crate::tui::messages::MessageLog;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Recent status messages, newest at the bottom.
+pub struct MessagePopup<'a> {
+    pub log: &'a MessageLog,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for MessagePopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(80);
+        let popup_height = area.height.min(self.log.entries().len().max(1) as u16 + 4);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Messages ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let rows = popup.height as usize - 3;
+        if self.log.is_empty() {
+            buf.set_string(popup.x + 2, popup.y + 1, "No messages yet", bg.fg(Color::DarkGray));
+        }
+        let shown = self.log.entries().len().min(rows);
+        for (i, (at, text)) in self.log.entries().skip(self.log.entries().len() - shown).enumerate() {
+            let row = popup.y + 1 + i as u16;
+            let time = at.format("%H:%M:%S ").to_string();
+            buf.set_string(popup.x + 2, row, &time, bg.fg(Color::DarkGray));
+            let display: String = text.chars().take(inner_width.saturating_sub(time.len())).collect();
+            buf.set_string(popup.x + 2 + time.len() as u16, row, &display, bg);
+        }
+
+        let help: String = self.glyphs.localize("any key to close").chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
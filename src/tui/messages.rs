@@ -0,0 +1,46 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+/// Messages kept for the log; older ones are dropped first.
+pub const MAX_MESSAGES: usize = 50;
+
+/// Recent status bar messages, so one overwritten by the next key press can
+/// still be read.
+#[derive(Debug, Default)]
+pub struct MessageLog {
+    entries: VecDeque<(DateTime<Local>, String)>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, text: String) {
+        if self.entries.len() == MAX_MESSAGES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Local::now(), text));
+    }
+
+    /// Oldest first.
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &(DateTime<Local>, String)> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_dropped_when_full() {
+        let mut log = MessageLog::default();
+        for i in 0..MAX_MESSAGES + 2 {
+            log.push(format!("m{}", i));
+        }
+        assert_eq!(log.entries().len(), MAX_MESSAGES);
+        assert_eq!(log.entries().next().unwrap().1, "m2");
+        assert_eq!(log.entries().last().unwrap().1, format!("m{}", MAX_MESSAGES + 1));
+    }
+}
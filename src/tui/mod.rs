@@ -1,12 +1,37 @@
+pub mod annotation_list;
+pub mod annotation_panel;
 pub mod annotation_popup;
 pub mod app;
+pub mod browser_popup;
+pub mod checklist_panel;
 pub mod conflict_popup;
+pub mod cursor;
 pub mod event;
+pub mod export_popup;
+pub mod handler;
 pub mod file_list_popup;
+pub mod glyphs;
+pub mod heatmap_popup;
 pub mod highlight;
 pub mod keymap;
+pub mod macros;
+pub mod message_popup;
+pub mod messages;
+pub mod note_popup;
+pub mod palette;
+pub mod pending_writes;
+pub mod placeholder;
 pub mod render;
+pub mod resume_popup;
+pub mod script;
+pub mod search_popup;
 pub mod selection;
+pub mod setup;
+pub mod setup_popup;
+pub mod skipped_popup;
 pub mod status_bar;
+pub mod syntax_popup;
+pub mod text_input;
 pub mod tree_view;
+pub mod unsaved_popup;
 pub mod viewer;
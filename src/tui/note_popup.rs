@@ -0,0 +1,65 @@
+use crate::core::annotation::Annotation;
+use crate::tui::annotation_panel::wrap_text;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Full text of the annotation under the cursor, for notes cut off inline.
+pub struct NotePopup<'a> {
+    pub annotation: &'a Annotation,
+    pub palette: &'a Palette,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for NotePopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(self.palette.accent(self.annotation));
+
+        let popup_width = area.width.min(72);
+        let inner_width = popup_width.saturating_sub(4) as usize;
+        if inner_width == 0 {
+            return;
+        }
+        let lines = wrap_text(&self.annotation.text, inner_width);
+        let popup_height = area.height.min(lines.len() as u16 + 4);
+        if popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let title = format!(" Lines {}-{} ", self.annotation.start_line, self.annotation.end_line);
+        buf.set_string(popup.x + 2, popup.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let rows = popup.height as usize - 3;
+        for (i, line) in lines.iter().take(rows).enumerate() {
+            buf.set_string(popup.x + 2, popup.y + 1 + i as u16, line, bg);
+        }
+        let help = if lines.len() > rows {
+            format!("+{} more lines · any key to close", lines.len() - rows)
+        } else {
+            "any key to close".to_string()
+        };
+        let help: String = self.glyphs.localize(&help).chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::core::annotation::{Annotation, Severity};
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A `#rrggbb` color as written in the palette file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct HexColor(pub u8, pub u8, pub u8);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(format!("invalid color '{}', expected #rrggbb", value));
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("invalid color '{}', expected #rrggbb", value))
+        };
+        Ok(HexColor(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl From<HexColor> for Color {
+    fn from(c: HexColor) -> Self {
+        Color::Rgb(c.0, c.1, c.2)
+    }
+}
+
+/// Accent of overlaid annotations whose source sets no color.
+pub const OVERLAY_COLOR: HexColor = HexColor(180, 130, 230);
+
+/// Accent of an overlay's annotations; `color` was checked when it loaded.
+pub fn overlay_color(overlay: &crate::core::overlay::Overlay) -> Color {
+    overlay
+        .color
+        .clone()
+        .and_then(|c| HexColor::try_from(c).ok())
+        .unwrap_or(OVERLAY_COLOR)
+        .into()
+}
+
+/// Accent colors for annotations, picked by severity and then by first tag.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    pub default: HexColor,
+    pub severity: HashMap<Severity, HexColor>,
+    pub tags: HashMap<String, HexColor>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            default: HexColor(200, 180, 100),
+            severity: HashMap::from([
+                (Severity::Blocker, HexColor(230, 80, 80)),
+                (Severity::Major, HexColor(230, 140, 60)),
+                (Severity::Minor, HexColor(200, 180, 100)),
+                (Severity::Nit, HexColor(140, 150, 160)),
+                (Severity::Info, HexColor(110, 170, 220)),
+            ]),
+            tags: HashMap::new(),
+        }
+    }
+}
+
+impl Palette {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            let data = std::fs::read_to_string(path)?;
+            Ok(serde_json::from_str(&data)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Foreground accent used for the gutter marker and inline note.
+    pub fn accent(&self, annotation: &Annotation) -> Color {
+        annotation
+            .severity
+            .and_then(|s| self.severity.get(&s))
+            .or_else(|| annotation.first_tag().and_then(|t| self.tags.get(t)))
+            .copied()
+            .unwrap_or(self.default)
+            .into()
+    }
+
+    /// Dimmed accent used as the background of annotated lines.
+    pub fn background(&self, annotation: &Annotation) -> Color {
+        match self.accent(annotation) {
+            Color::Rgb(r, g, b) => Color::Rgb(r / 4, g / 4, b / 4),
+            other => other,
+        }
+    }
+
+    /// Brighter background for the lines of the annotation under the cursor.
+    pub fn highlight(&self, annotation: &Annotation) -> Color {
+        match self.accent(annotation) {
+            Color::Rgb(r, g, b) => Color::Rgb(r / 2, g / 2, b / 2),
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::core::annotation::{Annotation, FileStatus};
+use crate::core::store::Store;
+use crate::core::timing::TimeEntry;
+
+/// A store write that failed and is tried again.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingWrite {
+    /// The whole annotation list, rewritten from memory, since the failed
+    /// write may have been any append, update or delete.
+    Annotations,
+    FileStatus { file: String, status: FileStatus },
+    /// Every checklist item ticked off for `file`.
+    Checklist { file: String, checked: Vec<String> },
+    TimeEntries(Vec<TimeEntry>),
+    /// The session and undo stack, saved again from memory.
+    Session,
+}
+
+/// Writes the store refused, kept until it accepts them so a read-only or
+/// full disk doesn't silently lose review data.
+#[derive(Debug, Default)]
+pub struct PendingWrites {
+    queue: Vec<PendingWrite>,
+    /// Error of the latest failed attempt, shown in the banner.
+    pub last_error: Option<String>,
+}
+
+impl PendingWrites {
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Queues `write` after it failed with `error`. A newer status for the
+    /// same file replaces the older one.
+    pub fn push(&mut self, write: PendingWrite, error: &anyhow::Error) {
+        self.last_error = Some(format!("{:#}", error));
+        match write {
+            PendingWrite::Annotations | PendingWrite::Session if self.queue.contains(&write) => {}
+            PendingWrite::FileStatus { file, status } => {
+                self.queue.retain(|w| !matches!(w, PendingWrite::FileStatus { file: f, .. } if *f == file));
+                self.queue.push(PendingWrite::FileStatus { file, status });
+            }
+            PendingWrite::Checklist { file, checked } => {
+                self.queue.retain(|w| !matches!(w, PendingWrite::Checklist { file: f, .. } if *f == file));
+                self.queue.push(PendingWrite::Checklist { file, checked });
+            }
+            PendingWrite::TimeEntries(entries) => match self.queue.iter_mut().find_map(|w| match w {
+                PendingWrite::TimeEntries(queued) => Some(queued),
+                _ => None,
+            }) {
+                Some(queued) => queued.extend(entries),
+                None => self.queue.push(PendingWrite::TimeEntries(entries)),
+            },
+            write => self.queue.push(write),
+        }
+    }
+
+    /// Tries every queued write again, keeping those that still fail.
+    /// Returns whether the queue is now empty.
+    pub fn retry(
+        &mut self,
+        store: &Store,
+        annotations: &[Annotation],
+        save_session: impl Fn() -> anyhow::Result<()>,
+    ) -> bool {
+        let mut failed = Vec::new();
+        for write in std::mem::take(&mut self.queue) {
+            let result = match &write {
+                PendingWrite::Annotations => store.save_annotations(annotations),
+                PendingWrite::FileStatus { file, status } => store.set_file_status(file, *status),
+                PendingWrite::Checklist { file, checked } => store.set_checked_items(file, checked),
+                PendingWrite::TimeEntries(entries) => store.append_time_entries(entries),
+                PendingWrite::Session => save_session(),
+            };
+            if let Err(e) = result {
+                self.last_error = Some(format!("{:#}", e));
+                failed.push(write);
+            }
+        }
+        self.queue = failed;
+        if self.queue.is_empty() {
+            self.last_error = None;
+        }
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_merges_repeated_writes() {
+        let mut pending = PendingWrites::default();
+        let error = anyhow::anyhow!("read-only file system");
+        pending.push(PendingWrite::Annotations, &error);
+        pending.push(PendingWrite::Annotations, &error);
+        pending.push(PendingWrite::Session, &error);
+        pending.push(PendingWrite::Session, &error);
+        pending.push(PendingWrite::FileStatus { file: "a.rs".into(), status: FileStatus::Annotated }, &error);
+        pending.push(PendingWrite::FileStatus { file: "a.rs".into(), status: FileStatus::Clean }, &error);
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending.last_error.as_deref(), Some("read-only file system"));
+        assert!(pending.queue.contains(&PendingWrite::FileStatus { file: "a.rs".into(), status: FileStatus::Clean }));
+    }
+}
@@ -0,0 +1,34 @@
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Centered notice shown in place of the viewer when there is nothing to show.
+pub struct Placeholder<'a> {
+    pub title: &'a str,
+    pub lines: &'a [String],
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for Placeholder<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.saturating_sub(4).min(76) as usize;
+        if width == 0 {
+            return;
+        }
+        let mut rows = vec![(self.title.to_string(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))];
+        rows.push((String::new(), Style::default()));
+        for line in self.lines {
+            let style = Style::default().fg(Color::DarkGray);
+            let wrapped = crate::tui::annotation_panel::wrap_text(&self.glyphs.localize(line), width);
+            rows.extend(wrapped.into_iter().map(|l| (l, style)));
+        }
+
+        let top = area.y + area.height.saturating_sub(rows.len() as u16) / 2;
+        let x = area.x + area.width.saturating_sub(width as u16) / 2;
+        for (i, (text, style)) in rows.iter().enumerate().take(area.height as usize) {
+            buf.set_string(x, top + i as u16, text, *style);
+        }
+    }
+}
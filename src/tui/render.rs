@@ -1,58 +1,190 @@
+use crate::core::annotation::Annotation;
+use crate::core::heatmap::heatmap;
+use crate::tui::annotation_list::AnnotationList;
+use crate::tui::annotation_panel::AnnotationPanel;
 use crate::tui::annotation_popup::AnnotationPopup;
 use crate::tui::app::{App, AppMode};
+use crate::tui::browser_popup::BrowserPopup;
+use crate::tui::checklist_panel::ChecklistPanel;
 use crate::tui::file_list_popup::FileListPopup;
+use crate::tui::export_popup::ExportPopup;
+use crate::tui::heatmap_popup::HeatmapPopup;
 use crate::tui::highlight::Highlighter;
+use crate::tui::message_popup::MessagePopup;
+use crate::tui::note_popup::NotePopup;
+use crate::tui::placeholder::Placeholder;
+use crate::tui::resume_popup::ResumePopup;
+use crate::tui::search_popup::SearchPopup;
+use crate::tui::setup_popup::SetupPopup;
+use crate::tui::skipped_popup::SkippedPopup;
 use crate::tui::status_bar::StatusBar;
+use crate::tui::syntax_popup::SyntaxPopup;
 use crate::tui::tree_view::TreeViewPopup;
+use crate::tui::unsaved_popup::UnsavedPopup;
 use crate::tui::viewer::FileViewer;
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+
+/// Smallest terminal the layout is drawn in; below it only a notice is shown.
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
 
 pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
     let size = frame.area();
+    let glyphs = app.glyphs();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(frame);
+        return;
+    }
 
+    let panel_height = app.visible_panel_height();
+    let show_panel = panel_height > 0;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(app.banner_height()),
             Constraint::Min(1),
+            Constraint::Length(panel_height),
             Constraint::Length(3),
         ])
         .split(size);
 
-    let viewer_area = chunks[0];
-    let status_area = chunks[1];
+    let banner_area = chunks[0];
+    let viewer_area = chunks[1];
+    let panel_area = chunks[2];
+    let status_area = chunks[3];
+    if let Some(error) = &app.pending_writes.last_error {
+        let banner = format!(
+            " Could not save {} change(s): {} — retrying; ^A shows the log",
+            app.pending_writes.len(),
+            error
+        );
+        let style = Style::default().bg(Color::Red).fg(Color::White).add_modifier(Modifier::BOLD);
+        frame.buffer_mut().set_stringn(banner_area.x, banner_area.y, &banner, banner_area.width as usize, style);
+    }
 
     // Highlight file content
     let content = app.file_content.join("\n");
     let file_path = app.current_file().unwrap_or("unknown");
-    let highlighted = highlighter.highlight_lines(&content, file_path);
+    let syntax = app.config.ui.syntax.get(file_path).map(|s| s.as_str());
+    let highlighted = highlighter.highlight_lines(&content, file_path, syntax);
 
     let annotations = app.current_file_annotations();
+    let cursor_annotation = app.cursor_annotation();
+    let annotation_at_cursor = cursor_annotation.map(|i| &app.annotations[i]);
+    let overlays = app.current_file_overlays();
     let viewer = FileViewer {
         highlighted_lines: &highlighted,
         scroll_offset: app.scroll_offset,
         cursor_line: app.cursor_line,
         cursor_col: app.cursor_col,
         annotations: &annotations,
+        overlays: &overlays,
+        active: annotation_at_cursor,
         selection: &app.selection,
+        palette: &app.palette,
+        monochrome: app.monochrome,
+        glyphs,
+        gutter: &app.config.ui.gutter,
+        inline: &app.config.ui.inline,
     };
-    frame.render_widget(viewer, viewer_area);
+    // The annotation list takes the right of the viewer; popups still
+    // center on the whole viewer area
+    let file_area = if app.annotation_list {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(crate::tui::annotation_list::width(viewer_area.width)),
+            ])
+            .split(viewer_area);
+        let entries: Vec<&Annotation> = app.annotation_list_entries().into_iter().map(|i| &app.annotations[i]).collect();
+        let list = AnnotationList {
+            annotations: &entries,
+            selected: app.annotation_list_selected,
+            focused: app.mode == AppMode::AnnotationList,
+            palette: &app.palette,
+            glyphs,
+        };
+        frame.render_widget(list, columns[1]);
+        columns[0]
+    } else {
+        viewer_area
+    };
+    match app.placeholder() {
+        Some((title, lines)) => frame.render_widget(Placeholder { title: &title, lines: &lines, glyphs }, file_area),
+        None => frame.render_widget(viewer, file_area),
+    }
 
     // Status bar
-    let (reviewed, total) = app.review_progress();
-    let annotation_at_cursor = annotations
-        .iter()
-        .find(|a| a.contains_line(app.cursor_line))
-        .map(|a| a.text.as_str());
+    let (reviewed, total, progress) = app.review_progress();
+    let covering = app.annotations_at_cursor();
+    let preview_position = cursor_annotation
+        .filter(|_| covering.len() > 1)
+        .and_then(|i| covering.iter().position(|&c| c == i))
+        .map(|pos| (pos + 1, covering.len()));
+    if show_panel {
+        let panel = AnnotationPanel {
+            annotation: annotation_at_cursor,
+            palette: &app.palette,
+            glyphs,
+        };
+        frame.render_widget(panel, panel_area);
+    }
+    let head_notice = app
+        .adjusting
+        .as_ref()
+        .map(|job| {
+            if job.progress.is_cancelled() {
+                "Cancelling adjustment…".to_string()
+            } else {
+                format!(
+                    "Adjusting annotations to {}…{} — Esc to cancel",
+                    crate::git::repo::short_id(&job.head),
+                    percent(&job.progress)
+                )
+            }
+        })
+        .or_else(|| {
+            app.reanchoring
+                .map(|_| "Select the new range and press ^O — ^O on the annotation cancels".to_string())
+        })
+        .or_else(|| {
+            app.linking
+                .map(|_| "Move to the related annotation and press ^B — ^B on the picked one cancels".to_string())
+        })
+        .or_else(|| {
+            app.binary_check
+                .as_ref()
+                .map(|_| format!("Checking files for binary content…{} — Esc to skip", percent(&app.listing_progress)))
+        })
+        .or_else(|| {
+            app.pending_head
+                .as_ref()
+                .map(|head| format!("HEAD moved to {} — ^R to adjust annotations", crate::git::repo::short_id(head)))
+        })
+        .or_else(|| {
+            (app.pending_duplicates > 0).then(|| {
+                format!("{} duplicate annotation(s) — ^G to merge", app.pending_duplicates)
+            })
+        });
     let status = StatusBar {
         filename: app.current_file().unwrap_or("(no file)"),
         cursor_line: app.cursor_line,
         cursor_col: app.cursor_col,
-        annotation_count: annotations.len(),
+        annotation_counts: crate::tui::status_bar::severity_counts(&annotations),
         reviewed,
         total_files: total,
-        message: app.status_message.as_deref(),
-        annotation_preview: annotation_at_cursor,
+        progress,
+        message: app.status_message.as_deref().or(head_notice.as_deref()),
+        annotation_preview: annotation_at_cursor
+            .filter(|_| !show_panel)
+            .map(|a| a.text.as_str()),
+        preview_position,
+        selection: app.selection.as_ref().map(|s| (s.start_line, s.end_line)),
+        recording: app.macros.is_recording(),
+        glyphs,
     };
     frame.render_widget(status, status_area);
 
@@ -66,6 +198,7 @@ pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
                 scroll_offset: app.scroll_offset,
                 viewport_height: viewer_area.height,
                 is_edit: false,
+                glyphs,
             };
             frame.render_widget(popup, viewer_area);
         }
@@ -77,6 +210,7 @@ pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
                 scroll_offset: app.scroll_offset,
                 viewport_height: viewer_area.height,
                 is_edit: true,
+                glyphs,
             };
             frame.render_widget(popup, viewer_area);
         }
@@ -85,7 +219,9 @@ pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
                 files: &app.files,
                 filter: &app.file_list_filter,
                 selected: app.file_list_selected,
+                labels: &app.file_list_labels,
                 store: &app.store,
+                glyphs,
             };
             frame.render_widget(popup, size);
         }
@@ -95,12 +231,158 @@ pub fn render(frame: &mut Frame, app: &App, highlighter: &Highlighter) {
                 expanded: &app.tree_expanded,
                 selected: app.tree_selected,
                 store: &app.store,
+                annotations: &app.annotations,
+                palette: &app.palette,
+                glyphs,
             };
             frame.render_widget(popup, size);
         }
         AppMode::ConflictResolution => {
             // Conflict resolution is handled separately
         }
-        AppMode::Viewing => {}
+        AppMode::Resume => {
+            if let Some(summary) = &app.resume {
+                frame.render_widget(ResumePopup { summary, glyphs }, viewer_area);
+            }
+        }
+        AppMode::AnnotationView => {
+            if let Some(annotation) = annotation_at_cursor {
+                let popup = NotePopup {
+                    annotation,
+                    palette: &app.palette,
+                    glyphs,
+                };
+                frame.render_widget(popup, viewer_area);
+            }
+        }
+        AppMode::SyntaxPicker => {
+            let popup = SyntaxPopup {
+                entries: &app.filtered_syntaxes(),
+                filter: &app.syntax_filter,
+                selected: app.syntax_selected,
+                current: syntax,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::UnsavedQuit => {
+            let popup = UnsavedPopup {
+                count: app.pending_writes.len(),
+                error: app.pending_writes.last_error.as_deref(),
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::MessageLog => {
+            frame.render_widget(MessagePopup { log: &app.messages, glyphs }, viewer_area);
+        }
+        AppMode::SkippedList => {
+            let popup = SkippedPopup {
+                files: &app.session.skipped,
+                selected: app.skipped_selected,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::AnnotationBrowser => {
+            let entries: Vec<&Annotation> = app.browser_entries().into_iter().map(|i| &app.annotations[i]).collect();
+            let popup = BrowserPopup {
+                annotations: &entries,
+                sort: app.browser_sort,
+                selected: app.browser_selected,
+                palette: &app.palette,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::Heatmap => {
+            let popup = HeatmapPopup {
+                files: &heatmap(&app.annotations, &app.heatmap_lines),
+                selected: app.heatmap_selected,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::ExportPicker => {
+            let popup = ExportPopup {
+                exporters: crate::export::EXPORTERS,
+                selected: app.export_selected,
+                default: &app.config.export.format,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::Search => {
+            let results: Vec<&Annotation> = app.search_results().into_iter().map(|i| &app.annotations[i]).collect();
+            let popup = SearchPopup {
+                query: &app.search_query,
+                results: &results,
+                selected: app.search_selected,
+                glyphs,
+            };
+            frame.render_widget(popup, viewer_area);
+        }
+        AppMode::Checklist => {
+            if let Some(checklist) = &app.checklist {
+                let panel = ChecklistPanel {
+                    items: &checklist.items,
+                    checked: &app.current_checked_items(),
+                    selected: app.checklist_selected,
+                    glyphs,
+                };
+                frame.render_widget(panel, viewer_area);
+            }
+        }
+        AppMode::Setup => {
+            if let Some(form) = &app.setup {
+                frame.render_widget(SetupPopup { form, glyphs }, viewer_area);
+            }
+        }
+        AppMode::Viewing | AppMode::AnnotationList => {}
+    }
+
+    if app.config.ui.accessible {
+        raise_contrast(frame);
+    }
+    if app.monochrome {
+        strip_colors(frame);
     }
 }
+
+/// Centered notice asking for a bigger terminal, cut to whatever fits.
+fn render_too_small(frame: &mut Frame) {
+    let area = frame.area();
+    let lines = [
+        "Terminal too small".to_string(),
+        format!("{}x{}, needs {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT),
+    ];
+    let top = area.y + area.height.saturating_sub(lines.len() as u16) / 2;
+    for (i, line) in lines.iter().enumerate().take(area.height as usize) {
+        let width = (line.chars().count() as u16).min(area.width);
+        let x = area.x + (area.width - width) / 2;
+        let style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        frame.buffer_mut().set_stringn(x, top + i as u16, line, width as usize, style);
+    }
+}
+
+/// Brightens dimmed hint and gutter text for accessible mode.
+fn raise_contrast(frame: &mut Frame) {
+    for cell in frame.buffer_mut().content.iter_mut() {
+        if cell.fg == Color::DarkGray {
+            cell.fg = Color::White;
+        }
+    }
+}
+
+/// Drops every foreground and background color, keeping bold/reverse/etc.
+fn strip_colors(frame: &mut Frame) {
+    for cell in frame.buffer_mut().content.iter_mut() {
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
+}
+
+/// " 42%" once the total of `progress` is known.
+fn percent(progress: &crate::core::progress::Progress) -> String {
+    progress.percent().map(|p| format!(" {p}%")).unwrap_or_default()
+}
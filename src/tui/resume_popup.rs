@@ -0,0 +1,78 @@
+use crate::tui::app::ResumeSummary;
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Startup overlay: where the last session stopped and what is left to do.
+pub struct ResumePopup<'a> {
+    pub summary: &'a ResumeSummary,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> ResumePopup<'a> {
+    fn lines(&self) -> Vec<String> {
+        let s = self.summary;
+        let mut lines = Vec::new();
+        if let Some((file, line)) = &s.stopped_at {
+            lines.push(format!("Stopped at {}:{}", file, line));
+        }
+        lines.push(format!("{} of {} files left to review", s.remaining, s.total));
+        if s.conflicts > 0 {
+            lines.push(format!("{} annotation(s) in conflict after adjusting", s.conflicts));
+        }
+        lines.push(String::new());
+        let mut keys = Vec::new();
+        if s.remaining > 0 {
+            keys.push("n next unreviewed");
+        }
+        if s.conflicts > 0 {
+            keys.push("c next conflict");
+        }
+        keys.push("any other key to continue");
+        lines.push(keys.join(" · "));
+        lines
+    }
+}
+
+impl<'a> Widget for ResumePopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 30, 40)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+        let lines = self.lines();
+
+        let popup_width = area.width.min(64);
+        let popup_height = area.height.min(lines.len() as u16 + 2);
+        if popup_width < 4 || popup_height < 3 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Welcome back ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let last = lines.len() - 1;
+        for (i, line) in lines.iter().enumerate().take(popup.height as usize - 2) {
+            let text: String = self.glyphs.localize(line).chars().take(inner_width).collect();
+            let style = if i == last { bg.fg(Color::DarkGray) } else { bg };
+            buf.set_string(popup.x + 2, popup.y + 1 + i as u16, text, style);
+        }
+    }
+}
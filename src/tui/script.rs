@@ -0,0 +1,1032 @@
+//! Headless driver that feeds scripted commands to the app without a terminal.
+//!
+//! One command per line; blank lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! open src/main.rs
+//! goto 42
+//! select 42 45
+//! annotate nit: extract this into a helper
+//! key ctrl+m
+//! type some text
+//! quit
+//! ```
+
+use crate::tui::app::App;
+use crate::tui::handler::handle_key;
+use crate::tui::keymap::parse_key;
+use crate::tui::selection::Selection;
+use anyhow::{Context, Result, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptCommand {
+    /// Switch to a tracked file by its repo-relative path.
+    Open(String),
+    /// Move the cursor to a 1-based line.
+    Goto(u32),
+    /// Select an inclusive line range.
+    Select(u32, u32),
+    /// Annotate the selection, or the cursor line; `\n` becomes a newline.
+    Annotate(String),
+    /// Press a sequence of key chords, e.g. `ctrl+m` or `shift+down`.
+    Keys(Vec<KeyEvent>),
+    /// Type text into whatever currently has focus.
+    Type(String),
+    Quit,
+}
+
+/// A parsed command together with its 1-based line in the script.
+pub type ScriptLine = (usize, ScriptCommand);
+
+pub fn parse_script(source: &str) -> Result<Vec<ScriptLine>> {
+    let mut commands = Vec::new();
+    for (idx, raw) in source.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (cmd, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        let command = match cmd {
+            "open" if !arg.is_empty() => ScriptCommand::Open(arg.to_string()),
+            "goto" => ScriptCommand::Goto(parse_line_no(arg, line_no)?),
+            "select" => {
+                let (start, end) = arg
+                    .split_once(' ')
+                    .with_context(|| format!("line {}: select needs <start> <end>", line_no))?;
+                let start = parse_line_no(start.trim(), line_no)?;
+                let end = parse_line_no(end.trim(), line_no)?;
+                if start > end {
+                    bail!("line {}: select range {}-{} is reversed", line_no, start, end);
+                }
+                ScriptCommand::Select(start, end)
+            }
+            "annotate" if !arg.is_empty() => ScriptCommand::Annotate(arg.replace("\\n", "\n")),
+            "key" => {
+                let keys = arg
+                    .split_whitespace()
+                    .map(|chord| {
+                        parse_key(chord)
+                            .with_context(|| format!("line {}: unknown key '{}'", line_no, chord))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if keys.is_empty() {
+                    bail!("line {}: key needs at least one chord", line_no);
+                }
+                ScriptCommand::Keys(keys)
+            }
+            "type" => ScriptCommand::Type(arg.to_string()),
+            "quit" => ScriptCommand::Quit,
+            _ => bail!("line {}: cannot parse '{}'", line_no, line),
+        };
+        commands.push((line_no, command));
+    }
+    Ok(commands)
+}
+
+fn parse_line_no(arg: &str, line_no: usize) -> Result<u32> {
+    match arg.parse::<u32>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => bail!("line {}: expected a line number, got '{}'", line_no, arg),
+    }
+}
+
+/// Runs commands in order, stopping at `quit` or when a key makes the app quit.
+pub fn run_script(app: &mut App, commands: &[ScriptLine]) -> Result<()> {
+    for (line_no, command) in commands {
+        match command {
+            ScriptCommand::Open(path) => {
+                let idx = app
+                    .files
+                    .iter()
+                    .position(|f| f == path)
+                    .with_context(|| format!("line {}: '{}' is not a tracked file", line_no, path))?;
+                app.switch_to_file(idx);
+            }
+            ScriptCommand::Goto(line) => {
+                app.move_cursor_to_line(*line);
+                app.move_cursor_to_col(0);
+                app.selection = None;
+                app.ensure_cursor_visible();
+            }
+            ScriptCommand::Select(start, end) => {
+                let max = app.total_lines().max(1);
+                let mut selection = Selection::new((*start).min(max), 0);
+                selection.extend_to((*end).min(max), 0);
+                app.move_cursor_to_line(selection.end_line);
+                app.move_cursor_to_col(0);
+                app.selection = Some(selection);
+                app.ensure_cursor_visible();
+            }
+            ScriptCommand::Annotate(text) => {
+                if app.current_file().is_none() {
+                    bail!("line {}: no file is open", line_no);
+                }
+                if app.read_only {
+                    bail!("line {}: annotations are read-only in this revision", line_no);
+                }
+                app.annotation_input = text.clone();
+                app.create_annotation();
+            }
+            ScriptCommand::Keys(keys) => {
+                for key in keys {
+                    handle_key(app, *key);
+                    // Scripts see each key's effect before the next one
+                    app.finish_adjustment();
+                }
+            }
+            ScriptCommand::Type(text) => {
+                for c in text.chars() {
+                    handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+                }
+            }
+            ScriptCommand::Quit => app.should_quit = true,
+        }
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::annotation::{FileStatus, Severity};
+    use crate::tui::app::{AppMode, BrowserSort};
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn setup_app() -> (TempDir, App) {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        git(dir.path(), &["config", "user.email", "t@t"]);
+        git(dir.path(), &["config", "user.name", "t"]);
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "alpha\nbeta\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "init"]);
+        let app = App::new(dir.path().to_path_buf()).unwrap();
+        (dir, app)
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let commands = parse_script("# review\nopen a.txt\n\ngoto 3\nselect 1 2\nannotate nit: x\\ny\nkey ctrl+m shift+down\nquit\n").unwrap();
+        assert_eq!(commands[0], (2, ScriptCommand::Open("a.txt".into())));
+        assert_eq!(commands[1], (4, ScriptCommand::Goto(3)));
+        assert_eq!(commands[2], (5, ScriptCommand::Select(1, 2)));
+        assert_eq!(commands[3], (6, ScriptCommand::Annotate("nit: x\ny".into())));
+        assert_eq!(
+            commands[4].1,
+            ScriptCommand::Keys(vec![
+                KeyEvent::new(KeyCode::Char('m'), KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT),
+            ])
+        );
+        assert_eq!(commands[5], (8, ScriptCommand::Quit));
+    }
+
+    #[test]
+    fn test_parse_errors_report_line() {
+        let err = parse_script("open a.txt\ngoto zero\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        assert!(parse_script("select 5 2").is_err());
+        assert!(parse_script("key ctrl+enterx").is_err());
+        assert!(parse_script("frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_script_annotates_and_marks_clean() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script(
+            "open a.txt\nselect 2 3\nannotate blocker: wrong order\nopen b.txt\nkey ctrl+m\n",
+        )
+        .unwrap();
+        run_script(&mut app, &script).unwrap();
+
+        assert_eq!(app.annotations.len(), 1);
+        let a = &app.annotations[0];
+        assert_eq!((a.file_path.as_str(), a.start_line, a.end_line), ("a.txt", 2, 3));
+        assert_eq!(app.store.get_file_status("a.txt").unwrap(), FileStatus::Annotated);
+        assert_eq!(app.store.get_file_status("b.txt").unwrap(), FileStatus::Clean);
+    }
+
+    #[test]
+    fn test_script_drives_popup_with_keys() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open b.txt\ngoto 2\nkey enter\ntype needs a test\nkey enter\nkey ctrl+z\n").unwrap();
+        run_script(&mut app, &script[..5]).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert_eq!(app.annotations[0].text, "needs a test");
+        assert_eq!(app.annotations[0].start_line, 2);
+
+        run_script(&mut app, &script[5..]).unwrap();
+        assert!(app.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_multi_line_annotation_input() {
+        let (_dir, mut app) = setup_app();
+        let script = "open b.txt\ngoto 1\nkey enter\ntype first\nkey alt+enter\ntype second line\nkey up end\ntype !\nkey shift+enter\ntype inserted";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!(app.annotation_input, "first!\ninserted\nsecond line");
+        run_script(&mut app, &parse_script("key enter").unwrap()).unwrap();
+        assert_eq!(app.annotations[0].text, "first!\ninserted\nsecond line");
+    }
+
+    #[test]
+    fn test_vim_keymap_and_author_from_config() {
+        let (dir, _) = setup_app();
+        std::fs::create_dir_all(dir.path().join(".annotator")).unwrap();
+        std::fs::write(
+            dir.path().join(".annotator/config.toml"),
+            "[ui]\nkeymap = \"vim\"\n[author]\nname = \"Ann\"\n[review]\nignore = [\"b.*\"]\n",
+        )
+        .unwrap();
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        assert_eq!(app.files, vec!["a.txt"]);
+        let script = parse_script("open a.txt\ngoto 1\nkey j j shift+j enter\ntype nit\nkey enter\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let a = &app.annotations[0];
+        assert_eq!((a.start_line, a.end_line), (3, 4));
+        assert_eq!(a.author.as_deref(), Some("Ann"));
+    }
+
+    #[test]
+    fn test_progress_weighted_by_lines() {
+        let (dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open b.txt\nkey ctrl+m\n").unwrap()).unwrap();
+        assert_eq!(app.review_progress(), (1, 2, 0.5));
+
+        // b.txt has 2 of the 6 lines
+        std::fs::write(dir.path().join(".annotator/config.toml"), "[ui]\nprogress = \"lines\"\n").unwrap();
+        let app = App::new(dir.path().to_path_buf()).unwrap();
+        assert!(app.review_progress().2 < 0.5);
+    }
+
+    #[test]
+    fn test_configured_key_bindings() {
+        let (dir, _) = setup_app();
+        let config = dir.path().join(".annotator/config.toml");
+        std::fs::write(&config, "[ui.keys]\n\"ctrl+d\" = \"none\"\n\"alt+x\" = \"delete_annotation\"\n").unwrap();
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        run_script(&mut app, &parse_script("open a.txt\nselect 1 1\nannotate nit: x\nkey ctrl+d").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        run_script(&mut app, &parse_script("key alt+x").unwrap()).unwrap();
+        assert!(app.annotations.is_empty());
+
+        // A bad entry is skipped and reported, the others still apply
+        std::fs::write(&config, "[ui.keys]\n\"alt+x\" = \"delete\"\n\"ctrl+d\" = \"none\"\n").unwrap();
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        let status = app.status_message.clone().unwrap();
+        assert!(status.contains("alt+x: unknown action 'delete'"), "{}", status);
+        run_script(&mut app, &parse_script("open a.txt\nselect 1 1\nannotate nit: x\nkey alt+x ctrl+d").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+    }
+
+    #[test]
+    fn test_split_annotation() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\nselect 1 4\nannotate nit: two issues\ngoto 3\nkey ctrl+s\ntype , second\nkey enter\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let ranges: Vec<(u32, u32, &str)> = app.annotations.iter().map(|a| (a.start_line, a.end_line, a.text.as_str())).collect();
+        assert_eq!(ranges, vec![(1, 2, "nit: two issues"), (3, 4, "nit: two issues, second")]);
+
+        // The text edit and the split undo separately
+        run_script(&mut app, &parse_script("key ctrl+z ctrl+z").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert_eq!((app.annotations[0].start_line, app.annotations[0].end_line), (1, 4));
+    }
+
+    #[test]
+    fn test_join_adjacent_annotations() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\nselect 3 4\nannotate second\nselect 1 2\nannotate nit: first\ngoto 1\nkey ctrl+j\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let a = &app.annotations[0];
+        assert_eq!((app.annotations.len(), a.start_line, a.end_line), (1, 1, 4));
+        assert_eq!(app.store.load_annotations().unwrap().len(), 1);
+
+        // Nothing starts below line 4 any more
+        run_script(&mut app, &parse_script("key ctrl+j").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+
+        run_script(&mut app, &parse_script("key ctrl+z").unwrap()).unwrap();
+        let ranges: Vec<(u32, u32)> = app.annotations.iter().map(|a| (a.start_line, a.end_line)).collect();
+        assert_eq!(ranges, vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn test_move_annotation_range() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\nannotate nit: off by one\nkey alt+down alt+shift+down\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let a = &app.annotations[0];
+        assert_eq!((a.start_line, a.end_line), (2, 3));
+        assert_eq!(a.snippet.as_deref(), Some("two\nthree"));
+        assert_eq!(app.cursor_line, 2);
+
+        run_script(&mut app, &parse_script("key alt+ctrl+down").unwrap()).unwrap();
+        assert_eq!((app.annotations[0].start_line, app.annotations[0].end_line), (3, 3));
+        assert_eq!(app.store.load_annotations().unwrap()[0].start_line, 3);
+    }
+
+    #[test]
+    fn test_reanchor_annotation() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\nselect 1 2\nannotate wrong lines\nselect 2 3\nkey ctrl+o\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        assert_eq!((app.annotations[0].start_line, app.annotations[0].end_line), (2, 3));
+
+        // Pick it, then drop it on b.txt
+        let script = parse_script("goto 2\nkey ctrl+o\nopen b.txt\nselect 1 2\nkey ctrl+o\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let a = &app.annotations[0];
+        assert_eq!((a.file_path.as_str(), a.start_line, a.end_line), ("b.txt", 1, 2));
+        assert!(app.reanchoring.is_none());
+        assert_eq!(app.store.get_file_status("a.txt").unwrap(), FileStatus::Unreviewed);
+        assert_eq!(app.store.get_file_status("b.txt").unwrap(), FileStatus::Annotated);
+
+        run_script(&mut app, &parse_script("key ctrl+z").unwrap()).unwrap();
+        assert_eq!(app.annotations[0].file_path, "a.txt");
+    }
+
+    #[test]
+    fn test_copy_annotation_to_other_places() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\ngoto 2\nannotate major: unchecked #errors\nkey ctrl+c\ngoto 4\nkey ctrl+v\nopen b.txt\nselect 1 2\nkey ctrl+v\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        let placed: Vec<(&str, u32, u32)> = app.annotations.iter().map(|a| (a.file_path.as_str(), a.start_line, a.end_line)).collect();
+        assert_eq!(placed, vec![("a.txt", 2, 2), ("a.txt", 4, 4), ("b.txt", 1, 2)]);
+        assert_eq!(app.annotations[2].text, "major: unchecked #errors");
+        assert_eq!(app.store.get_file_status("b.txt").unwrap(), FileStatus::Annotated);
+    }
+
+    #[test]
+    fn test_session_saved_after_changes() {
+        let (dir, mut app) = setup_app();
+        let session_path = dir.path().join(".annotator/session.json");
+        app.save_session_debounced(std::time::Duration::ZERO);
+        assert!(!session_path.exists());
+
+        run_script(&mut app, &parse_script("open b.txt\ngoto 2").unwrap()).unwrap();
+        app.save_session_debounced(std::time::Duration::from_secs(3600));
+        assert!(!session_path.exists());
+        app.save_session_debounced(std::time::Duration::ZERO);
+        let session = crate::core::session::Session::load(&session_path).unwrap();
+        assert_eq!((session.current_file.as_deref(), session.current_line), (Some("b.txt"), 2));
+        assert!(app.session_changed.is_none());
+    }
+
+    #[test]
+    fn test_resume_overlay_jumps_to_conflict() {
+        let (_dir, mut app) = setup_app();
+        app.open_resume_summary();
+        assert_eq!(app.mode, AppMode::Viewing);
+
+        run_script(&mut app, &parse_script("open b.txt\nannotate checked\nopen a.txt\ngoto 3").unwrap()).unwrap();
+        app.session.current_file = Some("a.txt".into());
+        app.session.conflicts = vec![uuid::Uuid::new_v4(), app.annotations[0].id];
+        app.open_resume_summary();
+        assert_eq!(app.mode, AppMode::Resume);
+        let summary = app.resume.clone().unwrap();
+        assert_eq!(summary.stopped_at, Some(("a.txt".into(), 3)));
+        assert_eq!((summary.conflicts, summary.remaining, summary.total), (1, 1, 2));
+
+        run_script(&mut app, &parse_script("key c").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!((app.current_file(), app.cursor_line), (Some("b.txt"), 1));
+        assert!(app.session.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_after_annotate_moves_cursor() {
+        use crate::core::config::AfterAnnotate;
+        let (dir, mut app) = setup_app();
+        std::fs::write(dir.path().join("a.txt"), "one\nTWO\nthree\nfour\nfive\n").unwrap();
+
+        app.config.ui.after_annotate = AfterAnnotate::NextLine;
+        run_script(&mut app, &parse_script("open a.txt\nselect 1 2\nannotate first").unwrap()).unwrap();
+        assert_eq!(app.cursor_line, 3);
+
+        app.config.ui.after_annotate = AfterAnnotate::NextHunk;
+        run_script(&mut app, &parse_script("goto 1\nannotate top").unwrap()).unwrap();
+        assert_eq!(app.cursor_line, 2);
+        run_script(&mut app, &parse_script("goto 5\nannotate added").unwrap()).unwrap();
+        assert_eq!(app.cursor_line, 5);
+        assert_eq!(app.status_message.as_deref(), Some("No more changes in this file"));
+    }
+
+    #[test]
+    fn test_cursor_column_clamped_and_remembered() {
+        let (dir, mut app) = setup_app();
+        std::fs::write(dir.path().join("a.txt"), "a long line\nab\n\nanother long one\n").unwrap();
+        run_script(&mut app, &parse_script("open a.txt\ngoto 1\nkey right right right right right").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 5);
+
+        run_script(&mut app, &parse_script("key down").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 2);
+        run_script(&mut app, &parse_script("key down").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 0);
+        run_script(&mut app, &parse_script("key down").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 5);
+
+        // After End, vertical movement keeps to line ends and Left is visible at once
+        run_script(&mut app, &parse_script("goto 2\nkey end up").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 11);
+        run_script(&mut app, &parse_script("key shift+down shift+left").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 1);
+        let sel = app.selection.clone().unwrap();
+        assert_eq!((sel.start_line, sel.start_col, sel.end_line, sel.end_col), (1, 11, 2, 1));
+
+        app.config.ui.virtual_edit = true;
+        run_script(&mut app, &parse_script("goto 2\nkey end right right down").unwrap()).unwrap();
+        assert_eq!(app.cursor_col, 4);
+    }
+
+    #[test]
+    fn test_scroll_view_and_place_cursor_line() {
+        let (dir, mut app) = setup_app();
+        let content: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+        std::fs::write(dir.path().join("a.txt"), content).unwrap();
+        app.viewport_height = 10;
+        run_script(&mut app, &parse_script("open a.txt\ngoto 20\nkey z z").unwrap()).unwrap();
+        assert_eq!((app.scroll_offset, app.cursor_line), (14, 20));
+        run_script(&mut app, &parse_script("key z t").unwrap()).unwrap();
+        assert_eq!(app.scroll_offset, 19);
+        run_script(&mut app, &parse_script("key z b").unwrap()).unwrap();
+        assert_eq!(app.scroll_offset, 10);
+
+        run_script(&mut app, &parse_script("key shift+pgdn shift+pgdn").unwrap()).unwrap();
+        assert_eq!((app.scroll_offset, app.cursor_line), (12, 20));
+        // The cursor is pushed along once its line scrolls out of view
+        run_script(&mut app, &parse_script("key z t shift+pgdn").unwrap()).unwrap();
+        assert_eq!((app.scroll_offset, app.cursor_line), (20, 21));
+
+        run_script(&mut app, &parse_script("key alt+y alt+y").unwrap()).unwrap();
+        assert_eq!((app.scroll_offset, app.cursor_line), (18, 21));
+    }
+
+    #[test]
+    fn test_message_log_keeps_overwritten_messages() {
+        let (_dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open a.txt\nkey ctrl+x ctrl+m down").unwrap()).unwrap();
+        assert!(app.status_message.is_none());
+
+        run_script(&mut app, &parse_script("key ctrl+a").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::MessageLog);
+        let texts: Vec<&str> = app.messages.entries().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(texts, ["No annotation on this line", "Marked a.txt as clean"]);
+        run_script(&mut app, &parse_script("key x").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+    }
+
+    #[test]
+    fn test_failed_writes_retried_and_block_quit() {
+        let (_dir, mut app) = setup_app();
+        let store_file = app.annotator_dir.join("annotations.jsonl");
+        run_script(&mut app, &parse_script("open a.txt\nannotate first").unwrap()).unwrap();
+        std::fs::remove_file(&store_file).unwrap();
+        std::fs::create_dir(&store_file).unwrap();
+
+        run_script(&mut app, &parse_script("goto 3\nannotate second").unwrap()).unwrap();
+        assert!(app.status_message.as_deref().unwrap().starts_with("Could not save"));
+        assert_eq!((app.pending_writes.len(), app.banner_height()), (1, 1));
+        run_script(&mut app, &parse_script("key ctrl+q").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::UnsavedQuit);
+        assert!(!app.should_quit);
+        run_script(&mut app, &parse_script("key esc").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+
+        std::fs::remove_dir(&store_file).unwrap();
+        run_script(&mut app, &parse_script("key ctrl+q").unwrap()).unwrap();
+        assert!(app.should_quit);
+        assert!(app.pending_writes.is_empty());
+        assert_eq!(app.store.load_annotations().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_link_annotations_and_follow_links() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\ngoto 3\nannotate leak\nopen b.txt\ngoto 2\nannotate same leak\n\
+                      key ctrl+b\nopen a.txt\ngoto 3\nkey ctrl+b";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Annotations linked — Alt+Right jumps between them"));
+        let (a, b) = (app.annotations[0].id, app.annotations[1].id);
+        assert_eq!((app.annotations[0].links.clone(), app.annotations[1].links.clone()), (vec![b], vec![a]));
+        assert_eq!(app.store.load_annotations().unwrap()[0].links, vec![b]);
+
+        run_script(&mut app, &parse_script("key alt+right").unwrap()).unwrap();
+        assert_eq!((app.current_file(), app.cursor_line), (Some("b.txt"), 2));
+        run_script(&mut app, &parse_script("key alt+right").unwrap()).unwrap();
+        assert_eq!((app.current_file(), app.cursor_line), (Some("a.txt"), 3));
+
+        run_script(&mut app, &parse_script("key ctrl+z").unwrap()).unwrap();
+        assert!(app.annotations.iter().all(|a| a.links.is_empty()));
+        run_script(&mut app, &parse_script("key alt+right").unwrap()).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("No linked annotations — ^B links two annotations"));
+    }
+
+    #[test]
+    fn test_overlays_adjusted_and_never_saved() {
+        let (dir, mut app) = setup_app();
+        let repo = crate::git::repo::open_repo(dir.path()).unwrap();
+        let base = crate::git::repo::head_commit_id(&repo).unwrap();
+        let theirs = crate::core::annotation::Annotation::new("a.txt".into(), 3, 3, "major: off by one".into());
+        let json = crate::export::json::export_json(std::slice::from_ref(&theirs), Some(&base)).unwrap();
+        std::fs::write(dir.path().join("alice.json"), json).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "zero\none\ntwo\nthree\nfour\n").unwrap();
+        git(dir.path(), &["commit", "-q", "-am", "prepend"]);
+
+        app.config.overlays = vec![
+            crate::core::config::OverlayConfig {
+                name: "alice".into(),
+                file: Some("alice.json".into()),
+                ..Default::default()
+            },
+            crate::core::config::OverlayConfig {
+                name: "bob".into(),
+                git_ref: Some("refs/annotator/reviews/bob".into()),
+                ..Default::default()
+            },
+        ];
+        app.load_overlays();
+        assert_eq!(app.overlays.len(), 1);
+        assert!(app.status_message.as_deref().unwrap().starts_with("Overlay 'bob' not shown"));
+
+        run_script(&mut app, &parse_script("open a.txt\nannotate mine").unwrap()).unwrap();
+        assert_eq!(app.current_file_overlays().len(), 1);
+        let stored = app.store.load_annotations().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].text, "mine");
+    }
+
+    #[test]
+    fn test_first_run_setup_saves_answers() {
+        let (dir, mut app) = setup_app();
+        assert!(app.first_run);
+        assert!(app.open_setup());
+        assert_eq!(app.setup.as_ref().unwrap().values[3], "t");
+
+        let script = "type a.*\nkey enter\ntype nope\nkey enter enter enter";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Setup);
+        assert!(app.setup.as_ref().unwrap().error.is_some());
+        assert!(!dir.path().join(".annotator/config.toml").exists());
+
+        run_script(&mut app, &parse_script("key tab tab backspace backspace backspace backspace tab tab enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.files, vec!["a.txt"]);
+        let config = crate::core::config::Config::load(&dir.path().join(".annotator/config.toml")).unwrap();
+        assert_eq!(config.review.include, vec!["a.*"]);
+        assert_eq!(config.author.name.as_deref(), Some("t"));
+    }
+
+    #[test]
+    fn test_checklist_ticked_per_file() {
+        let (_dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open a.txt\nkey alt+c").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.status_message.as_deref(), Some("No CHECKLIST.toml at the repository root"));
+
+        app.checklist = Some(crate::core::checklist::Checklist::parse(r#"items = ["Errors", "Input"]"#).unwrap());
+        run_script(&mut app, &parse_script("key alt+c down space up enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Checklist);
+        assert_eq!(app.store.checked_items("a.txt").unwrap(), vec!["Errors", "Input"]);
+        assert_eq!(app.status_message.as_deref(), Some("2/2 checked for a.txt"));
+
+        run_script(&mut app, &parse_script("key space esc\nopen b.txt").unwrap()).unwrap();
+        assert_eq!(app.store.checked_items("a.txt").unwrap(), vec!["Input"]);
+        assert!(app.current_checked_items().is_empty());
+        assert_eq!(app.store.get_file_status("a.txt").unwrap(), FileStatus::Unreviewed);
+    }
+
+    #[test]
+    fn test_bump_priority_and_undo() {
+        let (_dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open a.txt\ngoto 2\nannotate fix\nkey plus shift+plus -").unwrap()).unwrap();
+        assert_eq!(app.annotations[0].priority, 1);
+        assert_eq!(app.store.load_annotations().unwrap()[0].priority, 1);
+        run_script(&mut app, &parse_script("key -").unwrap()).unwrap();
+        assert_eq!(app.annotations[0].priority, 0);
+        assert_eq!(app.status_message.as_deref(), Some("Priority cleared"));
+        run_script(&mut app, &parse_script("key -").unwrap()).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("This annotation has no priority"));
+        run_script(&mut app, &parse_script("key ctrl+z").unwrap()).unwrap();
+        assert_eq!(app.annotations[0].priority, 1);
+    }
+
+    #[test]
+    fn test_tag_filter_hides_other_annotations() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\ngoto 1\nannotate check #security\ngoto 2\nannotate #todo rename\nkey alt+t";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!(app.tag_filter.as_deref(), Some("security"));
+        assert_eq!(app.current_file_annotations().len(), 1);
+        assert_eq!(app.cursor_annotation(), None);
+        assert_eq!(app.store.load_annotations().unwrap()[1].tags, vec!["todo"]);
+
+        run_script(&mut app, &parse_script("key alt+t").unwrap()).unwrap();
+        assert!(app.cursor_annotation().is_some());
+        run_script(&mut app, &parse_script("key alt+t").unwrap()).unwrap();
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.status_message.as_deref(), Some("Showing all annotations"));
+    }
+
+    #[test]
+    fn test_tiny_terminals_render_without_panicking() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\ngoto 2\nannotate major: a long note that will not fit anywhere\nkey ctrl+w";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        app.switch_to_file(0);
+        app.move_cursor_to_line(2);
+        app.annotation_input = "typed\ntext".into();
+        app.set_status("a status message");
+        app.checklist = Some(crate::core::checklist::Checklist::parse(r#"items = ["Errors"]"#).unwrap());
+        app.setup = Some(crate::tui::setup::SetupForm::new(&app.config, None));
+        app.resume = Some(crate::tui::app::ResumeSummary {
+            stopped_at: Some(("a.txt".into(), 2)),
+            conflicts: 1,
+            remaining: 1,
+            total: 2,
+        });
+        let highlighter = crate::tui::highlight::Highlighter::new();
+        let modes = [
+            AppMode::Viewing,
+            AppMode::AnnotationInput,
+            AppMode::AnnotationEdit,
+            AppMode::FileList,
+            AppMode::TreeView,
+            AppMode::Resume,
+            AppMode::AnnotationView,
+            AppMode::SkippedList,
+            AppMode::SyntaxPicker,
+            AppMode::MessageLog,
+            AppMode::UnsavedQuit,
+            AppMode::Setup,
+            AppMode::Checklist,
+            AppMode::Search,
+            AppMode::AnnotationList,
+            AppMode::AnnotationBrowser,
+            AppMode::Heatmap,
+            AppMode::ExportPicker,
+        ];
+        for (panel_height, annotation_list) in [(app.panel_height, false), (40, true)] {
+            app.panel_height = panel_height;
+            app.annotation_list = annotation_list;
+            for mode in &modes {
+                app.mode = *mode;
+                for width in [1, 2, 3, 5, 10, 20, 39, 40, 41, 80] {
+                    for height in [1, 2, 3, 5, 9, 10, 11, 14, 24] {
+                        let backend = ratatui::backend::TestBackend::new(width, height);
+                        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+                        terminal
+                            .draw(|frame| crate::tui::render::render(frame, &app, &highlighter))
+                            .unwrap_or_else(|e| panic!("{:?} at {}x{}: {}", mode, width, height, e));
+                    }
+                }
+            }
+        }
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(30, 24)).unwrap();
+        terminal.draw(|frame| crate::tui::render::render(frame, &app, &highlighter)).unwrap();
+        let screen: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("Terminal too small"));
+        assert!(screen.contains("30x24, needs 40x10"));
+    }
+
+    #[test]
+    fn test_full_annotation_popup() {
+        let (_dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open a.txt\ngoto 2\nkey ctrl+x").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.status_message.as_deref(), Some("No annotation on this line"));
+
+        run_script(&mut app, &parse_script("annotate a long note\nkey ctrl+x").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::AnnotationView);
+        run_script(&mut app, &parse_script("key esc").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+    }
+
+    #[test]
+    fn test_placeholder_for_empty_file_and_review() {
+        let (dir, app) = setup_app();
+        assert!(app.placeholder().is_none());
+
+        std::fs::write(dir.path().join("empty.txt"), "").unwrap();
+        git(dir.path(), &["add", "empty.txt"]);
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        run_script(&mut app, &parse_script("open empty.txt\nkey enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.placeholder().unwrap().0, "empty.txt is empty");
+
+        app.set_scope(crate::core::scope::Scope::new(vec!["missing".into()], &[]).unwrap());
+        let (title, lines) = app.placeholder().unwrap();
+        assert_eq!(title, "No files to review");
+        assert!(lines[0].contains("--include"));
+    }
+
+    #[test]
+    fn test_binary_files_drop_out_after_listing() {
+        let (dir, _) = setup_app();
+        std::fs::write(dir.path().join("blob.dat"), [0x47, 0x00, 0x01, 0x02]).unwrap();
+        git(dir.path(), &["add", "blob.dat"]);
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        assert_eq!(app.files, vec!["a.txt", "b.txt", "blob.dat"]);
+
+        run_script(&mut app, &parse_script("open blob.dat").unwrap()).unwrap();
+        assert_eq!(app.placeholder().unwrap().0, "blob.dat is binary");
+        assert!(app.file_content.is_empty());
+
+        app.finish_file_listing();
+        assert_eq!(app.files, vec!["a.txt", "b.txt"]);
+        assert!(app.binary_check.is_none());
+        assert_eq!(app.current_file(), Some("b.txt"));
+        assert!(app.placeholder().is_none());
+    }
+
+    #[test]
+    fn test_adjust_to_head_runs_in_background() {
+        let (dir, mut app) = setup_app();
+        let repo = crate::git::repo::open_repo(dir.path()).unwrap();
+        app.adjust_base.commit = Some(crate::git::repo::head_commit_id(&repo).unwrap());
+        run_script(&mut app, &parse_script("select 3 3\nannotate on three").unwrap()).unwrap();
+
+        std::fs::write(dir.path().join("a.txt"), "zero\none\ntwo\nthree\nfour\n").unwrap();
+        git(dir.path(), &["commit", "-q", "-am", "insert"]);
+        app.check_head();
+        app.adjust_to_pending_head();
+        assert!(app.adjusting.is_some());
+        // Keys are ignored until the adjustment is done
+        handle_key(&mut app, KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(app.cursor_line, 3);
+
+        app.finish_adjustment();
+        assert!(app.adjusting.is_none());
+        assert_eq!(app.annotations[0].start_line, 4);
+        assert_eq!(app.annotations[0].blob, crate::git::repo::blob_at(&repo, "HEAD", "a.txt").unwrap());
+        assert!(app.status_message.as_deref().unwrap().starts_with("Annotations adjusted to"));
+    }
+
+    #[test]
+    fn test_skipped_files_are_passed_over() {
+        let (dir, _) = setup_app();
+        std::fs::write(dir.path().join("c.txt"), "gamma\n").unwrap();
+        git(dir.path(), &["add", "c.txt"]);
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+
+        run_script(&mut app, &parse_script("open a.txt\nkey ctrl+w").unwrap()).unwrap();
+        assert_eq!(app.current_file(), Some("b.txt"));
+        run_script(&mut app, &parse_script("key ctrl+m").unwrap()).unwrap();
+        assert_eq!(app.current_file(), Some("c.txt"));
+        run_script(&mut app, &parse_script("key ctrl+m").unwrap()).unwrap();
+        assert_eq!(app.current_file(), Some("c.txt"));
+        assert!(app.status_message.as_deref().unwrap().starts_with("Only skipped files left (1)"));
+        assert_eq!(app.review_progress().0, 2);
+
+        run_script(&mut app, &parse_script("key ctrl+l enter").unwrap()).unwrap();
+        assert_eq!((app.mode, app.current_file()), (AppMode::Viewing, Some("a.txt")));
+        run_script(&mut app, &parse_script("key ctrl+m").unwrap()).unwrap();
+        assert!(app.session.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_function_keys_annotate_with_verdicts() {
+        let (_dir, mut app) = setup_app();
+        app.config.ui.verdicts.insert("F1".into(), "nit: naming".into());
+        app.config.ui.after_annotate = crate::core::config::AfterAnnotate::NextLine;
+        run_script(&mut app, &parse_script("open a.txt\ngoto 1\nkey f1\nselect 2 3\nkey f1 f2").unwrap()).unwrap();
+
+        let placed: Vec<(u32, u32, &str)> = app.annotations.iter().map(|a| (a.start_line, a.end_line, a.text.as_str())).collect();
+        assert_eq!(placed, vec![(1, 1, "nit: naming"), (2, 3, "nit: naming")]);
+        assert_eq!(app.annotations[0].severity, Some(Severity::Nit));
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.cursor_line, 4);
+        assert!(app.status_message.as_deref().unwrap().starts_with("F2 has no verdict"));
+    }
+
+    #[test]
+    fn test_browser_sorts_and_jumps() {
+        let (_dir, mut app) = setup_app();
+        let script = "open b.txt\nselect 2 2\nannotate nit: later\nopen a.txt\nselect 4 4\nannotate blocker: worst\nselect 1 1\nannotate plain";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        // Creation times of annotations made in a row may tie
+        for (i, a) in app.annotations.iter_mut().enumerate() {
+            a.created_at += chrono::Duration::seconds(i as i64);
+        }
+        let texts = |app: &App| -> Vec<String> {
+            app.browser_entries().iter().map(|&i| app.annotations[i].text.clone()).collect()
+        };
+
+        run_script(&mut app, &parse_script("key alt+b").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::AnnotationBrowser);
+        run_script(&mut app, &parse_script("key down down tab").unwrap()).unwrap();
+        assert_eq!(app.browser_sort, BrowserSort::Created);
+        // The selection stays on the same annotation
+        assert_eq!(texts(&app)[app.browser_selected], "nit: later");
+
+        run_script(&mut app, &parse_script("key tab enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!((app.current_file(), app.cursor_line), (Some("b.txt"), 2));
+    }
+
+    #[test]
+    fn test_heatmap_opens_the_hottest_files() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\nselect 1 1\nannotate nit: naming\nselect 2 2\nannotate typo\nopen b.txt\nselect 1 1\nannotate major: wrong\nkey alt+h";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Heatmap);
+
+        // b.txt, with the major annotation, is listed first
+        run_script(&mut app, &parse_script("key down enter").unwrap()).unwrap();
+        assert_eq!((app.mode, app.current_file()), (AppMode::Viewing, Some("a.txt")));
+        run_script(&mut app, &parse_script("key alt+h alt+h").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+    }
+
+    #[test]
+    fn test_export_picker_writes_the_selected_format() {
+        let (dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("key alt+o").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+
+        let script = "open a.txt\nselect 2 2\nannotate major: wrong\nkey alt+o";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!((app.mode, app.export_selected), (AppMode::ExportPicker, 0));
+        run_script(&mut app, &parse_script("key down enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        let json = std::fs::read_to_string(dir.path().join("annotations.json")).unwrap();
+        assert!(json.contains("major: wrong"), "{}", json);
+        assert!(app.status_message.as_deref().unwrap().starts_with("Exported 1 annotation(s) to "));
+    }
+
+    #[test]
+    fn test_file_list_filters_by_label() {
+        let (_dir, mut app) = setup_app();
+        app.store.set_labels("b.txt", &["security-sensitive".into()]).unwrap();
+        run_script(&mut app, &parse_script("open a.txt\nkey ctrl+f\ntype @sec\nkey enter").unwrap()).unwrap();
+        assert_eq!(app.current_file(), Some("b.txt"));
+    }
+
+    #[test]
+    fn test_annotation_list_moves_the_cursor() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\nselect 3 4\nannotate later\nselect 1 1\nannotate first\ngoto 2\nkey alt+a";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+        assert_eq!((app.mode, app.annotation_list), (AppMode::AnnotationList, true));
+        let listed: Vec<&str> = app.annotation_list_entries().iter().map(|&i| app.annotations[i].text.as_str()).collect();
+        assert_eq!(listed, vec!["first", "later"]);
+        // The first annotation not above the cursor is selected
+        assert_eq!(app.annotation_list_selected, 1);
+
+        run_script(&mut app, &parse_script("key up enter").unwrap()).unwrap();
+        assert_eq!((app.mode, app.cursor_line, app.annotation_list), (AppMode::Viewing, 1, true));
+        run_script(&mut app, &parse_script("key alt+a down enter").unwrap()).unwrap();
+        assert_eq!(app.cursor_line, 3);
+        run_script(&mut app, &parse_script("key alt+a alt+a").unwrap()).unwrap();
+        assert_eq!((app.mode, app.annotation_list), (AppMode::Viewing, false));
+    }
+
+    #[test]
+    fn test_search_jumps_to_matching_annotations() {
+        let (_dir, mut app) = setup_app();
+        let script = "open a.txt\nselect 3 3\nannotate Off by one here\nopen b.txt\nselect 2 2\nannotate off the happy path";
+        run_script(&mut app, &parse_script(script).unwrap()).unwrap();
+
+        run_script(&mut app, &parse_script("open a.txt\ngoto 1\nkey alt+s\ntype OFF").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Search);
+        run_script(&mut app, &parse_script("key esc alt+s\ntype off b.txt\nkey enter").unwrap()).unwrap();
+        assert_eq!((app.mode, app.current_file(), app.cursor_line), (AppMode::Viewing, Some("b.txt"), 2));
+
+        run_script(&mut app, &parse_script("key alt+s\ntype nothing like it\nkey enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Search);
+        run_script(&mut app, &parse_script("key esc").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+    }
+
+    #[test]
+    fn test_tab_cycles_overlapping_annotations() {
+        let (_dir, mut app) = setup_app();
+        run_script(&mut app, &parse_script("open a.txt\nselect 1 3\nannotate outer\nselect 2 2\nannotate inner\ngoto 2").unwrap()).unwrap();
+        assert_eq!(app.annotations_at_cursor().len(), 2);
+        assert_eq!(app.annotations[app.cursor_annotation().unwrap()].text, "outer");
+
+        run_script(&mut app, &parse_script("key tab").unwrap()).unwrap();
+        assert_eq!(app.annotations[app.cursor_annotation().unwrap()].text, "inner");
+        run_script(&mut app, &parse_script("key ctrl+d").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert_eq!(app.annotations[0].text, "outer");
+
+        run_script(&mut app, &parse_script("key tab").unwrap()).unwrap();
+        assert_eq!(app.status_message.as_deref(), Some("Only one annotation on this line"));
+    }
+
+    #[test]
+    fn test_syntax_picker_saves_override() {
+        let (dir, mut app) = setup_app();
+        app.syntax_names = vec!["C".into(), "C++".into(), "Rust".into()];
+        run_script(&mut app, &parse_script("open a.txt\nkey ctrl+u\ntype c+\nkey enter").unwrap()).unwrap();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.config.ui.syntax["a.txt"], "C++");
+        let saved = crate::core::config::Config::load(&dir.path().join(".annotator/config.toml")).unwrap();
+        assert_eq!(saved.ui.syntax["a.txt"], "C++");
+
+        run_script(&mut app, &parse_script("key ctrl+u enter").unwrap()).unwrap();
+        assert!(app.config.ui.syntax.is_empty());
+    }
+
+    #[test]
+    fn test_script_stops_at_quit() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open a.txt\nquit\nannotate never\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        assert!(app.should_quit);
+        assert!(app.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_review_at_revision_reads_git_objects() {
+        let (dir, _) = setup_app();
+        std::fs::write(dir.path().join("a.txt"), "changed\n").unwrap();
+        std::fs::remove_file(dir.path().join("b.txt")).unwrap();
+        let repo = crate::git::repo::open_repo(dir.path()).unwrap();
+        let head = crate::git::repo::head_commit_id(&repo).unwrap();
+
+        let mut app = App::with_revision(dir.path().to_path_buf(), Some(head)).unwrap();
+        assert_eq!(app.files, vec!["a.txt", "b.txt"]);
+        let script = parse_script("open b.txt\nannotate nit: kept\nopen a.txt\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        assert_eq!(app.file_content, vec!["one", "two", "three", "four"]);
+        assert_eq!(app.annotations[0].snippet.as_deref(), Some("alpha"));
+    }
+
+    #[test]
+    fn test_review_at_older_revision_writes_nothing() {
+        let (dir, _) = setup_app();
+        let repo = crate::git::repo::open_repo(dir.path()).unwrap();
+        let first = crate::git::repo::head_commit_id(&repo).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "zero\none\ntwo\nthree\nfour\n").unwrap();
+        git(dir.path(), &["commit", "-qam", "add zero"]);
+        let head = crate::git::repo::head_commit_id(&repo).unwrap();
+
+        let mut app = App::new(dir.path().to_path_buf()).unwrap();
+        app.set_adjust_base(head.clone()).unwrap();
+        let script = parse_script("open a.txt\ngoto 1\nannotate new line\ngoto 4\nannotate on three\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        app.save_session();
+
+        let mut app = App::with_revision(dir.path().to_path_buf(), Some(first.clone())).unwrap();
+        app.adjust_for_viewing(&head, &first).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert_eq!(app.annotations[0].start_line, 3);
+        let script = parse_script("open a.txt\ngoto 3\nkey ctrl+d\n").unwrap();
+        run_script(&mut app, &script).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert!(app.status_message.as_deref().unwrap().starts_with("Read-only"));
+        assert!(run_script(&mut app, &parse_script("annotate no").unwrap()).is_err());
+        app.save_session();
+
+        let stored = app.store.load_annotations().unwrap();
+        assert_eq!(stored.iter().map(|a| a.start_line).collect::<Vec<_>>(), vec![1, 4]);
+        let base = crate::core::session::AdjustBase::load(&app.annotator_dir, &app.session).unwrap();
+        assert_eq!(base.commit, Some(head));
+    }
+
+    #[test]
+    fn test_adjustment_clears_undo_history() {
+        let (dir, mut app) = setup_app();
+        let repo = crate::git::repo::open_repo(dir.path()).unwrap();
+        let first = crate::git::repo::head_commit_id(&repo).unwrap();
+        run_script(&mut app, &parse_script("open a.txt\ngoto 2\nannotate on two").unwrap()).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "zero\none\ntwo\nthree\nfour\n").unwrap();
+        git(dir.path(), &["commit", "-qam", "add zero"]);
+        let head = crate::git::repo::head_commit_id(&repo).unwrap();
+
+        app.run_adjustment(&first, &head).unwrap();
+        run_script(&mut app, &parse_script("key ctrl+z").unwrap()).unwrap();
+        assert_eq!(app.annotations.len(), 1);
+        assert_eq!(app.annotations[0].start_line, 3);
+    }
+
+    #[test]
+    fn test_open_unknown_file_fails() {
+        let (_dir, mut app) = setup_app();
+        let script = parse_script("open missing.txt").unwrap();
+        let err = run_script(&mut app, &script).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::core::annotation::Annotation;
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Annotations anywhere in the store whose text or path matches the query.
+pub struct SearchPopup<'a> {
+    pub query: &'a str,
+    /// Matching annotations, in the order they are listed.
+    pub results: &'a [&'a Annotation],
+    pub selected: usize,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for SearchPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(80);
+        let popup_height = area.height.min(20);
+        if popup_width < 4 || popup_height < 6 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        let title = format!(" Search annotations ({}) ", self.results.len());
+        buf.set_string(popup.x + 2, popup.y, &title, border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let query: String = format!("Search: {}", self.query).chars().take(inner_width).collect();
+        buf.set_string(popup.x + 2, popup.y + 1, &query, bg);
+
+        let list_start = popup.y + 3;
+        let max_items = popup.height as usize - 5;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, a) in self.results.iter().enumerate().skip(scroll).take(max_items) {
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let location = format!("{}:{}", a.file_path, a.start_line);
+            let text = a.text.lines().next().unwrap_or_default();
+            let display: String = format!("{}  {}", location, text).chars().take(inner_width).collect();
+            buf.set_string(popup.x + 2, list_start + (i - scroll) as u16, &display, style);
+            // The location stands out from the text
+            let location: String = location.chars().take(inner_width).collect();
+            buf.set_string(popup.x + 2, list_start + (i - scroll) as u16, &location, style.fg(Color::Yellow));
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: open │ Esc: close │ Type to search")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::core::config::Config;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Label, hint and config key of each setup field, in order.
+pub const FIELDS: [(&str, &str, &str); 4] = [
+    ("Review scope", "globs to review, e.g. src/**, docs/** — empty for everything", "review.include"),
+    ("Base commit", "revision changed hunks are found against — empty for HEAD", "review.base"),
+    ("Ignore", "globs left out of the review, e.g. *.lock, vendor/**", "review.ignore"),
+    ("Reviewer name", "recorded on your annotations", "author.name"),
+];
+
+/// Answers of the first-run setup, before they are written to the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupForm {
+    pub values: [String; 4],
+    /// Index into `FIELDS` of the field being edited.
+    pub field: usize,
+    /// Why the last attempt to save was rejected.
+    pub error: Option<String>,
+}
+
+impl SetupForm {
+    /// Starts from the current settings; `git_name` fills in the reviewer
+    /// name when none is configured.
+    pub fn new(config: &Config, git_name: Option<String>) -> Self {
+        Self {
+            values: [
+                config.review.include.join(", "),
+                config.review.base.clone().unwrap_or_default(),
+                config.review.ignore.join(", "),
+                config.author.name.clone().or(git_name).unwrap_or_default(),
+            ],
+            field: 0,
+            error: None,
+        }
+    }
+
+    pub fn is_last_field(&self) -> bool {
+        self.field == FIELDS.len() - 1
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % FIELDS.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.field = (self.field + FIELDS.len() - 1) % FIELDS.len();
+    }
+
+    pub fn input(&mut self, c: char) {
+        self.values[self.field].push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.values[self.field].pop();
+    }
+
+    /// Writes the non-empty answers to the config file at `path`. Lists are
+    /// comma-separated.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        // Check every pattern first, so a bad one leaves the config untouched
+        for glob in split_list(&self.values[0]).iter().chain(&split_list(&self.values[2])) {
+            glob::Pattern::new(glob).with_context(|| format!("invalid pattern '{}'", glob))?;
+        }
+        for (i, (_, _, key)) in FIELDS.iter().enumerate() {
+            let answer = self.values[i].trim();
+            if answer.is_empty() {
+                continue;
+            }
+            let value = match i {
+                0 | 2 => toml::Value::Array(split_list(answer).into_iter().map(toml::Value::String).collect()),
+                _ => toml::Value::String(answer.to_string()),
+            };
+            Config::set(path, key, &value.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_writes_answers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut form = SetupForm::new(&Config::default(), Some("Ann".into()));
+        assert_eq!(form.values[3], "Ann");
+        for c in "src/**, docs/**".chars() {
+            form.input(c);
+        }
+        form.previous_field();
+        assert!(form.is_last_field());
+        form.backspace();
+        form.save(&path).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.review.include, vec!["src/**", "docs/**"]);
+        assert_eq!(config.review.base, None);
+        assert_eq!(config.author.name.as_deref(), Some("An"));
+    }
+
+    #[test]
+    fn test_bad_pattern_leaves_config_untouched() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut form = SetupForm::new(&Config::default(), Some("Ann".into()));
+        form.values[2] = "*.lock, [vendor".into();
+        let err = form.save(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid pattern '[vendor'"), "{:#}", err);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::tui::glyphs::Glyphs;
+use crate::tui::setup::{FIELDS, SetupForm};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// First-run questions, one field per row with the hint of the active one.
+pub struct SetupPopup<'a> {
+    pub form: &'a SetupForm,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for SetupPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+        let hint_style = bg.fg(Color::DarkGray);
+
+        // Intro, blank, field rows, hint, error, blank, help
+        let popup_width = area.width.min(76);
+        let popup_height = area.height.min(FIELDS.len() as u16 + 8);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Set up this review ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let fit = |text: &str| -> String { self.glyphs.localize(text).chars().take(inner_width).collect() };
+        let last_row = popup.y + popup.height - 2;
+        let mut row = popup.y + 1;
+        let mut put = |buf: &mut Buffer, text: String, style: Style| {
+            if row < last_row {
+                buf.set_string(popup.x + 2, row, text, style);
+                row += 1;
+            }
+        };
+
+        put(buf, fit("No review data yet for this repository."), bg);
+        put(buf, String::new(), bg);
+        let label_width = FIELDS.iter().map(|(label, _, _)| label.len()).max().unwrap_or(0);
+        for (i, (label, _, _)) in FIELDS.iter().enumerate() {
+            let active = i == self.form.field;
+            let cursor = if active { "_" } else { "" };
+            let text = format!("{:<width$}  {}{}", label, self.form.values[i], cursor, width = label_width);
+            let style = if active { bg.add_modifier(Modifier::BOLD) } else { bg };
+            put(buf, fit(&text), style);
+        }
+        put(buf, fit(FIELDS[self.form.field].1), hint_style);
+        if let Some(error) = &self.form.error {
+            put(buf, fit(error), bg.fg(Color::Red));
+        }
+
+        let help = fit("Tab/↓ next · ↑ previous · Enter on the last field saves · Esc keeps the defaults");
+        buf.set_string(popup.x + 2, last_row, &help, hint_style);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Files set aside during the review, to open one or unskip it.
+pub struct SkippedPopup<'a> {
+    pub files: &'a [String],
+    pub selected: usize,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for SkippedPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(64);
+        let popup_height = area.height.min(self.files.len() as u16 + 4);
+        if popup_width < 4 || popup_height < 4 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Skipped files ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let max_items = popup.height as usize - 3;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, file) in self.files.iter().enumerate().skip(scroll).take(max_items) {
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let display: String = file.chars().take(inner_width).collect();
+            buf.set_string(popup.x + 2, popup.y + 1 + (i - scroll) as u16, &display, style);
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: open │ ^W: unskip │ Esc: close")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
@@ -1,17 +1,113 @@
+use crate::core::annotation::{Annotation, FileReviewState, FileStatus, Severity};
+use crate::tui::glyphs::Glyphs;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::Widget;
+use std::collections::HashMap;
+
+const PROGRESS_BAR_WIDTH: usize = 10;
+
+const HINTS: &[(&str, &str)] = &[
+    ("^Q", "Quit"),
+    ("Enter", "Annotate/Edit"),
+    ("^D", "Delete"),
+    ("^M", "Clean"),
+    ("^N", "Next"),
+    ("^F", "Files"),
+    ("^T", "Tree"),
+    ("^Z", "Undo"),
+];
+
+/// Shown instead of `HINTS` while lines are selected.
+const SELECTION_HINTS: &[(&str, &str)] = &[
+    ("Enter", "Annotate range"),
+    ("^V", "Paste here"),
+    ("^O", "Move annotation here"),
+    ("Shift+Arrows", "Extend"),
+    ("Arrows", "Clear"),
+];
 
 pub struct StatusBar<'a> {
     pub filename: &'a str,
     pub cursor_line: u32,
     pub cursor_col: u32,
-    pub annotation_count: usize,
+    /// Annotations in the current file by severity, see `severity_counts`.
+    pub annotation_counts: Vec<(Option<Severity>, usize)>,
     pub reviewed: usize,
     pub total_files: usize,
+    /// Reviewed fraction shown as a bar, weighted by files or lines.
+    pub progress: f64,
     pub message: Option<&'a str>,
     pub annotation_preview: Option<&'a str>,
+    /// Which of several annotations on the line is previewed, as (n, of).
+    pub preview_position: Option<(usize, usize)>,
+    /// First and last line of the active selection.
+    pub selection: Option<(u32, u32)>,
+    pub recording: bool,
+    pub glyphs: &'a Glyphs,
+}
+
+/// Reviewed and total counts of `files`, and the reviewed fraction, weighted
+/// by line count when `line_counts` is given.
+pub fn review_progress(
+    files: &[String],
+    statuses: &[FileReviewState],
+    line_counts: Option<&HashMap<String, usize>>,
+) -> (usize, usize, f64) {
+    let total = files.len();
+    let mut by_path: HashMap<&str, FileStatus> = HashMap::new();
+    for state in statuses {
+        // The first record for a path counts, as in `Store::get_file_status`
+        by_path.entry(&state.file_path).or_insert(state.status);
+    }
+    let reviewed: Vec<&String> = files
+        .iter()
+        .filter(|f| by_path.get(f.as_str()).is_some_and(|s| *s != FileStatus::Unreviewed))
+        .collect();
+    let fraction = match line_counts {
+        Some(counts) => {
+            let lines = |f: &String| counts.get(f).copied().unwrap_or(0);
+            let total_lines: usize = files.iter().map(lines).sum();
+            let reviewed_lines: usize = reviewed.iter().map(|f| lines(f)).sum();
+            if total_lines == 0 {
+                0.0
+            } else {
+                reviewed_lines as f64 / total_lines as f64
+            }
+        }
+        None if total == 0 => 0.0,
+        None => reviewed.len() as f64 / total as f64,
+    };
+    (reviewed.len(), total, fraction)
+}
+
+/// Non-zero annotation counts per severity, most severe first, with
+/// unlabeled annotations last.
+pub fn severity_counts(annotations: &[&Annotation]) -> Vec<(Option<Severity>, usize)> {
+    Severity::ALL
+        .iter()
+        .map(|s| Some(*s))
+        .chain(std::iter::once(None))
+        .map(|severity| (severity, annotations.iter().filter(|a| a.severity == severity).count()))
+        .filter(|(_, n)| *n > 0)
+        .collect()
+}
+
+/// E.g. "2 blockers · 5 nits · 1 note".
+fn counts_label(counts: &[(Option<Severity>, usize)], glyphs: &Glyphs) -> String {
+    if counts.is_empty() {
+        return "no annotations".to_string();
+    }
+    counts
+        .iter()
+        .map(|(severity, n)| {
+            let noun = severity.map_or("note", |s| s.as_str());
+            let plural = if *n == 1 || noun == "info" { "" } else { "s" };
+            format!("{} {}{}", n, noun, plural)
+        })
+        .collect::<Vec<_>>()
+        .join(&format!(" {} ", glyphs.dot))
 }
 
 impl<'a> Widget for StatusBar<'a> {
@@ -33,33 +129,44 @@ impl<'a> Widget for StatusBar<'a> {
         }
 
         // Row 0: separator
-        let sep = "─".repeat(area.width as usize);
+        let sep = self.glyphs.rule(area.width);
         buf.set_string(area.x, area.y, &sep, Style::default().fg(Color::DarkGray));
 
         // Row 1: file info
         if area.height > 1 {
+            let rec = if self.recording { " [REC]" } else { "" };
             let left = if let Some(msg) = self.message {
-                format!(" {}  {}", self.filename, msg)
+                format!("{} {}  {}", rec, self.filename, self.glyphs.localize(msg))
             } else {
-                format!(" {}", self.filename)
+                format!("{} {}", rec, self.filename)
+            };
+            let position = match self.selection {
+                Some((start, end)) => {
+                    let count = end - start + 1;
+                    let noun = if count == 1 { "line" } else { "lines" };
+                    format!("{} {} selected (L{}-L{})", count, noun, start, end)
+                }
+                None => format!("Ln {}, Col {}", self.cursor_line, self.cursor_col),
             };
             let right = format!(
-                "Ln {}, Col {} │ {} annotations │ {}/{} reviewed ",
-                self.cursor_line,
-                self.cursor_col,
-                self.annotation_count,
+                "{} {sep} {} {sep} {} {}/{} reviewed ",
+                position,
+                counts_label(&self.annotation_counts, self.glyphs),
+                self.glyphs.progress_bar(self.progress, PROGRESS_BAR_WIDTH),
                 self.reviewed,
                 self.total_files,
+                sep = self.glyphs.vertical,
             );
 
             buf.set_string(area.x, area.y + 1, &left, bg);
-            let right_x = (area.x + area.width).saturating_sub(right.len() as u16);
+            let right_x = (area.x + area.width).saturating_sub(right.chars().count() as u16);
             buf.set_string(right_x, area.y + 1, &right, bg);
         }
 
         // Row 2: annotation preview or hotkey hints
         if area.height > 2 {
-            if let Some(preview) = self.annotation_preview {
+            let preview = self.annotation_preview.filter(|_| self.selection.is_none());
+            if let Some(preview) = preview {
                 let note_style = Style::default()
                     .bg(Color::Rgb(40, 44, 52))
                     .fg(Color::Yellow);
@@ -67,25 +174,21 @@ impl<'a> Widget for StatusBar<'a> {
                     .bg(Color::Rgb(40, 44, 52))
                     .fg(Color::Rgb(180, 200, 255))
                     .add_modifier(Modifier::BOLD);
-                buf.set_string(area.x + 1, area.y + 2, "Note: ", label_style);
-                let max_len = area.width.saturating_sub(8) as usize;
+                let label = match self.preview_position {
+                    Some((n, of)) => format!("Note {}/{} (Tab): ", n, of),
+                    None => "Note: ".to_string(),
+                };
+                buf.set_string(area.x + 1, area.y + 2, &label, label_style);
+                let text_x = area.x + 1 + label.len() as u16;
+                let max_len = area.width.saturating_sub(text_x - area.x + 1) as usize;
                 let text: String = preview
                     .replace('\n', " ")
                     .chars()
                     .take(max_len)
                     .collect();
-                buf.set_string(area.x + 7, area.y + 2, &text, note_style);
+                buf.set_string(text_x, area.y + 2, &text, note_style);
             } else {
-                let hints: &[(&str, &str)] = &[
-                    ("^Q", "Quit"),
-                    ("Enter", "Annotate/Edit"),
-                    ("^D", "Delete"),
-                    ("^M", "Clean"),
-                    ("^N", "Next"),
-                    ("^F", "Files"),
-                    ("^T", "Tree"),
-                    ("^Z", "Undo"),
-                ];
+                let hints = if self.selection.is_some() { SELECTION_HINTS } else { HINTS };
 
                 let mut x = area.x + 1;
                 for (key, label) in hints {
@@ -103,3 +206,62 @@ impl<'a> Widget for StatusBar<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_rows(selection: Option<(u32, u32)>) -> (String, String) {
+        let bar = StatusBar {
+            filename: "a.rs",
+            cursor_line: 24,
+            cursor_col: 0,
+            annotation_counts: vec![(Some(Severity::Blocker), 2), (Some(Severity::Nit), 1), (None, 3)],
+            reviewed: 0,
+            total_files: 1,
+            progress: 0.0,
+            message: None,
+            annotation_preview: Some("note"),
+            preview_position: None,
+            selection,
+            recording: false,
+            glyphs: &crate::tui::glyphs::ASCII,
+        };
+        let area = Rect::new(0, 0, 100, 3);
+        let mut buf = Buffer::empty(area);
+        bar.render(area, &mut buf);
+        let row = |y| (0..area.width).map(|x| buf[(x, y)].symbol().to_string()).collect::<String>();
+        (row(1), row(2))
+    }
+
+    #[test]
+    fn test_progress_weighted_by_lines() {
+        let files = vec!["a.txt".to_string(), "b.txt".to_string(), "c.txt".to_string()];
+        let state = |path: &str, status| FileReviewState::new(path.into(), status);
+        let statuses = vec![
+            state("b.txt", FileStatus::Clean),
+            state("c.txt", FileStatus::Unreviewed),
+            state("c.txt", FileStatus::Annotated),
+            state("gone.txt", FileStatus::Clean),
+        ];
+        let (reviewed, total, fraction) = review_progress(&files, &statuses, None);
+        assert_eq!((reviewed, total), (1, 3));
+        assert!((fraction - 1.0 / 3.0).abs() < 1e-9);
+
+        let counts = HashMap::from([("a.txt".to_string(), 4), ("b.txt".to_string(), 2)]);
+        let (_, _, fraction) = review_progress(&files, &statuses, Some(&counts));
+        assert!((fraction - 2.0 / 6.0).abs() < 1e-9);
+        assert_eq!(review_progress(&[], &[], Some(&counts)), (0, 0, 0.0));
+    }
+
+    #[test]
+    fn test_selection_replaces_position_and_hints() {
+        let (info, hints) = render_rows(None);
+        assert!(info.contains("Ln 24, Col 0 | 2 blockers - 1 nit - 3 notes |"), "{:?}", info);
+        assert!(hints.contains("Note: note"));
+
+        let (info, hints) = render_rows(Some((10, 24)));
+        assert!(info.contains("15 lines selected (L10-L24)"), "{:?}", info);
+        assert!(hints.contains("Enter Annotate range"), "{:?}", hints);
+    }
+}
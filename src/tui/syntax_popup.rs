@@ -0,0 +1,100 @@
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Syntaxes to highlight the current file as, filtered by name.
+pub struct SyntaxPopup<'a> {
+    /// `None` is the entry for detecting the syntax from the file name.
+    pub entries: &'a [Option<&'a str>],
+    pub filter: &'a str,
+    pub selected: usize,
+    /// Syntax the file is set to, if any.
+    pub current: Option<&'a str>,
+    pub glyphs: &'a Glyphs,
+}
+
+/// Entries of `names` matching `filter`, case-insensitively; `None` stands
+/// for detecting the syntax from the file name and comes first when there
+/// is no filter.
+pub fn matching<'a>(names: &'a [String], filter: &str) -> Vec<Option<&'a str>> {
+    let filter = filter.to_lowercase();
+    std::iter::once(None)
+        .filter(|_| filter.is_empty())
+        .chain(
+            names
+                .iter()
+                .filter(|name| name.to_lowercase().contains(&filter))
+                .map(|name| Some(name.as_str())),
+        )
+        .collect()
+}
+
+impl<'a> Widget for SyntaxPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Cyan);
+
+        let popup_width = area.width.min(48);
+        let popup_height = area.height.min(20);
+        if popup_width < 4 || popup_height < 6 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Syntax ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        buf.set_string(popup.x + 2, popup.y + 1, format!("Filter: {}", self.filter), bg);
+
+        let list_start = popup.y + 3;
+        let max_items = popup.height as usize - 5;
+        let scroll = (self.selected + 1).saturating_sub(max_items);
+        for (i, entry) in self.entries.iter().enumerate().skip(scroll).take(max_items) {
+            let name = entry.unwrap_or("(detect from file name)");
+            let mark = if *entry == self.current { self.glyphs.pointer } else { " " };
+            let style = if i == self.selected { bg.add_modifier(Modifier::REVERSED) } else { bg };
+            let display: String = format!("{} {}", mark, name).chars().take(inner_width).collect();
+            buf.set_string(popup.x + 2, list_start + (i - scroll) as u16, &display, style);
+        }
+
+        let help: String = self
+            .glyphs
+            .localize("Enter: use │ Esc: close │ Type to filter")
+            .chars()
+            .take(inner_width)
+            .collect();
+        buf.set_string(popup.x + 2, popup.y + popup.height - 2, &help, bg.fg(Color::DarkGray));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_filters_by_name() {
+        let names = vec!["C".to_string(), "C++".to_string(), "Rust".to_string()];
+        assert_eq!(matching(&names, ""), vec![None, Some("C"), Some("C++"), Some("Rust")]);
+        assert_eq!(matching(&names, "c+"), vec![Some("C++")]);
+        assert_eq!(matching(&names, "RU"), vec![Some("Rust")]);
+        assert!(matching(&names, "go").is_empty());
+    }
+}
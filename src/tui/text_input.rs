@@ -0,0 +1,79 @@
+//! Cursor movement in the multi-line annotation input. Positions are byte
+//! offsets into the text, always on a character boundary; columns count
+//! characters.
+
+/// Line and column of `pos`, both from 0.
+pub fn line_and_column(text: &str, pos: usize) -> (usize, usize) {
+    let before = &text[..pos];
+    let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+    (before.matches('\n').count(), before[line_start..].chars().count())
+}
+
+pub fn left(text: &str, pos: usize) -> usize {
+    text[..pos].chars().next_back().map_or(pos, |c| pos - c.len_utf8())
+}
+
+pub fn right(text: &str, pos: usize) -> usize {
+    text[pos..].chars().next().map_or(pos, |c| pos + c.len_utf8())
+}
+
+pub fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |p| p + 1)
+}
+
+pub fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map_or(text.len(), |p| pos + p)
+}
+
+/// The same column on the line above, or its end when it is shorter; on the
+/// first line, the start of the text.
+pub fn up(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    if start == 0 {
+        return 0;
+    }
+    let column = text[start..pos].chars().count();
+    at_column(text, line_start(text, start - 1), column)
+}
+
+/// The same column on the line below, or its end when it is shorter; on the
+/// last line, the end of the text.
+pub fn down(text: &str, pos: usize) -> usize {
+    let end = line_end(text, pos);
+    if end == text.len() {
+        return end;
+    }
+    let column = text[line_start(text, pos)..pos].chars().count();
+    at_column(text, end + 1, column)
+}
+
+/// Position `column` characters into the line starting at `start`, or its end.
+fn at_column(text: &str, start: usize, column: usize) -> usize {
+    let end = line_end(text, start);
+    text[start..end].char_indices().nth(column).map_or(end, |(i, _)| start + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_across_lines() {
+        let text = "héllo\nab\nworld";
+        // After "hél"
+        let pos = "hél".len();
+        assert_eq!(line_and_column(text, pos), (0, 3));
+        let down_once = down(text, pos);
+        assert_eq!(line_and_column(text, down_once), (1, 2));
+        assert_eq!(line_and_column(text, down(text, down_once)), (2, 2));
+        assert_eq!(down(text, down(text, down_once)), text.len());
+        assert_eq!(line_and_column(text, up(text, text.len())), (1, 2));
+        assert_eq!(up(text, pos), 0);
+
+        assert_eq!(left(text, 3), 1);
+        assert_eq!(right(text, 1), 3);
+        assert_eq!(left(text, 0), 0);
+        assert_eq!(right(text, text.len()), text.len());
+        assert_eq!((line_start(text, 8), line_end(text, 8)), (7, 9));
+    }
+}
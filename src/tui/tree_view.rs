@@ -1,5 +1,7 @@
-use crate::core::annotation::FileStatus;
+use crate::core::annotation::{Annotation, FileStatus, Severity};
 use crate::core::store::Store;
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
@@ -35,6 +37,7 @@ impl TreeNode {
         &self,
         expanded: &HashSet<String>,
         prefix: &str,
+        glyphs: &Glyphs,
     ) -> Vec<(String, String, bool)> {
         // Returns (display_text, path_or_key, is_dir)
         let mut result = Vec::new();
@@ -48,17 +51,17 @@ impl TreeNode {
                             format!("{}/{}", prefix, name)
                         };
                         let is_expanded = expanded.contains(&path);
-                        let icon = if is_expanded { "▾ " } else { "▸ " };
+                        let icon = if is_expanded { glyphs.expanded } else { glyphs.collapsed };
                         let indent = prefix.matches('/').count();
                         let display = format!(
-                            "{}{}{}/",
+                            "{}{} {}/",
                             "  ".repeat(indent),
                             icon,
                             name
                         );
                         result.push((display, path.clone(), true));
                         if is_expanded {
-                            result.extend(node.flatten(expanded, &path));
+                            result.extend(node.flatten(expanded, &path, glyphs));
                         }
                     }
                     TreeNode::File { name, full_path } => {
@@ -96,17 +99,36 @@ fn insert_path(tree: &mut BTreeMap<String, TreeNode>, parts: &[&str], full_path:
     }
 }
 
+/// Open blockers and majors on files under `dir`, so subsystems with
+/// serious findings stand out in the tree.
+pub fn serious_counts(annotations: &[Annotation], dir: &str) -> [(Severity, usize); 2] {
+    let count = |severity| {
+        annotations
+            .iter()
+            .filter(|a| a.severity == Some(severity))
+            .filter(|a| a.file_path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/')))
+            .count()
+    };
+    [(Severity::Blocker, count(Severity::Blocker)), (Severity::Major, count(Severity::Major))]
+}
+
 pub struct TreeViewPopup<'a> {
     pub files: &'a [String],
     pub expanded: &'a HashSet<String>,
     pub selected: usize,
     pub store: &'a Store,
+    pub annotations: &'a [Annotation],
+    pub palette: &'a Palette,
+    pub glyphs: &'a Glyphs,
 }
 
 impl<'a> Widget for TreeViewPopup<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let bg = Style::default().bg(Color::Rgb(30, 34, 42)).fg(Color::White);
         let border_style = Style::default().fg(Color::Cyan);
+        if area.width < 4 || area.height < 4 {
+            return;
+        }
 
         // Clear area
         for y in area.y..area.y + area.height {
@@ -116,13 +138,13 @@ impl<'a> Widget for TreeViewPopup<'a> {
         }
 
         // Border
-        let top = format!("┌{}┐", "─".repeat(area.width.saturating_sub(2) as usize));
-        let bottom = format!("└{}┘", "─".repeat(area.width.saturating_sub(2) as usize));
+        let top = self.glyphs.frame_top(area.width);
+        let bottom = self.glyphs.frame_bottom(area.width);
         buf.set_string(area.x, area.y, &top, border_style);
         buf.set_string(area.x, area.y + area.height - 1, &bottom, border_style);
         for y in area.y + 1..area.y + area.height - 1 {
-            buf.set_string(area.x, y, "│", border_style);
-            buf.set_string(area.x + area.width - 1, y, "│", border_style);
+            buf.set_string(area.x, y, self.glyphs.vertical, border_style);
+            buf.set_string(area.x + area.width - 1, y, self.glyphs.vertical, border_style);
         }
 
         buf.set_string(
@@ -133,7 +155,7 @@ impl<'a> Widget for TreeViewPopup<'a> {
         );
 
         let tree = TreeNode::build(self.files);
-        let items = tree.flatten(self.expanded, "");
+        let items = tree.flatten(self.expanded, "", self.glyphs);
         let list_start = area.y + 1;
         let max_items = (area.height.saturating_sub(3)) as usize;
 
@@ -151,7 +173,7 @@ impl<'a> Widget for TreeViewPopup<'a> {
                 match self.store.get_file_status(path).unwrap_or(FileStatus::Unreviewed) {
                     FileStatus::Unreviewed => " ",
                     FileStatus::Annotated => "A",
-                    FileStatus::Clean => "✓",
+                    FileStatus::Clean => self.glyphs.clean,
                 }
             } else {
                 " "
@@ -166,18 +188,58 @@ impl<'a> Widget for TreeViewPopup<'a> {
             let inner_width = area.width.saturating_sub(6) as usize;
             let entry = format!("{} {}", status_icon, display);
             let truncated: String = entry.chars().take(inner_width).collect();
-            buf.set_string(area.x + 2, list_start + i as u16, &truncated, style);
+            let row = list_start + i as u16;
+            buf.set_string(area.x + 2, row, &truncated, style);
+
+            if *is_dir {
+                let mut used = truncated.chars().count();
+                for (severity, n) in serious_counts(self.annotations, path) {
+                    if n == 0 {
+                        continue;
+                    }
+                    let plural = if n == 1 { "" } else { "s" };
+                    let label = format!(" {} {}{}", n, severity.as_str(), plural);
+                    let room = inner_width.saturating_sub(used);
+                    let shown: String = label.chars().take(room).collect();
+                    let color = self.palette.severity.get(&severity).map_or(Color::Red, |c| (*c).into());
+                    buf.set_string(area.x + 2 + used as u16, row, &shown, style.fg(color).add_modifier(Modifier::BOLD));
+                    used += shown.chars().count();
+                }
+            }
         }
 
         // Help
         if area.height >= 3 {
-            let help = "Enter: open/toggle │ Esc: close";
+            let help = self.glyphs.localize("Enter: open/toggle │ Esc: close");
             buf.set_string(
                 area.x + 2,
                 area.y + area.height - 2,
-                help,
+                &help,
                 Style::default().fg(Color::DarkGray).bg(Color::Rgb(30, 34, 42)),
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(file: &str, severity: Option<Severity>) -> Annotation {
+        let mut a = Annotation::new(file.into(), 1, 1, "x".into());
+        a.severity = severity;
+        a
+    }
+
+    #[test]
+    fn test_serious_counts_cover_whole_subtree() {
+        let annotations = vec![
+            annotation("src/core/a.rs", Some(Severity::Blocker)),
+            annotation("src/tui/b.rs", Some(Severity::Major)),
+            annotation("src/tui/b.rs", Some(Severity::Nit)),
+            annotation("srcs/c.rs", Some(Severity::Blocker)),
+        ];
+        assert_eq!(serious_counts(&annotations, "src"), [(Severity::Blocker, 1), (Severity::Major, 1)]);
+        assert_eq!(serious_counts(&annotations, "src/tui"), [(Severity::Blocker, 0), (Severity::Major, 1)]);
+    }
+}
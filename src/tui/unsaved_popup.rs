@@ -0,0 +1,58 @@
+use crate::tui::glyphs::Glyphs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+/// Asked on quit while store writes are still failing.
+pub struct UnsavedPopup<'a> {
+    pub count: usize,
+    pub error: Option<&'a str>,
+    pub glyphs: &'a Glyphs,
+}
+
+impl<'a> Widget for UnsavedPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let bg = Style::default().bg(Color::Rgb(30, 30, 40)).fg(Color::White);
+        let border_style = Style::default().fg(Color::Red);
+        let mut lines = vec![format!("{} change(s) could not be saved", self.count)];
+        if let Some(error) = self.error {
+            lines.push(error.to_string());
+        }
+        lines.push(String::new());
+        lines.push("r retry · q quit and lose them · any other key to keep reviewing".to_string());
+
+        let popup_width = area.width.min(72);
+        let popup_height = area.height.min(lines.len() as u16 + 2);
+        if popup_width < 4 || popup_height < 3 {
+            return;
+        }
+        let x = (area.width.saturating_sub(popup_width)) / 2 + area.x;
+        let y = (area.height.saturating_sub(popup_height)) / 2 + area.y;
+        let popup = Rect::new(x, y, popup_width, popup_height);
+
+        for py in popup.y..popup.y + popup.height {
+            for px in popup.x..popup.x + popup.width {
+                buf.set_string(px, py, " ", bg);
+            }
+        }
+
+        let top = self.glyphs.frame_top(popup.width);
+        let bottom = self.glyphs.frame_bottom(popup.width);
+        buf.set_string(popup.x, popup.y, &top, border_style);
+        buf.set_string(popup.x, popup.y + popup.height - 1, &bottom, border_style);
+        for py in popup.y + 1..popup.y + popup.height - 1 {
+            buf.set_string(popup.x, py, self.glyphs.vertical, border_style);
+            buf.set_string(popup.x + popup.width - 1, py, self.glyphs.vertical, border_style);
+        }
+        buf.set_string(popup.x + 2, popup.y, " Unsaved changes ", border_style.add_modifier(Modifier::BOLD));
+
+        let inner_width = popup.width.saturating_sub(4) as usize;
+        let last = lines.len() - 1;
+        for (i, line) in lines.iter().enumerate().take(popup.height as usize - 2) {
+            let text: String = self.glyphs.localize(line).chars().take(inner_width).collect();
+            let style = if i == last { bg.fg(Color::DarkGray) } else { bg };
+            buf.set_string(popup.x + 2, popup.y + 1 + i as u16, text, style);
+        }
+    }
+}
@@ -1,12 +1,20 @@
-use crate::core::annotation::Annotation;
+use crate::core::annotation::{Annotation, Severity};
+use crate::core::config::{GutterConfig, InlineConfig};
+use crate::tui::glyphs::Glyphs;
+use crate::tui::palette::Palette;
 use crate::tui::selection::Selection;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::Line;
+use ratatui::text::{Line, Span};
 use ratatui::widgets::Widget;
 
-const GUTTER_WIDTH: u16 = 7;
+/// An annotation from a read-only overlay, drawn in its source's accent.
+pub struct OverlayNote<'a> {
+    pub annotation: &'a Annotation,
+    pub source: &'a str,
+    pub color: Color,
+}
 
 pub struct FileViewer<'a> {
     pub highlighted_lines: &'a [Line<'a>],
@@ -14,31 +22,104 @@ pub struct FileViewer<'a> {
     pub cursor_line: u32,
     pub cursor_col: u32,
     pub annotations: &'a [&'a Annotation],
+    /// Other reviews' annotations, shown where no local one covers the line.
+    pub overlays: &'a [OverlayNote<'a>],
+    /// Annotation the annotation keys act on, drawn brighter than the rest.
+    pub active: Option<&'a Annotation>,
     pub selection: &'a Option<Selection>,
+    pub palette: &'a Palette,
+    /// Mark selections and notes with modifiers since background colors are stripped.
+    pub monochrome: bool,
+    pub glyphs: &'a Glyphs,
+    pub gutter: &'a GutterConfig,
+    pub inline: &'a InlineConfig,
+}
+
+/// Column layout of the gutter for a given viewer width.
+struct GutterLayout {
+    /// Line number digits, 0 when numbers are off.
+    digits: u16,
+    marker_width: u16,
+    /// Everything left of the separator; the code starts one column further.
+    width: u16,
+}
+
+impl GutterLayout {
+    /// `None` when the gutter is hidden, by setting or for a narrow terminal.
+    fn new(config: &GutterConfig, line_count: usize, area_width: u16) -> Option<Self> {
+        if config.hidden || area_width < config.hide_below {
+            return None;
+        }
+        let digits = if config.line_numbers {
+            config.width.max(line_count.max(1).to_string().len() as u16)
+        } else {
+            0
+        };
+        // Wide enough for the widest marker, so emoji line up with ASCII ones
+        let marker_width = std::iter::once(&config.marker)
+            .chain(config.tags.values())
+            .map(|m| Span::raw(m.as_str()).width() as u16)
+            .max()
+            .unwrap_or(0);
+        let width = [digits, marker_width]
+            .iter()
+            .filter(|w| **w > 0)
+            .map(|w| w + 1)
+            .sum();
+        Some(Self { digits, marker_width, width })
+    }
+}
+
+impl<'a> FileViewer<'a> {
+    /// In monochrome mode selected cells are already reversed, so the cursor
+    /// flips back to normal video to stay visible.
+    fn cursor_style(&self, style: Style, is_selected: bool) -> Style {
+        if self.monochrome && is_selected {
+            style.add_modifier(Modifier::UNDERLINED)
+        } else {
+            style.add_modifier(Modifier::REVERSED)
+        }
+    }
 }
 
 impl<'a> Widget for FileViewer<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let gutter = GutterLayout::new(self.gutter, self.highlighted_lines.len(), area.width);
+        let code_offset = gutter.as_ref().map_or(0, |g| g.width + 1);
         let code_area = Rect {
-            x: area.x + GUTTER_WIDTH + 1,
+            x: area.x + code_offset,
             y: area.y,
-            width: area.width.saturating_sub(GUTTER_WIDTH + 1),
+            width: area.width.saturating_sub(code_offset),
             height: area.height,
         };
 
         // Build a set of annotation end_lines to show inline text after
-        let annotation_display: Vec<(u32, &str)> = self
+        let annotation_display: Vec<(u32, &Annotation)> = self
             .annotations
             .iter()
-            .map(|a| (a.end_line, a.text.as_str()))
+            .map(|a| (a.end_line, *a))
             .collect();
 
         for row in 0..area.height {
             let line_num = self.scroll_offset + row as u32 + 1;
-            let is_annotated = self
+            // Where annotations overlap, the most severe one sets the color
+            let line_annotation = self
                 .annotations
                 .iter()
-                .any(|a| a.contains_line(line_num));
+                .filter(|a| a.contains_line(line_num))
+                .min_by_key(|a| a.severity.unwrap_or(Severity::Info));
+            let overlay = self
+                .overlays
+                .iter()
+                .find(|o| o.annotation.contains_line(line_num))
+                .filter(|_| line_annotation.is_none());
+            let active = self.active.filter(|a| a.contains_line(line_num));
+            let annotated_bg = match active {
+                Some(a) => Some(self.palette.highlight(a)),
+                None => line_annotation
+                    .map(|a| self.palette.background(a))
+                    .or_else(|| overlay.map(|o| dim(o.color))),
+            };
             let is_cursor_line = line_num == self.cursor_line;
             let is_selected = self
                 .selection
@@ -46,30 +127,51 @@ impl<'a> Widget for FileViewer<'a> {
                 .is_some_and(|s| s.contains_line(line_num));
 
             // Gutter: line number + annotation marker
-            let marker = if is_annotated { ">" } else { " " };
-            let gutter_style = if is_cursor_line {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-            } else if is_annotated {
-                Style::default().fg(Color::Rgb(200, 180, 100))
-            } else {
-                Style::default().fg(Color::DarkGray)
-            };
-
-            let line_num_str = if (line_num as usize) <= self.highlighted_lines.len() {
-                format!("{:>4} {} ", line_num, marker)
-            } else {
-                format!("   ~ {} ", marker)
-            };
+            if let Some(gutter) = &gutter {
+                let gutter_style = if is_cursor_line {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else if let Some(a) = line_annotation {
+                    Style::default().fg(self.palette.accent(a))
+                } else if let Some(o) = overlay {
+                    Style::default().fg(o.color)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
 
-            buf.set_string(area.x, area.y + row, &line_num_str, gutter_style);
+                let mut gutter_str = String::new();
+                if gutter.digits > 0 {
+                    let number = if (line_num as usize) <= self.highlighted_lines.len() {
+                        line_num.to_string()
+                    } else {
+                        "~".to_string()
+                    };
+                    gutter_str.push_str(&format!("{:>width$} ", number, width = gutter.digits as usize));
+                }
+                if gutter.marker_width > 0 {
+                    let marker = line_annotation
+                        .map(|a| {
+                            a.first_tag()
+                                .and_then(|t| self.gutter.tags.get(t))
+                                .unwrap_or(&self.gutter.marker)
+                                .as_str()
+                        })
+                        .or_else(|| overlay.map(|_| self.gutter.marker.as_str()))
+                        .unwrap_or("");
+                    let padding = gutter.marker_width as usize - Span::raw(marker).width().min(gutter.marker_width as usize);
+                    gutter_str.push_str(marker);
+                    gutter_str.push_str(&" ".repeat(padding));
+                    gutter_str.push(' ');
+                }
+                buf.set_string(area.x, area.y + row, &gutter_str, gutter_style);
 
-            // Separator
-            buf.set_string(
-                area.x + GUTTER_WIDTH,
-                area.y + row,
-                "│",
-                Style::default().fg(Color::DarkGray),
-            );
+                // Separator
+                buf.set_string(
+                    area.x + gutter.width,
+                    area.y + row,
+                    self.glyphs.vertical,
+                    Style::default().fg(Color::DarkGray),
+                );
+            }
 
             // Code content
             let mut code_end_col = 0u16;
@@ -85,12 +187,17 @@ impl<'a> Widget for FileViewer<'a> {
                         let mut style = span.style;
                         if is_selected {
                             style = style.bg(Color::Rgb(68, 68, 120));
-                        } else if is_annotated {
-                            style = style.bg(Color::Rgb(50, 50, 30));
+                        } else if let Some(bg) = annotated_bg {
+                            style = style.bg(bg);
                         }
                         if is_cursor_line && col == self.cursor_col as u16 {
+                            style = self.cursor_style(style, is_selected);
+                        } else if self.monochrome && is_selected {
                             style = style.add_modifier(Modifier::REVERSED);
                         }
+                        if self.monochrome && active.is_some() {
+                            style = style.add_modifier(Modifier::BOLD);
+                        }
                         buf.set_string(
                             code_area.x + col,
                             area.y + row,
@@ -106,10 +213,12 @@ impl<'a> Widget for FileViewer<'a> {
                     let mut style = Style::default();
                     if is_selected {
                         style = style.bg(Color::Rgb(68, 68, 120));
-                    } else if is_annotated {
-                        style = style.bg(Color::Rgb(50, 50, 30));
+                    } else if let Some(bg) = annotated_bg {
+                        style = style.bg(bg);
                     }
                     if is_cursor_line && col == self.cursor_col as u16 {
+                        style = self.cursor_style(style, is_selected);
+                    } else if self.monochrome && is_selected {
                         style = style.add_modifier(Modifier::REVERSED);
                     }
                     buf.set_string(code_area.x + col, area.y + row, " ", style);
@@ -117,23 +226,44 @@ impl<'a> Widget for FileViewer<'a> {
                 }
             }
 
+            // Overlay notes go where no local note ends, prefixed by their source
+            let local_note = annotation_display.iter().any(|(end_line, _)| *end_line == line_num);
+            let overlay_notes = self.overlays.iter().filter(|o| o.annotation.end_line == line_num && !local_note);
+            for note in overlay_notes {
+                let mut note_style = Style::default().fg(note.color).bg(dim(note.color));
+                if self.monochrome {
+                    note_style = note_style.add_modifier(Modifier::ITALIC);
+                }
+                let start_col = code_end_col.saturating_add(self.inline.gap);
+                if start_col < code_area.width {
+                    let prefix = format!("{}{}: ", self.inline.prefix, note.source);
+                    let mut max_chars = ((code_area.width - start_col) as usize).saturating_sub(Span::raw(prefix.as_str()).width());
+                    if self.inline.max_length > 0 {
+                        max_chars = max_chars.min(self.inline.max_length);
+                    }
+                    let display = format!("{}{}", prefix, fit_note(&note.annotation.text, max_chars));
+                    buf.set_string(code_area.x + start_col, area.y + row, &display, note_style);
+                }
+            }
+
             // Show annotation text inline at the end_line of each annotation
-            for (end_line, text) in &annotation_display {
+            for (end_line, annotation) in &annotation_display {
                 if line_num == *end_line {
-                    let note_style = Style::default()
-                        .fg(Color::Rgb(180, 160, 80))
-                        .bg(Color::Rgb(50, 50, 30));
-                    let gap = 2u16;
-                    let start_col = code_end_col + gap;
+                    let text = &annotation.text;
+                    let mut note_style = Style::default()
+                        .fg(self.palette.accent(annotation))
+                        .bg(self.palette.background(annotation));
+                    if self.monochrome {
+                        note_style = note_style.add_modifier(Modifier::ITALIC);
+                    }
+                    let start_col = code_end_col.saturating_add(self.inline.gap);
                     if start_col < code_area.width {
-                        let prefix = " // ";
-                        let max_chars = (code_area.width - start_col) as usize;
-                        let note_text: String = text
-                            .replace('\n', " ")
-                            .chars()
-                            .take(max_chars.saturating_sub(prefix.len()))
-                            .collect();
-                        let display = format!("{}{}", prefix, note_text);
+                        let prefix = &self.inline.prefix;
+                        let mut max_chars = ((code_area.width - start_col) as usize).saturating_sub(Span::raw(prefix.as_str()).width());
+                        if self.inline.max_length > 0 {
+                            max_chars = max_chars.min(self.inline.max_length);
+                        }
+                        let display = format!("{}{}", prefix, fit_note(text, max_chars));
                         buf.set_string(
                             code_area.x + start_col,
                             area.y + row,
@@ -146,3 +276,194 @@ impl<'a> Widget for FileViewer<'a> {
         }
     }
 }
+
+/// Background for lines covered by an overlay note.
+fn dim(color: Color) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => Color::Rgb(r / 4, g / 4, b / 4),
+        other => other,
+    }
+}
+
+/// Flattens a note onto one line of at most `width` characters. A note that
+/// doesn't fit ends with how many characters were cut, e.g. `(+42 chars)`.
+fn fit_note(text: &str, width: usize) -> String {
+    let flat = text.replace('\n', " ");
+    let total = flat.chars().count();
+    if total <= width {
+        return flat;
+    }
+    let indicator_len = format!(" (+{} chars)", total).len();
+    let shown = width.saturating_sub(indicator_len);
+    let mut note: String = flat.chars().take(shown).collect();
+    note.push_str(&format!(" (+{} chars)", total - shown));
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_row(gutter: &GutterConfig, width: u16) -> String {
+        render_annotated_row(gutter, width, "note")
+    }
+
+    fn render_annotated_row(gutter: &GutterConfig, width: u16, text: &str) -> String {
+        render_with_inline(gutter, &InlineConfig::default(), width, text)
+    }
+
+    fn render_with_inline(gutter: &GutterConfig, inline: &InlineConfig, width: u16, text: &str) -> String {
+        let lines = vec![Line::from("fn main() {}")];
+        let annotation = Annotation::new("a.rs".into(), 1, 1, text.into());
+        let annotations = vec![&annotation];
+        let viewer = FileViewer {
+            highlighted_lines: &lines,
+            scroll_offset: 0,
+            cursor_line: 2,
+            cursor_col: 0,
+            annotations: &annotations,
+            overlays: &[],
+            active: None,
+            selection: &None,
+            palette: &Palette::default(),
+            monochrome: false,
+            glyphs: &crate::tui::glyphs::ASCII,
+            gutter,
+            inline,
+        };
+        let area = Rect::new(0, 0, width, 1);
+        let mut buf = Buffer::empty(area);
+        viewer.render(area, &mut buf);
+        (0..width).map(|x| buf[(x, 0)].symbol().to_string()).collect()
+    }
+
+    #[test]
+    fn test_default_gutter() {
+        let row = render_row(&GutterConfig::default(), 30);
+        assert!(row.starts_with("   1 > |fn main() {}"), "{:?}", row);
+    }
+
+    #[test]
+    fn test_custom_and_hidden_gutter() {
+        let custom = GutterConfig {
+            line_numbers: false,
+            marker: "*".into(),
+            ..GutterConfig::default()
+        };
+        assert!(render_row(&custom, 30).starts_with("* |fn main"));
+
+        let hidden = GutterConfig {
+            hidden: true,
+            ..GutterConfig::default()
+        };
+        assert!(render_row(&hidden, 30).starts_with("fn main() {}"));
+
+        let narrow = GutterConfig {
+            hide_below: 40,
+            ..GutterConfig::default()
+        };
+        assert!(render_row(&narrow, 30).starts_with("fn main"));
+        assert!(render_row(&narrow, 40).starts_with("   1 > |"));
+    }
+
+    #[test]
+    fn test_overflowing_note_shows_cut_length() {
+        let row = render_annotated_row(&GutterConfig::default(), 50, "this note is far too long to fit");
+        assert!(row.ends_with(" // this note is (+20 chars)"), "{:?}", row);
+        let row = render_annotated_row(&GutterConfig::default(), 60, "fits fine");
+        assert!(row.contains(" // fits fine"));
+        assert!(!row.contains("chars)"));
+    }
+
+    #[test]
+    fn test_inline_config() {
+        let hidden = GutterConfig { hidden: true, ..GutterConfig::default() };
+        let inline = InlineConfig {
+            prefix: "# ".into(),
+            gap: 1,
+            max_length: 30,
+        };
+        let row = render_with_inline(&hidden, &inline, 80, "short");
+        assert!(row.starts_with("fn main() {} # short "), "{:?}", row);
+        let row = render_with_inline(&hidden, &inline, 80, &"x".repeat(40));
+        assert!(row.trim_end().ends_with(" (+22 chars)"), "{:?}", row);
+        assert_eq!(row.trim_end().chars().count(), "fn main() {} # ".len() + 30);
+    }
+
+    #[test]
+    fn test_active_annotation_is_brighter() {
+        let lines = vec![Line::from("one"), Line::from("two")];
+        let first = Annotation::new("a.rs".into(), 1, 1, "nit: a".into());
+        let second = Annotation::new("a.rs".into(), 2, 2, "nit: b".into());
+        let annotations = vec![&first, &second];
+        let palette = Palette::default();
+        let viewer = FileViewer {
+            highlighted_lines: &lines,
+            scroll_offset: 0,
+            cursor_line: 2,
+            cursor_col: 0,
+            annotations: &annotations,
+            overlays: &[],
+            active: Some(&second),
+            selection: &None,
+            palette: &palette,
+            monochrome: false,
+            glyphs: &crate::tui::glyphs::ASCII,
+            gutter: &GutterConfig::default(),
+            inline: &InlineConfig::default(),
+        };
+        let area = Rect::new(0, 0, 30, 2);
+        let mut buf = Buffer::empty(area);
+        viewer.render(area, &mut buf);
+        assert_eq!(buf[(9, 0)].bg, palette.background(&first));
+        assert_eq!(buf[(9, 1)].bg, palette.highlight(&second));
+        assert_ne!(palette.highlight(&second), palette.background(&second));
+    }
+
+    #[test]
+    fn test_overlay_notes_in_their_color_under_local_ones() {
+        let lines = vec![Line::from("one"), Line::from("two")];
+        let local = Annotation::new("a.rs".into(), 1, 1, "mine".into());
+        let theirs = Annotation::new("a.rs".into(), 1, 2, "theirs".into());
+        let annotations = vec![&local];
+        let color = Color::Rgb(180, 130, 230);
+        let overlays = [OverlayNote { annotation: &theirs, source: "alice", color }];
+        let palette = Palette::default();
+        let viewer = FileViewer {
+            highlighted_lines: &lines,
+            scroll_offset: 0,
+            cursor_line: 3,
+            cursor_col: 0,
+            annotations: &annotations,
+            overlays: &overlays,
+            active: None,
+            selection: &None,
+            palette: &palette,
+            monochrome: false,
+            glyphs: &crate::tui::glyphs::ASCII,
+            gutter: &GutterConfig::default(),
+            inline: &InlineConfig::default(),
+        };
+        let area = Rect::new(0, 0, 40, 2);
+        let mut buf = Buffer::empty(area);
+        viewer.render(area, &mut buf);
+        let row = |y| (0..40).map(|x| buf[(x, y)].symbol().to_string()).collect::<String>();
+        assert!(row(0).contains("// mine"), "{:?}", row(0));
+        assert!(row(1).starts_with("   2 > |two   // alice: theirs"), "{:?}", row(1));
+        assert_eq!(buf[(9, 0)].bg, palette.background(&local));
+        assert_eq!((buf[(2, 1)].fg, buf[(9, 1)].bg), (color, Color::Rgb(45, 32, 57)));
+    }
+
+    #[test]
+    fn test_tag_markers() {
+        let gutter = GutterConfig {
+            tags: [("perf".to_string(), "P".to_string()), ("security".to_string(), "🔒".to_string())].into(),
+            ..GutterConfig::default()
+        };
+        assert!(render_annotated_row(&gutter, 30, "slow #perf").starts_with("   1 P  |fn main"));
+        assert!(render_annotated_row(&gutter, 30, "plain").starts_with("   1 >  |fn main"));
+        let row = render_annotated_row(&gutter, 30, "#security check");
+        assert!(row.starts_with("   1 🔒"), "{:?}", row);
+        assert!(row.contains(" |fn main"));
+    }
+}